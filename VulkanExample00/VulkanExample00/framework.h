@@ -10,6 +10,28 @@
 
 #define MAX_MSG_BUF_SIZE 4096
  
+bool setFrameworkMaxObjects(unsigned int maxObjects);
+
+bool setFrameworkDemoMode(bool enabled, float degreesPerSec);
+
+bool setFrameworkRenderScale(float scale);
+
+bool setFrameworkMaxConcurrentUploads(unsigned int maxConcurrent);
+
+void setFrameworkCoalesceTouchMoves(bool enabled);
+
+bool setFrameworkSampleCount(unsigned int sampleCount);
+
+bool setFrameworkUpdateRate(float hz);
+
+void setFrameworkWideColor(bool enabled);
+
+void setFrameworkTextureBudget(unsigned long long bytes);
+
+bool setFrameworkSsao(bool enabled, float radius, float intensity);
+
+bool setFrameworkWorkerQos(unsigned int qosClass);
+
 void *createFramework(void *view, const char* assets_dir, float scale_factor, unsigned int screenWidth, unsigned int screenHeight, int viewerTop, int viewerLeft, int viewerBottom, int viewerRight);
 
 void destroyFramework(void *framework);
@@ -20,6 +42,51 @@ void *pauseFramework(void *framework);
 
 void *resumeFramework(void *framework);
 
+typedef struct {
+    unsigned int frameCount;
+    float averageMs;
+    float minMs;
+    float maxMs;
+    float p50Ms;
+    float p95Ms;
+    float p99Ms;
+} FrameworkBenchmarkResult;
+
+bool frameworkBeginBenchmark(void *framework, float durationSec);
+
+bool frameworkGetBenchmarkResult(void *framework, FrameworkBenchmarkResult *result);
+
+bool frameworkResize(void *framework, unsigned int screenWidth, unsigned int screenHeight);
+bool frameworkTouch(void *framework, unsigned long long touchId, unsigned int phase, float x, float y);
+bool frameworkSetPresentRegion(void *framework, unsigned int x, unsigned int y, unsigned int width, unsigned int height);
+bool frameworkSetPresentMode(void *framework, unsigned int presentMode, unsigned int *selectedMode);
+bool recreateFrameworkRenderer(void *framework);
+
+bool frameworkReloadShader(void *framework, const char *path);
+
+bool frameworkSetObjectColor(void *framework, unsigned int objectId, float r, float g, float b, float a);
+
+bool frameworkSetObjectSpeed(void *framework, unsigned int objectId, float speed);
+
+bool frameworkSpawnObject(void *framework, unsigned int *objectId);
+
+bool frameworkDespawnObject(void *framework, unsigned int objectId);
+
+typedef struct {
+    unsigned int numAllocated;
+    unsigned int numReused;
+    unsigned int numActive;
+    unsigned int numFree;
+} FrameworkObjectPoolStats;
+
+bool frameworkGetObjectPoolStats(void *framework, FrameworkObjectPoolStats *stats);
+
+bool frameworkTriggerCameraShake(void *framework, float intensity, float durationSec);
+
+bool frameworkDebugDump(void *framework, char *buf, unsigned int bufSize);
+
+unsigned int getLastFrameworkErrMsgLen(void);
+
 bool getLastFrameworkErrMsg(char *buf, unsigned int bufSize);
 
 bool getLastFrameworkErrMsgDbg(char *buf, unsigned int bufSize);
@@ -0,0 +1,29 @@
+#version 450
+
+layout (location = 0) in vec3 in_position;
+layout (location = 1) in vec4 in_instance_transform_0;
+layout (location = 2) in vec4 in_instance_transform_1;
+layout (location = 3) in vec4 in_instance_transform_2;
+layout (location = 4) in vec4 in_instance_transform_3;
+layout (location = 5) in vec4 in_instance_color;
+layout (location = 0) out vec4 out_color;
+
+layout (binding = 0) uniform VS_UBO_VIEW_INFO
+{
+    mat4 view_mtx;
+    mat4 projection_mtx;
+    mat4 prev_view_mtx;
+    mat4 prev_projection_mtx;
+    vec4 jitter;
+} camera;
+
+layout(push_constant) uniform ObjectData {
+    vec4 color;
+    mat4 transform;
+} object;
+
+void main() {
+    mat4 instance_transform = mat4(in_instance_transform_0, in_instance_transform_1, in_instance_transform_2, in_instance_transform_3);
+    out_color = object.color * in_instance_color;
+    gl_Position = camera.projection_mtx * camera.view_mtx * object.transform * instance_transform * vec4(in_position, 1.0);
+}
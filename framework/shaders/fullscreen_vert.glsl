@@ -0,0 +1,7 @@
+#version 450
+
+void main() {
+    // a triangle that covers the whole viewport: (-1, -1), (3, -1), (-1, 3).
+    vec2 position = vec2(float((gl_VertexIndex << 1) & 2), float(gl_VertexIndex & 2));
+    gl_Position = vec4(position * 2.0 - 1.0, 0.0, 1.0);
+}
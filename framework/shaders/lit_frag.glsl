@@ -0,0 +1,49 @@
+#version 450
+
+layout (location = 0) in vec4 in_color;
+layout (location = 1) in vec3 in_normal;
+layout (location = 2) in vec3 in_world_position;
+layout (location = 3) in float in_view_depth;
+layout (location = 0) out vec4 frag_color;
+
+const int MAX_CASCADES = 4;
+
+layout (binding = 2) uniform FS_UBO_CASCADES
+{
+    mat4 view_projection_mtx[MAX_CASCADES];
+    vec4 split_distances;
+} cascades;
+
+layout (binding = 3) uniform texture2DArray shadow_map;
+layout (binding = 4) uniform samplerShadow shadow_sampler;
+
+// must match `LIGHT_DIRECTION` of the framework.
+const vec3 LIGHT_DIRECTION = vec3(-0.4, -1.0, -0.6);
+const float AMBIENT = 0.25;
+
+// returns the fraction of the light that reaches the fragment.
+// the fragment is sampled in the first cascade whose split distance is not nearer than the fragment,
+// and the fragments beyond the last cascade are not shadowed.
+float get_shadow(vec3 world_position, float view_depth) {
+    vec4 split_distances = cascades.split_distances;
+    int cascade = MAX_CASCADES;
+    for (int i = MAX_CASCADES - 1; i >= 0; i--) {
+        if (view_depth <= split_distances[i]) {
+            cascade = i;
+        }
+    }
+
+    if (cascade == MAX_CASCADES) {
+        return 1.0;
+    }
+
+    vec4 clip = cascades.view_projection_mtx[cascade] * vec4(world_position, 1.0);
+    vec3 ndc = clip.xyz / clip.w;
+    return texture(sampler2DArrayShadow(shadow_map, shadow_sampler), vec4(ndc.xy * 0.5 + 0.5, float(cascade), ndc.z));
+}
+
+void main() {
+    float diffuse = max(dot(normalize(in_normal), -normalize(LIGHT_DIRECTION)), 0.0);
+    float shadow = get_shadow(in_world_position, in_view_depth);
+    frag_color = vec4(in_color.rgb * (AMBIENT + (1.0 - AMBIENT) * diffuse * shadow), in_color.a);
+}
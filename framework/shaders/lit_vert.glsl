@@ -0,0 +1,39 @@
+#version 450
+
+layout (location = 0) in vec3 in_position;
+layout (location = 1) in vec3 in_normal;
+layout (location = 0) out vec4 out_color;
+layout (location = 1) out vec3 out_normal;
+layout (location = 2) out vec3 out_world_position;
+layout (location = 3) out float out_view_depth;
+
+layout (binding = 0) uniform VS_UBO_VIEW_INFO
+{
+    mat4 view_mtx;
+    mat4 projection_mtx;
+    mat4 prev_view_mtx;
+    mat4 prev_projection_mtx;
+    vec4 jitter;
+} camera;
+
+layout (binding = 1) readonly buffer VS_SSBO_TRANSFORMS
+{
+    mat4 transforms[];
+} instances;
+
+layout(push_constant) uniform ObjectData {
+    vec4 color;
+    mat4 transform;
+} object;
+
+void main() {
+    mat4 world_mtx = instances.transforms[gl_InstanceIndex];
+    vec4 world_position = world_mtx * vec4(in_position, 1.0);
+    vec4 view_position = camera.view_mtx * world_position;
+    out_color = object.color;
+    // the transforms are rotations and uniform scales, so the normal is transformed by the world matrix.
+    out_normal = mat3(world_mtx) * in_normal;
+    out_world_position = world_position.xyz;
+    out_view_depth = view_position.z;
+    gl_Position = camera.projection_mtx * view_position;
+}
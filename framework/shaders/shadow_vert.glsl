@@ -0,0 +1,18 @@
+#version 450
+
+layout (location = 0) in vec3 in_position;
+
+layout (binding = 0) uniform VS_UBO_LIGHT_INFO
+{
+    mat4 view_projection_mtx;
+} light;
+
+layout(push_constant) uniform ObjectData {
+    vec4 color;
+    mat4 transform;
+} object;
+
+void main() {
+    // the shadow pass pushes the world matrix of each node, so the instance transform buffer is not read.
+    gl_Position = light.view_projection_mtx * object.transform * vec4(in_position, 1.0);
+}
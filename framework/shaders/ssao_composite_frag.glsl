@@ -0,0 +1,23 @@
+#version 450
+
+layout (location = 0) out vec4 frag_color;
+
+// must match `SSAO_NOISE_SIZE` of the framework.
+const int NOISE_SIZE = 4;
+
+layout (binding = 0) uniform texture2D occlusion;
+layout (binding = 1) uniform sampler occlusion_sampler;
+
+void main() {
+    // the box blur covers one tile of the noise, so the pattern of the rotated kernel is removed.
+    vec2 target_size = vec2(textureSize(sampler2D(occlusion, occlusion_sampler), 0));
+    float sum = 0.0;
+    for (int y = -NOISE_SIZE / 2; y < NOISE_SIZE / 2; y++) {
+        for (int x = -NOISE_SIZE / 2; x < NOISE_SIZE / 2; x++) {
+            vec2 uv = (gl_FragCoord.xy + vec2(float(x), float(y))) / target_size;
+            sum += texture(sampler2D(occlusion, occlusion_sampler), uv).r;
+        }
+    }
+    // the pipeline multiplies the color of the scene by the output.
+    frag_color = vec4(vec3(sum / float(NOISE_SIZE * NOISE_SIZE)), 1.0);
+}
@@ -0,0 +1,77 @@
+#version 450
+
+layout (location = 0) out vec4 out_occlusion;
+
+// must match `SSAO_KERNEL_SIZE` and `SSAO_NOISE_SIZE` of the framework.
+const int KERNEL_SIZE = 32;
+const int NOISE_SIZE = 4;
+const float DEPTH_BIAS = 0.025;
+
+layout (binding = 0) uniform FS_UBO_VIEW_INFO
+{
+    mat4 view_mtx;
+    mat4 projection_mtx;
+    mat4 prev_view_mtx;
+    mat4 prev_projection_mtx;
+    vec4 jitter;
+} camera;
+
+layout (binding = 1) uniform FS_UBO_SSAO
+{
+    vec4 kernel[KERNEL_SIZE];
+    vec4 noise[NOISE_SIZE * NOISE_SIZE];
+    vec4 settings;
+    vec4 viewport;
+} ssao;
+
+layout (binding = 2) uniform texture2D geometry;
+layout (binding = 3) uniform sampler geometry_sampler;
+
+void main() {
+    mat4 projection_mtx = camera.projection_mtx;
+    vec4 settings = ssao.settings;
+    vec4 viewport = ssao.viewport;
+    float radius = settings.x;
+    float intensity = settings.y;
+
+    vec2 target_size = vec2(textureSize(sampler2D(geometry, geometry_sampler), 0));
+    vec4 center = texture(sampler2D(geometry, geometry_sampler), gl_FragCoord.xy / target_size);
+    if (center.w <= 0.0) {
+        // nothing was drawn to the pixel.
+        out_occlusion = vec4(1.0);
+        return;
+    }
+
+    // reconstruct the view-space position of the pixel from its view depth.
+    vec2 ndc = (gl_FragCoord.xy - viewport.xy) / viewport.zw * 2.0 - 1.0;
+    float depth = center.w;
+    vec3 position = vec3(
+        (ndc.x - projection_mtx[2][0]) * depth / projection_mtx[0][0],
+        (ndc.y - projection_mtx[2][1]) * depth / projection_mtx[1][1],
+        depth
+    );
+    vec3 normal = normalize(center.xyz);
+
+    // rotate the kernel around the normal with the noise vector of the pixel.
+    ivec2 noise_coord = ivec2(gl_FragCoord.xy) % ivec2(NOISE_SIZE);
+    vec3 random_vector = ssao.noise[noise_coord.y * NOISE_SIZE + noise_coord.x].xyz;
+    vec3 tangent = normalize(random_vector - normal * dot(random_vector, normal));
+    vec3 bitangent = cross(normal, tangent);
+    mat3 tbn = mat3(tangent, bitangent, normal);
+
+    float occlusion = 0.0;
+    for (int i = 0; i < KERNEL_SIZE; i++) {
+        vec3 sample_position = position + tbn * ssao.kernel[i].xyz * radius;
+        vec4 clip = projection_mtx * vec4(sample_position, 1.0);
+        vec2 sample_pixel = (clip.xy / clip.w * 0.5 + 0.5) * viewport.zw + viewport.xy;
+        float sample_depth = texture(sampler2D(geometry, geometry_sampler), sample_pixel / target_size).w;
+        if (sample_depth <= 0.0) {
+            continue;
+        }
+        // the occluders far from the pixel do not darken it.
+        float range = smoothstep(0.0, 1.0, radius / abs(depth - sample_depth));
+        occlusion += (sample_depth <= sample_position.z - DEPTH_BIAS ? 1.0 : 0.0) * range;
+    }
+
+    out_occlusion = vec4(clamp(1.0 - intensity * occlusion / float(KERNEL_SIZE), 0.0, 1.0));
+}
@@ -0,0 +1,13 @@
+#version 450
+
+layout (location = 0) in vec3 in_view_position;
+layout (location = 0) out vec4 out_geometry;
+
+void main() {
+    // the normal of the face is taken from the screen-space derivatives and flipped to face the camera.
+    vec3 normal = normalize(cross(dFdx(in_view_position), dFdy(in_view_position)));
+    if (dot(normal, in_view_position) > 0.0) {
+        normal = -normal;
+    }
+    out_geometry = vec4(normal, in_view_position.z);
+}
@@ -0,0 +1,25 @@
+#version 450
+
+layout (location = 0) in vec3 in_position;
+layout (location = 0) out vec3 out_view_position;
+
+layout (binding = 0) uniform VS_UBO_VIEW_INFO
+{
+    mat4 view_mtx;
+    mat4 projection_mtx;
+    mat4 prev_view_mtx;
+    mat4 prev_projection_mtx;
+    vec4 jitter;
+} camera;
+
+layout(push_constant) uniform ObjectData {
+    vec4 color;
+    mat4 transform;
+} object;
+
+void main() {
+    // the geometry pass pushes the world matrix of each node, so the instance transform buffer is not read.
+    vec4 view_position = camera.view_mtx * object.transform * vec4(in_position, 1.0);
+    out_view_position = view_position.xyz;
+    gl_Position = camera.projection_mtx * view_position;
+}
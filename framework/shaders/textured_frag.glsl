@@ -0,0 +1,12 @@
+#version 450
+
+layout (location = 0) in vec4 in_color;
+layout (location = 1) in vec2 in_uv;
+layout (location = 0) out vec4 frag_color;
+
+layout (binding = 2) uniform texture2D base_texture;
+layout (binding = 3) uniform sampler base_sampler;
+
+void main() {
+    frag_color = in_color * texture(sampler2D(base_texture, base_sampler), in_uv);
+}
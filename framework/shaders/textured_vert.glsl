@@ -0,0 +1,31 @@
+#version 450
+
+layout (location = 0) in vec3 in_position;
+layout (location = 1) in vec2 in_uv;
+layout (location = 0) out vec4 out_color;
+layout (location = 1) out vec2 out_uv;
+
+layout (binding = 0) uniform VS_UBO_VIEW_INFO
+{
+    mat4 view_mtx;
+    mat4 projection_mtx;
+    mat4 prev_view_mtx;
+    mat4 prev_projection_mtx;
+    vec4 jitter;
+} camera;
+
+layout (binding = 1) readonly buffer VS_SSBO_TRANSFORMS
+{
+    mat4 transforms[];
+} instances;
+
+layout(push_constant) uniform ObjectData {
+    vec4 color;
+    mat4 transform;
+} object;
+
+void main() {
+    out_color = object.color;
+    out_uv = in_uv;
+    gl_Position = camera.projection_mtx * camera.view_mtx * instances.transforms[gl_InstanceIndex] * vec4(in_position, 1.0);
+}
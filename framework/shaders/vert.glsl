@@ -7,8 +7,16 @@ layout (binding = 0) uniform VS_UBO_VIEW_INFO
 {
     mat4 view_mtx;
     mat4 projection_mtx;
+    mat4 prev_view_mtx;
+    mat4 prev_projection_mtx;
+    vec4 jitter;
 } camera;
 
+layout (binding = 1) readonly buffer VS_SSBO_TRANSFORMS
+{
+    mat4 transforms[];
+} instances;
+
 layout(push_constant) uniform ObjectData {
     vec4 color;
     mat4 transform;
@@ -16,5 +24,5 @@ layout(push_constant) uniform ObjectData {
 
 void main() {
     out_color = object.color;
-    gl_Position = camera.projection_mtx * camera.view_mtx * object.transform * vec4(in_position, 1.0);
+    gl_Position = camera.projection_mtx * camera.view_mtx * instances.transforms[gl_InstanceIndex] * vec4(in_position, 1.0);
 }
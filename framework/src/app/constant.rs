@@ -1,10 +1,23 @@
+use vulkano::format::Format;
+use vulkano::pipeline::graphics::rasterization::DepthBias;
+
 use crate::math::*;
 
 
 
 pub const MAX_OBJECTS_NUM: usize = 5_000;
+pub const MAX_CONCURRENT_UPLOADS: usize = 4;
+/// the maximum number of fixed-rate updates in a frame. the rest of the elapsed time is dropped.
+pub const MAX_FIXED_UPDATES_PER_FRAME: u32 = 4;
+pub const BACKGROUND_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+/// the tessellation of the procedural meshes.
+pub const SPHERE_RINGS: u32 = 16;
+pub const SPHERE_SECTORS: u32 = 32;
+pub const PLANE_SUBDIVISIONS: u32 = 8;
 
 
+/// the built-in meshes are wound counter-clockwise seen from the outside. (use `FrontFace::CounterClockwise`)
+/// the triangle and the quad are single-sided and face the +z axis, so they are culled when seen from behind with `CullMode::Back`.
 pub const TRIANGLE_POSITIONS: [Vec3; 3] = [
     Vec3::new_vector(-0.5, -0.25, 0.0),
     Vec3::new_vector(0.5, -0.25, 0.0),
@@ -22,15 +35,22 @@ pub const QUAD_POSITIONS: [Vec3; 4] = [
     Vec3::new_vector(1.0, -1.0, 0.0),
     Vec3::new_vector(1.0, 1.0, 0.0),
 ];
+/// the texture coordinates of the quad. the top-left corner is `(0, 0)`.
+pub const QUAD_UVS: [Vec2; 4] = [
+    Vec2::new_vector(0.0, 0.0),
+    Vec2::new_vector(0.0, 1.0),
+    Vec2::new_vector(1.0, 1.0),
+    Vec2::new_vector(1.0, 0.0),
+];
 
 
 pub const CUBE_INDICES: [u16; 36] = [
     3, 2, 0, 0, 1, 3, // top
     2, 6, 4, 4, 0, 2, // front
     0, 4, 5, 5, 1, 0, // right
-    3, 2, 6, 6, 7, 3, // left
-    5, 1, 3, 3, 7, 5, // back
-    6, 4, 5, 5, 7, 6, // bottom
+    6, 2, 3, 3, 7, 6, // left
+    3, 1, 5, 5, 7, 3, // back
+    5, 4, 6, 6, 7, 5, // bottom
 ];
 pub const CUBE_POSITIONS: [Vec3; 8] = [
     Vec3::new_vector(1.0, 1.0, 1.0), // 0
@@ -44,5 +64,89 @@ pub const CUBE_POSITIONS: [Vec3; 8] = [
 ];
 
 
+/// the cube with separate vertices for each face, so that each face has its own texture coordinates.
+/// the faces are in the same order and winding as `CUBE_INDICES`.
+pub const CUBE_FACE_INDICES: [u16; 36] = [
+    0, 1, 2, 2, 3, 0, // top
+    4, 5, 6, 6, 7, 4, // front
+    8, 9, 10, 10, 11, 8, // right
+    12, 13, 14, 14, 15, 12, // left
+    16, 17, 18, 18, 19, 16, // back
+    20, 21, 22, 22, 23, 20, // bottom
+];
+pub const CUBE_FACE_POSITIONS: [Vec3; 24] = [
+    Vec3::new_vector(-1.0, 1.0, -1.0), Vec3::new_vector(-1.0, 1.0, 1.0),
+    Vec3::new_vector(1.0, 1.0, 1.0), Vec3::new_vector(1.0, 1.0, -1.0), // top
+    Vec3::new_vector(-1.0, 1.0, 1.0), Vec3::new_vector(-1.0, -1.0, 1.0),
+    Vec3::new_vector(1.0, -1.0, 1.0), Vec3::new_vector(1.0, 1.0, 1.0), // front
+    Vec3::new_vector(1.0, 1.0, 1.0), Vec3::new_vector(1.0, -1.0, 1.0),
+    Vec3::new_vector(1.0, -1.0, -1.0), Vec3::new_vector(1.0, 1.0, -1.0), // right
+    Vec3::new_vector(-1.0, -1.0, 1.0), Vec3::new_vector(-1.0, 1.0, 1.0),
+    Vec3::new_vector(-1.0, 1.0, -1.0), Vec3::new_vector(-1.0, -1.0, -1.0), // left
+    Vec3::new_vector(-1.0, 1.0, -1.0), Vec3::new_vector(1.0, 1.0, -1.0),
+    Vec3::new_vector(1.0, -1.0, -1.0), Vec3::new_vector(-1.0, -1.0, -1.0), // back
+    Vec3::new_vector(1.0, -1.0, -1.0), Vec3::new_vector(1.0, -1.0, 1.0),
+    Vec3::new_vector(-1.0, -1.0, 1.0), Vec3::new_vector(-1.0, -1.0, -1.0), // bottom
+];
+/// each face is mapped to the whole `0..1` range.
+pub const CUBE_FACE_UVS: [Vec2; 24] = [
+    Vec2::new_vector(0.0, 0.0), Vec2::new_vector(0.0, 1.0), Vec2::new_vector(1.0, 1.0), Vec2::new_vector(1.0, 0.0), // top
+    Vec2::new_vector(0.0, 0.0), Vec2::new_vector(0.0, 1.0), Vec2::new_vector(1.0, 1.0), Vec2::new_vector(1.0, 0.0), // front
+    Vec2::new_vector(0.0, 0.0), Vec2::new_vector(0.0, 1.0), Vec2::new_vector(1.0, 1.0), Vec2::new_vector(1.0, 0.0), // right
+    Vec2::new_vector(0.0, 0.0), Vec2::new_vector(0.0, 1.0), Vec2::new_vector(1.0, 1.0), Vec2::new_vector(1.0, 0.0), // left
+    Vec2::new_vector(0.0, 0.0), Vec2::new_vector(0.0, 1.0), Vec2::new_vector(1.0, 1.0), Vec2::new_vector(1.0, 0.0), // back
+    Vec2::new_vector(0.0, 0.0), Vec2::new_vector(0.0, 1.0), Vec2::new_vector(1.0, 1.0), Vec2::new_vector(1.0, 0.0), // bottom
+];
+
+
+/// the vertex formats of the meshes drawn with the default shader. (the position)
+pub const DEFAULT_VERTEX_FORMATS: [Format; 1] = [Format::R32G32B32_SFLOAT];
+/// the vertex formats of the meshes drawn with the textured shader. (the position and the texture coordinates)
+pub const TEXTURED_VERTEX_FORMATS: [Format; 2] = [Format::R32G32B32_SFLOAT, Format::R32G32_SFLOAT];
+/// the vertex formats of the meshes drawn with the lit shader. (the position and the normal)
+pub const LIT_VERTEX_FORMATS: [Format; 2] = [Format::R32G32B32_SFLOAT, Format::R32G32B32_SFLOAT];
+
+
 pub const VERT_SHADER_PATH: &'static str = "shaders/vert.spv";
 pub const FRAG_SHADER_PATH: &'static str = "shaders/frag.spv";
+pub const FOLIAGE_VERT_SHADER_PATH: &'static str = "shaders/foliage_vert.spv";
+pub const TEXTURED_VERT_SHADER_PATH: &'static str = "shaders/textured_vert.spv";
+pub const TEXTURED_FRAG_SHADER_PATH: &'static str = "shaders/textured_frag.spv";
+pub const LIT_VERT_SHADER_PATH: &'static str = "shaders/lit_vert.spv";
+pub const LIT_FRAG_SHADER_PATH: &'static str = "shaders/lit_frag.spv";
+pub const SHADOW_VERT_SHADER_PATH: &'static str = "shaders/shadow_vert.spv";
+pub const SSAO_GEOMETRY_VERT_SHADER_PATH: &'static str = "shaders/ssao_geometry_vert.spv";
+pub const SSAO_GEOMETRY_FRAG_SHADER_PATH: &'static str = "shaders/ssao_geometry_frag.spv";
+pub const FULLSCREEN_VERT_SHADER_PATH: &'static str = "shaders/fullscreen_vert.spv";
+pub const SSAO_FRAG_SHADER_PATH: &'static str = "shaders/ssao_frag.spv";
+pub const SSAO_COMPOSITE_FRAG_SHADER_PATH: &'static str = "shaders/ssao_composite_frag.spv";
+/// the texture of the meshes with texture coordinates. it is loaded through the texture cache of the scene.
+pub const CHECKER_TEXTURE_PATH: &'static str = "textures/checker.astc";
+
+
+/// the direction the light travels in. it must match `LIGHT_DIRECTION` of `lit_frag.glsl`.
+pub const LIGHT_DIRECTION: Vec3 = Vec3::new_vector(-0.4, -1.0, -0.6);
+/// the width and height of each cascade of the shadow map.
+pub const SHADOW_MAP_SIZE: u32 = 2048;
+pub const NUM_SHADOW_CASCADES: usize = 3;
+/// the distance from the camera covered by the shadow cascades. the fragments beyond it are not shadowed.
+pub const SHADOW_DISTANCE: f32 = 50.0;
+/// the blend of the logarithmic and uniform cascade splits. (see `cascade_split_distances`)
+pub const CASCADE_SPLIT_LAMBDA: f32 = 0.75;
+/// the depth bias of the shadow casters, so the lit surfaces do not shadow themselves.
+pub const SHADOW_DEPTH_BIAS: DepthBias = DepthBias { constant_factor: 1.25, clamp: 0.0, slope_factor: 1.75 };
+
+/// the foliage is scattered with a fixed seed, so that it looks the same on every launch.
+pub const FOLIAGE_SEED: u64 = 0x0f01_1a6e;
+pub const FOLIAGE_REGION_MIN: Vec2 = Vec2::new_vector(-20.0, -20.0);
+pub const FOLIAGE_REGION_MAX: Vec2 = Vec2::new_vector(20.0, 20.0);
+/// the number of blades per unit area.
+pub const FOLIAGE_DENSITY: f32 = 2.0;
+pub const FOLIAGE_HEIGHT: f32 = -3.0;
+/// a single blade of grass. it is double-sided, so draw it with `CullMode::None`.
+pub const FOLIAGE_BLADE_INDICES: [u16; 3] = [0, 1, 2];
+pub const FOLIAGE_BLADE_POSITIONS: [Vec3; 3] = [
+    Vec3::new_vector(-0.1, 0.0, 0.0),
+    Vec3::new_vector(0.1, 0.0, 0.0),
+    Vec3::new_vector(0.0, 0.6, 0.0),
+];
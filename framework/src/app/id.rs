@@ -7,14 +7,41 @@ pub enum MeshID {
     Triangle = 0,
     Quad = 1,
     Cube = 2,
+    /// the cube with per-face normals. (see `create_cube_mesh_with_normals`)
+    CubeWithNormals = 3,
+    /// the quad with texture coordinates. (see `create_quad_mesh_with_uvs`)
+    QuadWithUvs = 4,
+    /// the cube with texture coordinates. (see `create_cube_mesh_with_uvs`)
+    CubeWithUvs = 5,
+    /// the uv-sphere with normals. (see `create_sphere_mesh`)
+    Sphere = 6,
+    /// the subdivided ground plane with normals. (see `create_plane_mesh`)
+    Plane = 7,
+}
+
+impl MeshID {
+    /// return the shader that consumes the vertex attributes of the mesh.
+    #[inline]
+    pub fn get_shader_id(&self) -> ShaderID {
+        match self {
+            MeshID::QuadWithUvs | MeshID::CubeWithUvs => ShaderID::Textured,
+            MeshID::CubeWithNormals | MeshID::Sphere | MeshID::Plane => ShaderID::Lit,
+            _ => ShaderID::Default,
+        }
+    }
 }
 
 impl Distribution<MeshID> for Standard {
     fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> MeshID {
-        match rng.gen_range(0..3) {
+        match rng.gen_range(0..8) {
             0 => MeshID::Triangle,
             1 => MeshID::Quad,
-            _ => MeshID::Cube,
+            2 => MeshID::Cube,
+            3 => MeshID::CubeWithNormals,
+            4 => MeshID::QuadWithUvs,
+            5 => MeshID::CubeWithUvs,
+            6 => MeshID::Sphere,
+            _ => MeshID::Plane,
         }
     }
 }
@@ -36,13 +63,10 @@ impl Distribution<SystemID> for Standard {
 #[repr(u8)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum ShaderID {
+    /// draws the positions with the object color.
     Default = 0,
-}
-
-impl Distribution<ShaderID> for Standard {
-    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> ShaderID {
-        match rng.gen_range(0..1) {
-            _ => ShaderID::Default,
-        }
-    }
+    /// draws the positions and texture coordinates with the checker texture. (see `CHECKER_TEXTURE_PATH`)
+    Textured = 1,
+    /// draws the positions and normals with the object color lit by a directional light.
+    Lit = 2,
 }
@@ -11,24 +11,29 @@ use std::sync::atomic::Ordering;
 use std::sync::mpsc;
 use std::sync::mpsc::TryRecvError;
 use std::thread;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex, Condvar};
 use std::collections::HashMap;
+use std::hash::Hash;
 
 use rand::prelude::*;
 use bytemuck::{Pod, Zeroable};
 use vulkano::buffer::allocator;
 use vulkano::command_buffer::AutoCommandBufferBuilder;
+use vulkano::command_buffer::ClearAttachment;
+use vulkano::command_buffer::ClearRect;
 use vulkano::command_buffer::CommandBufferInheritanceInfo;
 use vulkano::command_buffer::CommandBufferInheritanceRenderPassInfo;
 use vulkano::command_buffer::CommandBufferInheritanceRenderPassType;
 use vulkano::command_buffer::CommandBufferUsage;
+use vulkano::command_buffer::PrimaryAutoCommandBuffer;
 use vulkano::command_buffer::PrimaryCommandBufferAbstract;
 use vulkano::command_buffer::RenderPassBeginInfo;
 use vulkano::command_buffer::SecondaryAutoCommandBuffer;
 use vulkano::command_buffer::SubpassContents;
 use vulkano::command_buffer::allocator::StandardCommandBufferAllocator;
 use vulkano::command_buffer::allocator::StandardCommandBufferAllocatorCreateInfo;
+use vulkano::format::ClearColorValue;
 use vulkano::format::ClearValue;
 use vulkano::format::Format;
 use vulkano::pipeline::GraphicsPipeline;
@@ -43,6 +48,9 @@ use vulkano::pipeline::graphics::color_blend::LogicOp;
 use vulkano::pipeline::graphics::depth_stencil::CompareOp;
 use vulkano::pipeline::graphics::depth_stencil::DepthState;
 use vulkano::pipeline::graphics::depth_stencil::DepthStencilState;
+use vulkano::pipeline::graphics::input_assembly::InputAssemblyState;
+use vulkano::pipeline::graphics::input_assembly::PrimitiveTopology;
+use vulkano::pipeline::graphics::multisample::MultisampleState;
 use vulkano::pipeline::graphics::rasterization::CullMode;
 use vulkano::pipeline::graphics::rasterization::FrontFace;
 use vulkano::pipeline::graphics::rasterization::PolygonMode;
@@ -51,131 +59,338 @@ use vulkano::pipeline::graphics::vertex_input::VertexInputAttributeDescription;
 use vulkano::pipeline::graphics::vertex_input::VertexInputBindingDescription;
 use vulkano::pipeline::graphics::vertex_input::VertexInputRate;
 use vulkano::pipeline::graphics::vertex_input::VertexInputState;
+use vulkano::pipeline::graphics::viewport::Scissor;
 use vulkano::pipeline::graphics::viewport::Viewport;
 use vulkano::pipeline::graphics::viewport::ViewportState;
 use vulkano::render_pass::Subpass;
+use vulkano::shader::ShaderModule;
+use vulkano::swapchain::RectangleLayer;
 use vulkano::sync::GpuFuture;
 
 use crate::math::*;
 use crate::timer::*;
 use crate::renderer::*;
+use crate::input::{TouchEvent, TouchPhase};
+use crate::world::bvh::*;
 use crate::world::mesh;
+use crate::world::loader::AssetLoader;
+use crate::world::texture::{TextureCache, TextureSampler, SampledImageView};
 use crate::world::mesh::*;
 use crate::world::model::*;
 use crate::world::scene::*;
 use crate::world::shader;
 use crate::world::shader::*;
 use crate::world::object::*;
+use crate::world::pool::*;
 use crate::world::variable::*;
+use crate::worker::spawn_worker;
 use crate::{err, error::RuntimeError};
 
 use self::id::*;
 use self::objects::*;
 use self::constant::*;
+pub use self::constant::{MAX_OBJECTS_NUM, MAX_CONCURRENT_UPLOADS};
 
 
 pub struct MainScene {
     camera: Option<Camera>,
     objects: Vec<Arc<Mutex<dyn WorldObject>>>,
+    foliage: Option<Arc<Foliage>>,
+    object_pool: ObjectPool<RotateObject>,
+    max_objects: usize,
+    demo_rotation: Option<f32>,
+    taa_jitter: bool,
+    update_rate: Option<f32>,
+    update_accumulator: f32,
+    rasterization: RasterizationSettings,
+    transform_buffer: Option<Arc<StorageBuffer<Mat4x4>>>,
+    asset_loader: AssetLoader,
+    textures: TextureCache,
+    damage_tracking: bool,
+    damaged_regions: Vec<RectangleLayer>,
+    meshes: HashMap<MeshID, Arc<Mesh>>,
+    shaders: HashMap<ShaderID, Arc<GraphicsShader>>,
+    shader_modules: Option<(Arc<ShaderModule>, Arc<ShaderModule>)>,
+    shadows: Option<SceneShadows>,
+    /// the settings of the ambient occlusion. `None` if it is disabled.
+    ssao: Option<SsaoSettings>,
+    /// the passes of the ambient occlusion. they are created by the first draw after it is enabled.
+    ssao_passes: Option<SceneSsao>,
+    /// the bounding spheres of the objects by their ids. (see `pick_object`)
+    bvh: Bvh<usize>,
+    /// `true` if the objects have moved since the bounding spheres were last collected.
+    object_bounds_dirty: bool,
+    picked_object: Option<usize>,
 }
 
 impl MainScene {
     pub fn new() -> Box<Self> {
+        Self::with_max_objects(MAX_OBJECTS_NUM)
+    }
+
+    /// # Panics
+    /// - Panics if `max_objects` is zero.
+    pub fn with_max_objects(max_objects: usize) -> Box<Self> {
+        assert!(max_objects > 0, "the maximum number of objects cannot be zero.");
         Box::new(Self {
             camera: None,
-            objects: Vec::with_capacity(MAX_OBJECTS_NUM),
+            objects: Vec::with_capacity(max_objects),
+            foliage: None,
+            object_pool: ObjectPool::new(),
+            max_objects,
+            demo_rotation: None,
+            taa_jitter: false,
+            update_rate: None,
+            update_accumulator: 0.0,
+            rasterization: RasterizationSettings::default(),
+            transform_buffer: None,
+            asset_loader: AssetLoader::new(MAX_CONCURRENT_UPLOADS),
+            textures: TextureCache::new(u64::MAX),
+            damage_tracking: false,
+            damaged_regions: Vec::new(),
+            meshes: HashMap::new(),
+            shaders: HashMap::new(),
+            shader_modules: None,
+            shadows: None,
+            ssao: None,
+            ssao_passes: None,
+            bvh: Bvh::new(),
+            object_bounds_dirty: false,
+            picked_object: None,
         })
     }
-}
 
-impl SceneNode<String> for MainScene {
-    fn enter(&mut self, renderer: &Renderer) -> Result<(), RuntimeError> {
+    /// get the object with the given id. (the index of the object)
+    /// 
+    /// # Runtime Errors
+    /// - Returns a runtime error message if there is no object with the given id.
+    /// 
+    #[inline]
+    fn try_get_object(&self, id: usize) -> Result<&Arc<Mutex<dyn WorldObject>>, RuntimeError> {
+        self.objects.get(id)
+            .ok_or_else(|| err!("Object ID not found in scene. (object id: {})", id))
+    }
+
+    /// returns the not-ready error if the scene assets are not loaded yet.
+    #[inline]
+    fn check_ready(&self) -> Result<(), RuntimeError> {
+        match self.is_ready() {
+            true => Ok(()),
+            false => Err(err!("Logic Error: The scene is not ready. (scene assets are not loaded)")),
+        }
+    }
+
+    /// set the camera demo-rotation speed. (degree per second)
+    /// `None` disables the demo-rotation.
+    pub fn set_demo_rotation(&mut self, degrees_per_sec: Option<f32>) {
+        self.demo_rotation = degrees_per_sec;
+        if let Some(camera) = &mut self.camera {
+            camera.demo_rotation = degrees_per_sec;
+        }
+    }
+
+    /// enable or disable the sub-pixel jitter of the camera projection. (for temporal anti-aliasing)
+    pub fn set_taa_jitter(&mut self, enabled: bool) {
+        self.taa_jitter = enabled;
+        if let Some(camera) = &mut self.camera {
+            camera.set_taa_jitter(enabled);
+        }
+    }
+
+    /// set the polygon mode of the scene pipeline. `PolygonMode::Line` draws the objects as wireframe.
+    /// the polygon mode is applied when the scene is entered.
+    pub fn set_polygon_mode(&mut self, polygon_mode: PolygonMode) {
+        self.rasterization.polygon_mode = polygon_mode;
+    }
+
+    /// set the rasterization settings of the scene pipeline. (e.g. the cull mode, front face and depth bias)
+    /// the settings are applied when the scene is entered.
+    pub fn set_rasterization_settings(&mut self, settings: RasterizationSettings) {
+        self.rasterization = settings;
+    }
+
+    /// enable or disable the screen-space ambient occlusion. `None` disables it.
+    /// the occlusion darkens the drawn scene, and its passes are created or removed on the next draw.
+    /// 
+    /// # Panics
+    /// - Panics if the radius is not a positive finite number.
+    /// - Panics if the intensity is not a non-negative finite number.
+    pub fn set_ssao(&mut self, settings: Option<SsaoSettings>) {
+        if let Some(settings) = &settings {
+            assert!(settings.radius.is_finite() && settings.radius > 0.0, "the ambient occlusion radius must be a positive finite number.");
+            assert!(settings.intensity.is_finite() && settings.intensity >= 0.0, "the ambient occlusion intensity must be a non-negative finite number.");
+        }
+        self.ssao = settings;
+    }
+
+    /// set the rate at which the objects are updated. (hertz)
+    /// between the updates, the objects are drawn at the blend of the two latest update states.
+    /// `None` updates the objects once per frame.
+    /// 
+    /// # Panics
+    /// - Panics if `hz` is not a positive finite number.
+    pub fn set_update_rate(&mut self, hz: Option<f32>) {
+        if let Some(hz) = hz {
+            assert!(hz.is_finite() && hz > 0.0, "the update rate must be a positive finite number.");
+        }
+        self.update_rate = hz;
+        self.update_accumulator = 0.0;
+    }
+
+    /// set the maximum number of assets uploaded at the same time when entering the scene.
+    /// 
+    /// # Panics
+    /// - Panics if `max_concurrent` is zero.
+    pub fn set_max_concurrent_uploads(&mut self, max_concurrent: usize) {
+        self.asset_loader = AssetLoader::new(max_concurrent);
+    }
+
+    /// set the byte budget of the texture cache. the least recently used textures not in use are evicted over the budget.
+    /// the budget is unlimited by default.
+    pub fn set_texture_budget(&mut self, bytes: u64) {
+        self.textures.set_budget(bytes);
+    }
+
+    /// get the texture cache of the scene. (e.g. to load textures with `TextureCache::load_texture`)
+    #[inline]
+    pub fn mut_textures(&mut self) -> &mut TextureCache {
+        &mut self.textures
+    }
+
+    /// enable or disable damage tracking.
+    /// when enabled, frames are skipped until a region is marked as damaged,
+    /// and only the damaged regions are presented.
+    pub fn set_damage_tracking(&mut self, enabled: bool) {
+        self.damage_tracking = enabled;
+        self.damaged_regions.clear();
+    }
+
+    /// mark a region of the screen as changed. (pixel)
+    /// the region is presented on the next frame.
+    pub fn mark_damaged(&mut self, region: RectangleLayer) {
+        if self.damage_tracking {
+            self.damaged_regions.push(region);
+        }
+    }
+
+    /// return the nearest object hit by the ray and the distance to it.
+    /// the bounding spheres of the moved objects are collected again before the query,
+    /// and the hierarchy is rebuilt lazily by the query.
+    pub fn pick_object(&mut self, ray: &Ray) -> Option<(usize, f32)> {
+        if self.object_bounds_dirty {
+            let spheres = self.objects.iter()
+                .enumerate()
+                .filter_map(|(id, object)| object.lock().unwrap().get_bounding_sphere().map(|sphere| (id, sphere)));
+            self.bvh.reset(spheres);
+            self.object_bounds_dirty = false;
+        }
+        self.bvh.raycast(ray)
+    }
+
+    /// return the id of the object picked by the last touch.
+    #[inline]
+    pub fn get_picked_object(&self) -> Option<usize> {
+        self.picked_object
+    }
+
+    /// returns `true` if damage tracking is enabled and no region has changed since the last present.
+    #[inline]
+    fn is_frame_skipped(&self) -> bool {
+        self.damage_tracking && self.damaged_regions.is_empty()
+    }
+
+    /// load the meshes and shaders of the scene and create the camera uniform buffer and the instance transform buffer.
+    /// also returns the vertex and fragment shader modules of the default shader.
+    fn load_resources(&self, renderer: &Renderer) -> Result<(Arc<UniformBuffer<CameraData>>, Arc<StorageBuffer<Mat4x4>>, HashMap<MeshID, Arc<Mesh>>, HashMap<ShaderID, Arc<GraphicsShader>>, (Arc<ShaderModule>, Arc<ShaderModule>)), RuntimeError> {
         // create triangle mesh.
         let render_ctx = renderer.ref_render_context().clone();
-        let triangle_mesh = thread::spawn(move || {
+        let triangle_mesh = self.asset_loader.spawn(move || {
             create_triangle_mesh(render_ctx)
         });
 
         // create quad mesh.
         let render_ctx = renderer.ref_render_context().clone();
-        let quad_mesh = thread::spawn(move || {
+        let quad_mesh = self.asset_loader.spawn(move || {
             create_quad_mesh(render_ctx)
         });
 
         // create cube mesh.
         let render_ctx = renderer.ref_render_context().clone();
-        let cube_mesh = thread::spawn(move || {
+        let cube_mesh = self.asset_loader.spawn(move || {
             create_cube_mesh(render_ctx)
         });
 
+        // create cube mesh with normals.
+        let render_ctx = renderer.ref_render_context().clone();
+        let cube_mesh_with_normals = self.asset_loader.spawn(move || {
+            create_cube_mesh_with_normals(render_ctx)
+        });
+
+        // create quad mesh with texture coordinates.
+        let render_ctx = renderer.ref_render_context().clone();
+        let quad_mesh_with_uvs = self.asset_loader.spawn(move || {
+            create_quad_mesh_with_uvs(render_ctx)
+        });
+
+        // create cube mesh with texture coordinates.
+        let render_ctx = renderer.ref_render_context().clone();
+        let cube_mesh_with_uvs = self.asset_loader.spawn(move || {
+            create_cube_mesh_with_uvs(render_ctx)
+        });
+
+        // create sphere mesh.
+        let render_ctx = renderer.ref_render_context().clone();
+        let sphere_mesh = self.asset_loader.spawn(move || {
+            create_sphere_mesh(render_ctx, SPHERE_RINGS, SPHERE_SECTORS)
+        });
+
+        // create plane mesh.
+        let render_ctx = renderer.ref_render_context().clone();
+        let plane_mesh = self.asset_loader.spawn(move || {
+            create_plane_mesh(render_ctx, PLANE_SUBDIVISIONS)
+        });
+
         // load shader module
         let assets_dir = renderer.ref_assets_dir().to_path_buf();
         let render_ctx = renderer.ref_render_context().clone();
-        let vs = thread::spawn(move || {
+        let vs = self.asset_loader.spawn(move || {
             let path = PathBuf::from_iter([ assets_dir, PathBuf::from(VERT_SHADER_PATH) ]);
             load_from_spv_file(&path, &render_ctx)
         });
         let assets_dir = renderer.ref_assets_dir().to_path_buf();
         let render_ctx = renderer.ref_render_context().clone();
-        let fs = thread::spawn(move || {
+        let fs = self.asset_loader.spawn(move || {
             let path = PathBuf::from_iter([ assets_dir, PathBuf::from(FRAG_SHADER_PATH) ]);
             load_from_spv_file(&path, &render_ctx)
         });
 
         // create a graphics pipeline.
-        let pipeline = GraphicsPipeline::start()
-            .vertex_input_state(
-                VertexInputState::new()
-                    .binding(0, VertexInputBindingDescription {
-                        stride: mem::size_of::<Vec3>() as u32,
-                        input_rate: VertexInputRate::Vertex,
-                    })
-                    .attribute(0, VertexInputAttributeDescription {
-                        binding: 0,
-                        offset: 0,
-                        format: Format::R32G32B32_SFLOAT,
-                    })
-            )
-            .depth_stencil_state(DepthStencilState::simple_depth_test())
-            .viewport_state(ViewportState::viewport_dynamic_scissor_irrelevant())
-            .vertex_shader(vs.join().unwrap()?.entry_point("main").unwrap(), ())
-            .fragment_shader(fs.join().unwrap()?.entry_point("main").unwrap(), ())
-            .render_pass(renderer.pipeline_begin_render_pass_type(0).unwrap())
-            .build_with_cache(renderer.ref_pipeline_cache().clone())
-            .build(renderer.ref_render_context().ref_device().clone())
-            .map_err(|e| err!("Graphics pipeline creation failed: {}", e.to_string()))?;
+        let (vs, fs) = (vs.join().unwrap()?, fs.join().unwrap()?);
+        let pipeline = self.create_default_pipeline(renderer, &vs, &fs)?;
 
 
-        // create the shader variable.
+        // create the shader variables.
+        // the default shader reads the transform of each object from the instance transform buffer.
         let render_ctx = renderer.ref_render_context().clone();
         let uniform_buffer: Arc<UniformBuffer<CameraData>> = UniformBuffer::from_data(
-            CameraData { view: Mat4x4::IDENTITY, projection: Mat4x4::IDENTITY },
+            CameraData { view: Mat4x4::IDENTITY, projection: Mat4x4::IDENTITY, prev_view: Mat4x4::IDENTITY, prev_projection: Mat4x4::IDENTITY, jitter: Vec4::ZERO },
+            render_ctx.ref_memory_allocator(),
+        )?;
+        // the buffer has a region of `max_objects` transforms for each frame in flight.
+        let transform_buffer: Arc<StorageBuffer<Mat4x4>> = StorageBuffer::with_capacity(
+            self.max_objects * renderer.get_max_frame_in_flight() as usize,
             render_ctx.ref_memory_allocator(),
         )?;
-
-        
-        // create a camera object.
-        let mut camera = Camera {
-            mat: Mat4x4::IDENTITY,
-            screen_width: renderer.get_screen_size().0,
-            screen_height: renderer.get_screen_size().1,
-            uniform_buffer: uniform_buffer.clone(),
-        };
-
-        camera.set_position(Vec3::new_vector(0.0, 0.0, -10.0));
-        camera.set_look_at_point(Vec3::ZERO);
-
-        self.camera = Some(camera);
 
 
         // create a graphics shader.
         let default_shader = GraphicsShader::new(
             pipeline, 
             render_ctx.ref_descriptor_allocator(), 
-            [uniform_buffer.clone() as _]
+            [uniform_buffer.clone() as _, transform_buffer.clone() as _]
         )?;
 
-        // create game objects.
+        // collect the shaders and meshes.
         let shaders = HashMap::from([(ShaderID::Default, default_shader)]);
         let mut meshes = HashMap::new();
         let mut command_buffers = Vec::new();
@@ -192,10 +407,25 @@ impl SceneNode<String> for MainScene {
         meshes.insert(MeshID::Cube, mesh);
         command_buffers.push(command_buffer);
 
-        let objects = thread::spawn(move || {
-            create_game_objects(meshes, shaders)
-        });
+        let (mesh, command_buffer) = cube_mesh_with_normals.join().unwrap()?;
+        meshes.insert(MeshID::CubeWithNormals, mesh);
+        command_buffers.push(command_buffer);
+
+        let (mesh, command_buffer) = quad_mesh_with_uvs.join().unwrap()?;
+        meshes.insert(MeshID::QuadWithUvs, mesh);
+        command_buffers.push(command_buffer);
+
+        let (mesh, command_buffer) = cube_mesh_with_uvs.join().unwrap()?;
+        meshes.insert(MeshID::CubeWithUvs, mesh);
+        command_buffers.push(command_buffer);
+
+        let (mesh, command_buffer) = sphere_mesh.join().unwrap()?;
+        meshes.insert(MeshID::Sphere, mesh);
+        command_buffers.push(command_buffer);
 
+        let (mesh, command_buffer) = plane_mesh.join().unwrap()?;
+        meshes.insert(MeshID::Plane, mesh);
+        command_buffers.push(command_buffer);
 
         let render_ctx = renderer.ref_render_context().clone();
         let allocator = render_ctx.get_command_buffer_allocator();
@@ -219,11 +449,413 @@ impl SceneNode<String> for MainScene {
             .wait(None)
             .map_err(|e| err!("Primary command buffer flush failed: {}", e.to_string()))?;
 
-        self.objects = objects.join().unwrap();
+        Ok((uniform_buffer, transform_buffer, meshes, shaders, (vs, fs)))
+    }
+
+    /// create the foliage of the scene. the instances are scattered with a fixed seed.
+    /// the foliage shader shares the camera uniform buffer with the default shader.
+    fn create_foliage(&self, renderer: &Renderer, uniform_buffer: &Arc<UniformBuffer<CameraData>>) -> Result<Arc<Foliage>, RuntimeError> {
+        let render_ctx = renderer.ref_render_context();
+        let vs = load_from_spv_file(&renderer.ref_assets_dir().join(FOLIAGE_VERT_SHADER_PATH), render_ctx)?;
+        let fs = load_from_spv_file(&renderer.ref_assets_dir().join(FRAG_SHADER_PATH), render_ctx)?;
+
+        // the instance transform takes four locations, one for each row.
+        let vertex_input_state = (0..4).fold(
+            VertexInputState::new()
+                .binding(0, VertexInputBindingDescription {
+                    stride: mem::size_of::<Vec3>() as u32,
+                    input_rate: VertexInputRate::Vertex,
+                })
+                .binding(1, VertexInputBindingDescription {
+                    stride: mem::size_of::<Mat4x4>() as u32,
+                    input_rate: VertexInputRate::Instance { divisor: 1 },
+                })
+                .binding(2, VertexInputBindingDescription {
+                    stride: mem::size_of::<Vec4>() as u32,
+                    input_rate: VertexInputRate::Instance { divisor: 1 },
+                })
+                .attribute(0, VertexInputAttributeDescription {
+                    binding: 0,
+                    offset: 0,
+                    format: Format::R32G32B32_SFLOAT,
+                })
+                .attribute(5, VertexInputAttributeDescription {
+                    binding: 2,
+                    offset: 0,
+                    format: Format::R32G32B32A32_SFLOAT,
+                }),
+            |state, row| state.attribute(1 + row, VertexInputAttributeDescription {
+                binding: 1,
+                offset: row * mem::size_of::<Vec4>() as u32,
+                format: Format::R32G32B32A32_SFLOAT,
+            })
+        );
+
+        // the blades are double-sided.
+        let rasterization = RasterizationSettings { cull_mode: CullMode::None, ..self.rasterization };
+        let pipeline = GraphicsPipeline::start()
+            .vertex_input_state(vertex_input_state)
+            .input_assembly_state(InputAssemblyState::new().topology(PrimitiveTopology::TriangleList))
+            .depth_stencil_state(DepthStencilState::simple_depth_test())
+            .rasterization_state(create_rasterization_state(&rasterization, render_ctx)?)
+            .multisample_state(MultisampleState {
+                rasterization_samples: renderer.get_sample_count(),
+                ..Default::default()
+            })
+            .viewport_state(ViewportState::viewport_dynamic_scissor_dynamic(1))
+            .vertex_shader(vs.entry_point("main").unwrap(), ())
+            .fragment_shader(fs.entry_point("main").unwrap(), ())
+            .render_pass(renderer.pipeline_begin_render_pass_type(0).unwrap())
+            .build_with_cache(renderer.ref_pipeline_cache().clone())
+            .build(render_ctx.ref_device().clone())
+            .map_err(|e| err!("Graphics pipeline creation failed: {}", e.to_string()))?;
+        let shader = GraphicsShader::new(
+            pipeline, 
+            render_ctx.ref_descriptor_allocator(), 
+            [uniform_buffer.clone() as _]
+        )?;
+
+        let allocator = render_ctx.get_command_buffer_allocator();
+        let mut command_buffer_builder = AutoCommandBufferBuilder::primary(
+            &allocator, 
+            render_ctx.get_queue_fmaily_index(), 
+            CommandBufferUsage::OneTimeSubmit
+        ).map_err(|e| err!("Primary command buffer begining failed: {}", e.to_string()))?;
+
+        let mut foliage = Foliage::new(
+            &mut StdRng::seed_from_u64(FOLIAGE_SEED), 
+            FOLIAGE_REGION_MIN, 
+            FOLIAGE_REGION_MAX, 
+            FOLIAGE_DENSITY, 
+            &FOLIAGE_BLADE_POSITIONS, 
+            &FOLIAGE_BLADE_INDICES, 
+            shader, 
+            render_ctx, 
+            &mut command_buffer_builder
+        )?;
+        foliage.mat = Mat4x4::from_translation(Vec3::new_vector(0.0, FOLIAGE_HEIGHT, 0.0));
+
+        command_buffer_builder.build()
+            .map_err(|e| err!("Primary command buffer building failed: {}", e.to_string()))?
+            .execute(render_ctx.ref_integrated_queue().clone())
+            .map_err(|e| err!("Primary command buffer execution failed: {}", e.to_string()))?
+            .then_signal_fence_and_flush()
+            .map_err(|e| err!("Primary command buffer flush failed: {}", e.to_string()))?
+            .wait(None)
+            .map_err(|e| err!("Primary command buffer flush failed: {}", e.to_string()))?;
+
+        Ok(Arc::new(foliage))
+    }
+
+    /// create the graphics pipeline of the default shader with the given shader modules.
+    #[inline]
+    fn create_default_pipeline(&self, renderer: &Renderer, vs: &Arc<ShaderModule>, fs: &Arc<ShaderModule>) -> Result<Arc<GraphicsPipeline>, RuntimeError> {
+        self.create_mesh_pipeline(renderer, &DEFAULT_VERTEX_FORMATS, vs, fs)
+    }
+
+    /// create the shader of the meshes with texture coordinates.
+    /// the texture is loaded through the texture cache of the scene, so it counts against the texture budget
+    /// and is not loaded again while it stays in the cache.
+    fn create_textured_shader(
+        &mut self,
+        renderer: &Renderer,
+        uniform_buffer: &Arc<UniformBuffer<CameraData>>,
+        transform_buffer: &Arc<StorageBuffer<Mat4x4>>
+    ) -> Result<Arc<GraphicsShader>, RuntimeError> {
+        let render_ctx = renderer.ref_render_context();
+        let vs = load_from_spv_file(&renderer.ref_assets_dir().join(TEXTURED_VERT_SHADER_PATH), render_ctx)?;
+        let fs = load_from_spv_file(&renderer.ref_assets_dir().join(TEXTURED_FRAG_SHADER_PATH), render_ctx)?;
+        let pipeline = self.create_mesh_pipeline(renderer, &TEXTURED_VERTEX_FORMATS, &vs, &fs)?;
+
+        let allocator = render_ctx.get_command_buffer_allocator();
+        let mut command_buffer_builder = AutoCommandBufferBuilder::primary(
+            &allocator, 
+            render_ctx.get_queue_fmaily_index(), 
+            CommandBufferUsage::OneTimeSubmit
+        ).map_err(|e| err!("Primary command buffer begining failed: {}", e.to_string()))?;
+
+        let texture = self.textures.load_texture(
+            &renderer.ref_assets_dir().join(CHECKER_TEXTURE_PATH), 
+            true, 
+            render_ctx, 
+            &mut command_buffer_builder
+        )?;
+
+        // the command buffer is empty if the texture was already in the cache.
+        command_buffer_builder.build()
+            .map_err(|e| err!("Primary command buffer building failed: {}", e.to_string()))?
+            .execute(render_ctx.ref_integrated_queue().clone())
+            .map_err(|e| err!("Primary command buffer execution failed: {}", e.to_string()))?
+            .then_signal_fence_and_flush()
+            .map_err(|e| err!("Primary command buffer flush failed: {}", e.to_string()))?
+            .wait(None)
+            .map_err(|e| err!("Primary command buffer flush failed: {}", e.to_string()))?;
+
+        GraphicsShader::new(
+            pipeline, 
+            render_ctx.ref_descriptor_allocator(), 
+            [
+                uniform_buffer.clone() as _, 
+                transform_buffer.clone() as _, 
+                texture as _, 
+                TextureSampler::repeat_linear(render_ctx)? as _
+            ]
+        )
+    }
+
+    /// create the shader of the meshes with normals.
+    /// the meshes are shadowed by the cascades of the scene shadows.
+    fn create_lit_shader(
+        &self,
+        renderer: &Renderer,
+        uniform_buffer: &Arc<UniformBuffer<CameraData>>,
+        transform_buffer: &Arc<StorageBuffer<Mat4x4>>,
+        shadows: &SceneShadows
+    ) -> Result<Arc<GraphicsShader>, RuntimeError> {
+        let render_ctx = renderer.ref_render_context();
+        let vs = load_from_spv_file(&renderer.ref_assets_dir().join(LIT_VERT_SHADER_PATH), render_ctx)?;
+        let fs = load_from_spv_file(&renderer.ref_assets_dir().join(LIT_FRAG_SHADER_PATH), render_ctx)?;
+        let pipeline = self.create_mesh_pipeline(renderer, &LIT_VERTEX_FORMATS, &vs, &fs)?;
+
+        GraphicsShader::new(
+            pipeline, 
+            render_ctx.ref_descriptor_allocator(), 
+            [
+                uniform_buffer.clone() as _, 
+                transform_buffer.clone() as _, 
+                shadows.cascade_buffer.clone() as _, 
+                SampledImageView::new(shadows.shadow_map.ref_array_view().clone()) as _, 
+                TextureSampler::shadow_compare(shadows.shadow_map.get_format(), render_ctx)? as _
+            ]
+        )
+    }
+
+    /// create the cascaded shadow map of the scene and a depth shader for each cascade.
+    fn create_shadows(&self, renderer: &Renderer) -> Result<SceneShadows, RuntimeError> {
+        let render_ctx = renderer.ref_render_context();
+        let shadow_map = renderer.create_cascaded_shadow_map(SHADOW_MAP_SIZE, NUM_SHADOW_CASCADES)?;
+        let vs = load_from_spv_file(&renderer.ref_assets_dir().join(SHADOW_VERT_SHADER_PATH), render_ctx)?;
+
+        // the casters are not clipped by the near plane of the cascade if the depth can be clamped.
+        let mut rasterization_state = create_rasterization_state(
+            &RasterizationSettings { depth_bias: Some(SHADOW_DEPTH_BIAS), ..Default::default() }, 
+            render_ctx
+        )?;
+        rasterization_state.depth_clamp_enable = render_ctx.ref_device_enabled_features().depth_clamp;
+
+        // the pipeline only writes the depth, so it has no fragment shader.
+        // every built-in mesh has the positions in its first vertex buffer.
+        let pipeline = GraphicsPipeline::start()
+            .vertex_input_state(create_vertex_input_state(&DEFAULT_VERTEX_FORMATS))
+            .input_assembly_state(InputAssemblyState::new().topology(PrimitiveTopology::TriangleList))
+            .depth_stencil_state(DepthStencilState::simple_depth_test())
+            .rasterization_state(rasterization_state)
+            .viewport_state(ViewportState::viewport_fixed_scissor_irrelevant([shadow_map.get_viewport()]))
+            .vertex_shader(vs.entry_point("main").unwrap(), ())
+            .render_pass(shadow_map.pipeline_render_pass_type())
+            .build_with_cache(renderer.ref_pipeline_cache().clone())
+            .build(render_ctx.ref_device().clone())
+            .map_err(|e| err!("Graphics pipeline creation failed: {}", e.to_string()))?;
+
+        let cascade_buffer = UniformBuffer::from_data(CascadeData::zeroed(), render_ctx.ref_memory_allocator())?;
+        let mut light_buffers = Vec::with_capacity(NUM_SHADOW_CASCADES);
+        let mut shaders = Vec::with_capacity(NUM_SHADOW_CASCADES);
+        for _ in 0..NUM_SHADOW_CASCADES {
+            let light_buffer = UniformBuffer::from_data(Mat4x4::IDENTITY, render_ctx.ref_memory_allocator())?;
+            shaders.push(GraphicsShader::new(
+                pipeline.clone(), 
+                render_ctx.ref_descriptor_allocator(), 
+                [light_buffer.clone() as _]
+            )?);
+            light_buffers.push(light_buffer);
+        }
+
+        Ok(SceneShadows { shadow_map, cascade_buffer, light_buffers, shaders })
+    }
+
+    /// create the passes of the ambient occlusion with targets of the render size.
+    /// the geometry pass reads the camera from the given uniform buffer.
+    fn create_ssao(&self, renderer: &Renderer, uniform_buffer: &Arc<UniformBuffer<CameraData>>) -> Result<SceneSsao, RuntimeError> {
+        let render_ctx = renderer.ref_render_context();
+        let targets = renderer.create_ssao_targets()?;
+        let geometry_vs = load_from_spv_file(&renderer.ref_assets_dir().join(SSAO_GEOMETRY_VERT_SHADER_PATH), render_ctx)?;
+        let geometry_fs = load_from_spv_file(&renderer.ref_assets_dir().join(SSAO_GEOMETRY_FRAG_SHADER_PATH), render_ctx)?;
+        let fullscreen_vs = load_from_spv_file(&renderer.ref_assets_dir().join(FULLSCREEN_VERT_SHADER_PATH), render_ctx)?;
+        let occlusion_fs = load_from_spv_file(&renderer.ref_assets_dir().join(SSAO_FRAG_SHADER_PATH), render_ctx)?;
+        let composite_fs = load_from_spv_file(&renderer.ref_assets_dir().join(SSAO_COMPOSITE_FRAG_SHADER_PATH), render_ctx)?;
+
+        // every built-in mesh has the positions in its first vertex buffer.
+        let geometry_pipeline = GraphicsPipeline::start()
+            .vertex_input_state(create_vertex_input_state(&DEFAULT_VERTEX_FORMATS))
+            .input_assembly_state(InputAssemblyState::new().topology(PrimitiveTopology::TriangleList))
+            .depth_stencil_state(DepthStencilState::simple_depth_test())
+            .viewport_state(ViewportState::viewport_dynamic_scissor_dynamic(1))
+            .vertex_shader(geometry_vs.entry_point("main").unwrap(), ())
+            .fragment_shader(geometry_fs.entry_point("main").unwrap(), ())
+            .render_pass(targets.geometry_render_pass_type())
+            .build_with_cache(renderer.ref_pipeline_cache().clone())
+            .build(render_ctx.ref_device().clone())
+            .map_err(|e| err!("Graphics pipeline creation failed: {}", e.to_string()))?;
+
+        // the fullscreen passes draw a single triangle without vertex buffers.
+        let occlusion_pipeline = GraphicsPipeline::start()
+            .vertex_input_state(VertexInputState::new())
+            .input_assembly_state(InputAssemblyState::new().topology(PrimitiveTopology::TriangleList))
+            .viewport_state(ViewportState::viewport_dynamic_scissor_dynamic(1))
+            .vertex_shader(fullscreen_vs.entry_point("main").unwrap(), ())
+            .fragment_shader(occlusion_fs.entry_point("main").unwrap(), ())
+            .render_pass(targets.occlusion_render_pass_type())
+            .build_with_cache(renderer.ref_pipeline_cache().clone())
+            .build(render_ctx.ref_device().clone())
+            .map_err(|e| err!("Graphics pipeline creation failed: {}", e.to_string()))?;
+
+        // the composite multiplies the color of the scene by the occlusion and keeps its alpha.
+        let composite_pipeline = GraphicsPipeline::start()
+            .vertex_input_state(VertexInputState::new())
+            .input_assembly_state(InputAssemblyState::new().topology(PrimitiveTopology::TriangleList))
+            .multisample_state(MultisampleState {
+                rasterization_samples: renderer.get_sample_count(),
+                ..Default::default()
+            })
+            .color_blend_state(ColorBlendState::new(1).blend(AttachmentBlend {
+                color_op: BlendOp::Add,
+                color_source: BlendFactor::DstColor,
+                color_destination: BlendFactor::Zero,
+                alpha_op: BlendOp::Add,
+                alpha_source: BlendFactor::Zero,
+                alpha_destination: BlendFactor::One,
+            }))
+            .viewport_state(ViewportState::viewport_dynamic_scissor_dynamic(1))
+            .vertex_shader(fullscreen_vs.entry_point("main").unwrap(), ())
+            .fragment_shader(composite_fs.entry_point("main").unwrap(), ())
+            .render_pass(renderer.pipeline_begin_render_pass_type(0).unwrap())
+            .build_with_cache(renderer.ref_pipeline_cache().clone())
+            .build(render_ctx.ref_device().clone())
+            .map_err(|e| err!("Graphics pipeline creation failed: {}", e.to_string()))?;
+
+        let mut rng = rand::thread_rng();
+        let kernel = generate_ssao_kernel(SSAO_KERNEL_SIZE, &mut rng);
+        let noise = generate_ssao_noise(&mut rng);
+        let data_buffer = UniformBuffer::from_data(SsaoData::zeroed(), render_ctx.ref_memory_allocator())?;
+        let geometry_shader = GraphicsShader::new(
+            geometry_pipeline, 
+            render_ctx.ref_descriptor_allocator(), 
+            [uniform_buffer.clone() as _]
+        )?;
+        let (occlusion_shader, composite_shader) = create_ssao_target_shaders(
+            &targets, 
+            &occlusion_pipeline, 
+            &composite_pipeline, 
+            uniform_buffer, 
+            &data_buffer, 
+            render_ctx
+        )?;
+
+        Ok(SceneSsao {
+            targets,
+            kernel,
+            noise,
+            uniform_buffer: uniform_buffer.clone(),
+            data_buffer,
+            occlusion_pipeline,
+            composite_pipeline,
+            geometry_shader,
+            occlusion_shader,
+            composite_shader,
+        })
+    }
+
+    /// create the ambient occlusion passes when it is enabled, remove them when it is disabled,
+    /// and resize their targets with the render target.
+    fn prepare_ssao(&mut self, renderer: &Renderer) -> Result<(), RuntimeError> {
+        match (self.ssao.is_some(), &mut self.ssao_passes, &self.camera) {
+            (false, _, _) => self.ssao_passes = None,
+            (true, Some(ssao_passes), _) => ssao_passes.resize(renderer)?,
+            (true, None, Some(camera)) => self.ssao_passes = Some(self.create_ssao(renderer, &camera.uniform_buffer)?),
+            (true, None, None) => (),
+        }
+        Ok(())
+    }
+
+    /// create a graphics pipeline for the built-in meshes.
+    /// the mesh has a vertex buffer for each of the vertex formats. (see `create_vertex_input_state`)
+    fn create_mesh_pipeline(&self, renderer: &Renderer, vertex_formats: &[Format], vs: &Arc<ShaderModule>, fs: &Arc<ShaderModule>) -> Result<Arc<GraphicsPipeline>, RuntimeError> {
+        GraphicsPipeline::start()
+            .vertex_input_state(create_vertex_input_state(vertex_formats))
+            // the built-in meshes are triangle lists.
+            .input_assembly_state(InputAssemblyState::new().topology(PrimitiveTopology::TriangleList))
+            .depth_stencil_state(DepthStencilState::simple_depth_test())
+            .rasterization_state(create_rasterization_state(&self.rasterization, renderer.ref_render_context())?)
+            .multisample_state(MultisampleState {
+                rasterization_samples: renderer.get_sample_count(),
+                ..Default::default()
+            })
+            .viewport_state(ViewportState::viewport_dynamic_scissor_dynamic(1))
+            .vertex_shader(vs.entry_point("main").unwrap(), ())
+            .fragment_shader(fs.entry_point("main").unwrap(), ())
+            .render_pass(renderer.pipeline_begin_render_pass_type(0).unwrap())
+            .build_with_cache(renderer.ref_pipeline_cache().clone())
+            .build(renderer.ref_render_context().ref_device().clone())
+            .map_err(|e| err!("Graphics pipeline creation failed: {}", e.to_string()))
+    }
+}
+
+impl SceneNode<String> for MainScene {
+    /// returns `true` if the scene assets are loaded and the scene can be updated and drawn.
+    #[inline]
+    fn is_ready(&self) -> bool {
+        self.camera.is_some()
+    }
+
+    fn enter(&mut self, renderer: &Renderer) -> Result<(), RuntimeError> {
+        let (uniform_buffer, transform_buffer, meshes, mut shaders, shader_modules) = self.load_resources(renderer)?;
+        let shadows = self.create_shadows(renderer)?;
+        shaders.insert(ShaderID::Textured, self.create_textured_shader(renderer, &uniform_buffer, &transform_buffer)?);
+        shaders.insert(ShaderID::Lit, self.create_lit_shader(renderer, &uniform_buffer, &transform_buffer, &shadows)?);
+        self.foliage = Some(self.create_foliage(renderer, &uniform_buffer)?);
+        self.transform_buffer = Some(transform_buffer);
+        self.shader_modules = Some(shader_modules);
+        self.shadows = Some(shadows);
+
+        // create a camera object.
+        let mut camera = Camera {
+            mat: Mat4x4::IDENTITY,
+            screen_width: renderer.get_screen_size().0,
+            screen_height: renderer.get_screen_size().1,
+            uniform_buffer: uniform_buffer.clone(),
+            demo_rotation: self.demo_rotation,
+            prev_matrices: None,
+            shake: None,
+            taa_jitter: self.taa_jitter,
+            jitter_index: 0,
+        };
+
+        camera.set_position(Vec3::new_vector(0.0, 0.0, -10.0));
+        camera.set_look_at_point(Vec3::ZERO);
+
+        self.camera = Some(camera);
+
+        // create game objects.
+        self.meshes = meshes.clone();
+        self.shaders = shaders.clone();
+        let max_objects = self.max_objects;
+        let objects = spawn_worker("object-creation".to_string(), move || {
+            create_game_objects(max_objects, |rng| create_rotate_object(rng, &meshes, &shaders))
+        });
+
+        self.object_pool.clear();
+        self.objects = objects.join().unwrap()
+            .into_iter()
+            .map(|object| self.object_pool.acquire(object) as _)
+            .collect();
+        self.object_bounds_dirty = true;
+        self.picked_object = None;
         Ok(())
     }
 
     fn update(&mut self, timer: &Timer, renderer: &Renderer) -> Result<(), RuntimeError> {
+        self.check_ready()?;
+
         let elapsed_time_in_sec = timer.get_elapsed_time_in_sec();
 
         if let Some(camera) = &mut self.camera {
@@ -232,15 +864,28 @@ impl SceneNode<String> for MainScene {
             }
         }
 
+        // with a fixed update rate, the objects are updated zero or more times per frame
+        // and drawn at the blend of the two latest update states.
+        let fixed_update = self.update_rate
+            .map(|hz| advance_update_accumulator(&mut self.update_accumulator, hz, elapsed_time_in_sec));
+
         let num_threads = renderer.get_num_threads();
-        let object_range = MAX_OBJECTS_NUM / num_threads;
         let mut handles = Vec::with_capacity(num_threads);
         for i in 0..renderer.get_num_threads() {
             let objects = self.objects.clone();
             let render_ctx = renderer.ref_render_context().clone();
-            handles.push(thread::spawn(move || -> Result<(), RuntimeError> {
-                for idx in object_range * i..object_range * (i + 1) {
-                    objects[idx].lock().unwrap().update(elapsed_time_in_sec, &render_ctx)?;
+            let range = get_object_range(self.objects.len(), num_threads, i);
+            handles.push(spawn_worker(format!("scene-update-{}", i), move || -> Result<(), RuntimeError> {
+                for idx in range {
+                    let mut object = objects[idx].lock().unwrap();
+                    match fixed_update {
+                        Some((interval, num_updates, alpha)) => {
+                            update_object_at_fixed_rate(&mut *object, interval, num_updates, alpha, |object, elapsed_time_in_sec| {
+                                object.update(elapsed_time_in_sec, &render_ctx)
+                            })?;
+                        },
+                        None => object.update(elapsed_time_in_sec, &render_ctx)?,
+                    }
                 }
 
                 Ok(())
@@ -251,16 +896,67 @@ impl SceneNode<String> for MainScene {
             handle.join().unwrap()?;
         }
 
+        // the objects have moved, so their bounding spheres are collected again on the next pick.
+        self.object_bounds_dirty = true;
+
+        Ok(())
+    }
+
+    /// pick the nearest object under the point where a touch begins.
+    fn touch(&mut self, event: &TouchEvent, renderer: &Renderer) -> Result<(), RuntimeError> {
+        if event.phase != TouchPhase::Began {
+            return Ok(());
+        }
+
+        let (screen_width, screen_height) = renderer.get_screen_size();
+        let ray = match &self.camera {
+            Some(camera) => get_screen_ray(
+                camera.ref_transform(), 
+                event.position.0 * renderer.get_scale_factor(), 
+                event.position.1 * renderer.get_scale_factor(), 
+                screen_width, 
+                screen_height
+            ),
+            None => return Ok(()),
+        };
+        self.picked_object = self.pick_object(&ray).map(|(id, _)| id);
         Ok(())
     }
 
     fn draw(&mut self, renderer: &mut Renderer) -> Result<(), RuntimeError> {
+        self.check_ready()?;
+
+        // skip the frame if nothing has changed.
+        if self.is_frame_skipped() {
+            return Ok(());
+        }
+
         // wait for next frame.
-        let (acquire_future, framebuffer) = match renderer.wait_for_next_frame()? {
+        let frame = match renderer.wait_for_next_frame()? {
             Some(it) => it,
             None => return Ok(())
         };
 
+        // upload the drawn transforms of all objects at once to the region of the acquired frame,
+        // so the regions of the other frames in flight are not overwritten while the GPU reads them.
+        // each object draws with its index in the buffer as the first instance.
+        if let Some(transform_buffer) = &self.transform_buffer {
+            let first = get_transform_region_start(
+                renderer.get_current_frame(), 
+                transform_buffer.get_capacity(), 
+                self.max_objects
+            );
+            let transforms: Vec<Mat4x4> = self.objects.iter()
+                .enumerate()
+                .map(|(index, object)| {
+                    let mut object = object.lock().unwrap();
+                    object.set_instance_index((first + index) as u32);
+                    object.get_draw_transform()
+                })
+                .collect();
+            transform_buffer.write_at(first, &transforms)?;
+        }
+
         // create a primary command buffer.
         let render_ctx = renderer.ref_render_context().clone();
         let allocator = render_ctx.get_command_buffer_allocator();
@@ -270,14 +966,38 @@ impl SceneNode<String> for MainScene {
             CommandBufferUsage::OneTimeSubmit
         ).map_err(|e| err!("Command buffer begining failed: {}", e.to_string()))?;
 
+        // the ambient occlusion passes read the present area of the render target.
+        self.prepare_ssao(renderer)?;
+        let present_area = renderer.get_present_viewport().zip(renderer.get_present_scissor());
+
+        // render the shadow cascades of the camera before the main pass samples them.
+        if let (Some(shadows), Some(camera)) = (&self.shadows, &self.camera) {
+            shadows.write_cascades(&camera.get_frustum(SHADOW_DISTANCE));
+            shadows.record_passes(&self.objects, &mut command_buffer_builder)?;
+        }
+
+        // estimate the ambient occlusion of the objects before the main pass composites it.
+        if let (Some(ssao), Some(settings), Some((viewport, scissor))) = (&self.ssao_passes, &self.ssao, &present_area) {
+            ssao.write_data(settings, viewport);
+            ssao.record_passes(&self.objects, viewport, scissor, &mut command_buffer_builder)?;
+        }
+
         // begin render pass.
+        // with a present region, the drawable is cleared to transparent and only the region is cleared to the background.
+        // nothing is drawn if the present region lies outside the render target.
+        let present_region = renderer.get_present_region().and(renderer.get_present_scissor());
+        let mut clear_values = match renderer.get_present_region() {
+            Some(_) => vec![Some(ClearValue::Float([0.0, 0.0, 0.0, 0.0]))],
+            None => vec![Some(ClearValue::Float(BACKGROUND_COLOR))],
+        };
+        clear_values.resize(renderer.get_num_color_attachments(), Some(ClearValue::Float([0.0, 0.0, 0.0, 0.0])));
+        clear_values.push(Some(ClearValue::DepthStencil((1.0, 0))));
+        // the resolve attachments are not cleared.
+        clear_values.resize(renderer.get_num_attachments(), None);
         command_buffer_builder.begin_render_pass(
             RenderPassBeginInfo {
-                clear_values: vec![
-                    Some(ClearValue::Float([1.0, 1.0, 1.0, 1.0])),
-                    Some(ClearValue::DepthStencil((1.0, 0)))
-                ],
-                ..RenderPassBeginInfo::framebuffer(framebuffer.clone())
+                clear_values,
+                ..RenderPassBeginInfo::framebuffer(frame.ref_framebuffer().clone())
             }, 
             SubpassContents::SecondaryCommandBuffers
         ).map_err(|e| err!("Render pass begining failed: {}", e.to_string()))?;
@@ -285,8 +1005,8 @@ impl SceneNode<String> for MainScene {
             render_pass: Some(
                 CommandBufferInheritanceRenderPassType::BeginRenderPass(
                     CommandBufferInheritanceRenderPassInfo {
-                        framebuffer: Some(framebuffer.clone()),
-                        subpass: Subpass::from(framebuffer.render_pass().clone(), 0).unwrap()
+                        framebuffer: Some(frame.ref_framebuffer().clone()),
+                        subpass: Subpass::from(frame.ref_framebuffer().render_pass().clone(), 0).unwrap()
                     }
                 )
             ),
@@ -295,15 +1015,17 @@ impl SceneNode<String> for MainScene {
 
         // muti-thread rendering
         let num_threads = renderer.get_num_threads();
-        let object_range = MAX_OBJECTS_NUM / num_threads;
         let mut handles = Vec::with_capacity(num_threads);
         for i in 0..renderer.get_num_threads() {
-            let screen_size = renderer.get_screen_size();
+            let range = get_object_range(self.objects.len(), num_threads, i);
+            let present_area = present_area.clone();
+            let frame_index = renderer.get_current_frame();
             let render_ctx = renderer.ref_render_context().clone();
             // let jobs_cp = jobs.clone();
             let objects = self.objects.clone();
+            let foliage = self.foliage.clone();
             let inheritance_info_cp = inheritance_info.clone();
-            handles.push(thread::spawn(move || -> Result<SecondaryAutoCommandBuffer, RuntimeError> {
+            handles.push(spawn_worker(format!("scene-draw-{}", i), move || -> Result<SecondaryAutoCommandBuffer, RuntimeError> {
                 let allocator = render_ctx.get_command_buffer_allocator();
                 let mut command_buffer_builder = AutoCommandBufferBuilder::secondary(
                     &allocator, 
@@ -312,15 +1034,32 @@ impl SceneNode<String> for MainScene {
                     inheritance_info_cp,
                 ).map_err(|e| err!("Secondary command buffer begining failed: {}", e.to_string()))?;
 
-                // set viewport
-                command_buffer_builder.set_viewport(0, [Viewport {
-                    origin: [0.0, 0.0],
-                    dimensions: [screen_size.0 as f32, screen_size.1 as f32],
-                    depth_range: (0.0..1.0)
-                }]);
+                // clear the present region to the background.
+                if let (0, Some(region)) = (i, present_region) {
+                    command_buffer_builder.clear_attachments(
+                        [ClearAttachment::Color { color_attachment: 0, clear_value: ClearColorValue::Float(BACKGROUND_COLOR) }],
+                        [ClearRect { offset: region.origin, extent: region.dimensions, array_layers: 0..1 }]
+                    ).map_err(|e| err!("Present region clear failed: {}", e.to_string()))?;
+                }
 
-                for idx in object_range * i..object_range * (i + 1) {
-                    objects[idx].lock().unwrap().darw(&render_ctx, &mut command_buffer_builder)?;
+                // set viewport and scissor.
+                if let Some((viewport, scissor)) = present_area {
+                    command_buffer_builder
+                        .set_viewport(0, [viewport])
+                        .set_scissor(0, [scissor]);
+
+                    record_object_draws(
+                        &objects[range], 
+                        &render_ctx, 
+                        &mut command_buffer_builder, 
+                        |object, render_ctx| object.upload_uniforms(render_ctx, frame_index), 
+                        |object, render_ctx, builder| object.darw(render_ctx, builder)
+                    )?;
+
+                    // the foliage is a single instanced draw call, recorded by the first thread.
+                    if let (0, Some(foliage)) = (i, &foliage) {
+                        foliage.darw(&render_ctx, &mut command_buffer_builder)?;
+                    }
                 }
 
                 Ok(command_buffer_builder
@@ -334,6 +1073,11 @@ impl SceneNode<String> for MainScene {
             command_buffers.push(handle.join().unwrap()?);
         }
 
+        // the occlusion darkens everything drawn before, so it is composited last.
+        if let (Some(ssao), Some((viewport, scissor))) = (&self.ssao_passes, &present_area) {
+            command_buffers.push(ssao.record_composite(&render_ctx, inheritance_info, viewport, scissor)?);
+        }
+
         // command buffer building.
         command_buffer_builder.execute_commands_from_vec(command_buffers)
             .map_err(|e| err!("Primary command buffer execution failed: {}", e.to_string()))?
@@ -344,84 +1088,628 @@ impl SceneNode<String> for MainScene {
             .map_err(|e| err!("Primary command buffer building failed: {}", e.to_string()))?;
 
         // queue submit and present.
-        renderer.queue_submit_and_present(acquire_future, command_buffer)?;
+        let damaged_regions = mem::take(&mut self.damaged_regions);
+        renderer.queue_submit_and_present_regions(frame, command_buffer, &damaged_regions)?;
         Ok(())
     }
-}
 
-impl fmt::Debug for MainScene {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_struct("MainScene").finish()
+    /// set the color of the object with the given id. (the index of the object)
+    /// the color components are clamped to `[0, 1]`.
+    fn set_object_color(&mut self, id: usize, color: Vec4) -> Result<(), RuntimeError> {
+        match self.try_get_object(id)?.lock().unwrap().set_color(color.map(|c| c.clamp(0.0, 1.0))) {
+            true => Ok(()),
+            false => Err(err!("The object has no color. (object id: {})", id)),
+        }
     }
-}
 
+    /// spawn a random object from the object pool.
+    /// returns `None` if the scene is not ready or already has the maximum number of objects.
+    fn spawn_object(&mut self) -> Option<usize> {
+        if !self.is_ready() || self.objects.len() >= self.max_objects {
+            return None;
+        }
 
-#[inline]
-fn create_game_objects(
-    meshes: HashMap<MeshID, Arc<Mesh>>, 
-    shaders: HashMap<ShaderID, Arc<GraphicsShader>>
-) -> Vec<Arc<Mutex<dyn WorldObject>>> {
-    let mut rng = thread_rng();
-    let mut objects = Vec::with_capacity(MAX_OBJECTS_NUM);
-    for _ in 0..MAX_OBJECTS_NUM {
-        let position = Vec3::new_vector(
-            rng.gen_range(-100.0..=100.0),
-            rng.gen_range(-100.0..=100.0),
-            rng.gen_range(-100.0..=100.0)
-        );
+        let object = create_rotate_object(&mut thread_rng(), &self.meshes, &self.shaders);
+        let id = self.objects.len();
+        if let Some(sphere) = object.get_bounding_sphere() {
+            self.bvh.insert(id, sphere);
+        }
+        self.objects.push(self.object_pool.acquire(object) as _);
+        Some(id)
+    }
 
-        let axis = Vec3::new_vector(
-            rng.gen_range(-1.0..=1.0), 
-            rng.gen_range(-1.0..=1.0), 
-            rng.gen_range(-1.0..=1.0)
-        ).normalize();
+    /// despawn the object with the given id and return it to the object pool.
+    /// the last object takes the id of the despawned object.
+    /// returns `false` if the object does not exist.
+    fn despawn_object(&mut self, id: usize) -> bool {
+        if id >= self.objects.len() {
+            return false;
+        }
 
-        let speed: f32 = rng.gen_range(-1.0..=1.0);
+        let object = self.objects.swap_remove(id);
+        self.object_pool.release(object);
 
-        let color = Vec4::new_vector(
-            rng.gen_range(0.0..=1.0),
-            rng.gen_range(0.0..=1.0),
-            rng.gen_range(0.0..=1.0),
-            rng.gen_range(0.0..=1.0),
-        );
+        // the last object takes the id of the despawned object in the hierarchy too.
+        let last_id = self.objects.len();
+        self.bvh.remove(&id);
+        if id < last_id {
+            self.bvh.remove(&last_id);
+            if let Some(sphere) = self.objects[id].lock().unwrap().get_bounding_sphere() {
+                self.bvh.insert(id, sphere);
+            }
+        }
+        self.picked_object = match self.picked_object {
+            Some(picked) if picked == id => None,
+            Some(picked) if picked == last_id => Some(id),
+            picked => picked,
+        };
+        true
+    }
 
-        let q = Quat::from_angle_axis(0.0, axis);
-        let mut mat = q.normalize().into_matrix4x4();
-        mat.r4c1 = position.x;
-        mat.r4c2 = position.y;
-        mat.r4c3 = position.z;
+    #[inline]
+    fn get_object_pool_stats(&self) -> Option<ObjectPoolStats> {
+        Some(self.object_pool.get_stats())
+    }
 
-        let mesh = meshes.get(&rand::random()).unwrap().clone();
-        let shader = shaders.get(&rand::random()).unwrap().clone();
-        let model_node = ModelNode {
-            id: "Root".to_string(),
-            transform: Mat4x4::IDENTITY,
-            world_matrix: mat,
-            mesh: Some(mesh),
-            shader: Some(shader),
-            parent: None,
-            sibling: None,
-            child: None
+    /// describe the objects and the camera pose of the scene.
+    fn debug_dump(&self) -> String {
+        get_debug_dump(
+            self.objects.len(), 
+            self.max_objects, 
+            self.camera.as_ref().map(|camera| (camera.get_position(), camera.get_look_vector())), 
+            self.update_rate, 
+            &self.rasterization,
+            self.picked_object
+        )
+    }
+
+    /// shake the camera with the given intensity for the given duration.
+    /// returns `false` if there is no camera.
+    fn trigger_camera_shake(&mut self, intensity: f32, duration_in_sec: f32) -> bool {
+        match &mut self.camera {
+            Some(camera) => {
+                camera.shake = Some(CameraShake::new(intensity, duration_in_sec));
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// set the animation speed of the object with the given id. (the index of the object)
+    fn set_object_speed(&mut self, id: usize, speed: f32) -> Result<(), RuntimeError> {
+        match self.try_get_object(id)?.lock().unwrap().set_speed(speed) {
+            true => Ok(()),
+            false => Err(err!("The object is not animated. (object id: {})", id)),
+        }
+    }
+
+    /// reload the meshes and shaders on the recreated renderer and rebind them to the objects.
+    /// the object transforms, colors and the camera are kept.
+    fn recreate_resources(&mut self, renderer: &Renderer) -> Result<(), RuntimeError> {
+        if !self.is_ready() {
+            return Ok(());
+        }
+
+        // the textures belong to the old device and are loaded again.
+        self.textures.clear();
+        let (uniform_buffer, transform_buffer, meshes, mut shaders, shader_modules) = self.load_resources(renderer)?;
+        let shadows = self.create_shadows(renderer)?;
+        shaders.insert(ShaderID::Textured, self.create_textured_shader(renderer, &uniform_buffer, &transform_buffer)?);
+        shaders.insert(ShaderID::Lit, self.create_lit_shader(renderer, &uniform_buffer, &transform_buffer, &shadows)?);
+        let mesh_map = get_recreated_resource_map(&self.meshes, &meshes);
+        let shader_map = get_recreated_resource_map(&self.shaders, &shaders);
+
+        for object in self.objects.iter() {
+            object.lock().unwrap().rebind_resources(
+                &|mesh| mesh_map.get(&Arc::as_ptr(mesh)).cloned(),
+                &|shader| shader_map.get(&Arc::as_ptr(shader)).cloned(),
+            );
+        }
+
+        self.foliage = Some(self.create_foliage(renderer, &uniform_buffer)?);
+        if let Some(camera) = &mut self.camera {
+            camera.uniform_buffer = uniform_buffer;
+        }
+
+        self.transform_buffer = Some(transform_buffer);
+        self.meshes = meshes;
+        self.shaders = shaders;
+        self.shader_modules = Some(shader_modules);
+        self.shadows = Some(shadows);
+        // the ambient occlusion passes belong to the old device and are created again by the next draw.
+        self.ssao_passes = None;
+        Ok(())
+    }
+
+    /// rebuild the default pipeline if its vertex or fragment shader was reloaded and rebind it to the objects.
+    fn reload_shaders(&mut self, reloaded: &[PathBuf], renderer: &Renderer) -> Result<(), RuntimeError> {
+        let (vs, fs) = match &self.shader_modules {
+            Some(shader_modules) if self.is_ready() => shader_modules.clone(),
+            _ => return Ok(()),
         };
-        let model = Model::from_nodes(
-            "Unknown",
-            "Root".to_string(),
-            [model_node]
-        ).unwrap();
 
-        objects.push(match rand::random() {
-            SystemID::Rotation => { 
-                Arc::new(Mutex::new(RotateObject {
-                    mat,
-                    color,
-                    axis,
-                    speed,
-                    model
-                })) as _
+        let assets_dir = renderer.ref_assets_dir();
+        let is_reloaded = |path: &str| reloaded.contains(&assets_dir.join(path));
+        if !is_reloaded(VERT_SHADER_PATH) && !is_reloaded(FRAG_SHADER_PATH) {
+            return Ok(());
+        }
+
+        let vs = renderer.get_reloaded_shader(Path::new(VERT_SHADER_PATH)).unwrap_or(vs);
+        let fs = renderer.get_reloaded_shader(Path::new(FRAG_SHADER_PATH)).unwrap_or(fs);
+        let pipeline = self.create_default_pipeline(renderer, &vs, &fs)?;
+        let default_shader = GraphicsShader::new(
+            pipeline, 
+            renderer.ref_render_context().ref_descriptor_allocator(), 
+            [self.camera.as_ref().unwrap().uniform_buffer.clone() as _, self.transform_buffer.clone().unwrap() as _]
+        )?;
+
+        // the other shaders do not use the reloaded modules and are kept.
+        let shader_map: HashMap<_, _> = self.shaders.get(&ShaderID::Default)
+            .map(|shader| (Arc::as_ptr(shader), default_shader.clone()))
+            .into_iter()
+            .collect();
+        for object in self.objects.iter() {
+            object.lock().unwrap().rebind_resources(
+                &|_| None,
+                &|shader| shader_map.get(&Arc::as_ptr(shader)).cloned(),
+            );
+        }
+
+        self.shaders.insert(ShaderID::Default, default_shader);
+        self.shader_modules = Some((vs, fs));
+        Ok(())
+    }
+}
+
+/// the cascaded shadow map of the scene and the resources of its passes.
+struct SceneShadows {
+    shadow_map: CascadedShadowMap,
+    /// the cascades of the frame. the lit shader reads them to sample the shadow map.
+    cascade_buffer: Arc<UniformBuffer<CascadeData>>,
+    /// the light view-projection matrix of each cascade.
+    light_buffers: Vec<Arc<UniformBuffer<Mat4x4>>>,
+    /// the depth shader of each cascade. it reads the matrix of the cascade from the light buffer.
+    shaders: Vec<Arc<GraphicsShader>>,
+}
+
+impl SceneShadows {
+    /// fit the cascades to the given camera frustum and write them to the buffers.
+    fn write_cascades(&self, frustum: &CameraFrustum) {
+        let data = compute_cascade_data(frustum, LIGHT_DIRECTION, self.shadow_map.get_num_cascades(), CASCADE_SPLIT_LAMBDA);
+        self.cascade_buffer.write_data_if_changed(data);
+        for (light_buffer, view_projection) in self.light_buffers.iter().zip(data.view_projection) {
+            light_buffer.write_data_if_changed(view_projection);
+        }
+    }
+
+    /// record a depth pass of the objects for each cascade.
+    fn record_passes(
+        &self, 
+        objects: &[Arc<Mutex<dyn WorldObject>>], 
+        command_buffer_builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>
+    ) -> Result<(), RuntimeError> {
+        for (cascade, shader) in self.shaders.iter().enumerate() {
+            let framebuffer = self.shadow_map.ref_framebuffer(cascade).unwrap();
+            command_buffer_builder.begin_render_pass(
+                RenderPassBeginInfo {
+                    clear_values: vec![Some(ClearValue::Depth(1.0))],
+                    ..RenderPassBeginInfo::framebuffer(framebuffer.clone())
+                }, 
+                SubpassContents::Inline
+            ).map_err(|e| err!("Shadow pass begining failed: {}", e.to_string()))?;
+
+            for object in objects.iter() {
+                object.lock().unwrap().draw_depth(shader, command_buffer_builder)?;
             }
-        });
+
+            command_buffer_builder.end_render_pass()
+                .map_err(|e| err!("Shadow pass recording failed: {}", e.to_string()))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// the passes of the screen-space ambient occlusion.
+/// the geometry pass writes the view normal and depth of the objects, the occlusion pass estimates the occlusion from them,
+/// and the composite blurs the occlusion and multiplies the color of the main pass by it.
+struct SceneSsao {
+    targets: SsaoTargets,
+    kernel: Vec<Vec3>,
+    noise: Vec<Vec3>,
+    uniform_buffer: Arc<UniformBuffer<CameraData>>,
+    /// the kernel, the noise, the settings and the viewport of the occlusion pass.
+    data_buffer: Arc<UniformBuffer<SsaoData>>,
+    occlusion_pipeline: Arc<GraphicsPipeline>,
+    composite_pipeline: Arc<GraphicsPipeline>,
+    geometry_shader: Arc<GraphicsShader>,
+    /// the shaders that sample the targets. they are created again when the targets are resized.
+    occlusion_shader: Arc<GraphicsShader>,
+    composite_shader: Arc<GraphicsShader>,
+}
+
+impl SceneSsao {
+    /// recreate the targets and the shaders that sample them if the render size has changed.
+    fn resize(&mut self, renderer: &Renderer) -> Result<(), RuntimeError> {
+        let (width, height) = renderer.get_render_size();
+        if self.targets.get_size() == (width, height) {
+            return Ok(());
+        }
+
+        self.targets.recreate(width, height)?;
+        (self.occlusion_shader, self.composite_shader) = create_ssao_target_shaders(
+            &self.targets, 
+            &self.occlusion_pipeline, 
+            &self.composite_pipeline, 
+            &self.uniform_buffer, 
+            &self.data_buffer, 
+            renderer.ref_render_context()
+        )?;
+        Ok(())
+    }
+
+    /// write the settings and the viewport of the frame to the buffer of the occlusion pass.
+    fn write_data(&self, settings: &SsaoSettings, viewport: &Viewport) {
+        let viewport = Vec4::new_vector(viewport.origin[0], viewport.origin[1], viewport.dimensions[0], viewport.dimensions[1]);
+        self.data_buffer.write_data_if_changed(SsaoData::new(&self.kernel, &self.noise, settings, viewport));
+    }
+
+    /// record the geometry pass of the objects and the occlusion pass in the given area.
+    fn record_passes(
+        &self, 
+        objects: &[Arc<Mutex<dyn WorldObject>>], 
+        viewport: &Viewport, 
+        scissor: &Scissor, 
+        command_buffer_builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>
+    ) -> Result<(), RuntimeError> {
+        // the pixels without objects keep a zero depth, so they are not occluded.
+        command_buffer_builder.begin_render_pass(
+            RenderPassBeginInfo {
+                clear_values: vec![Some(ClearValue::Float([0.0, 0.0, 0.0, 0.0])), Some(ClearValue::DepthStencil((1.0, 0)))],
+                ..RenderPassBeginInfo::framebuffer(self.targets.ref_geometry_framebuffer().clone())
+            }, 
+            SubpassContents::Inline
+        ).map_err(|e| err!("Ambient occlusion geometry pass begining failed: {}", e.to_string()))?;
+        command_buffer_builder
+            .set_viewport(0, [viewport.clone()])
+            .set_scissor(0, [scissor.clone()]);
+
+        for object in objects.iter() {
+            object.lock().unwrap().draw_depth(&self.geometry_shader, command_buffer_builder)?;
+        }
+
+        command_buffer_builder.end_render_pass()
+            .map_err(|e| err!("Ambient occlusion geometry pass recording failed: {}", e.to_string()))?;
+
+        command_buffer_builder.begin_render_pass(
+            RenderPassBeginInfo {
+                clear_values: vec![Some(ClearValue::Float([1.0, 1.0, 1.0, 1.0]))],
+                ..RenderPassBeginInfo::framebuffer(self.targets.ref_occlusion_framebuffer().clone())
+            }, 
+            SubpassContents::Inline
+        ).map_err(|e| err!("Ambient occlusion pass begining failed: {}", e.to_string()))?;
+        command_buffer_builder
+            .set_viewport(0, [viewport.clone()])
+            .set_scissor(0, [scissor.clone()]);
+
+        unsafe {
+            self.occlusion_shader.bind_pipeline(command_buffer_builder);
+            self.occlusion_shader.bind_descriptor_set(command_buffer_builder);
+        }
+        command_buffer_builder.draw(3, 1, 0, 0)
+            .map_err(|e| err!("Ambient occlusion pass recording failed: {}", e.to_string()))?
+            .end_render_pass()
+            .map_err(|e| err!("Ambient occlusion pass recording failed: {}", e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// record the composite of the occlusion into a secondary command buffer of the main pass.
+    fn record_composite(
+        &self, 
+        render_ctx: &Arc<RenderContext>, 
+        inheritance_info: CommandBufferInheritanceInfo, 
+        viewport: &Viewport, 
+        scissor: &Scissor
+    ) -> Result<SecondaryAutoCommandBuffer, RuntimeError> {
+        let allocator = render_ctx.get_command_buffer_allocator();
+        let mut command_buffer_builder = AutoCommandBufferBuilder::secondary(
+            &allocator, 
+            render_ctx.get_queue_fmaily_index(), 
+            CommandBufferUsage::OneTimeSubmit, 
+            inheritance_info,
+        ).map_err(|e| err!("Secondary command buffer begining failed: {}", e.to_string()))?;
+
+        command_buffer_builder
+            .set_viewport(0, [viewport.clone()])
+            .set_scissor(0, [scissor.clone()]);
+
+        unsafe {
+            self.composite_shader.bind_pipeline(&mut command_buffer_builder);
+            self.composite_shader.bind_descriptor_set(&mut command_buffer_builder);
+        }
+        command_buffer_builder.draw(3, 1, 0, 0)
+            .map_err(|e| err!("Ambient occlusion composite recording failed: {}", e.to_string()))?;
+
+        command_buffer_builder
+            .build()
+            .map_err(|e| err!("Secondary command buffer building failed: {}", e.to_string()))
+    }
+}
+
+impl fmt::Debug for MainScene {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MainScene").finish()
+    }
+}
+
+
+/// create the occlusion shader that samples the geometry target and the composite shader that samples the occlusion target.
+fn create_ssao_target_shaders(
+    targets: &SsaoTargets,
+    occlusion_pipeline: &Arc<GraphicsPipeline>,
+    composite_pipeline: &Arc<GraphicsPipeline>,
+    uniform_buffer: &Arc<UniformBuffer<CameraData>>,
+    data_buffer: &Arc<UniformBuffer<SsaoData>>,
+    render_ctx: &Arc<RenderContext>,
+) -> Result<(Arc<GraphicsShader>, Arc<GraphicsShader>), RuntimeError> {
+    let sampler = TextureSampler::clamp_nearest(render_ctx)?;
+    let occlusion_shader = GraphicsShader::new(
+        occlusion_pipeline.clone(), 
+        render_ctx.ref_descriptor_allocator(), 
+        [
+            uniform_buffer.clone() as _, 
+            data_buffer.clone() as _, 
+            SampledImageView::new(targets.ref_geometry_view().clone()) as _, 
+            sampler.clone() as _
+        ]
+    )?;
+    let composite_shader = GraphicsShader::new(
+        composite_pipeline.clone(), 
+        render_ctx.ref_descriptor_allocator(), 
+        [
+            SampledImageView::new(targets.ref_occlusion_view().clone()) as _, 
+            sampler as _
+        ]
+    )?;
+    Ok((occlusion_shader, composite_shader))
+}
+
+
+/// return the vertex input state of a mesh with a vertex buffer for each vertex format.
+/// the attribute of binding `i` is read from location `i`.
+///
+/// # Panics
+/// - Panics if a format has no block size.
+fn create_vertex_input_state(vertex_formats: &[Format]) -> VertexInputState {
+    vertex_formats.iter()
+        .zip(0..)
+        .fold(VertexInputState::new(), |state, (&format, index)| state
+            .binding(index, VertexInputBindingDescription {
+                stride: format.block_size().expect("the vertex format must have a block size.") as u32,
+                input_rate: VertexInputRate::Vertex,
+            })
+            .attribute(index, VertexInputAttributeDescription {
+                binding: index,
+                offset: 0,
+                format,
+            })
+        )
+}
+
+
+/// return the range of objects handled by the `index`-th thread.
+/// the last thread also takes the remaining objects.
+#[inline]
+fn get_object_range(num_objects: usize, num_threads: usize, index: usize) -> std::ops::Range<usize> {
+    let object_range = num_objects / num_threads;
+    let start = object_range * index;
+    let end = match index + 1 == num_threads {
+        true => num_objects,
+        false => object_range * (index + 1),
+    };
+    start..end
+}
+
+
+/// get the first element of the transform buffer region of the given swapchain image.
+/// the buffer has `capacity / max_objects` regions, and frames beyond them wrap around.
+#[inline]
+fn get_transform_region_start(frame_index: u32, capacity: usize, max_objects: usize) -> usize {
+    let num_regions = (capacity / max_objects).max(1);
+    (frame_index as usize % num_regions) * max_objects
+}
+
+
+/// add the elapsed time to the update accumulator and return the update interval,
+/// the number of fixed updates to run this frame and the blend factor of the two latest update states.
+#[inline]
+fn advance_update_accumulator(accumulator: &mut f32, hz: f32, elapsed_time_in_sec: f32) -> (f32, u32, f32) {
+    let interval = 1.0 / hz;
+    *accumulator += elapsed_time_in_sec;
+    let num_updates = ((*accumulator / interval) as u32).min(MAX_FIXED_UPDATES_PER_FRAME);
+    *accumulator = (*accumulator - num_updates as f32 * interval).min(interval);
+    (interval, num_updates, *accumulator / interval)
+}
+
+
+/// run the fixed updates of the object, then blend its drawn transform between the two latest update states.
+#[inline]
+fn update_object_at_fixed_rate<O: WorldObject + ?Sized>(
+    object: &mut O,
+    interval: f32,
+    num_updates: u32,
+    alpha: f32,
+    mut update: impl FnMut(&mut O, f32) -> Result<(), RuntimeError>
+) -> Result<(), RuntimeError> {
+    for _ in 0..num_updates {
+        object.store_previous_transform();
+        update(object, interval)?;
+    }
+    object.interpolate_transform(alpha);
+    Ok(())
+}
+
+
+/// describe the objects, the camera pose (position and look vector) and the settings of the scene.
+#[inline]
+fn get_debug_dump(
+    num_objects: usize,
+    max_objects: usize,
+    camera_pose: Option<(Vec3, Vec3)>,
+    update_rate: Option<f32>,
+    rasterization: &RasterizationSettings,
+    picked_object: Option<usize>
+) -> String {
+    let mut dump = format!("objects: {} (max: {})\n", num_objects, max_objects);
+    match camera_pose {
+        Some((position, look)) => {
+            dump += &format!("camera position: {}\n", position);
+            dump += &format!("camera look: {}\n", look);
+        },
+        None => dump += "camera: none\n",
+    }
+    dump += &format!("update rate: {:?}\n", update_rate);
+    dump += &format!("rasterization: {:?}\n", rasterization);
+    dump += &format!("picked object: {:?}\n", picked_object);
+    dump
+}
+
+
+/// return the ray from the camera through the point on the screen. (pixel)
+/// the point is mapped to the view space the same way the projection of the camera maps the view space to the screen.
+#[inline]
+fn get_screen_ray(camera_mat: &Mat4x4, x: f32, y: f32, screen_width: u32, screen_height: u32) -> Ray {
+    let tan_half_fovy = (Camera::FOV_Y * 0.5).tan();
+    let aspect = screen_width as f32 / screen_height as f32;
+    let ndc_x = 2.0 * x / screen_width as f32 - 1.0;
+    let ndc_y = 2.0 * y / screen_height as f32 - 1.0;
+
+    let axis = |row: usize| {
+        let v = camera_mat.row(row);
+        Vec3::new_vector(v.x, v.y, v.z)
+    };
+    let direction = axis(2) + axis(0) * (ndc_x * tan_half_fovy * aspect) + axis(1) * (ndc_y * tan_half_fovy);
+    Ray::new(axis(3), direction)
+}
+
+
+/// upload the uniforms of each object, then record its draw commands.
+/// each object is locked once, so its uniforms match the recorded draw.
+#[inline]
+fn record_object_draws<O: ?Sized, C, B>(
+    objects: &[Arc<Mutex<O>>],
+    render_ctx: &C,
+    command_buffer_builder: &mut B,
+    upload_uniforms: impl Fn(&O, &C) -> Result<(), RuntimeError>,
+    draw: impl Fn(&O, &C, &mut B) -> Result<(), RuntimeError>
+) -> Result<(), RuntimeError> {
+    for object in objects.iter() {
+        let object = object.lock().unwrap();
+        upload_uniforms(&object, render_ctx)?;
+        draw(&object, render_ctx, command_buffer_builder)?;
+    }
+    Ok(())
+}
+
+
+#[inline]
+fn create_game_objects<O>(
+    num_objects: usize,
+    mut create_object: impl FnMut(&mut ThreadRng) -> O
+) -> Vec<O> {
+    let mut rng = thread_rng();
+    (0..num_objects)
+        .map(|_| create_object(&mut rng))
+        .collect()
+}
+
+
+/// create an object with a random position, rotation axis, speed, color, mesh and shader.
+fn create_rotate_object(
+    rng: &mut impl Rng,
+    meshes: &HashMap<MeshID, Arc<Mesh>>, 
+    shaders: &HashMap<ShaderID, Arc<GraphicsShader>>
+) -> RotateObject {
+    let position = Vec3::new_vector(
+        rng.gen_range(-100.0..=100.0),
+        rng.gen_range(-100.0..=100.0),
+        rng.gen_range(-100.0..=100.0)
+    );
+
+    let axis = Vec3::new_vector(
+        rng.gen_range(-1.0..=1.0), 
+        rng.gen_range(-1.0..=1.0), 
+        rng.gen_range(-1.0..=1.0)
+    ).normalize();
+
+    let speed: f32 = rng.gen_range(-1.0..=1.0);
+
+    let color = Vec4::new_vector(
+        rng.gen_range(0.0..=1.0),
+        rng.gen_range(0.0..=1.0),
+        rng.gen_range(0.0..=1.0),
+        rng.gen_range(0.0..=1.0),
+    );
+
+    let q = Quat::from_angle_axis(0.0, axis);
+    let mut mat = q.normalize().into_matrix4x4();
+    mat.r4c1 = position.x;
+    mat.r4c2 = position.y;
+    mat.r4c3 = position.z;
+
+    let mesh_id: MeshID = rng.gen();
+    let mesh = meshes.get(&mesh_id).unwrap().clone();
+    let shader = shaders.get(&mesh_id.get_shader_id()).unwrap().clone();
+    let model_node = ModelNode {
+        id: "Root".to_string(),
+        transform: Mat4x4::IDENTITY,
+        world_matrix: mat,
+        mesh: Some(mesh),
+        shader: Some(shader),
+        parent: None,
+        sibling: None,
+        child: None
+    };
+    let model = Model::from_nodes(
+        "Unknown",
+        "Root".to_string(),
+        [model_node]
+    ).unwrap();
+
+    match rng.gen() {
+        SystemID::Rotation => RotateObject {
+            mat,
+            prev_mat: mat,
+            color,
+            axis,
+            speed,
+            model,
+            shader_override: None,
+            instance_index: 0,
+        }
     }
-    return objects;
+}
+
+
+/// return the map from each old resource to the recreated resource with the same id.
+/// the objects hold the resources, so they are looked up by pointer when rebinding.
+///
+/// # Panics
+/// - Panics if a resource was not recreated.
+#[inline]
+fn get_recreated_resource_map<K, T>(old: &HashMap<K, Arc<T>>, new: &HashMap<K, Arc<T>>) -> HashMap<*const T, Arc<T>>
+where K: Eq + Hash {
+    old.iter()
+        .map(|(id, resource)| (Arc::as_ptr(resource), new[id].clone()))
+        .collect()
+}
+
+
+/// return `true` if the indices form a triangle list and every index is less than the vertex count.
+#[inline]
+fn is_valid_triangle_list(indices: &[u16], vertex_count: usize) -> bool {
+    indices.len() % 3 == 0 && indices.iter().all(|&index| (index as usize) < vertex_count)
 }
 
 
@@ -429,6 +1717,8 @@ fn create_game_objects(
 fn create_triangle_mesh(
     render_ctx: Arc<RenderContext>
 ) -> Result<(Arc<Mesh>, SecondaryAutoCommandBuffer), RuntimeError> {
+    debug_assert!(TRIANGLE_POSITIONS.len() % 3 == 0, "The triangle positions must form a triangle list.");
+
     // create secondary command buffer.
     let allocator = render_ctx.get_command_buffer_allocator();
     let mut command_buffer_builder = AutoCommandBufferBuilder::secondary(
@@ -462,6 +1752,11 @@ fn create_triangle_mesh(
 fn create_quad_mesh(
     render_ctx: Arc<RenderContext>
 ) -> Result<(Arc<Mesh>, SecondaryAutoCommandBuffer), RuntimeError> {
+    debug_assert!(
+        is_valid_triangle_list(&QUAD_INDICES, QUAD_POSITIONS.len()), 
+        "The quad indices must form a triangle list within the vertex count."
+    );
+
     // create secondary command buffer.
     let allocator = render_ctx.get_command_buffer_allocator();
     let mut command_buffer_builder = AutoCommandBufferBuilder::secondary(
@@ -502,6 +1797,11 @@ fn create_quad_mesh(
 fn create_cube_mesh(
     render_ctx: Arc<RenderContext>
 ) -> Result<(Arc<Mesh>, SecondaryAutoCommandBuffer), RuntimeError> {
+    debug_assert!(
+        is_valid_triangle_list(&CUBE_INDICES, CUBE_POSITIONS.len()), 
+        "The cube indices must form a triangle list within the vertex count."
+    );
+
     // create secondary command buffer.
     let allocator = render_ctx.get_command_buffer_allocator();
     let mut command_buffer_builder = AutoCommandBufferBuilder::secondary(
@@ -537,3 +1837,571 @@ fn create_cube_mesh(
         command_buffer
     ))
 }
+
+
+/// create the cube mesh with per-face normals for lighting.
+/// the vertices are not shared between faces, so the mesh is drawn without an index buffer.
+/// 
+/// - binding 0, location 0: the position. (`Vec3`)
+/// - binding 1, location 1: the normal. (`Vec3`)
+#[inline]
+fn create_cube_mesh_with_normals(
+    render_ctx: Arc<RenderContext>
+) -> Result<(Arc<Mesh>, SecondaryAutoCommandBuffer), RuntimeError> {
+    debug_assert!(
+        is_valid_triangle_list(&CUBE_INDICES, CUBE_POSITIONS.len()), 
+        "The cube indices must form a triangle list within the vertex count."
+    );
+
+    // create secondary command buffer.
+    let allocator = render_ctx.get_command_buffer_allocator();
+    let mut command_buffer_builder = AutoCommandBufferBuilder::secondary(
+        &allocator, 
+        render_ctx.get_queue_fmaily_index(), 
+        CommandBufferUsage::OneTimeSubmit, 
+        CommandBufferInheritanceInfo::default()
+    ).map_err(|e| err!("Secondary command buffer begining failed: {}", e.to_string()))?;
+
+    // the normals of the cube faces.
+    let indices: Vec<u32> = CUBE_INDICES.iter().map(|&index| index as u32).collect();
+    let (positions, normals) = mesh::compute_flat_normals(&CUBE_POSITIONS, &indices);
+    let vertex_count = positions.len() as u32;
+
+    // create vertex buffers.
+    let positions = GpuVertexBuffer::from_iter_vec3(
+        positions,
+        VertexInputRate::Vertex,
+        render_ctx.ref_memory_allocator(),
+        &mut command_buffer_builder
+    )? as _;
+
+    let normals = GpuVertexBuffer::from_iter_vec3(
+        normals,
+        VertexInputRate::Vertex,
+        render_ctx.ref_memory_allocator(),
+        &mut command_buffer_builder
+    )? as _;
+
+    // build command buffer.
+    let command_buffer = command_buffer_builder
+        .build()
+        .map_err(|e| err!("Secondary command buffer building failed: {}", e.to_string()))?;
+
+    Ok((
+        Mesh::new(vertex_count, [positions, normals]), 
+        command_buffer
+    ))
+}
+
+
+/// create the quad mesh with texture coordinates.
+/// the top-left corner of the quad is mapped to `(0, 0)`. (see `QUAD_UVS`)
+/// 
+/// - binding 0, location 0: the position. (`Vec3`)
+/// - binding 1, location 1: the texture coordinates. (`Vec2`)
+#[inline]
+fn create_quad_mesh_with_uvs(
+    render_ctx: Arc<RenderContext>
+) -> Result<(Arc<Mesh>, SecondaryAutoCommandBuffer), RuntimeError> {
+    debug_assert!(
+        is_valid_triangle_list(&QUAD_INDICES, QUAD_POSITIONS.len()), 
+        "The quad indices must form a triangle list within the vertex count."
+    );
+    debug_assert!(QUAD_POSITIONS.len() == QUAD_UVS.len(), "The quad must have texture coordinates for each vertex.");
+
+    // create secondary command buffer.
+    let allocator = render_ctx.get_command_buffer_allocator();
+    let mut command_buffer_builder = AutoCommandBufferBuilder::secondary(
+        &allocator, 
+        render_ctx.get_queue_fmaily_index(), 
+        CommandBufferUsage::OneTimeSubmit, 
+        CommandBufferInheritanceInfo::default()
+    ).map_err(|e| err!("Secondary command buffer begining failed: {}", e.to_string()))?;
+
+    // create index buffer.
+    let index_buffer = IndexBuffer::from_iter_u16(
+        QUAD_INDICES,
+        render_ctx.ref_memory_allocator(),
+        &mut command_buffer_builder
+    )?;
+
+    // create vertex buffers.
+    let positions = GpuVertexBuffer::from_iter_vec3(
+        QUAD_POSITIONS,
+        VertexInputRate::Vertex,
+        render_ctx.ref_memory_allocator(),
+        &mut command_buffer_builder
+    )? as _;
+
+    let uvs = GpuVertexBuffer::from_iter_vec2(
+        QUAD_UVS,
+        VertexInputRate::Vertex,
+        render_ctx.ref_memory_allocator(),
+        &mut command_buffer_builder
+    )? as _;
+
+    // build command buffer.
+    let command_buffer = command_buffer_builder
+        .build()
+        .map_err(|e| err!("Secondary command buffer building failed: {}", e.to_string()))?;
+
+    Ok((
+        Mesh::new_with_index(QUAD_INDICES.len() as u32, index_buffer, QUAD_POSITIONS.len() as u32, [positions, uvs]), 
+        command_buffer
+    ))
+}
+
+
+/// create the cube mesh with texture coordinates.
+/// the vertices are not shared between faces, and each face is mapped to the whole `0..1` range. (see `CUBE_FACE_UVS`)
+/// 
+/// - binding 0, location 0: the position. (`Vec3`)
+/// - binding 1, location 1: the texture coordinates. (`Vec2`)
+#[inline]
+fn create_cube_mesh_with_uvs(
+    render_ctx: Arc<RenderContext>
+) -> Result<(Arc<Mesh>, SecondaryAutoCommandBuffer), RuntimeError> {
+    debug_assert!(
+        is_valid_triangle_list(&CUBE_FACE_INDICES, CUBE_FACE_POSITIONS.len()), 
+        "The cube indices must form a triangle list within the vertex count."
+    );
+    debug_assert!(CUBE_FACE_POSITIONS.len() == CUBE_FACE_UVS.len(), "The cube must have texture coordinates for each vertex.");
+
+    // create secondary command buffer.
+    let allocator = render_ctx.get_command_buffer_allocator();
+    let mut command_buffer_builder = AutoCommandBufferBuilder::secondary(
+        &allocator, 
+        render_ctx.get_queue_fmaily_index(), 
+        CommandBufferUsage::OneTimeSubmit, 
+        CommandBufferInheritanceInfo::default()
+    ).map_err(|e| err!("Secondary command buffer begining failed: {}", e.to_string()))?;
+
+    // create index buffer.
+    let index_buffer = IndexBuffer::from_iter_u16(
+        CUBE_FACE_INDICES,
+        render_ctx.ref_memory_allocator(),
+        &mut command_buffer_builder
+    )?;
+
+    // create vertex buffers.
+    let positions = GpuVertexBuffer::from_iter_vec3(
+        CUBE_FACE_POSITIONS,
+        VertexInputRate::Vertex,
+        render_ctx.ref_memory_allocator(),
+        &mut command_buffer_builder
+    )? as _;
+
+    let uvs = GpuVertexBuffer::from_iter_vec2(
+        CUBE_FACE_UVS,
+        VertexInputRate::Vertex,
+        render_ctx.ref_memory_allocator(),
+        &mut command_buffer_builder
+    )? as _;
+
+    // build command buffer.
+    let command_buffer = command_buffer_builder
+        .build()
+        .map_err(|e| err!("Secondary command buffer building failed: {}", e.to_string()))?;
+
+    Ok((
+        Mesh::new_with_index(CUBE_FACE_INDICES.len() as u32, index_buffer, CUBE_FACE_POSITIONS.len() as u32, [positions, uvs]), 
+        command_buffer
+    ))
+}
+
+
+/// create a uv-sphere mesh of radius `1` with smooth normals.
+/// the sphere has `rings + 1` rows of `sectors + 1` vertices from the top (+y) to the bottom (-y),
+/// and the first and the last vertex of each row are at the same position to close the seam.
+/// 
+/// - binding 0, location 0: the position. (`Vec3`)
+/// - binding 1, location 1: the normal. (`Vec3`)
+/// 
+/// # Panics
+/// - Stop program execution if `rings` is less than 2 or `sectors` is less than 3.
+/// 
+#[inline]
+fn create_sphere_mesh(
+    render_ctx: Arc<RenderContext>,
+    rings: u32,
+    sectors: u32
+) -> Result<(Arc<Mesh>, SecondaryAutoCommandBuffer), RuntimeError> {
+    let (positions, normals, indices) = mesh::generate_sphere(rings, sectors);
+    mesh::create_lit_mesh(render_ctx, positions, normals, indices)
+}
+
+
+/// create a plane mesh of size `2` on the xz-plane facing the +y axis, divided into `subdivisions` cells on each side.
+/// 
+/// - binding 0, location 0: the position. (`Vec3`)
+/// - binding 1, location 1: the normal. (`Vec3`)
+/// 
+/// # Panics
+/// - Stop program execution if `subdivisions` is zero.
+/// 
+#[inline]
+fn create_plane_mesh(
+    render_ctx: Arc<RenderContext>,
+    subdivisions: u32
+) -> Result<(Arc<Mesh>, SecondaryAutoCommandBuffer), RuntimeError> {
+    let (positions, normals, indices) = mesh::generate_plane(subdivisions);
+    mesh::create_lit_mesh(render_ctx, positions, normals, indices)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// a rotating object without a mesh and a shader, so it is created without a device.
+    fn rotate_object() -> RotateObject {
+        let model_node = ModelNode {
+            id: "Root".to_string(),
+            transform: Mat4x4::IDENTITY,
+            world_matrix: Mat4x4::IDENTITY,
+            mesh: None,
+            shader: None,
+            parent: None,
+            sibling: None,
+            child: None
+        };
+        RotateObject {
+            mat: Mat4x4::IDENTITY,
+            prev_mat: Mat4x4::IDENTITY,
+            color: Vec4::ONE,
+            axis: Vec3::Y,
+            speed: 1.0,
+            model: Model::from_nodes("Test", "Root".to_string(), [model_node]).unwrap(),
+            shader_override: None,
+            instance_index: 0,
+        }
+    }
+
+    #[test]
+    fn un_entered_scene_is_not_ready() {
+        let scene = MainScene::new();
+        assert!(!scene.is_ready());
+        let msg = scene.check_ready().unwrap_err();
+        assert!(msg.what().contains("not ready"), "{}", msg.what());
+    }
+
+    #[test]
+    fn object_ranges_partition_all_objects() {
+        let objects: Vec<usize> = (0..50).collect();
+        for num_threads in 1..=8 {
+            let mut partitioned = Vec::with_capacity(objects.len());
+            for index in 0..num_threads {
+                let range = get_object_range(objects.len(), num_threads, index);
+                assert!(range.len() >= objects.len() / num_threads, "thread {} of {} got {:?}", index, num_threads, range);
+                partitioned.extend_from_slice(&objects[range]);
+            }
+            assert_eq!(partitioned, objects, "{} threads", num_threads);
+        }
+    }
+
+    #[test]
+    fn scene_with_50_max_objects_creates_50_objects() {
+        let scene = MainScene::with_max_objects(50);
+        let objects = create_game_objects(scene.max_objects, |_| rotate_object());
+        assert_eq!(objects.len(), 50);
+
+        let num_threads = 4;
+        let covered: usize = (0..num_threads)
+            .map(|index| get_object_range(objects.len(), num_threads, index).len())
+            .sum();
+        assert_eq!(covered, 50);
+    }
+
+    #[test]
+    fn frames_in_flight_write_disjoint_transform_regions() {
+        let max_objects = 50;
+        let capacity = max_objects * 3;
+        let starts: Vec<usize> = (0..3)
+            .map(|frame_index| get_transform_region_start(frame_index, capacity, max_objects))
+            .collect();
+        assert_eq!(starts, [0, 50, 100]);
+        assert!(starts.iter().all(|start| start + max_objects <= capacity));
+        assert_eq!(get_transform_region_start(3, capacity, max_objects), 0);
+    }
+
+    #[test]
+    fn last_object_range_takes_the_remainder() {
+        assert_eq!(get_object_range(50, 4, 0), 0..12);
+        assert_eq!(get_object_range(50, 4, 2), 24..36);
+        assert_eq!(get_object_range(50, 4, 3), 36..50);
+    }
+
+    #[test]
+    fn frame_is_skipped_until_a_region_is_damaged() {
+        let mut scene = MainScene::new();
+        assert!(!scene.is_frame_skipped());
+
+        scene.set_damage_tracking(true);
+        assert!(scene.is_frame_skipped());
+
+        scene.mark_damaged(RectangleLayer { offset: [10, 20], extent: [30, 40], layer: 0 });
+        assert!(!scene.is_frame_skipped());
+
+        // the damaged regions are taken when the frame is presented.
+        let presented = mem::take(&mut scene.damaged_regions);
+        assert_eq!(presented.len(), 1);
+        assert!(scene.is_frame_skipped());
+    }
+
+    #[test]
+    fn cube_indices_form_a_triangle_list_of_its_vertices() {
+        assert_eq!(CUBE_INDICES.len(), 36);
+        assert_eq!(CUBE_INDICES.len() % 3, 0);
+        assert!(CUBE_INDICES.iter().all(|&index| (0..8).contains(&index)));
+        assert!(is_valid_triangle_list(&CUBE_INDICES, CUBE_POSITIONS.len()));
+    }
+
+    #[test]
+    fn quad_indices_form_a_triangle_list_of_its_vertices() {
+        assert!(QUAD_INDICES.iter().all(|&index| (0..4).contains(&index)));
+        assert!(is_valid_triangle_list(&QUAD_INDICES, QUAD_POSITIONS.len()));
+        assert_eq!(TRIANGLE_POSITIONS.len() % 3, 0);
+
+        // an index past the last vertex and an incomplete triangle are rejected.
+        assert!(!is_valid_triangle_list(&[0, 1, 8], 8));
+        assert!(!is_valid_triangle_list(&[0, 1], 8));
+    }
+
+
+    #[test]
+    fn object_color_and_speed_are_set_by_id() {
+        let mut scene = MainScene::new();
+        let object = Arc::new(Mutex::new(rotate_object()));
+        scene.objects.push(object.clone());
+
+        scene.set_object_color(0, Vec4::new_vector(0.5, 2.0, -1.0, 1.0)).unwrap();
+        assert_eq!(object.lock().unwrap().color, Vec4::new_vector(0.5, 1.0, 0.0, 1.0));
+        scene.set_object_speed(0, 3.0).unwrap();
+        assert_eq!(object.lock().unwrap().speed, 3.0);
+
+        assert!(scene.set_object_color(1, Vec4::ZERO).is_err());
+        assert!(scene.set_object_speed(1, 0.0).is_err());
+    }
+
+    #[test]
+    fn recreated_resources_are_rebound_and_objects_are_kept() {
+        let old_mesh = Mesh::new(3, Vec::<Arc<dyn VertexBufferAbstract>>::new());
+        let new_mesh = Mesh::new(3, Vec::<Arc<dyn VertexBufferAbstract>>::new());
+        let old_meshes = HashMap::from([(MeshID::Cube, old_mesh.clone())]);
+        let new_meshes = HashMap::from([(MeshID::Cube, new_mesh.clone())]);
+
+        let mut scene = MainScene::new();
+        let transform = Mat4x4::from_translation(Vec3::new_vector(1.0, 2.0, 3.0));
+        let objects: Vec<_> = (0..3).map(|_| {
+            let mut object = rotate_object();
+            let node = ModelNode { mesh: Some(old_mesh.clone()), ..object.model.iter().next().unwrap().clone() };
+            object.model = Model::from_nodes("Test", "Root".to_string(), [node]).unwrap();
+            object.mat = transform;
+            Arc::new(Mutex::new(object))
+        }).collect();
+        scene.objects.extend(objects.iter().map(|object| object.clone() as Arc<Mutex<dyn WorldObject>>));
+
+        // the device was lost, and the meshes were uploaded again to the new device.
+        let mesh_map = get_recreated_resource_map(&old_meshes, &new_meshes);
+        for object in scene.objects.iter() {
+            object.lock().unwrap().rebind_resources(&|mesh| mesh_map.get(&Arc::as_ptr(mesh)).cloned(), &|_| None);
+        }
+
+        assert_eq!(scene.objects.len(), 3);
+        for object in objects.iter() {
+            let object = object.lock().unwrap();
+            assert_eq!(object.mat, transform);
+            assert!(Arc::ptr_eq(object.model.iter().next().unwrap().mesh.as_ref().unwrap(), &new_mesh));
+        }
+    }
+
+
+    #[test]
+    fn uniforms_are_uploaded_once_before_each_draw() {
+        #[derive(Default)]
+        struct CountingObject {
+            uploads: std::sync::atomic::AtomicU32,
+        }
+
+        let objects: Vec<_> = (0..3).map(|_| Arc::new(Mutex::new(CountingObject::default()))).collect();
+        let mut recorded = Vec::new();
+        record_object_draws(
+            &objects, 
+            &(), 
+            &mut recorded, 
+            |object, _| {
+                object.uploads.fetch_add(1, Ordering::Relaxed);
+                Ok(())
+            }, 
+            |object, _, recorded| {
+                // the number of uploads when the draw is recorded.
+                recorded.push(object.uploads.load(Ordering::Relaxed));
+                Ok(())
+            }
+        ).unwrap();
+
+        assert_eq!(recorded, vec![1, 1, 1]);
+        assert!(objects.iter().all(|object| object.lock().unwrap().uploads.load(Ordering::Relaxed) == 1));
+    }
+
+    #[test]
+    fn despawned_object_returns_to_the_pool_for_the_next_spawn() {
+        let mut scene = MainScene::new();
+        let object = scene.object_pool.acquire(rotate_object());
+        let address = Arc::as_ptr(&object);
+        scene.objects.push(object as _);
+
+        assert!(scene.despawn_object(0));
+        assert!(!scene.despawn_object(0));
+        assert!(scene.objects.is_empty());
+
+        // `spawn_object` takes the next object from the pool the same way.
+        let respawned = scene.object_pool.acquire(rotate_object());
+        assert_eq!(Arc::as_ptr(&respawned), address);
+        assert_eq!(scene.get_object_pool_stats().unwrap().num_reused, 1);
+        assert_eq!(scene.get_object_pool_stats().unwrap().num_allocated, 1);
+    }
+
+    #[test]
+    fn debug_dump_contains_the_object_count_and_camera_position() {
+        let position = Vec3::new_vector(1.0, 2.0, -5.0);
+        let dump = get_debug_dump(12, 100, Some((position, Vec3::Z)), None, &RasterizationSettings::default(), Some(3));
+        assert!(dump.contains("objects: 12 (max: 100)"), "{}", dump);
+        assert!(dump.contains(&format!("camera position: {}", position)), "{}", dump);
+
+        let dump = MainScene::new().debug_dump();
+        assert!(dump.contains("objects: 0"), "{}", dump);
+        assert!(dump.contains("camera: none"), "{}", dump);
+    }
+
+    #[test]
+    fn objects_update_at_half_the_frame_rate_and_draw_the_blend() {
+        let mut object = rotate_object();
+        let mut accumulator = 0.0;
+        let mut num_updates_total = 0;
+        for frame in 0..60 {
+            // each update moves the object one unit along the x-axis.
+            let (interval, num_updates, alpha) = advance_update_accumulator(&mut accumulator, 30.0, 1.0 / 60.0);
+            update_object_at_fixed_rate(&mut object, interval, num_updates, alpha, |object, _| {
+                object.mat = object.mat * Mat4x4::from_translation(Vec3::X);
+                num_updates_total += 1;
+                Ok(())
+            }).unwrap();
+            assert!(num_updates <= 1, "frame {}: {} updates", frame, num_updates);
+
+            let drawn = object.get_draw_transform();
+            let expected = object.prev_mat.r4c1 + (object.mat.r4c1 - object.prev_mat.r4c1) * alpha;
+            assert!((drawn.r4c1 - expected).abs() <= 1e-4, "frame {}: {} != {}", frame, drawn.r4c1, expected);
+        }
+
+        assert_eq!(num_updates_total, 30);
+        assert_eq!(object.mat.r4c1, num_updates_total as f32);
+    }
+
+    #[test]
+    fn textured_meshes_read_the_uvs_from_the_second_binding() {
+        let state = create_vertex_input_state(&TEXTURED_VERTEX_FORMATS);
+        assert_eq!(state.bindings.len(), 2);
+        assert_eq!(state.bindings[&0].stride, mem::size_of::<Vec3>() as u32);
+        assert_eq!(state.bindings[&1].stride, mem::size_of::<Vec2>() as u32);
+        assert_eq!(state.attributes[&1].binding, 1);
+        assert_eq!(state.attributes[&1].format, Format::R32G32_SFLOAT);
+
+        assert_eq!(MeshID::QuadWithUvs.get_shader_id(), ShaderID::Textured);
+        assert_eq!(MeshID::CubeWithUvs.get_shader_id(), ShaderID::Textured);
+        assert_eq!(MeshID::Cube.get_shader_id(), ShaderID::Default);
+    }
+
+    #[test]
+    fn meshes_with_normals_are_drawn_with_the_lit_shader() {
+        let state = create_vertex_input_state(&LIT_VERTEX_FORMATS);
+        assert_eq!(state.bindings.len(), 2);
+        assert_eq!(state.bindings[&1].stride, mem::size_of::<Vec3>() as u32);
+        assert_eq!(state.attributes[&1].binding, 1);
+        assert_eq!(state.attributes[&1].format, Format::R32G32B32_SFLOAT);
+
+        for mesh_id in [MeshID::CubeWithNormals, MeshID::Sphere, MeshID::Plane] {
+            assert_eq!(mesh_id.get_shader_id(), ShaderID::Lit);
+        }
+
+        // every uploaded mesh can be picked for an object.
+        let mut rng = StdRng::seed_from_u64(0);
+        let picked: std::collections::HashSet<MeshID> = (0..1000).map(|_| rng.gen()).collect();
+        assert_eq!(picked.len(), 8);
+    }
+
+    /// an object picked by a unit sphere at its position, so it is created without a device.
+    struct SphereObject {
+        mat: Mat4x4,
+    }
+
+    impl GameObject for SphereObject { }
+    impl DrawAttributePrimary for SphereObject { }
+    impl DrawAttributeSecondary for SphereObject { }
+    impl DrawableObject for SphereObject { }
+    impl DynamicObject for SphereObject { }
+
+    impl WorldObject for SphereObject {
+        fn ref_transform(&self) -> &Mat4x4 {
+            &self.mat
+        }
+
+        fn mut_transform(&mut self) -> &mut Mat4x4 {
+            &mut self.mat
+        }
+
+        fn get_bounding_sphere(&self) -> Option<BoundingSphere> {
+            Some(BoundingSphere::new(self.get_position(), 1.0))
+        }
+    }
+
+    fn sphere_object(z: f32) -> Arc<Mutex<dyn WorldObject>> {
+        Arc::new(Mutex::new(SphereObject { mat: Mat4x4::from_translation(Vec3::new_vector(0.0, 0.0, z)) }))
+    }
+
+    #[test]
+    fn picking_follows_moved_and_despawned_objects() {
+        let mut scene = MainScene::new();
+        scene.objects = vec![sphere_object(5.0), sphere_object(10.0), sphere_object(15.0)];
+        scene.object_bounds_dirty = true;
+
+        let ray = Ray::new(Vec3::new_vector(0.0, 0.0, -10.0), Vec3::Z);
+        assert_eq!(scene.pick_object(&ray).map(|(id, _)| id), Some(0));
+
+        // moving an object marks the bounds dirty the same way `update` does.
+        scene.objects[2].lock().unwrap().set_position(Vec3::new_vector(0.0, 0.0, 0.0));
+        scene.object_bounds_dirty = true;
+        let (id, distance) = scene.pick_object(&ray).unwrap();
+        assert_eq!(id, 2);
+        assert!((distance - 9.0).abs() <= 1e-4, "{}", distance);
+
+        // the last object takes the id of the despawned object.
+        assert!(scene.despawn_object(0));
+        assert_eq!(scene.pick_object(&ray).map(|(id, _)| id), Some(0));
+        assert!(scene.despawn_object(0));
+        assert_eq!(scene.pick_object(&ray).map(|(id, _)| id), Some(0));
+        assert!(scene.despawn_object(0));
+        assert_eq!(scene.pick_object(&ray), None);
+    }
+
+    #[test]
+    fn screen_ray_through_the_center_follows_the_look_vector() {
+        let camera_mat = Mat4x4::from_translation(Vec3::new_vector(0.0, 0.0, -10.0));
+        let ray = get_screen_ray(&camera_mat, 400.0, 300.0, 800, 600);
+        assert_eq!(ray.origin, Vec3::new_vector(0.0, 0.0, -10.0));
+        assert!((ray.direction - Vec3::Z).length() <= 1e-6, "{:?}", ray.direction);
+
+        // the top-left corner maps to the negative x and y of the view space, as the projection does.
+        let ray = get_screen_ray(&camera_mat, 0.0, 0.0, 800, 600);
+        assert!(ray.direction.x < 0.0 && ray.direction.y < 0.0, "{:?}", ray.direction);
+        let tan_half_fovy = (Camera::FOV_Y * 0.5).tan();
+        assert!((ray.direction.y / ray.direction.z + tan_half_fovy).abs() <= 1e-5);
+    }
+
+    #[test]
+    fn light_direction_matches_the_lit_shader() {
+        let source = include_str!("../../shaders/lit_frag.glsl");
+        let light_direction = format!("vec3({:?}, {:?}, {:?})", LIGHT_DIRECTION.x, LIGHT_DIRECTION.y, LIGHT_DIRECTION.z);
+        assert!(source.contains(&light_direction), "{}", light_direction);
+    }
+}
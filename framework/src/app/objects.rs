@@ -1,15 +1,21 @@
 use std::sync::Arc;
 
+use rand::prelude::*;
 use bytemuck::{Pod, Zeroable};
 use vulkano::command_buffer::PrimaryAutoCommandBuffer;
 use vulkano::command_buffer::{AutoCommandBufferBuilder, SecondaryAutoCommandBuffer};
+use vulkano::command_buffer::allocator::CommandBufferAllocator;
+use vulkano::pipeline::graphics::vertex_input::VertexInputRate;
 
 use crate::math::*;
 use crate::timer::Timer;
+use crate::world::bvh::*;
+use crate::world::mesh::*;
 use crate::world::model::*;
+use crate::world::shader::*;
 use crate::world::object::*;
 use crate::world::variable::*;
-use crate::renderer::RenderContext;
+use crate::renderer::{RenderContext, CameraFrustum};
 use crate::{err, error::RuntimeError};
 
 
@@ -25,6 +31,111 @@ pub struct ObjectData {
 pub struct CameraData {
     pub view: Mat4x4,
     pub projection: Mat4x4,
+    /// the matrices of the previous frame. (for temporal effects)
+    pub prev_view: Mat4x4,
+    pub prev_projection: Mat4x4,
+    /// the sub-pixel jitter of `projection` (xy) and `prev_projection` (zw) in NDC.
+    /// zero when the temporal anti-aliasing jitter is disabled.
+    pub jitter: Vec4,
+}
+
+/// the camera data of each view when rendering with multiview.
+/// shaders index the matrices with `gl_ViewIndex`.
+#[repr(C, align(16))]
+#[derive(Debug, Clone, Copy, PartialEq, Pod, Zeroable)]
+pub struct MultiviewCameraData {
+    pub view: [Mat4x4; MAX_VIEWS],
+    pub projection: [Mat4x4; MAX_VIEWS],
+}
+
+/// the number of views of `MultiviewCameraData`. (e.g. the left and right eyes)
+pub const MAX_VIEWS: usize = 2;
+
+/// the draw commands recorded by the scene objects.
+/// the command buffer builders record them on the device. the mesh and shader types are generic,
+/// so the recorded commands can be checked without a device.
+pub trait DrawCommands<M = Arc<Mesh>, S = Arc<GraphicsShader>> {
+    /// bind the pipeline and the descriptor set of the shader and push the object data.
+    unsafe fn bind_shader(&mut self, shader: &S, object_data: ObjectData);
+
+    /// bind the buffers of the mesh and draw `instance_count` instances from `first_instance`.
+    unsafe fn draw_mesh(&mut self, mesh: &M, instance_count: u32, first_instance: u32) -> Result<(), RuntimeError>;
+}
+
+impl<L, A: CommandBufferAllocator> DrawCommands for AutoCommandBufferBuilder<L, A> {
+    #[inline]
+    unsafe fn bind_shader(&mut self, shader: &Arc<GraphicsShader>, object_data: ObjectData) {
+        shader.bind_pipeline(self);
+        shader.bind_descriptor_set(self);
+        shader.push_constants(0, object_data, self);
+    }
+
+    #[inline]
+    unsafe fn draw_mesh(&mut self, mesh: &Arc<Mesh>, instance_count: u32, first_instance: u32) -> Result<(), RuntimeError> {
+        mesh.bind_buffers(self);
+        mesh.draw(instance_count, first_instance, self)
+    }
+}
+
+/// a decaying shake of the camera. (e.g. impact feedback)
+/// the shake is a deterministic sum of sine waves, so the same elapsed time always gives the same offset.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CameraShake {
+    intensity: f32,
+    duration: f32,
+    elapsed: f32,
+}
+
+impl CameraShake {
+    /// the maximum roll angle at intensity `1`. (radian)
+    pub const MAX_ROLL: f32 = 0.1;
+
+    /// create a new shake. `intensity` is the maximum offset of the camera position.
+    #[inline]
+    pub fn new(intensity: f32, duration_in_sec: f32) -> Self {
+        Self { intensity, duration: duration_in_sec, elapsed: 0.0 }
+    }
+
+    /// advance the shake by the given time.
+    #[inline]
+    pub fn advance(&mut self, elapsed_time_in_sec: f32) {
+        self.elapsed += elapsed_time_in_sec.max(0.0);
+    }
+
+    #[inline]
+    pub fn is_finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+
+    /// return the current intensity. it decays quadratically to zero at the end of the duration.
+    #[inline]
+    pub fn get_amplitude(&self) -> f32 {
+        if self.is_finished() {
+            return 0.0;
+        }
+        let remain = 1.0 - self.elapsed / self.duration;
+        self.intensity * remain * remain
+    }
+
+    /// return the current shake transform in view space.
+    pub fn get_shake_mat(&self) -> Mat4x4 {
+        let amplitude = self.get_amplitude();
+        let roll = Quat::from_angle_axis(amplitude * Self::MAX_ROLL * shake_noise(self.elapsed, 3.0), Vec3::Z);
+        let mut mat = roll.into_matrix4x4();
+        mat.set_row(3, Vec4::new_vector(
+            amplitude * shake_noise(self.elapsed, 0.0),
+            amplitude * shake_noise(self.elapsed, 1.0),
+            amplitude * shake_noise(self.elapsed, 2.0),
+            1.0
+        ));
+        return mat;
+    }
+}
+
+/// a deterministic noise in `[-1, 1]` made of sine waves. each seed gives a different wave.
+#[inline]
+fn shake_noise(time: f32, seed: f32) -> f32 {
+    ((time * 37.0 + seed * 1.3).sin() + 0.5 * (time * 71.0 + seed * 2.9).sin()) / 1.5
 }
 
 pub struct Camera {
@@ -32,6 +143,146 @@ pub struct Camera {
     pub screen_width: u32,
     pub screen_height: u32,
     pub uniform_buffer: Arc<UniformBuffer<CameraData>>,
+    /// when set, the camera orbits the origin around the y-axis at this speed. (degree per second)
+    pub demo_rotation: Option<f32>,
+    /// the view and projection matrices of the previous frame. `None` before the first frame.
+    pub prev_matrices: Option<(Mat4x4, Mat4x4)>,
+    /// the shake applied on top of the view matrix.
+    pub shake: Option<CameraShake>,
+    /// when set, the projection matrix is offset by a sub-pixel jitter each frame. (for temporal anti-aliasing)
+    pub taa_jitter: bool,
+    /// the index of the next jitter sample in the halton sequence.
+    pub jitter_index: u32,
+}
+
+impl Camera {
+    /// the vertical field of view. (radian)
+    pub const FOV_Y: f32 = 60.0 * std::f32::consts::PI / 180.0;
+
+    /// the near plane distance.
+    pub const NEAR: f32 = 0.001;
+
+    /// the far plane distance.
+    pub const FAR: f32 = 1000.0;
+
+    /// the number of jitter samples before the sequence repeats.
+    pub const TAA_JITTER_SAMPLES: u32 = 8;
+
+    /// get the frustum of the camera that ends at the given distance. (e.g. the distance covered by the shadows)
+    #[inline]
+    pub fn get_frustum(&self, far: f32) -> CameraFrustum {
+        CameraFrustum {
+            view: self.get_camera_mat(),
+            fov_y: Self::FOV_Y,
+            aspect: self.screen_width as f32 / self.screen_height as f32,
+            near: Self::NEAR,
+            far,
+        }
+    }
+
+    /// enable or disable the sub-pixel jitter of the projection matrix.
+    /// the jitter sequence restarts from the first sample.
+    #[inline]
+    pub fn set_taa_jitter(&mut self, enabled: bool) {
+        self.taa_jitter = enabled;
+        self.jitter_index = 0;
+    }
+
+    /// return the jitter of the current frame in NDC and advance to the next sample.
+    /// the jitter is a halton(2, 3) point in `[-0.5, 0.5)` pixel. returns zero if the jitter is disabled.
+    #[inline]
+    pub fn next_jitter(&mut self) -> Vec2 {
+        next_taa_jitter(self.taa_jitter, &mut self.jitter_index, self.screen_width, self.screen_height)
+    }
+
+    /// return the approximate radius of the world-space sphere on the screen. (pixel)
+    /// returns `0` if the sphere is behind the camera.
+    #[inline]
+    pub fn projected_radius(&self, center: Vec3, radius: f32, screen_height: u32) -> f32 {
+        projected_sphere_radius(self.get_position(), self.get_look_vector(), center, radius, screen_height)
+    }
+
+    /// orbit the camera around the origin by the given angle and look at the origin again.
+    pub fn orbit(&mut self, angle: f32) {
+        if let Some(position) = orbit_position(self.get_position(), angle) {
+            self.set_position(position);
+            self.set_look_at_point(Vec3::ZERO);
+        }
+    }
+}
+
+/// return the jitter of the `jitter_index`-th frame in NDC and advance the index.
+/// returns zero if the jitter is disabled or the screen is empty.
+fn next_taa_jitter(enabled: bool, jitter_index: &mut u32, screen_width: u32, screen_height: u32) -> Vec2 {
+    if !enabled || screen_width == 0 || screen_height == 0 {
+        return Vec2::ZERO;
+    }
+
+    // the first element of the sequence is zero, so the sequence starts at one.
+    let index = *jitter_index % Camera::TAA_JITTER_SAMPLES + 1;
+    *jitter_index = jitter_index.wrapping_add(1);
+    Vec2::new_vector(
+        (halton(index, 2) - 0.5) * 2.0 / screen_width as f32,
+        (halton(index, 3) - 0.5) * 2.0 / screen_height as f32,
+    )
+}
+
+/// offset the projection matrix by the jitter. (NDC)
+/// the jitter is a translation in clip space, and the clip-space w is the view-space z.
+#[inline]
+fn jitter_projection(projection: Mat4x4, jitter: Vec2) -> Mat4x4 {
+    let mut projection = projection;
+    projection.r3c1 += jitter.x;
+    projection.r3c2 += jitter.y;
+    projection
+}
+
+/// return the approximate radius on the screen of a sphere seen from the camera position along the look vector. (pixel)
+/// returns `0` if the sphere is behind the camera.
+#[inline]
+fn projected_sphere_radius(position: Vec3, look: Vec3, center: Vec3, radius: f32, screen_height: u32) -> f32 {
+    let depth = (center - position).dot(&look);
+    if depth <= 0.0 {
+        return 0.0;
+    }
+
+    let depth = depth.max(Camera::NEAR);
+    radius / (depth * (Camera::FOV_Y * 0.5).tan()) * screen_height as f32 * 0.5
+}
+
+/// return the position orbited around the y-axis through the origin by the given angle. (radian)
+/// returns `None` if the position is at the origin.
+#[inline]
+fn orbit_position(position: Vec3, angle: f32) -> Option<Vec3> {
+    let (radius, azimuth, elevation) = position.to_spherical();
+    if radius <= f32::EPSILON {
+        return None;
+    }
+    Some(Vec3::from_spherical(radius, azimuth + angle, elevation))
+}
+
+/// return the demo-rotation angle of the elapsed time. (radian)
+#[inline]
+fn demo_rotation_angle(degrees_per_sec: f32, elapsed_time_in_sec: f32) -> f32 {
+    degrees_per_sec.to_radians() * elapsed_time_in_sec
+}
+
+/// return the camera data of the current frame and keep its matrices as the previous matrices of the next frame.
+/// on the first frame there is no history, so the current matrices are used as the previous ones.
+#[inline]
+fn advance_camera_data(prev_matrices: &mut Option<(Mat4x4, Mat4x4)>, view: Mat4x4, projection: Mat4x4, jitter: Vec2) -> CameraData {
+    let (prev_view, prev_projection) = prev_matrices.unwrap_or((view, projection));
+    *prev_matrices = Some((view, projection));
+    // the projection without jitter has no `r3c1` and `r3c2` terms.
+    let prev_jitter = Vec2::new_vector(prev_projection.r3c1, prev_projection.r3c2);
+
+    CameraData { 
+        view, 
+        projection,
+        prev_view,
+        prev_projection,
+        jitter: Vec4::new_vector(jitter.x, jitter.y, prev_jitter.x, prev_jitter.y),
+    }
 }
 
 impl GameObject for Camera { }
@@ -48,13 +299,28 @@ impl DynamicObject for Camera {
 
     fn update(
         &mut self, 
-        _elapsed_time_in_sec: f32, 
+        elapsed_time_in_sec: f32, 
         _render_ctx: &Arc<RenderContext>
     ) -> Result<(), RuntimeError> {
-        self.uniform_buffer.write_data(CameraData { 
-            view: self.get_camera_mat(), 
-            projection: self.get_projection_mat() 
-        });
+        if let Some(speed) = self.demo_rotation {
+            self.orbit(demo_rotation_angle(speed, elapsed_time_in_sec));
+        }
+
+        // the shake is composed on top of the view matrix.
+        let mut view = self.get_camera_mat();
+        if let Some(shake) = &mut self.shake {
+            shake.advance(elapsed_time_in_sec);
+            view = view * shake.get_shake_mat();
+            if shake.is_finished() {
+                self.shake = None;
+            }
+        }
+
+        let jitter = self.next_jitter();
+        let projection = jitter_projection(self.get_projection_mat(), jitter);
+
+        let camera_data = advance_camera_data(&mut self.prev_matrices, view, projection, jitter);
+        self.uniform_buffer.write_data_if_changed(camera_data);
 
         Ok(())    
     }
@@ -83,10 +349,10 @@ impl CameraObject for Camera {
 
     fn get_projection_mat(&self) -> Mat4x4 {
         perspective_lh_zo(
-            60_f32.to_radians(), 
+            Self::FOV_Y, 
             self.screen_width as f32 / self.screen_height as f32,
-            0.001, 
-            1000.0
+            Self::NEAR, 
+            Self::FAR
         )
     }
 }
@@ -95,15 +361,76 @@ impl CameraObject for Camera {
 
 pub struct RotateObject {
     pub mat: Mat4x4,
+    /// the transform of the previous update. (for the fixed-rate update)
+    pub prev_mat: Mat4x4,
     pub color: Vec4,
     pub axis: Vec3,
     pub speed: f32,
     pub model: Model,
+    pub shader_override: Option<Arc<GraphicsShader>>,
+    /// the index of the object's transform in the instance transform buffer of the scene.
+    pub instance_index: u32,
 }
 
 impl GameObject for RotateObject { }
 
 impl WorldObject for RotateObject {
+    #[inline]
+    fn set_color(&mut self, color: Vec4) -> bool {
+        self.color = color;
+        true
+    }
+
+    #[inline]
+    fn set_speed(&mut self, speed: f32) -> bool {
+        self.speed = speed;
+        true
+    }
+
+    #[inline]
+    fn store_previous_transform(&mut self) {
+        self.prev_mat = self.mat;
+    }
+
+    /// only the drawn transform of the model is interpolated. the transform of the object is kept.
+    #[inline]
+    fn interpolate_transform(&mut self, alpha: f32) {
+        let mat = self.prev_mat.blend(self.mat, alpha);
+        self.model.update_transform(&"Root".to_string(), Some(mat));
+    }
+
+    /// the drawn transform is the world matrix of the root node, which may be interpolated.
+    #[inline]
+    fn get_draw_transform(&self) -> Mat4x4 {
+        self.model.iter().next().map_or(self.mat, |node| node.world_matrix)
+    }
+
+    #[inline]
+    fn set_instance_index(&mut self, index: u32) {
+        self.instance_index = index;
+    }
+
+    /// the sphere enclosing the meshes of all model nodes at their world matrices.
+    fn get_bounding_sphere(&self) -> Option<BoundingSphere> {
+        self.model.iter()
+            .filter_map(|node| node.mesh.as_ref().map(|mesh| {
+                let (center, radius) = mesh.bounding_sphere();
+                BoundingSphere::new(center, radius).transformed(&node.world_matrix)
+            }))
+            .reduce(|sphere, other| sphere.merge(&other))
+    }
+
+    fn rebind_resources(
+        &mut self,
+        meshes: &dyn Fn(&Arc<Mesh>) -> Option<Arc<Mesh>>,
+        shaders: &dyn Fn(&Arc<GraphicsShader>) -> Option<Arc<GraphicsShader>>
+    ) {
+        self.model.rebind_resources(meshes, shaders);
+        if let Some(shader) = self.shader_override.as_ref().and_then(|shader| shaders(shader)) {
+            self.shader_override = Some(shader);
+        }
+    }
+
     fn set_position(&mut self, position: Vec3) {
         let mut mat = self.mut_transform();
         mat.r4c1 = position.x;
@@ -189,6 +516,11 @@ impl WorldObject for RotateObject {
     fn mut_transform(&mut self) -> &mut Mat4x4 {
         &mut self.mat
     }
+
+    #[inline]
+    fn ref_shader_override(&self) -> Option<&Arc<GraphicsShader>> {
+        self.shader_override.as_ref()
+    }
 }
 
 impl DrawAttributePrimary for RotateObject {
@@ -197,32 +529,49 @@ impl DrawAttributePrimary for RotateObject {
         _render_ctx: &Arc<RenderContext>, 
         command_buffer_builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>
     ) -> Result<(), RuntimeError> {
-        let nodes = self.model.ref_nodes();
-        for node in nodes {
-            if let Some(shader) = &node.shader {
-                unsafe { 
-                    shader.bind_pipeline(command_buffer_builder);
-                    shader.bind_descriptor_set(command_buffer_builder);
-                    shader.push_constants(
-                        0, 
-                        ObjectData {
-                            color: self.color,
-                            transform: node.world_matrix,
-                        }, 
-                        command_buffer_builder
-                    );
-                }
+        for node in self.model.iter() {
+            unsafe {
+                record_node_draw(
+                    command_buffer_builder, 
+                    node.mesh.as_ref(), 
+                    node.shader.as_ref(), 
+                    self.ref_shader_override(), 
+                    ObjectData {
+                        color: self.color,
+                        transform: node.world_matrix,
+                    }, 
+                    self.instance_index
+                )?;
             }
+        }
+
+        Ok(())    
+    }
 
-            if let Some(mesh) = &node.mesh {
-                unsafe {
-                    mesh.bind_buffers(command_buffer_builder);
-                    mesh.draw(1, 0, command_buffer_builder)?;
-                }
+    /// draw the meshes of the object with the depth shader.
+    /// the depth shader reads the world matrix of each node from the pushed object data.
+    fn draw_depth(
+        &self, 
+        shader: &Arc<GraphicsShader>, 
+        command_buffer_builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>
+    ) -> Result<(), RuntimeError> {
+        for node in self.model.iter() {
+            unsafe {
+                record_node_draw(
+                    command_buffer_builder, 
+                    node.mesh.as_ref(), 
+                    None, 
+                    Some(shader), 
+                    ObjectData {
+                        color: self.color,
+                        transform: node.world_matrix,
+                    }, 
+                    self.instance_index
+                )?;
             }
         }
 
-        Ok(())    
+        Ok(())
     }
 }
 
@@ -232,28 +581,19 @@ impl DrawAttributeSecondary for RotateObject {
         _render_ctx: &Arc<RenderContext>, 
         command_buffer_builder: &mut AutoCommandBufferBuilder<SecondaryAutoCommandBuffer>
     ) -> Result<(), RuntimeError> {
-        let nodes = self.model.ref_nodes();
-        for node in nodes {
-            if let Some(shader) = &node.shader {
-                unsafe { 
-                    shader.bind_pipeline(command_buffer_builder);
-                    shader.bind_descriptor_set(command_buffer_builder);
-                    shader.push_constants(
-                        0, 
-                        ObjectData {
-                            color: self.color,
-                            transform: node.world_matrix,
-                        }, 
-                        command_buffer_builder
-                    );
-                }
-            }
-
-            if let Some(mesh) = &node.mesh {
-                unsafe {
-                    mesh.bind_buffers(command_buffer_builder);
-                    mesh.draw(1, 0, command_buffer_builder)?;
-                }
+        for node in self.model.iter() {
+            unsafe {
+                record_node_draw(
+                    command_buffer_builder, 
+                    node.mesh.as_ref(), 
+                    node.shader.as_ref(), 
+                    self.ref_shader_override(), 
+                    ObjectData {
+                        color: self.color,
+                        transform: node.world_matrix,
+                    }, 
+                    self.instance_index
+                )?;
             }
         }
 
@@ -286,3 +626,370 @@ impl DynamicObject for RotateObject {
         Ok(())
     }
 }
+
+
+
+/// Scatter foliage instances over the region of the xz-plane.
+/// Returns the transform and color of each instance. (`density` is the number of instances per unit area)
+/// The same seed of `rng` gives the same instances.
+pub fn scatter_foliage_instances(rng: &mut impl Rng, region_min: Vec2, region_max: Vec2, density: f32) -> Vec<(Mat4x4, Vec4)> {
+    let area = (region_max.x - region_min.x).max(0.0) * (region_max.y - region_min.y).max(0.0);
+    let count = (area * density.max(0.0)).round() as usize;
+
+    (0..count)
+        .map(|_| {
+            let mut mat = Quat::from_angle_axis(
+                rng.gen_range(0.0..std::f32::consts::TAU), 
+                Vec3::Y
+            ).into_matrix4x4();
+            mat.r4c1 = rng.gen_range(region_min.x..=region_max.x);
+            mat.r4c3 = rng.gen_range(region_min.y..=region_max.y);
+
+            let color = Vec4::new_vector(
+                rng.gen_range(0.1..=0.3),
+                rng.gen_range(0.5..=0.9),
+                rng.gen_range(0.1..=0.3),
+                1.0
+            );
+            (mat, color)
+        })
+        .collect()
+}
+
+
+
+/// Many instances of a small mesh scattered across a region, drawn with a single instanced draw call.
+/// 
+/// The mesh has the vertex positions at location `0`,
+/// the per-instance transform at locations `1..=4` and the per-instance color at location `5`.
+/// The shader must combine the instance transform with the `ObjectData` push constant.
+pub struct Foliage {
+    pub mat: Mat4x4,
+    pub instance_count: u32,
+    pub mesh: Arc<Mesh>,
+    pub shader: Arc<GraphicsShader>,
+}
+
+impl Foliage {
+    /// Create a new foliage object.
+    /// 
+    /// # Runtime Errors
+    /// - Returns a runtime error message if the region contains no instances.
+    /// - Returns a runtime error message if buffer creation fails.
+    /// 
+    pub fn new<L, A: CommandBufferAllocator>(
+        rng: &mut impl Rng,
+        region_min: Vec2,
+        region_max: Vec2,
+        density: f32,
+        positions: &[Vec3],
+        indices: &[u16],
+        shader: Arc<GraphicsShader>,
+        render_ctx: &Arc<RenderContext>,
+        command_buffer_builder: &mut AutoCommandBufferBuilder<L, A>
+    ) -> Result<Self, RuntimeError> {
+        let (transforms, colors): (Vec<_>, Vec<_>) = scatter_foliage_instances(rng, region_min, region_max, density)
+            .into_iter()
+            .unzip();
+        if transforms.is_empty() {
+            return Err(err!("Foliage region contains no instances."));
+        }
+
+        let index_buffer = IndexBuffer::from_iter_u16(
+            indices.iter().copied(),
+            render_ctx.ref_memory_allocator(),
+            command_buffer_builder
+        )?;
+
+        let positions_buffer = GpuVertexBuffer::from_iter_vec3(
+            positions.iter().copied(),
+            VertexInputRate::Vertex,
+            render_ctx.ref_memory_allocator(),
+            command_buffer_builder
+        )? as _;
+
+        let instance_count = transforms.len() as u32;
+        let transforms_buffer = GpuVertexBuffer::from_iter_mat4(
+            transforms,
+            VertexInputRate::Instance { divisor: 1 },
+            render_ctx.ref_memory_allocator(),
+            command_buffer_builder
+        )? as _;
+
+        let colors_buffer = GpuVertexBuffer::from_iter_vec4(
+            colors,
+            VertexInputRate::Instance { divisor: 1 },
+            render_ctx.ref_memory_allocator(),
+            command_buffer_builder
+        )? as _;
+
+        let mesh = Mesh::new_with_index(
+            indices.len() as u32, 
+            index_buffer, 
+            positions.len() as u32, 
+            [positions_buffer, transforms_buffer, colors_buffer]
+        );
+
+        Ok(Self { mat: Mat4x4::IDENTITY, instance_count, mesh, shader })
+    }
+}
+
+impl GameObject for Foliage { }
+
+impl WorldObject for Foliage {
+    #[inline]
+    fn ref_transform(&self) -> &Mat4x4 {
+        &self.mat
+    }
+
+    #[inline]
+    fn mut_transform(&mut self) -> &mut Mat4x4 {
+        &mut self.mat
+    }
+}
+
+impl DrawAttributePrimary for Foliage {
+    fn draw(
+        &self, 
+        _render_ctx: &Arc<RenderContext>, 
+        command_buffer_builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>
+    ) -> Result<(), RuntimeError> {
+        unsafe { record_instanced_draw(command_buffer_builder, &self.mesh, &self.shader, self.mat, self.instance_count) }
+    }
+}
+
+impl DrawAttributeSecondary for Foliage {
+    fn darw(
+        &self, 
+        _render_ctx: &Arc<RenderContext>, 
+        command_buffer_builder: &mut AutoCommandBufferBuilder<SecondaryAutoCommandBuffer>
+    ) -> Result<(), RuntimeError> {
+        unsafe { record_instanced_draw(command_buffer_builder, &self.mesh, &self.shader, self.mat, self.instance_count) }
+    }
+}
+
+impl DrawableObject for Foliage {
+    #[inline]
+    fn is_visible(&self) -> bool {
+        true
+    }
+}
+
+impl DynamicObject for Foliage { }
+
+
+/// Record the draw of all instances of the mesh with a single instanced draw call.
+/// The instance transforms and colors come from the per-instance vertex buffers of the mesh.
+#[inline]
+unsafe fn record_instanced_draw<M, S>(
+    commands: &mut impl DrawCommands<M, S>,
+    mesh: &M,
+    shader: &S,
+    transform: Mat4x4,
+    instance_count: u32
+) -> Result<(), RuntimeError> {
+    commands.bind_shader(shader, ObjectData { color: Vec4::ONE, transform });
+    commands.draw_mesh(mesh, instance_count, 0)
+}
+
+
+
+/// Record the draw of a model node.
+/// The shader override is bound instead of the node's shader if it is given.
+#[inline]
+unsafe fn record_node_draw<M, S>(
+    commands: &mut impl DrawCommands<M, S>,
+    mesh: Option<&M>,
+    shader: Option<&S>,
+    shader_override: Option<&S>,
+    object_data: ObjectData,
+    instance_index: u32
+) -> Result<(), RuntimeError> {
+    if let Some(shader) = shader_override.or(shader) {
+        commands.bind_shader(shader, object_data);
+    }
+    if let Some(mesh) = mesh {
+        commands.draw_mesh(mesh, 1, instance_index)?;
+    }
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+
+    use super::*;
+
+    /// the draw commands recorded without a device. meshes and shaders are named by strings.
+    #[derive(Debug, Default)]
+    struct RecordedCommands {
+        bound_shaders: Vec<(&'static str, ObjectData)>,
+        draws: Vec<(&'static str, u32, u32)>,
+    }
+
+    impl DrawCommands<&'static str, &'static str> for RecordedCommands {
+        unsafe fn bind_shader(&mut self, shader: &&'static str, object_data: ObjectData) {
+            self.bound_shaders.push((shader, object_data));
+        }
+
+        unsafe fn draw_mesh(&mut self, mesh: &&'static str, instance_count: u32, first_instance: u32) -> Result<(), RuntimeError> {
+            self.draws.push((mesh, instance_count, first_instance));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn foliage_instance_count_follows_density() {
+        let (region_min, region_max) = (Vec2::new_vector(-10.0, -5.0), Vec2::new_vector(10.0, 5.0));
+        let instances = scatter_foliage_instances(&mut StdRng::seed_from_u64(0xf011a6e), region_min, region_max, 2.5);
+        assert_eq!(instances.len(), 500);
+        for (mat, color) in instances.iter() {
+            assert!(region_min.x <= mat.r4c1 && mat.r4c1 <= region_max.x);
+            assert!(region_min.y <= mat.r4c3 && mat.r4c3 <= region_max.y);
+            assert_eq!(mat.r4c2, 0.0);
+            assert_eq!(color.w, 1.0);
+        }
+
+        assert!(scatter_foliage_instances(&mut StdRng::seed_from_u64(0), region_min, region_min, 2.5).is_empty());
+    }
+
+    #[test]
+    fn foliage_scatter_is_reproducible_with_the_same_seed() {
+        let scatter = |seed| scatter_foliage_instances(&mut StdRng::seed_from_u64(seed), Vec2::ZERO, Vec2::ONE, 16.0);
+        assert_eq!(scatter(7), scatter(7));
+        assert_ne!(scatter(7), scatter(8));
+    }
+
+    #[test]
+    fn foliage_is_drawn_with_a_single_draw_call() {
+        let mut commands = RecordedCommands::default();
+        let transform = Mat4x4::from_translation(Vec3::new_vector(0.0, -3.0, 0.0));
+        unsafe { record_instanced_draw(&mut commands, &"blade", &"foliage", transform, 500) }.unwrap();
+
+        assert_eq!(commands.bound_shaders.len(), 1);
+        assert_eq!(commands.bound_shaders[0].0, "foliage");
+        assert_eq!(commands.bound_shaders[0].1.transform, transform);
+        assert_eq!(commands.draws, vec![("blade", 500, 0)]);
+    }
+
+    #[test]
+    fn demo_rotation_orbits_90_degrees_in_a_second() {
+        let start = Vec3::new_vector(0.0, 2.0, -10.0);
+        let end = orbit_position(start, demo_rotation_angle(90.0, 1.0)).unwrap();
+
+        let (start_radius, start_azimuth, start_elevation) = start.to_spherical();
+        let (end_radius, end_azimuth, end_elevation) = end.to_spherical();
+        let advanced = (end_azimuth - start_azimuth).rem_euclid(std::f32::consts::TAU);
+        assert!((advanced.to_degrees() - 90.0).abs() <= 1e-3, "{}", advanced.to_degrees());
+        assert!((end_radius - start_radius).abs() <= 1e-4);
+        assert!((end_elevation - start_elevation).abs() <= 1e-5);
+
+        assert!(orbit_position(Vec3::ZERO, 1.0).is_none());
+    }
+
+
+    #[test]
+    fn shader_override_is_bound_instead_of_the_node_shader() {
+        let object_data = ObjectData { color: Vec4::ONE, transform: Mat4x4::IDENTITY };
+
+        let mut commands = RecordedCommands::default();
+        unsafe { record_node_draw(&mut commands, Some(&"cube"), Some(&"default"), Some(&"highlight"), object_data, 2) }.unwrap();
+        assert_eq!(commands.bound_shaders, vec![("highlight", object_data)]);
+        assert_eq!(commands.draws, vec![("cube", 1, 2)]);
+
+        let mut commands = RecordedCommands::default();
+        unsafe { record_node_draw(&mut commands, Some(&"cube"), Some(&"default"), None, object_data, 2) }.unwrap();
+        assert_eq!(commands.bound_shaders, vec![("default", object_data)]);
+    }
+
+
+    #[test]
+    fn previous_matrices_are_the_last_frame_matrices() {
+        let mut prev_matrices = None;
+        let first_view = Mat4x4::from_translation(Vec3::new_vector(0.0, 0.0, -10.0));
+        let first_projection = perspective_lh_zo(Camera::FOV_Y, 1.0, Camera::NEAR, Camera::FAR);
+
+        // the first frame has no history, so it falls back to the current matrices.
+        let first = advance_camera_data(&mut prev_matrices, first_view, first_projection, Vec2::ZERO);
+        assert_eq!((first.prev_view, first.prev_projection), (first_view, first_projection));
+
+        let second_view = Mat4x4::from_translation(Vec3::new_vector(1.0, 0.0, -10.0));
+        let mut second_projection = first_projection;
+        second_projection.r3c1 += 0.01;
+        let second = advance_camera_data(&mut prev_matrices, second_view, second_projection, Vec2::new_vector(0.01, 0.0));
+        assert_eq!((second.view, second.projection), (second_view, second_projection));
+        assert_eq!((second.prev_view, second.prev_projection), (first_view, first_projection));
+        assert_eq!(second.jitter, Vec4::new_vector(0.01, 0.0, 0.0, 0.0));
+    }
+
+
+    #[test]
+    fn camera_shake_decays_to_zero() {
+        let mut shake = CameraShake::new(0.5, 1.0);
+        let mut amplitude = shake.get_amplitude();
+        assert_eq!(amplitude, 0.5);
+        for _ in 0..59 {
+            shake.advance(1.0 / 60.0);
+            assert!(shake.get_amplitude() <= amplitude);
+            amplitude = shake.get_amplitude();
+        }
+        assert!(amplitude < 1e-3, "{}", amplitude);
+
+        // past the end of the duration.
+        shake.advance(0.1);
+        assert!(shake.is_finished());
+        assert_eq!(shake.get_shake_mat(), Mat4x4::IDENTITY);
+    }
+
+    #[test]
+    fn zero_intensity_shake_leaves_the_view_unchanged() {
+        let view = Mat4x4::from_translation(Vec3::new_vector(0.0, 0.0, -10.0));
+        let mut shake = CameraShake::new(0.0, 1.0);
+        for _ in 0..30 {
+            shake.advance(1.0 / 60.0);
+            assert_eq!(view * shake.get_shake_mat(), view);
+        }
+    }
+
+
+    #[test]
+    fn near_sphere_projects_larger_than_far_sphere() {
+        let (position, look) = (Vec3::ZERO, Vec3::Z);
+        let near = projected_sphere_radius(position, look, Vec3::new_vector(0.0, 0.0, 5.0), 1.0, 1080);
+        let far = projected_sphere_radius(position, look, Vec3::new_vector(0.0, 0.0, 50.0), 1.0, 1080);
+        assert!(near > far && far > 0.0, "{} {}", near, far);
+        // the radius is inversely proportional to the depth.
+        assert!((near / far - 10.0).abs() < 1e-3, "{}", near / far);
+
+        assert_eq!(projected_sphere_radius(position, look, Vec3::new_vector(0.0, 0.0, -5.0), 1.0, 1080), 0.0);
+    }
+
+    #[test]
+    fn jitter_offsets_the_projection_only_when_enabled() {
+        let projection = perspective_lh_zo(Camera::FOV_Y, 1.0, Camera::NEAR, Camera::FAR);
+        assert_eq!((projection.r3c1, projection.r3c2), (0.0, 0.0));
+
+        let mut jitter_index = 0;
+        let mut offsets = Vec::new();
+        for _ in 0..Camera::TAA_JITTER_SAMPLES {
+            let jitter = next_taa_jitter(true, &mut jitter_index, 100, 50);
+            let jittered = jitter_projection(projection, jitter);
+            assert!(jittered.r3c1 != 0.0 || jittered.r3c2 != 0.0, "{:?}", jitter);
+            // the jitter stays within half a pixel.
+            assert!(jittered.r3c1.abs() <= 1.0 / 100.0 && jittered.r3c2.abs() <= 1.0 / 50.0, "{:?}", jitter);
+            offsets.push((jittered.r3c1, jittered.r3c2));
+        }
+        // the first sample is (halton(1, 2), halton(1, 3)) = (1/2, 1/3), shifted into `[-0.5, 0.5)` pixel.
+        assert_eq!(offsets[0].0, 0.0);
+        assert!((offsets[0].1 - (1.0 / 3.0 - 0.5) * 2.0 / 50.0).abs() <= 1e-7, "{:?}", offsets[0]);
+        assert!(offsets.windows(2).all(|pair| pair[0] != pair[1]));
+
+        let mut jitter_index = 0;
+        for _ in 0..Camera::TAA_JITTER_SAMPLES {
+            let jittered = jitter_projection(projection, next_taa_jitter(false, &mut jitter_index, 100, 50));
+            assert_eq!((jittered.r3c1, jittered.r3c2), (0.0, 0.0));
+        }
+        assert_eq!(jitter_index, 0);
+    }
+}
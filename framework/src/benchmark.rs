@@ -0,0 +1,122 @@
+use std::time::Instant;
+
+/// Frame time statistics of a benchmark. (millisecond)
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct BenchmarkResult {
+    pub frame_count: u32,
+    pub average_ms: f32,
+    pub min_ms: f32,
+    pub max_ms: f32,
+    pub p50_ms: f32,
+    pub p95_ms: f32,
+    pub p99_ms: f32,
+}
+
+impl BenchmarkResult {
+    /// Aggregate the given frame times. (second)
+    /// Percentiles use the nearest-rank method.
+    pub fn from_frame_times(frame_times: &[f32]) -> Self {
+        if frame_times.is_empty() {
+            return Self::default();
+        }
+
+        let mut sorted: Vec<f32> = frame_times.iter().map(|&t| t * 1000.0).collect();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+
+        let percentile = |p: f32| -> f32 {
+            let rank = (p / 100.0 * sorted.len() as f32).ceil() as usize;
+            sorted[rank.clamp(1, sorted.len()) - 1]
+        };
+
+        Self {
+            frame_count: sorted.len() as u32,
+            average_ms: sorted.iter().sum::<f32>() / sorted.len() as f32,
+            min_ms: sorted[0],
+            max_ms: sorted[sorted.len() - 1],
+            p50_ms: percentile(50.0),
+            p95_ms: percentile(95.0),
+            p99_ms: percentile(99.0),
+        }
+    }
+}
+
+
+/// Collects per-frame times on the CPU over a measurement window.
+#[derive(Debug, Clone)]
+pub struct Benchmark {
+    duration_in_sec: f32,
+    base_time_point: Option<Instant>,
+    prev_time_point: Option<Instant>,
+    frame_times: Vec<f32>,
+}
+
+impl Benchmark {
+    #[inline]
+    pub fn new(duration_in_sec: f32) -> Self {
+        Self {
+            duration_in_sec,
+            base_time_point: None,
+            prev_time_point: None,
+            frame_times: Vec::new(),
+        }
+    }
+
+    /// Record the end of a frame.
+    /// The first call only starts the measurement window.
+    pub fn record_frame(&mut self) {
+        let time_point = Instant::now();
+        if let Some(prev_time_point) = self.prev_time_point {
+            self.frame_times.push(
+                time_point.saturating_duration_since(prev_time_point).as_secs_f32()
+            );
+        }
+        self.base_time_point.get_or_insert(time_point);
+        self.prev_time_point = Some(time_point);
+    }
+
+    /// Returns `true` if the measurement window has elapsed.
+    #[inline]
+    pub fn is_finished(&self) -> bool {
+        match (self.base_time_point, self.prev_time_point) {
+            (Some(base), Some(prev)) => {
+                prev.saturating_duration_since(base).as_secs_f32() >= self.duration_in_sec
+            },
+            _ => false,
+        }
+    }
+
+    #[inline]
+    pub fn get_result(&self) -> BenchmarkResult {
+        BenchmarkResult::from_frame_times(&self.frame_times)
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn result_aggregates_frame_times() {
+        // 1ms to 100ms, shuffled so the aggregation does not rely on the recording order.
+        let mut frame_times: Vec<f32> = (1..=100).map(|ms| ms as f32 / 1000.0).collect();
+        frame_times.reverse();
+        frame_times.swap(10, 70);
+
+        let result = BenchmarkResult::from_frame_times(&frame_times);
+        assert_eq!(result.frame_count, 100);
+        assert!((result.average_ms - 50.5).abs() < 1e-3, "average: {}", result.average_ms);
+        assert!((result.min_ms - 1.0).abs() < 1e-3, "min: {}", result.min_ms);
+        assert!((result.max_ms - 100.0).abs() < 1e-3, "max: {}", result.max_ms);
+        assert!((result.p50_ms - 50.0).abs() < 1e-3, "p50: {}", result.p50_ms);
+        assert!((result.p95_ms - 95.0).abs() < 1e-3, "p95: {}", result.p95_ms);
+        assert!((result.p99_ms - 99.0).abs() < 1e-3, "p99: {}", result.p99_ms);
+    }
+
+    #[test]
+    fn result_of_no_frames_is_default() {
+        assert_eq!(BenchmarkResult::from_frame_times(&[]), BenchmarkResult::default());
+    }
+}
@@ -7,18 +7,44 @@ macro_rules! err {
     };
 }
 
+/// The kind of a runtime error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// The operation failed.
+    Runtime,
+    /// The device does not support the operation. The rest of the framework keeps working.
+    Unsupported,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct RuntimeError {
     file: &'static str,
     line: u32,
     column: u32,
     message: String,
+    kind: ErrorKind,
 }
 
 impl RuntimeError {
     #[inline]
     pub fn new(file: &'static str, line: u32, column: u32, message: String) -> Self {
-        Self { file, line, column, message }
+        Self { file, line, column, message, kind: ErrorKind::Runtime }
+    }
+
+    /// Returns the error with the given kind.
+    #[inline]
+    pub fn with_kind(self, kind: ErrorKind) -> Self {
+        Self { kind, ..self }
+    }
+
+    #[inline]
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    #[inline]
+    pub fn is_unsupported(&self) -> bool {
+        self.kind == ErrorKind::Unsupported
     }
 
     #[inline]
@@ -1,20 +1,71 @@
 #![allow(unused_imports)]
 use std::ffi::c_void;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
+use vulkano::image::SampleCount;
+use vulkano::pipeline::graphics::viewport::Scissor;
+
+use crate::math::*;
 use crate::timer::*;
+use crate::benchmark::*;
 use crate::renderer::*;
+use crate::input::*;
 use crate::world::scene::SceneManager;
+use crate::world::pool::ObjectPoolStats;
 use crate::{err, error::RuntimeError};
 
 use crate::app::*;
 
 
+/// The settings of a `Framework`, collected from the `setFramework*` functions before it is created.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameworkConfig {
+    pub max_objects: usize,
+    /// the camera demo-rotation speed. (degree per second) `None` disables the demo-rotation.
+    pub demo_rotation: Option<f32>,
+    pub render_scale: f32,
+    pub max_concurrent_uploads: usize,
+    pub coalesce_touch_moves: bool,
+    pub sample_count: u32,
+    /// the rate at which the objects are updated. (hertz) `None` updates the objects once per frame.
+    pub update_rate: Option<f32>,
+    pub wide_color: bool,
+    pub texture_budget: u64,
+    /// the settings of the screen-space ambient occlusion. `None` disables it.
+    pub ssao: Option<SsaoSettings>,
+}
+
+impl FrameworkConfig {
+    pub const DEFAULT: Self = Self {
+        max_objects: MAX_OBJECTS_NUM,
+        demo_rotation: None,
+        render_scale: 1.0,
+        max_concurrent_uploads: MAX_CONCURRENT_UPLOADS,
+        coalesce_touch_moves: true,
+        sample_count: 1,
+        update_rate: None,
+        wide_color: false,
+        texture_budget: u64::MAX,
+        ssao: None,
+    };
+}
+
+impl Default for FrameworkConfig {
+    #[inline]
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+
 #[derive(Debug)]
 pub struct Framework {
     timer: Timer,
     renderer: Renderer,
     scene_manager: SceneManager,
+    touch_queue: TouchQueue,
+    benchmark: Option<(Benchmark, RenderPresentMode)>,
+    benchmark_result: Option<BenchmarkResult>,
 }
 
 impl Framework {
@@ -24,11 +75,20 @@ impl Framework {
         scale_factor: f32,
         screen_size: (u32, u32),
         viewer_area: (i32, i32, i32, i32),
+        config: FrameworkConfig,
     ) -> Result<Self, RuntimeError> {
         let timer = Timer::new();
-        let renderer = Renderer::new(handle, &assets_dir, scale_factor, screen_size, viewer_area)?;
+        let samples = SampleCount::try_from(config.sample_count)
+            .map_err(|_| err!("Unsupported sample count: {}", config.sample_count))?;
+        let mut renderer = Renderer::new(handle, &assets_dir, scale_factor, config.render_scale, screen_size, viewer_area, &[], samples, config.wide_color)?;
+        let mut main_scene = MainScene::with_max_objects(config.max_objects);
+        main_scene.set_demo_rotation(config.demo_rotation);
+        main_scene.set_update_rate(config.update_rate);
+        main_scene.set_max_concurrent_uploads(config.max_concurrent_uploads);
+        main_scene.set_texture_budget(config.texture_budget);
+        main_scene.set_ssao(config.ssao);
         let scene_manager = SceneManager::new(
-            [("Main".to_string(), MainScene::new() as _)],
+            [("Main".to_string(), main_scene as _)],
             "Main".to_string(),
             &renderer
         )?;
@@ -37,12 +97,28 @@ impl Framework {
             timer,
             renderer,
             scene_manager,
+            touch_queue: TouchQueue::new(config.coalesce_touch_moves),
+            benchmark: None,
+            benchmark_result: None,
         })
     }
 
     pub fn frame_advanced(&mut self) -> Result<(), RuntimeError> {
-        self.timer.tick(Some(60));
+        // the frame rate is not capped while benchmarking.
+        self.timer.tick(match self.benchmark {
+            Some(_) => None,
+            None => Some(60),
+        });
+        let touch_events = self.touch_queue.take_events();
+        self.scene_manager.touch(&touch_events, &self.renderer)?;
         self.scene_manager.frame_advanced(&mut self.timer, &mut self.renderer)?;
+
+        if let Some((benchmark, _)) = &mut self.benchmark {
+            benchmark.record_frame();
+            if benchmark.is_finished() {
+                self.end_benchmark()?;
+            }
+        }
         
         #[cfg(feature = "monitor")]
         println!("<monitor> frame_rate={}", self.timer.get_frame_rate());
@@ -50,6 +126,184 @@ impl Framework {
         Ok(())
     }
 
+    /// Begin measuring frame times for the given duration.
+    /// The swapchain uses `RenderPresentMode::Immediate` while benchmarking, if supported.
+    /// 
+    /// # Runtime Errors
+    /// - Returns a runtime error message if the duration is not a positive number.
+    /// - Returns a runtime error message if changing the present mode fails.
+    /// 
+    pub fn begin_benchmark(&mut self, duration_in_sec: f32) -> Result<(), RuntimeError> {
+        if !(duration_in_sec > 0.0 && duration_in_sec.is_finite()) {
+            return Err(err!("The benchmark duration must be a positive number."));
+        }
+
+        let present_mode = match &self.benchmark {
+            Some((_, present_mode)) => *present_mode,
+            None => self.renderer.get_present_mode(),
+        };
+        self.renderer.set_present_mode(RenderPresentMode::Immediate)?;
+        self.benchmark = Some((Benchmark::new(duration_in_sec), present_mode));
+        self.benchmark_result = None;
+
+        #[cfg(feature = "monitor")]
+        println!("<monitor> benchmark started. (duration={}sec)", duration_in_sec);
+
+        Ok(())
+    }
+
+    /// Finish the running benchmark and restore the previous present mode.
+    fn end_benchmark(&mut self) -> Result<(), RuntimeError> {
+        if let Some((benchmark, present_mode)) = self.benchmark.take() {
+            self.renderer.set_present_mode(present_mode)?;
+            self.benchmark_result = Some(benchmark.get_result());
+
+            #[cfg(feature = "monitor")]
+            println!("<monitor> benchmark finished. ({:?})", self.benchmark_result.as_ref().unwrap());
+        }
+        Ok(())
+    }
+
+    /// Change the present mode of the swapchain. See `Renderer::set_present_mode`.
+    /// While benchmarking, the present mode is applied when the benchmark finishes.
+    /// 
+    /// # Runtime Errors
+    /// - Returns a runtime error message if getting the surface present modes fails.
+    /// 
+    pub fn set_present_mode(&mut self, present_mode: RenderPresentMode) -> Result<RenderPresentMode, RuntimeError> {
+        if let Some((_, restored_mode)) = self.benchmark.as_mut() {
+            *restored_mode = present_mode;
+            return Ok(present_mode);
+        }
+        self.renderer.set_present_mode(present_mode)
+    }
+
+    #[inline]
+    pub fn get_present_mode(&self) -> RenderPresentMode {
+        self.renderer.get_present_mode()
+    }
+
+    /// Returns the result of the last finished benchmark.
+    #[inline]
+    pub fn get_benchmark_result(&self) -> Option<BenchmarkResult> {
+        self.benchmark_result
+    }
+
+    /// Request a new screen size. See `Renderer::resize`.
+    #[inline]
+    pub fn resize(&mut self, screen_width: u32, screen_height: u32) {
+        self.renderer.resize(screen_width, screen_height)
+    }
+
+    /// Queue a touch event. (iOS logical points)
+    /// The queued events are passed to the current scene on the next frame. See `TouchQueue`.
+    #[inline]
+    pub fn touch(&mut self, touch_id: u64, phase: TouchPhase, x: f32, y: f32) {
+        self.touch_queue.push(touch_id, phase, (x, y))
+    }
+
+    /// Constrain rendering to the given region of the drawable. (pixel) See `Renderer::set_present_region`.
+    /// 
+    /// # Runtime Errors
+    /// - Returns a runtime error message if the region is empty or out of range.
+    /// 
+    #[inline]
+    pub fn set_present_region(&mut self, region: Option<(u32, u32, u32, u32)>) -> Result<(), RuntimeError> {
+        self.renderer.set_present_region(region.map(|(x, y, width, height)| Scissor {
+            origin: [x, y],
+            dimensions: [width, height],
+        }))
+    }
+
+    /// Rebuild the renderer from the stored `AppHandle` and recreate the GPU resources of the current scene.
+    /// The logical scene state (e.g. object transforms and the camera) is preserved.
+    /// See `Renderer::recreate`.
+    /// 
+    /// # Runtime Errors
+    /// - Returns a runtime error message if renderer recreation fails.
+    /// - Returns a runtime error message if scene resource recreation fails.
+    /// 
+    pub fn recreate_renderer(&mut self) -> Result<(), RuntimeError> {
+        self.renderer.recreate()?;
+        self.scene_manager.recreate_resources(&self.renderer)
+    }
+
+    /// Reload a shader from disk and rebuild the pipelines of the current scene that use it.
+    /// See `Renderer::reload_shader` and `SceneManager::reload_shaders`.
+    /// 
+    /// # Runtime Errors
+    /// - Returns a runtime error message if reading the file or creating the shader module fails.
+    /// - Returns a runtime error message if rebuilding the pipelines fails. The old pipelines are kept in that case.
+    /// 
+    #[inline]
+    pub fn reload_shader(&mut self, path: &Path) -> Result<(), RuntimeError> {
+        self.renderer.reload_shader(path)?;
+        self.scene_manager.reload_shaders(&mut self.renderer)
+    }
+
+    /// Set the color of an object in the current scene. See `SceneManager::set_object_color`.
+    #[inline]
+    pub fn set_object_color(&mut self, object_id: usize, color: Vec4) -> Result<(), RuntimeError> {
+        self.scene_manager.set_object_color(object_id, color)
+    }
+
+    /// Set the animation speed of an object in the current scene. See `SceneManager::set_object_speed`.
+    #[inline]
+    pub fn set_object_speed(&mut self, object_id: usize, speed: f32) -> Result<(), RuntimeError> {
+        self.scene_manager.set_object_speed(object_id, speed)
+    }
+
+    /// Spawn an object in the current scene. See `SceneManager::spawn_object`.
+    #[inline]
+    pub fn spawn_object(&mut self) -> Option<usize> {
+        self.scene_manager.spawn_object()
+    }
+
+    /// Despawn an object in the current scene. See `SceneManager::despawn_object`.
+    #[inline]
+    pub fn despawn_object(&mut self, object_id: usize) -> bool {
+        self.scene_manager.despawn_object(object_id)
+    }
+
+    /// Get the object pool statistics of the current scene. See `SceneManager::get_object_pool_stats`.
+    #[inline]
+    pub fn get_object_pool_stats(&mut self) -> Option<ObjectPoolStats> {
+        self.scene_manager.get_object_pool_stats()
+    }
+
+    /// Shake the camera of the current scene. See `SceneManager::trigger_camera_shake`.
+    #[inline]
+    pub fn trigger_camera_shake(&mut self, intensity: f32, duration_in_sec: f32) -> bool {
+        self.scene_manager.trigger_camera_shake(intensity, duration_in_sec)
+    }
+
+    /// Describe the current configuration of the framework for bug reports.
+    /// The dump contains the device, the screen, the render state and the state of the current scene, one item per line.
+    pub fn debug_dump(&self) -> String {
+        let render_ctx = self.renderer.ref_render_context();
+        let properties = render_ctx.ref_device().physical_device().properties();
+        let (screen_width, screen_height) = self.renderer.get_screen_size();
+        let (render_width, render_height) = self.renderer.get_render_size();
+        let enabled_features: Vec<_> = render_ctx.ref_device_enabled_features().into_iter()
+            .filter_map(|(name, enabled)| enabled.then_some(name))
+            .collect();
+
+        let mut dump = String::new();
+        dump += &format!("device: {} ({:?})\n", properties.device_name, properties.device_type);
+        dump += &format!("api version: {}\n", properties.api_version);
+        dump += &format!("enabled features: [{}]\n", enabled_features.join(", "));
+        dump += &format!("screen size: {}x{}\n", screen_width, screen_height);
+        dump += &format!("scale factor: {}\n", self.renderer.get_scale_factor());
+        dump += &format!("render size: {}x{}\n", render_width, render_height);
+        dump += &format!("swapchain format: {:?}\n", self.renderer.get_swapchain_format());
+        dump += &format!("present mode: {:?}\n", self.renderer.get_present_mode());
+        dump += &format!("sample count: {:?}\n", self.renderer.get_sample_count());
+        dump += &format!("color space: {:?}\n", self.renderer.get_color_space());
+        dump += &format!("benchmarking: {}\n", self.benchmark.is_some());
+        dump += &self.scene_manager.debug_dump();
+        dump
+    }
+
     pub fn paused(&mut self) -> Result<(), RuntimeError> {
         self.timer.pause();
         self.scene_manager.pause(&self.timer, &self.renderer)?;
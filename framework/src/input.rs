@@ -0,0 +1,150 @@
+use std::collections::HashMap;
+
+
+
+/// The phase of a touch event. The values are the same as the `UITouch.Phase` values of UIKit.
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TouchPhase {
+    Began = 0,
+    Moved = 1,
+    Ended = 3,
+    Cancelled = 4,
+}
+
+impl TouchPhase {
+    /// Convert a `UITouch.Phase` value. Returns `None` if the value is not a supported phase.
+    #[inline]
+    pub fn from_raw(value: u32) -> Option<Self> {
+        match value {
+            0 => Some(Self::Began),
+            1 => Some(Self::Moved),
+            3 => Some(Self::Ended),
+            4 => Some(Self::Cancelled),
+            _ => None,
+        }
+    }
+}
+
+
+
+/// A touch event. (iOS logical points)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TouchEvent {
+    pub id: u64,
+    pub phase: TouchPhase,
+    pub position: (f32, f32),
+    /// The movement since the previous event of the touch. `(0, 0)` when the touch begins.
+    pub delta: (f32, f32),
+}
+
+
+
+/// The touch events received since the last frame.
+/// 
+/// Touch-move events may arrive faster than the frame rate. (e.g. 120Hz displays)
+/// When coalescing is enabled, consecutive `Moved` events of the same touch are merged into one event
+/// with the latest position and the accumulated movement. `Began`, `Ended` and `Cancelled` events are never merged.
+#[derive(Debug)]
+pub struct TouchQueue {
+    events: Vec<TouchEvent>,
+    positions: HashMap<u64, (f32, f32)>,
+    coalesce_moves: bool,
+}
+
+impl TouchQueue {
+    #[inline]
+    pub fn new(coalesce_moves: bool) -> Self {
+        Self { events: Vec::new(), positions: HashMap::new(), coalesce_moves }
+    }
+
+    #[inline]
+    pub fn set_coalesce_moves(&mut self, coalesce_moves: bool) {
+        self.coalesce_moves = coalesce_moves;
+    }
+
+    #[inline]
+    pub fn is_coalescing_moves(&self) -> bool {
+        self.coalesce_moves
+    }
+
+    /// Add a touch event to the queue.
+    pub fn push(&mut self, id: u64, phase: TouchPhase, position: (f32, f32)) {
+        let previous = match phase {
+            TouchPhase::Began => None,
+            _ => self.positions.get(&id).copied(),
+        };
+        let delta = previous.map_or((0.0, 0.0), |(x, y)| (position.0 - x, position.1 - y));
+        match phase {
+            TouchPhase::Began | TouchPhase::Moved => { self.positions.insert(id, position); },
+            TouchPhase::Ended | TouchPhase::Cancelled => { self.positions.remove(&id); },
+        }
+
+        if self.coalesce_moves && phase == TouchPhase::Moved {
+            // merge with the last queued event of the touch if it is also a move.
+            if let Some(last) = self.events.iter_mut().rev().find(|event| event.id == id) {
+                if last.phase == TouchPhase::Moved {
+                    last.position = position;
+                    last.delta = (last.delta.0 + delta.0, last.delta.1 + delta.1);
+                    return;
+                }
+            }
+        }
+
+        self.events.push(TouchEvent { id, phase, position, delta });
+    }
+
+    /// Take the queued events in the order they were received.
+    #[inline]
+    pub fn take_events(&mut self) -> Vec<TouchEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+}
+
+impl Default for TouchQueue {
+    #[inline]
+    fn default() -> Self {
+        Self::new(true)
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn three_moves_coalesce_into_one_with_the_final_position() {
+        let mut queue = TouchQueue::new(true);
+        queue.push(7, TouchPhase::Began, (0.0, 0.0));
+        queue.take_events();
+
+        queue.push(7, TouchPhase::Moved, (1.0, 0.0));
+        queue.push(7, TouchPhase::Moved, (2.0, 1.0));
+        queue.push(7, TouchPhase::Moved, (4.0, 3.0));
+
+        let events = queue.take_events();
+        assert_eq!(events, [TouchEvent { id: 7, phase: TouchPhase::Moved, position: (4.0, 3.0), delta: (4.0, 3.0) }]);
+    }
+
+    #[test]
+    fn moves_are_kept_when_coalescing_is_disabled() {
+        let mut queue = TouchQueue::new(false);
+        queue.push(7, TouchPhase::Began, (0.0, 0.0));
+        queue.push(7, TouchPhase::Moved, (1.0, 0.0));
+        queue.push(7, TouchPhase::Moved, (2.0, 1.0));
+        queue.push(7, TouchPhase::Ended, (2.0, 1.0));
+        assert_eq!(queue.len(), 4);
+    }
+}
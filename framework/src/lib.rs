@@ -2,13 +2,15 @@
 mod app;
 mod math;
 mod timer;
+mod benchmark;
 mod error;
 mod world;
 mod renderer;
 mod framework;
+mod worker;
+mod input;
 
 use std::ptr;
-use std::str::FromStr;
 use std::path::PathBuf;
 use std::ffi::{c_void, c_char, CString, CStr};
 
@@ -16,9 +18,253 @@ use std::ffi::{c_void, c_char, CString, CStr};
 
 use error::RuntimeError;
 use renderer::AppHandle;
-use framework::Framework;
+use framework::{Framework, FrameworkConfig};
+use benchmark::BenchmarkResult;
+use world::pool::ObjectPoolStats;
 
 static mut LAST_FRAMEWORK_ERR_MSG: Option<RuntimeError> = None;
+static mut FRAMEWORK_CONFIG: FrameworkConfig = FrameworkConfig::DEFAULT;
+
+
+/// An opaque handle to a `Framework` passed across the C interface.
+/// It has the same layout as a `void *`.
+#[repr(transparent)]
+#[derive(Debug, Clone, Copy)]
+pub struct FrameworkHandle(*mut c_void);
+
+impl FrameworkHandle {
+    #[inline]
+    #[cfg(target_os = "ios")]
+    fn new(framework: Framework) -> Self {
+        Self::from_box(Box::new(framework))
+    }
+
+    /// Create a handle that owns the boxed object.
+    #[inline]
+    fn from_box<T>(object: Box<T>) -> Self {
+        Self(Box::into_raw(object) as *mut c_void)
+    }
+
+    #[inline]
+    fn null() -> Self {
+        Self(ptr::null_mut())
+    }
+}
+
+
+/// Borrow the framework of the handle and call the function with it.
+/// 
+/// # Safety
+/// - The handle must be returned by `createFramework` and not yet passed to `destroyFramework`.
+/// - The handle must not be used by more than one thread at a time.
+/// 
+/// # Panics
+/// - Stop program execution if the handle is a null pointer.
+/// 
+#[inline]
+unsafe fn with_framework<R>(handle: FrameworkHandle, f: impl FnOnce(&mut Framework) -> R) -> R {
+    with_object(handle, f)
+}
+
+
+/// Borrow the object of the handle and call the function with it.
+/// 
+/// # Safety
+/// - The handle must own a `T` created by `FrameworkHandle::from_box` and not yet passed to `destroy_object`.
+/// - The handle must not be used by more than one thread at a time.
+/// 
+/// # Panics
+/// - Stop program execution if the handle is a null pointer.
+/// 
+#[inline]
+unsafe fn with_object<T, R>(handle: FrameworkHandle, f: impl FnOnce(&mut T) -> R) -> R {
+    assert!(!handle.0.is_null(), "framework cannot be a null pointer.");
+    f(&mut *(handle.0 as *mut T))
+}
+
+
+/// Drop the object owned by the handle.
+/// 
+/// # Safety
+/// - The handle must own a `T` created by `FrameworkHandle::from_box` and not yet passed to `destroy_object`.
+/// 
+/// # Panics
+/// - Stop program execution if the handle is a null pointer.
+/// 
+#[inline]
+unsafe fn destroy_object<T>(handle: FrameworkHandle) {
+    assert!(!handle.0.is_null(), "framework cannot be a null pointer.");
+    drop(Box::from_raw(handle.0 as *mut T));
+}
+
+
+/// Copies the string into the buffer as a null-terminated string.
+/// The string is truncated to `buf_size - 1` bytes if it does not fit.
+/// Returns `true` if the whole string was copied.
+/// 
+/// # Safety
+/// - The buffer must be valid for writes of `buf_size` bytes.
+/// 
+/// # Panics
+/// - Stop program execution if the buffer is a null pointer or the buffer size is zero.
+/// 
+#[inline]
+unsafe fn copy_to_c_buffer(s: &str, buf: *mut c_char, buf_size: u32) -> bool {
+    assert!(!buf.is_null(), "buffer cannot be a null pointer.");
+    assert!(buf_size > 0, "buffer size cannot be zero.");
+    let len = s.len().min(buf_size as usize - 1);
+    buf.copy_from(s.as_ptr() as *const c_char, len);
+    *buf.add(len) = 0;
+    len == s.len()
+}
+
+
+/// Converts the assets directory path from the host application. A null pointer is an empty path.
+/// 
+/// # Safety
+/// - The path must be a null pointer or a null-terminated string.
+/// 
+/// # Runtime Errors
+/// - Returns a runtime error message if the path is not valid UTF-8. The message is also stored as the last error.
+/// 
+#[inline]
+unsafe fn parse_assets_dir(assets_dir: *const c_char) -> Result<PathBuf, RuntimeError> {
+    if assets_dir.is_null() {
+        return Ok(PathBuf::new());
+    }
+
+    CStr::from_ptr(assets_dir).to_str()
+        .map(PathBuf::from)
+        .map_err(|e| {
+            let msg = err!("Invalid assets directory path: {}", e.to_string());
+            LAST_FRAMEWORK_ERR_MSG = Some(msg.clone());
+            msg
+        })
+}
+
+
+/// Returns the handle if the call succeeded.
+/// Otherwise, stores the error message, destroys the framework and returns a null handle.
+#[inline]
+fn keep_or_destroy(handle: FrameworkHandle, result: Result<(), RuntimeError>) -> FrameworkHandle {
+    match result {
+        Ok(_) => handle,
+        Err(msg) => {
+            unsafe { LAST_FRAMEWORK_ERR_MSG = Some(msg) };
+            destroyFramework(handle);
+            FrameworkHandle::null()
+        }
+    }
+}
+
+
+#[no_mangle]
+pub extern "C" fn setFrameworkMaxObjects(max_objects: u32) -> bool {
+    if max_objects == 0 {
+        unsafe { LAST_FRAMEWORK_ERR_MSG = Some(err!("The maximum number of objects cannot be zero.")) };
+        return false;
+    }
+    unsafe { FRAMEWORK_CONFIG.max_objects = max_objects as usize };
+    true
+}
+
+#[no_mangle]
+pub extern "C" fn setFrameworkDemoMode(enabled: bool, degrees_per_sec: f32) -> bool {
+    if enabled && !degrees_per_sec.is_finite() {
+        unsafe { LAST_FRAMEWORK_ERR_MSG = Some(err!("The demo-rotation speed must be a finite number.")) };
+        return false;
+    }
+    unsafe { FRAMEWORK_CONFIG.demo_rotation = enabled.then_some(degrees_per_sec) };
+    true
+}
+
+#[no_mangle]
+pub extern "C" fn setFrameworkRenderScale(scale: f32) -> bool {
+    if !scale.is_finite() {
+        unsafe { LAST_FRAMEWORK_ERR_MSG = Some(err!("The render scale must be a finite number.")) };
+        return false;
+    }
+    unsafe { FRAMEWORK_CONFIG.render_scale = scale.clamp(renderer::MIN_RENDER_SCALE, renderer::MAX_RENDER_SCALE) };
+    true
+}
+
+#[no_mangle]
+pub extern "C" fn setFrameworkMaxConcurrentUploads(max_concurrent: u32) -> bool {
+    if max_concurrent == 0 {
+        unsafe { LAST_FRAMEWORK_ERR_MSG = Some(err!("The maximum number of concurrent uploads cannot be zero.")) };
+        return false;
+    }
+    unsafe { FRAMEWORK_CONFIG.max_concurrent_uploads = max_concurrent as usize };
+    true
+}
+
+#[no_mangle]
+pub extern "C" fn setFrameworkCoalesceTouchMoves(enabled: bool) {
+    unsafe { FRAMEWORK_CONFIG.coalesce_touch_moves = enabled };
+}
+
+#[no_mangle]
+pub extern "C" fn setFrameworkSampleCount(sample_count: u32) -> bool {
+    // the count is clamped to the counts supported by the device when the framework is created.
+    if !sample_count.is_power_of_two() || sample_count > 64 {
+        unsafe { LAST_FRAMEWORK_ERR_MSG = Some(err!("The sample count must be a power of two from 1 to 64. ({})", sample_count)) };
+        return false;
+    }
+    unsafe { FRAMEWORK_CONFIG.sample_count = sample_count };
+    true
+}
+
+#[no_mangle]
+pub extern "C" fn setFrameworkUpdateRate(hz: f32) -> bool {
+    // zero updates the objects once per frame.
+    if !(hz.is_finite() && hz >= 0.0) {
+        unsafe { LAST_FRAMEWORK_ERR_MSG = Some(err!("The update rate must be a non-negative finite number. ({})", hz)) };
+        return false;
+    }
+    unsafe { FRAMEWORK_CONFIG.update_rate = (hz > 0.0).then_some(hz) };
+    true
+}
+
+#[no_mangle]
+pub extern "C" fn setFrameworkWideColor(enabled: bool) {
+    // the sRGB color space is used if the surface does not support Display-P3.
+    unsafe { FRAMEWORK_CONFIG.wide_color = enabled };
+}
+
+#[no_mangle]
+pub extern "C" fn setFrameworkTextureBudget(bytes: u64) {
+    // the main scene loads its textures through a cache with this budget.
+    // the least recently used textures that are not in use are evicted over the budget.
+    unsafe { FRAMEWORK_CONFIG.texture_budget = bytes };
+}
+
+#[no_mangle]
+pub extern "C" fn setFrameworkSsao(enabled: bool, radius: f32, intensity: f32) -> bool {
+    if enabled && !(radius.is_finite() && radius > 0.0) {
+        unsafe { LAST_FRAMEWORK_ERR_MSG = Some(err!("The ambient occlusion radius must be a positive finite number. ({})", radius)) };
+        return false;
+    }
+    if enabled && !(intensity.is_finite() && intensity >= 0.0) {
+        unsafe { LAST_FRAMEWORK_ERR_MSG = Some(err!("The ambient occlusion intensity must be a non-negative finite number. ({})", intensity)) };
+        return false;
+    }
+    unsafe { FRAMEWORK_CONFIG.ssao = enabled.then_some(renderer::SsaoSettings { radius, intensity }) };
+    true
+}
+
+#[no_mangle]
+pub extern "C" fn setFrameworkWorkerQos(qos_class: u32) -> bool {
+    match worker::WorkerQos::from_raw(qos_class) {
+        Some(qos) => {
+            worker::set_worker_qos(qos);
+            true
+        },
+        None => {
+            unsafe { LAST_FRAMEWORK_ERR_MSG = Some(err!("Unknown worker thread QoS class: {:#x}", qos_class)) };
+            false
+        }
+    }
+}
 
 #[no_mangle]
 #[cfg(target_os = "ios")]
@@ -32,100 +278,401 @@ pub extern "C" fn createFramework(
     viewer_left: i32,
     viewer_bottom: i32,
     viewer_right: i32,
-) -> *mut c_void {
+) -> FrameworkHandle {
     assert!(!ui_view.is_null(), "view cannot be a null pointer.");
     let handle = unsafe { AppHandle::from_ios(ui_view) };
-    let assets_dir = match assets_dir.is_null() {
-        false =>  {
-            let assets_dir = unsafe { CStr::from_ptr(assets_dir as *const i8) };
-            PathBuf::from_str(assets_dir.to_str().unwrap()).unwrap()
-        },
-        true => {
-            PathBuf::new()
-        },
+    let assets_dir = match unsafe { parse_assets_dir(assets_dir) } {
+        Ok(assets_dir) => assets_dir,
+        // the error message is stored by `parse_assets_dir`.
+        Err(_) => return FrameworkHandle::null(),
     };
 
     let screen_size = (screen_width, screen_height);
     let viewer_area = (viewer_top, viewer_left, viewer_bottom, viewer_right);
-    return match Framework::new(handle, assets_dir, scale_factor, screen_size, viewer_area) {
-        Ok(framework) => {
-            Box::into_raw(Box::new(framework)) as *mut c_void
+    return match Framework::new(handle, assets_dir, scale_factor, screen_size, viewer_area, unsafe { FRAMEWORK_CONFIG }) {
+        Ok(framework) => FrameworkHandle::new(framework),
+        Err(msg) => {
+            unsafe { LAST_FRAMEWORK_ERR_MSG = Some(msg) };
+            FrameworkHandle::null()
+        }
+    };
+}
+
+#[no_mangle]
+pub extern "C" fn destroyFramework(framework: FrameworkHandle) {
+    unsafe { destroy_object::<Framework>(framework) };
+}
+
+#[no_mangle]
+pub extern "C" fn updateFramework(framework: FrameworkHandle) -> FrameworkHandle {
+    let result = unsafe { with_framework(framework, |framework| framework.frame_advanced()) };
+    keep_or_destroy(framework, result)
+}
+
+#[no_mangle]
+pub extern "C" fn pauseFramework(framework: FrameworkHandle) -> FrameworkHandle {
+    let result = unsafe { with_framework(framework, |framework| framework.paused()) };
+    keep_or_destroy(framework, result)
+}
+
+#[no_mangle]
+pub extern "C" fn resumeFramework(framework: FrameworkHandle) -> FrameworkHandle {
+    let result = unsafe { with_framework(framework, |framework| framework.resume()) };
+    keep_or_destroy(framework, result)
+}
+
+#[no_mangle]
+pub extern "C" fn frameworkBeginBenchmark(framework: FrameworkHandle, duration_sec: f32) -> bool {
+    // the framework stays usable if the benchmark cannot be started.
+    return match unsafe { with_framework(framework, |framework| framework.begin_benchmark(duration_sec)) } {
+        Ok(_) => true,
+        Err(msg) => {
+            unsafe { LAST_FRAMEWORK_ERR_MSG = Some(msg) };
+            false
+        }
+    };
+}
+
+/// # Safety
+/// - `result` must be valid for writes of a `BenchmarkResult`.
+/// 
+#[no_mangle]
+pub unsafe extern "C" fn frameworkGetBenchmarkResult(framework: FrameworkHandle, result: *mut BenchmarkResult) -> bool {
+    assert!(!result.is_null(), "result cannot be a null pointer.");
+    return match unsafe { with_framework(framework, |framework| framework.get_benchmark_result()) } {
+        Some(benchmark_result) => {
+            unsafe { *result = benchmark_result };
+            true
+        },
+        None => false
+    };
+}
+
+#[no_mangle]
+pub extern "C" fn frameworkResize(framework: FrameworkHandle, screen_width: u32, screen_height: u32) -> bool {
+    if screen_width == 0 || screen_height == 0 {
+        unsafe { LAST_FRAMEWORK_ERR_MSG = Some(err!("The screen size cannot be zero. ({}x{})", screen_width, screen_height)) };
+        return false;
+    }
+
+    unsafe { with_framework(framework, |framework| framework.resize(screen_width, screen_height)) };
+    true
+}
+
+#[no_mangle]
+pub extern "C" fn frameworkTouch(framework: FrameworkHandle, touch_id: u64, phase: u32, x: f32, y: f32) -> bool {
+    let phase = match input::TouchPhase::from_raw(phase) {
+        Some(phase) => phase,
+        None => {
+            unsafe { LAST_FRAMEWORK_ERR_MSG = Some(err!("Unknown touch phase: {}", phase)) };
+            return false;
+        }
+    };
+    unsafe { with_framework(framework, |framework| framework.touch(touch_id, phase, x, y)) };
+    true
+}
+
+#[no_mangle]
+pub extern "C" fn frameworkSetPresentRegion(framework: FrameworkHandle, x: u32, y: u32, width: u32, height: u32) -> bool {
+    // a zero width and height renders to the whole drawable.
+    let region = match (width, height) {
+        (0, 0) => None,
+        _ => Some((x, y, width, height)),
+    };
+    match unsafe { with_framework(framework, |framework| framework.set_present_region(region)) } {
+        Ok(_) => true,
+        Err(msg) => {
+            unsafe { LAST_FRAMEWORK_ERR_MSG = Some(msg) };
+            false
+        }
+    }
+}
+
+/// # Safety
+/// - `selected_mode` must be a null pointer or valid for writes of a `u32`.
+/// 
+#[no_mangle]
+pub unsafe extern "C" fn frameworkSetPresentMode(framework: FrameworkHandle, present_mode: u32, selected_mode: *mut u32) -> bool {
+    let present_mode = match renderer::RenderPresentMode::from_raw(present_mode) {
+        Some(present_mode) => present_mode,
+        None => {
+            unsafe { LAST_FRAMEWORK_ERR_MSG = Some(err!("Unknown present mode: {}", present_mode)) };
+            return false;
+        }
+    };
+
+    return match unsafe { with_framework(framework, |framework| framework.set_present_mode(present_mode)) } {
+        Ok(present_mode) => {
+            // the selected mode differs from the requested one if the surface does not support it.
+            if !selected_mode.is_null() {
+                unsafe { *selected_mode = present_mode as u32 };
+            }
+            true
         },
         Err(msg) => {
             unsafe { LAST_FRAMEWORK_ERR_MSG = Some(msg) };
-            ptr::null_mut()
+            false
         }
     };
 }
 
 #[no_mangle]
-pub extern "C" fn destroyFramework(framework: *mut c_void) {
-    assert!(!framework.is_null(), "framework cannot be a null pointer.");
-    unsafe { Box::from_raw(framework as *mut Framework) };
+pub extern "C" fn recreateFrameworkRenderer(framework: FrameworkHandle) -> bool {
+    return match unsafe { with_framework(framework, |framework| framework.recreate_renderer()) } {
+        Ok(_) => true,
+        Err(msg) => {
+            unsafe { LAST_FRAMEWORK_ERR_MSG = Some(msg) };
+            false
+        }
+    };
+}
+
+/// # Safety
+/// - `path` must point to a null-terminated string.
+/// 
+#[no_mangle]
+pub unsafe extern "C" fn frameworkReloadShader(framework: FrameworkHandle, path: *const c_char) -> bool {
+    assert!(!path.is_null(), "path cannot be a null pointer.");
+    let path = match unsafe { CStr::from_ptr(path) }.to_str() {
+        Ok(path) => PathBuf::from(path),
+        Err(e) => {
+            unsafe { LAST_FRAMEWORK_ERR_MSG = Some(err!("Invalid shader path: {}", e.to_string())) };
+            return false;
+        }
+    };
+
+    return match unsafe { with_framework(framework, |framework| framework.reload_shader(&path)) } {
+        Ok(_) => true,
+        Err(msg) => {
+            unsafe { LAST_FRAMEWORK_ERR_MSG = Some(msg) };
+            false
+        }
+    };
 }
 
 #[no_mangle]
-pub extern "C" fn updateFramework(framework: *mut c_void) -> *mut c_void {
-    assert!(!framework.is_null(), "framework cannot be a null pointer.");
-    let mut framework = unsafe { Box::from_raw(framework as *mut Framework) };
-    return if let Err(msg) = framework.frame_advanced() {
-        unsafe { LAST_FRAMEWORK_ERR_MSG = Some(msg) };
-        ptr::null_mut()
+pub extern "C" fn frameworkSetObjectColor(framework: FrameworkHandle, object_id: u32, r: f32, g: f32, b: f32, a: f32) -> bool {
+    let color = math::Vec4::new_vector(r, g, b, a);
+    if !color.is_finite() {
+        unsafe { LAST_FRAMEWORK_ERR_MSG = Some(err!("The object color must be finite numbers.")) };
+        return false;
     }
-    else {
-        Box::into_raw(framework) as *mut c_void
+
+    return match unsafe { with_framework(framework, |framework| framework.set_object_color(object_id as usize, color)) } {
+        Ok(_) => true,
+        Err(msg) => {
+            unsafe { LAST_FRAMEWORK_ERR_MSG = Some(msg) };
+            false
+        }
+    };
+}
+
+#[no_mangle]
+pub extern "C" fn frameworkSetObjectSpeed(framework: FrameworkHandle, object_id: u32, speed: f32) -> bool {
+    if !speed.is_finite() {
+        unsafe { LAST_FRAMEWORK_ERR_MSG = Some(err!("The object speed must be a finite number.")) };
+        return false;
+    }
+
+    return match unsafe { with_framework(framework, |framework| framework.set_object_speed(object_id as usize, speed)) } {
+        Ok(_) => true,
+        Err(msg) => {
+            unsafe { LAST_FRAMEWORK_ERR_MSG = Some(msg) };
+            false
+        }
     };
 }
 
+/// # Safety
+/// - `object_id` must be valid for writes of a `u32`.
+/// 
 #[no_mangle]
-pub extern "C" fn pauseFramework(framework: *mut c_void) -> *mut c_void {
-    assert!(!framework.is_null(), "framework cannot be a null pointer.");
-    let mut framework = unsafe { Box::from_raw(framework as *mut Framework) };
-    return if let Err(msg) = framework.paused() {
-        unsafe { LAST_FRAMEWORK_ERR_MSG = Some(msg) };
-        ptr::null_mut()
+pub unsafe extern "C" fn frameworkSpawnObject(framework: FrameworkHandle, object_id: *mut u32) -> bool {
+    assert!(!object_id.is_null(), "object id cannot be a null pointer.");
+    return match unsafe { with_framework(framework, |framework| framework.spawn_object()) } {
+        Some(id) => {
+            unsafe { *object_id = id as u32 };
+            true
+        },
+        None => {
+            unsafe { LAST_FRAMEWORK_ERR_MSG = Some(err!("No more objects can be spawned in the current scene.")) };
+            false
+        }
+    };
+}
+
+#[no_mangle]
+pub extern "C" fn frameworkDespawnObject(framework: FrameworkHandle, object_id: u32) -> bool {
+    if !unsafe { with_framework(framework, |framework| framework.despawn_object(object_id as usize)) } {
+        unsafe { LAST_FRAMEWORK_ERR_MSG = Some(err!("Object not found. (object id: {})", object_id)) };
+        return false;
     }
-    else {
-        Box::into_raw(framework) as *mut c_void
+    true
+}
+
+/// # Safety
+/// - `stats` must be valid for writes of an `ObjectPoolStats`.
+/// 
+#[no_mangle]
+pub unsafe extern "C" fn frameworkGetObjectPoolStats(framework: FrameworkHandle, stats: *mut ObjectPoolStats) -> bool {
+    assert!(!stats.is_null(), "stats cannot be a null pointer.");
+    return match unsafe { with_framework(framework, |framework| framework.get_object_pool_stats()) } {
+        Some(pool_stats) => {
+            unsafe { *stats = pool_stats };
+            true
+        },
+        None => false
     };
 }
 
 #[no_mangle]
-pub extern "C" fn resumeFramework(framework: *mut c_void) -> *mut c_void {
-    assert!(!framework.is_null(), "framework cannot be a null pointer.");
-    let mut framework = unsafe { Box::from_raw(framework as *mut Framework) };
-    return if let Err(msg) = framework.resume() {
-        unsafe { LAST_FRAMEWORK_ERR_MSG = Some(msg) };
-        ptr::null_mut()
+pub extern "C" fn frameworkTriggerCameraShake(framework: FrameworkHandle, intensity: f32, duration_sec: f32) -> bool {
+    if !intensity.is_finite() || !duration_sec.is_finite() || intensity < 0.0 || duration_sec < 0.0 {
+        unsafe { LAST_FRAMEWORK_ERR_MSG = Some(err!("The camera shake intensity and duration must be non-negative finite numbers.")) };
+        return false;
+    }
+
+    if !unsafe { with_framework(framework, |framework| framework.trigger_camera_shake(intensity, duration_sec)) } {
+        unsafe { LAST_FRAMEWORK_ERR_MSG = Some(err!("The current scene has no camera.")) };
+        return false;
     }
-    else {
-        Box::into_raw(framework) as *mut c_void
+    true
+}
+
+/// # Safety
+/// - `buf` must be valid for writes of `buf_size` bytes.
+/// 
+#[no_mangle]
+pub unsafe extern "C" fn frameworkDebugDump(framework: FrameworkHandle, buf: *mut c_char, buf_size: u32) -> bool {
+    let mut dump = unsafe { with_framework(framework, |framework| framework.debug_dump()) };
+    match unsafe { &LAST_FRAMEWORK_ERR_MSG } {
+        Some(msg) => dump += &format!("last error: {}\n", msg.what()),
+        None => dump += "last error: none\n",
     };
+
+    // the dump is truncated to fit the buffer.
+    unsafe { copy_to_c_buffer(&dump, buf, buf_size) }
 }
 
 #[no_mangle]
-pub extern "C" fn getLastFrameworkErrMsg(buf: *mut c_char, buf_size: u32) -> bool {
-    assert!(!buf.is_null(), "buffer cannot be a null pointer.");
-    assert!(buf_size > 0, "buffer size cannot be zero.");
+pub extern "C" fn getLastFrameworkErrMsgLen() -> u32 {
+    // the length excludes the null terminator.
+    return match unsafe { &LAST_FRAMEWORK_ERR_MSG } {
+        Some(msg) => msg.what().len() as u32,
+        None => 0
+    };
+}
+
+/// # Safety
+/// - `buf` must be valid for writes of `buf_size` bytes.
+/// 
+#[no_mangle]
+pub unsafe extern "C" fn getLastFrameworkErrMsg(buf: *mut c_char, buf_size: u32) -> bool {
     return match unsafe { &LAST_FRAMEWORK_ERR_MSG } {
         Some(msg) => {
-            unsafe { buf.copy_from(msg.what().as_ptr() as *const i8, buf_size as usize) };
+            unsafe { copy_to_c_buffer(msg.what(), buf, buf_size) };
             true
         },
         None => false
     };
 }
 
+/// # Safety
+/// - `buf` must be valid for writes of `buf_size` bytes.
+/// 
 #[no_mangle]
-pub extern "C" fn getLastFrameworkErrMsgDbg(buf: *mut c_char, buf_size: u32) -> bool {
-    assert!(!buf.is_null(), "buffer cannot be a null pointer.");
-    assert!(buf_size > 0, "buffer size cannot be zero.");
+pub unsafe extern "C" fn getLastFrameworkErrMsgDbg(buf: *mut c_char, buf_size: u32) -> bool {
     return match unsafe { &LAST_FRAMEWORK_ERR_MSG } {
         Some(msg) => {
             println!("{}", msg.what());
-            unsafe { buf.copy_from(msg.debug_info().as_ptr() as *const i8, buf_size as usize) };
+            unsafe { copy_to_c_buffer(&msg.debug_info(), buf, buf_size) };
             true
         },
         None => false
     };
-}
\ No newline at end of file
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::sync::Mutex;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    /// serializes the tests that touch the last error message.
+    static LAST_ERR_LOCK: Mutex<()> = Mutex::new(());
+
+    struct DropCounter {
+        value: u32,
+        drops: Arc<AtomicUsize>,
+    }
+
+    impl Drop for DropCounter {
+        fn drop(&mut self) {
+            self.drops.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn handle_mutates_the_object_and_drops_it_once() {
+        let drops = Arc::new(AtomicUsize::new(0));
+        let handle = FrameworkHandle::from_box(Box::new(DropCounter { value: 0, drops: drops.clone() }));
+
+        // the handle is copied into each call, like the host application does.
+        for _ in 0..3 {
+            unsafe { with_object(handle, |counter: &mut DropCounter| counter.value += 1) };
+        }
+        assert_eq!(unsafe { with_object(handle, |counter: &mut DropCounter| counter.value) }, 3);
+        assert_eq!(drops.load(Ordering::SeqCst), 0);
+
+        unsafe { destroy_object::<DropCounter>(handle) };
+        assert_eq!(drops.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn last_error_message_fits_in_its_length_plus_one() {
+        let _lock = LAST_ERR_LOCK.lock().unwrap();
+        let error = err!("Framework error message for the host. (code: {})", 42);
+        unsafe { LAST_FRAMEWORK_ERR_MSG = Some(error.clone()) };
+
+        let len = getLastFrameworkErrMsgLen();
+        assert_eq!(len as usize, error.what().len());
+
+        let mut buf = vec![1 as c_char; len as usize + 1];
+        assert!(unsafe { getLastFrameworkErrMsg(buf.as_mut_ptr(), len + 1) });
+        let copied = unsafe { CStr::from_ptr(buf.as_ptr()) };
+        assert_eq!(copied.to_str().unwrap(), error.what());
+        assert_eq!(buf[len as usize], 0);
+
+        // the whole string is copied with room for the null terminator only.
+        assert!(unsafe { copy_to_c_buffer(error.what(), buf.as_mut_ptr(), len + 1) });
+        assert!(!unsafe { copy_to_c_buffer(error.what(), buf.as_mut_ptr(), len) });
+        assert_eq!(unsafe { CStr::from_ptr(buf.as_ptr()) }.to_bytes(), &error.what().as_bytes()[..len as usize - 1]);
+    }
+
+    #[test]
+    fn invalid_utf8_assets_dir_is_a_readable_error() {
+        let _lock = LAST_ERR_LOCK.lock().unwrap();
+        unsafe { LAST_FRAMEWORK_ERR_MSG = None };
+        let path = CString::new(vec![b'/', b'a', 0xff, 0xfe, b'b']).unwrap();
+        let error = unsafe { parse_assets_dir(path.as_ptr()) }.unwrap_err();
+        assert!(error.what().starts_with("Invalid assets directory path:"), "{}", error.what());
+
+        // the error is recorded for the host application.
+        let len = getLastFrameworkErrMsgLen();
+        let mut buf = vec![0 as c_char; len as usize + 1];
+        assert!(unsafe { getLastFrameworkErrMsg(buf.as_mut_ptr(), len + 1) });
+        let msg = unsafe { CStr::from_ptr(buf.as_ptr()) }.to_str().unwrap();
+        assert_eq!(msg, error.what());
+    }
+
+    #[test]
+    fn assets_dir_is_parsed_from_the_c_string() {
+        let path = CString::new("/var/assets").unwrap();
+        assert_eq!(unsafe { parse_assets_dir(path.as_ptr()) }.unwrap(), PathBuf::from("/var/assets"));
+        assert_eq!(unsafe { parse_assets_dir(ptr::null()) }.unwrap(), PathBuf::new());
+    }
+}
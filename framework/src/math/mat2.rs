@@ -42,6 +42,13 @@ impl Mat2x2 {
         Self { r1c1: row1.x, r1c2: row1.y, r2c1: row2.x, r2c2: row2.y }
     }
 
+    /// create a matrix that rotates counter-clockwise by the given angle. (`v * M`)
+    #[inline]
+    pub fn from_angle(angle_radian: f32) -> Self {
+        let (sin, cos) = angle_radian.sin_cos();
+        Self { r1c1: cos, r1c2: sin, r2c1: -sin, r2c2: cos }
+    }
+
     #[inline]
     pub fn add_scalar(self, rhs: f32) -> Self {
         Self {
@@ -467,3 +474,16 @@ fn minor_matrix(mat: &Mat2x2, row: usize, col: usize) -> f32 {
         _ => { panic!("out of range!") }
     }
 }
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quarter_turn_rotates_x_to_y() {
+        let rotated = Vec2::X * Mat2x2::from_angle(std::f32::consts::FRAC_PI_2);
+        assert!((rotated - Vec2::Y).length() <= 1e-6, "{:?} != {:?}", rotated, Vec2::Y);
+    }
+}
@@ -3,6 +3,7 @@ use std::fmt;
 use std::cmp;
 use bytemuck::{Zeroable, Pod};
 use super::mat2::Mat2x2;
+use super::mat4::Mat4x4;
 use super::quat::Quat;
 use super::vec3::Vec3;
 
@@ -62,6 +63,42 @@ impl Mat3x3 {
         }
     }
 
+    /// return the row of the given index. (zero-based)
+    #[inline]
+    pub fn row(&self, index: usize) -> Vec3 {
+        assert!(index < 3, "row out of range!");
+        let m = self.as_ref();
+        Vec3::new_vector(m[index * 3 + 0], m[index * 3 + 1], m[index * 3 + 2])
+    }
+
+    /// return the column of the given index. (zero-based)
+    #[inline]
+    pub fn col(&self, index: usize) -> Vec3 {
+        assert!(index < 3, "column out of range!");
+        let m = self.as_ref();
+        Vec3::new_vector(m[index], m[1 * 3 + index], m[2 * 3 + index])
+    }
+
+    /// set the row of the given index. (zero-based)
+    #[inline]
+    pub fn set_row(&mut self, index: usize, row: Vec3) {
+        assert!(index < 3, "row out of range!");
+        let m = self.as_mut();
+        m[index * 3 + 0] = row.x;
+        m[index * 3 + 1] = row.y;
+        m[index * 3 + 2] = row.z;
+    }
+
+    /// set the column of the given index. (zero-based)
+    #[inline]
+    pub fn set_col(&mut self, index: usize, col: Vec3) {
+        assert!(index < 3, "column out of range!");
+        let m = self.as_mut();
+        m[index] = col.x;
+        m[1 * 3 + index] = col.y;
+        m[2 * 3 + index] = col.z;
+    }
+
     /// create a matrix with given quaternion.
     #[inline]
     pub fn from_quat(quat: Quat) -> Self {
@@ -86,6 +123,28 @@ impl Mat3x3 {
         Quat::from_matrix3x3(self)
     }
 
+    /// create a matrix with the upper-left 3by3 elements of the given matrix.
+    #[inline]
+    pub fn from_mat4_upper_left(m: &Mat4x4) -> Self {
+        Self {
+            r1c1: m.r1c1, r1c2: m.r1c2, r1c3: m.r1c3,
+            r2c1: m.r2c1, r2c2: m.r2c2, r2c3: m.r2c3,
+            r3c1: m.r3c1, r3c2: m.r3c2, r3c3: m.r3c3
+        }
+    }
+
+    /// return the matrix that transforms normals by the given model matrix.
+    /// (the inverse-transpose of the upper-left 3by3 elements)
+    /// the upper-left elements are returned as they are if they cannot be inverted.
+    #[inline]
+    pub fn normal_matrix(model: &Mat4x4) -> Self {
+        let upper_left = Self::from_mat4_upper_left(model);
+        match upper_left.try_inverse() {
+            Some(inverse) => inverse.transpose(),
+            None => upper_left,
+        }
+    }
+
     #[inline]
     pub fn add_scalar(self, rhs: f32) -> Self {
         Self {
@@ -579,3 +638,45 @@ fn minor_matrix(mat: &Mat3x3, row: usize, col: usize) -> Mat2x2 {
         _ => { panic!("out of range!") }
     }
 }
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identity_rows_and_columns_are_unit_vectors() {
+        assert_eq!(Mat3x3::IDENTITY.row(0), Vec3::X);
+        assert_eq!(Mat3x3::IDENTITY.row(2), Vec3::Z);
+        assert_eq!(Mat3x3::IDENTITY.col(0), Vec3::X);
+        assert_eq!(Mat3x3::IDENTITY.col(2), Vec3::Z);
+    }
+
+    #[test]
+    fn set_row_and_set_col_round_trip() {
+        for index in 0..3 {
+            let v = Vec3::new_vector(-1.0, -2.0, -3.0);
+            let mut rows = Mat3x3::IDENTITY;
+            rows.set_row(index, v);
+            assert_eq!(rows.row(index), v);
+            let mut cols = Mat3x3::IDENTITY;
+            cols.set_col(index, v);
+            assert_eq!(cols.col(index), v);
+            assert_eq!(cols.transpose().row(index), v);
+        }
+    }
+
+    #[test]
+    fn normal_matrix_keeps_normals_perpendicular_under_non_uniform_scale() {
+        let model = Mat4x4::from_trs(Vec3::ZERO, Quat::IDENTITY, Vec3::new_vector(2.0, 1.0, 1.0));
+        let tangent = Vec3::new_vector(1.0, -1.0, 0.0) * Mat3x3::from_mat4_upper_left(&model);
+        let normal = Vec3::new_vector(1.0, 1.0, 0.0) * Mat3x3::normal_matrix(&model);
+
+        assert_eq!(normal, Vec3::new_vector(0.5, 1.0, 0.0));
+        assert!(tangent.dot(&normal).abs() <= 1e-6, "{:?} . {:?}", tangent, normal);
+
+        let scaled_normal = Vec3::new_vector(1.0, 1.0, 0.0) * Mat3x3::from_mat4_upper_left(&model);
+        assert!(tangent.dot(&scaled_normal).abs() > 1.0);
+    }
+}
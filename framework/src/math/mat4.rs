@@ -4,7 +4,9 @@ use std::fmt;
 use bytemuck::{Zeroable, Pod};
 use super::mat3::Mat3x3;
 use super::quat::Quat;
+use super::vec3::Vec3;
 use super::vec4::Vec4;
+use super::madd;
 
 /// 4by4 matrix.
 /// - row major
@@ -68,6 +70,44 @@ impl Mat4x4 {
         }
     }
 
+    /// return the row of the given index. (zero-based)
+    #[inline]
+    pub fn row(&self, index: usize) -> Vec4 {
+        assert!(index < 4, "row out of range!");
+        let m = self.as_ref();
+        Vec4::new_vector(m[index * 4 + 0], m[index * 4 + 1], m[index * 4 + 2], m[index * 4 + 3])
+    }
+
+    /// return the column of the given index. (zero-based)
+    #[inline]
+    pub fn col(&self, index: usize) -> Vec4 {
+        assert!(index < 4, "column out of range!");
+        let m = self.as_ref();
+        Vec4::new_vector(m[index], m[1 * 4 + index], m[2 * 4 + index], m[3 * 4 + index])
+    }
+
+    /// set the row of the given index. (zero-based)
+    #[inline]
+    pub fn set_row(&mut self, index: usize, row: Vec4) {
+        assert!(index < 4, "row out of range!");
+        let m = self.as_mut();
+        m[index * 4 + 0] = row.x;
+        m[index * 4 + 1] = row.y;
+        m[index * 4 + 2] = row.z;
+        m[index * 4 + 3] = row.w;
+    }
+
+    /// set the column of the given index. (zero-based)
+    #[inline]
+    pub fn set_col(&mut self, index: usize, col: Vec4) {
+        assert!(index < 4, "column out of range!");
+        let m = self.as_mut();
+        m[index] = col.x;
+        m[1 * 4 + index] = col.y;
+        m[2 * 4 + index] = col.z;
+        m[3 * 4 + index] = col.w;
+    }
+
     /// create a matrix with given quaternion.
     pub fn from_quat(quat: Quat) -> Self {
         Self {
@@ -99,6 +139,81 @@ impl Mat4x4 {
         Quat::from_matrix4x4(self)
     }
 
+    /// create a translation matrix. the translation is in the last row.
+    #[inline]
+    pub const fn from_translation(v: Vec3) -> Self {
+        Self::new_rows(
+            Vec4::new_vector(1.0, 0.0, 0.0, 0.0), 
+            Vec4::new_vector(0.0, 1.0, 0.0, 0.0), 
+            Vec4::new_vector(0.0, 0.0, 1.0, 0.0), 
+            Vec4::new_vector(v.x, v.y, v.z, 1.0)
+        )
+    }
+
+    /// create a scale matrix.
+    #[inline]
+    pub const fn from_scale(v: Vec3) -> Self {
+        Self::new_rows(
+            Vec4::new_vector(v.x, 0.0, 0.0, 0.0), 
+            Vec4::new_vector(0.0, v.y, 0.0, 0.0), 
+            Vec4::new_vector(0.0, 0.0, v.z, 0.0), 
+            Vec4::new_vector(0.0, 0.0, 0.0, 1.0)
+        )
+    }
+
+    /// create a rotation matrix with given quaternion. (same as `from_quat`)
+    #[inline]
+    pub fn from_rotation(q: Quat) -> Self {
+        Self::from_quat(q)
+    }
+
+    /// create a matrix that scales, then rotates, then translates.
+    /// the same as `from_scale(s) * from_rotation(r) * from_translation(t)`.
+    #[inline]
+    pub fn from_trs(t: Vec3, r: Quat, s: Vec3) -> Self {
+        let mut mat = Self::from_quat(r);
+        mat.set_row(0, mat.row(0) * s.x);
+        mat.set_row(1, mat.row(1) * s.y);
+        mat.set_row(2, mat.row(2) * s.z);
+        mat.set_row(3, Vec4::new_vector(t.x, t.y, t.z, 1.0));
+        mat
+    }
+
+    /// decompose a matrix into translation, rotation and scale. `(translation, rotation, scale)`
+    /// the inverse of `from_trs` for matrices without shear.
+    /// if the determinant is negative, the x-axis scale is negated so that the rotation stays proper.
+    pub fn decompose(&self) -> (Vec3, Quat, Vec3) {
+        let translation = Vec3::new_vector(self.r4c1, self.r4c2, self.r4c3);
+
+        let mut axes = [0, 1, 2].map(|i| {
+            let row = self.row(i);
+            Vec3::new_vector(row.x, row.y, row.z)
+        });
+        let mut scale = Vec3::new_vector(axes[0].length(), axes[1].length(), axes[2].length());
+        if axes[0].cross(&axes[1]).dot(&axes[2]) < 0.0 {
+            scale.x = -scale.x;
+        }
+
+        let mut rotation = Self::IDENTITY;
+        for (i, s) in [scale.x, scale.y, scale.z].into_iter().enumerate() {
+            if s.abs() > f32::EPSILON {
+                axes[i] /= s;
+                rotation.set_row(i, Vec4::new_vector(axes[i].x, axes[i].y, axes[i].z, 0.0));
+            }
+        }
+
+        (translation, Quat::from_matrix4x4(rotation).normalize(), scale)
+    }
+
+    /// blend two transforms by interpolating the translation, rotation and scale separately.
+    /// the rotation is interpolated with `slerp`, so a rigid transform stays rigid. (see `decompose`)
+    #[inline]
+    pub fn blend(self, other: Self, t: f32) -> Self {
+        let (t0, r0, s0) = self.decompose();
+        let (t1, r1, s1) = other.decompose();
+        Self::from_trs(t0.lerp(t1, t), r0.slerp(r1, t), s0.lerp(s1, t))
+    }
+
     #[inline]
     pub fn add_scalar(self, rhs: f32) -> Self {
         Self {
@@ -177,25 +292,25 @@ impl Mat4x4 {
     #[inline]
     pub fn mul_matrix4x4(self, rhs: Self) -> Self {
         Mat4x4 {
-            r1c1: self.r1c1 * rhs.r1c1 + self.r1c2 * rhs.r2c1 + self.r1c3 * rhs.r3c1 + self.r1c4 * rhs.r4c1,
-            r2c1: self.r2c1 * rhs.r1c1 + self.r2c2 * rhs.r2c1 + self.r2c3 * rhs.r3c1 + self.r2c4 * rhs.r4c1,
-            r3c1: self.r3c1 * rhs.r1c1 + self.r3c2 * rhs.r2c1 + self.r3c3 * rhs.r3c1 + self.r3c4 * rhs.r4c1,
-            r4c1: self.r4c1 * rhs.r1c1 + self.r4c2 * rhs.r2c1 + self.r4c3 * rhs.r3c1 + self.r4c4 * rhs.r4c1,
-
-            r1c2: self.r1c1 * rhs.r1c2 + self.r1c2 * rhs.r2c2 + self.r1c3 * rhs.r3c2 + self.r1c4 * rhs.r4c2,
-            r2c2: self.r2c1 * rhs.r1c2 + self.r2c2 * rhs.r2c2 + self.r2c3 * rhs.r3c2 + self.r2c4 * rhs.r4c2,
-            r3c2: self.r3c1 * rhs.r1c2 + self.r3c2 * rhs.r2c2 + self.r3c3 * rhs.r3c2 + self.r3c4 * rhs.r4c2,
-            r4c2: self.r4c1 * rhs.r1c2 + self.r4c2 * rhs.r2c2 + self.r4c3 * rhs.r3c2 + self.r4c4 * rhs.r4c2,
+            r1c1: madd(self.r1c4, rhs.r4c1, madd(self.r1c3, rhs.r3c1, madd(self.r1c2, rhs.r2c1, self.r1c1 * rhs.r1c1))),
+            r2c1: madd(self.r2c4, rhs.r4c1, madd(self.r2c3, rhs.r3c1, madd(self.r2c2, rhs.r2c1, self.r2c1 * rhs.r1c1))),
+            r3c1: madd(self.r3c4, rhs.r4c1, madd(self.r3c3, rhs.r3c1, madd(self.r3c2, rhs.r2c1, self.r3c1 * rhs.r1c1))),
+            r4c1: madd(self.r4c4, rhs.r4c1, madd(self.r4c3, rhs.r3c1, madd(self.r4c2, rhs.r2c1, self.r4c1 * rhs.r1c1))),
+
+            r1c2: madd(self.r1c4, rhs.r4c2, madd(self.r1c3, rhs.r3c2, madd(self.r1c2, rhs.r2c2, self.r1c1 * rhs.r1c2))),
+            r2c2: madd(self.r2c4, rhs.r4c2, madd(self.r2c3, rhs.r3c2, madd(self.r2c2, rhs.r2c2, self.r2c1 * rhs.r1c2))),
+            r3c2: madd(self.r3c4, rhs.r4c2, madd(self.r3c3, rhs.r3c2, madd(self.r3c2, rhs.r2c2, self.r3c1 * rhs.r1c2))),
+            r4c2: madd(self.r4c4, rhs.r4c2, madd(self.r4c3, rhs.r3c2, madd(self.r4c2, rhs.r2c2, self.r4c1 * rhs.r1c2))),
             
-            r1c3: self.r1c1 * rhs.r1c3 + self.r1c2 * rhs.r2c3 + self.r1c3 * rhs.r3c3 + self.r1c4 * rhs.r4c3,
-            r2c3: self.r2c1 * rhs.r1c3 + self.r2c2 * rhs.r2c3 + self.r2c3 * rhs.r3c3 + self.r2c4 * rhs.r4c3,
-            r3c3: self.r3c1 * rhs.r1c3 + self.r3c2 * rhs.r2c3 + self.r3c3 * rhs.r3c3 + self.r3c4 * rhs.r4c3,
-            r4c3: self.r4c1 * rhs.r1c3 + self.r4c2 * rhs.r2c3 + self.r4c3 * rhs.r3c3 + self.r4c4 * rhs.r4c3,
-
-            r1c4: self.r1c1 * rhs.r1c4 + self.r1c2 * rhs.r2c4 + self.r1c3 * rhs.r3c4 + self.r1c4 * rhs.r4c4,
-            r2c4: self.r2c1 * rhs.r1c4 + self.r2c2 * rhs.r2c4 + self.r2c3 * rhs.r3c4 + self.r2c4 * rhs.r4c4,
-            r3c4: self.r3c1 * rhs.r1c4 + self.r3c2 * rhs.r2c4 + self.r3c3 * rhs.r3c4 + self.r3c4 * rhs.r4c4,
-            r4c4: self.r4c1 * rhs.r1c4 + self.r4c2 * rhs.r2c4 + self.r4c3 * rhs.r3c4 + self.r4c4 * rhs.r4c4 
+            r1c3: madd(self.r1c4, rhs.r4c3, madd(self.r1c3, rhs.r3c3, madd(self.r1c2, rhs.r2c3, self.r1c1 * rhs.r1c3))),
+            r2c3: madd(self.r2c4, rhs.r4c3, madd(self.r2c3, rhs.r3c3, madd(self.r2c2, rhs.r2c3, self.r2c1 * rhs.r1c3))),
+            r3c3: madd(self.r3c4, rhs.r4c3, madd(self.r3c3, rhs.r3c3, madd(self.r3c2, rhs.r2c3, self.r3c1 * rhs.r1c3))),
+            r4c3: madd(self.r4c4, rhs.r4c3, madd(self.r4c3, rhs.r3c3, madd(self.r4c2, rhs.r2c3, self.r4c1 * rhs.r1c3))),
+
+            r1c4: madd(self.r1c4, rhs.r4c4, madd(self.r1c3, rhs.r3c4, madd(self.r1c2, rhs.r2c4, self.r1c1 * rhs.r1c4))),
+            r2c4: madd(self.r2c4, rhs.r4c4, madd(self.r2c3, rhs.r3c4, madd(self.r2c2, rhs.r2c4, self.r2c1 * rhs.r1c4))),
+            r3c4: madd(self.r3c4, rhs.r4c4, madd(self.r3c3, rhs.r3c4, madd(self.r3c2, rhs.r2c4, self.r3c1 * rhs.r1c4))),
+            r4c4: madd(self.r4c4, rhs.r4c4, madd(self.r4c3, rhs.r3c4, madd(self.r4c2, rhs.r2c4, self.r4c1 * rhs.r1c4)))
         }
     }
 
@@ -731,3 +846,61 @@ fn minor_matrix(mat: &Mat4x4, row: usize, col: usize) -> Mat3x3 {
         _ => { panic!("out of range!") }
     }
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matrix_from_fn(f: impl Fn(usize) -> f32) -> Mat4x4 {
+        let mut m = Mat4x4::ZERO;
+        for (i, value) in m.as_mut().iter_mut().enumerate() {
+            *value = f(i);
+        }
+        m
+    }
+
+    #[test]
+    #[cfg(any(feature = "deterministic", not(any(target_arch = "aarch64", target_feature = "fma"))))]
+    fn mul_matrix4x4_is_bit_exact() {
+        // expected bits of the product with every product and sum rounded to `f32` separately.
+        // (a fused multiply-add gives a different `r1c3` and `r4c3`)
+        const EXPECTED: [u32; 16] = [
+            0x3ded3fb5, 0x3dcccccd, 0x3db5ebc0, 0x3da47d7c,
+            0x3ebd1b88, 0x3e9dddde, 0x3e88ff27, 0x3e737c05,
+            0x3f1f7391, 0x3f044445, 0x3ee4835d, 0x3eca5ca6,
+            0x3f60595f, 0x3f39999a, 0x3f2003cb, 0x3f0d7da5,
+        ];
+
+        let a = matrix_from_fn(|i| 0.1 * (i + 1) as f32);
+        let b = matrix_from_fn(|i| 1.0 / (i + 3) as f32);
+        let bits = a.mul_matrix4x4(b).as_ref().map(f32::to_bits);
+        assert_eq!(bits, EXPECTED);
+    }
+
+    #[test]
+    fn identity_rows_and_columns_are_unit_vectors() {
+        assert_eq!(Mat4x4::IDENTITY.row(0), Vec4::X);
+        assert_eq!(Mat4x4::IDENTITY.row(3), Vec4::W);
+        assert_eq!(Mat4x4::IDENTITY.col(0), Vec4::X);
+        assert_eq!(Mat4x4::IDENTITY.col(3), Vec4::W);
+    }
+
+    #[test]
+    fn set_row_and_set_col_round_trip() {
+        let m = matrix_from_fn(|i| i as f32);
+        assert_eq!(m.row(1), Vec4::new_vector(4.0, 5.0, 6.0, 7.0));
+        assert_eq!(m.col(1), Vec4::new_vector(1.0, 5.0, 9.0, 13.0));
+
+        for index in 0..4 {
+            let v = Vec4::new_vector(-1.0, -2.0, -3.0, -4.0);
+            let mut rows = m;
+            rows.set_row(index, v);
+            assert_eq!(rows.row(index), v);
+            let mut cols = m;
+            cols.set_col(index, v);
+            assert_eq!(cols.col(index), v);
+            assert_eq!(cols.transpose().row(index), v);
+        }
+    }
+}
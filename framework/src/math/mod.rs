@@ -2,20 +2,41 @@ mod vec2;
 mod vec3;
 mod vec4;
 mod quat;
+mod plane;
 
 mod mat2;
 mod mat3;
 mod mat4;
 
+#[cfg(test)]
+mod tests;
+
 pub use vec2::*;
 pub use vec3::*;
 pub use vec4::*;
 pub use quat::*;
+pub use plane::*;
 
 pub use mat2::*;
 pub use mat3::*;
 pub use mat4::*;
 
+/// `a * b + c` for the hot math paths. (matrix products and vector lengths)
+/// 
+/// by default it uses a fused multiply-add where the hardware has one (e.g. arm64 devices).
+/// it rounds once instead of twice, so results may differ in the last bits between devices
+/// (e.g. the x86_64 simulator). the `deterministic` feature rounds the product and the sum separately,
+/// so the results are the same on every device. (e.g. for reproducible bug reports)
+#[inline(always)]
+pub(crate) fn madd(a: f32, b: f32, c: f32) -> f32 {
+    if cfg!(all(not(feature = "deterministic"), any(target_arch = "aarch64", target_feature = "fma"))) {
+        a.mul_add(b, c)
+    }
+    else {
+        a * b + c
+    }
+}
+
 #[inline]
 pub fn orthographic_lh_zo(
     left: f32,
@@ -279,3 +300,145 @@ pub fn perspective_lh_no(
         r4c4: 0.0
     }
 }
+
+/// create a left-handed view matrix looking from `eye` at `target`.
+/// the camera looks along the positive z-axis of the view space.
+/// returns a matrix without rotation if `eye` equals `target` or the view direction is parallel to `up`.
+#[inline]
+pub fn look_at_lh(eye: Vec3, target: Vec3, up: Vec3) -> Mat4x4 {
+    look_at(eye, target - eye, up)
+}
+
+/// create a right-handed view matrix looking from `eye` at `target`.
+/// the camera looks along the negative z-axis of the view space.
+/// returns a matrix without rotation if `eye` equals `target` or the view direction is parallel to `up`.
+#[inline]
+pub fn look_at_rh(eye: Vec3, target: Vec3, up: Vec3) -> Mat4x4 {
+    look_at(eye, eye - target, up)
+}
+
+/// create a view matrix whose view-space z-axis is `z_axis`.
+/// the basis vectors are the columns of the rotation, and the translation is in the last row.
+#[inline]
+fn look_at(eye: Vec3, z_axis: Vec3, up: Vec3) -> Mat4x4 {
+    let basis = z_axis.try_normalized().and_then(|z| {
+        let x = up.cross(&z).try_normalized()?;
+        Some((x, z.cross(&x), z))
+    });
+    let (x, y, z) = basis.unwrap_or((Vec3::X, Vec3::Y, Vec3::Z));
+
+    Mat4x4 {
+        r1c1: x.x,
+        r1c2: y.x,
+        r1c3: z.x,
+        r1c4: 0.0,
+
+        r2c1: x.y,
+        r2c2: y.y,
+        r2c3: z.y,
+        r2c4: 0.0,
+
+        r3c1: x.z,
+        r3c2: y.z,
+        r3c3: z.z,
+        r3c4: 0.0,
+
+        r4c1: - x.dot(&eye),
+        r4c2: - y.dot(&eye),
+        r4c3: - z.dot(&eye),
+        r4c4: 1.0
+    }
+}
+
+/// linear interpolation between two values. (`a + (b - a) * t`)
+/// returns `b` at `t = 1`, which the formula can miss by rounding.
+#[inline]
+pub fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    if t == 1.0 {
+        return b;
+    }
+    a + (b - a) * t
+}
+
+/// the `index`-th element of the halton sequence of the given `base`. the result is in `[0, 1)`.
+/// (e.g. `halton(1, 2) = 0.5`, `halton(2, 2) = 0.25`, `halton(1, 3) = 1/3`)
+/// 
+/// # Panics
+/// - Stop program execution if `base` is less than two.
+/// 
+#[inline]
+pub fn halton(index: u32, base: u32) -> f32 {
+    assert!(base >= 2, "the base of the halton sequence must be at least two.");
+    let mut index = index;
+    let mut fraction = 1.0;
+    let mut result = 0.0;
+    while index > 0 {
+        fraction /= base as f32;
+        result += fraction * (index % base) as f32;
+        index /= base;
+    }
+    result
+}
+
+/// average position of the given points. returns `Vec3::ZERO` if the slice is empty.
+/// (e.g. the point for the camera to frame a group of objects)
+#[inline]
+pub fn centroid(points: &[Vec3]) -> Vec3 {
+    Vec3::centroid(points)
+}
+
+/// return the far distance of each cascade of a cascaded shadow map.
+/// blends the logarithmic and uniform split schemes by `lambda`. (0.0 = uniform, 1.0 = logarithmic)
+/// 
+/// # Panics
+/// - Stop program execution if `near` is not positive or is not less than `far`.
+/// 
+#[inline]
+pub fn cascade_split_distances(near: f32, far: f32, cascade_count: usize, lambda: f32) -> Vec<f32> {
+    assert!(0.0 < near && near < far, "near must be positive and less than far.");
+    let lambda = lambda.clamp(0.0, 1.0);
+    (1..=cascade_count)
+        .map(|i| {
+            let ratio = i as f32 / cascade_count as f32;
+            let log = near * (far / near).powf(ratio);
+            let uniform = near + (far - near) * ratio;
+            (lambda * log + (1.0 - lambda) * uniform).clamp(near, far)
+        })
+        .collect()
+}
+
+/// return the world-space corners of the slice of a perspective view frustum between `near` and `far`.
+/// `view` is the left-handed view matrix of the camera and `fov_y` is the vertical field of view in radians.
+/// the first four corners are on the near plane and the last four are on the far plane.
+#[inline]
+pub fn frustum_slice_corners(view: Mat4x4, fov_y: f32, aspect: f32, near: f32, far: f32) -> [Vec3; 8] {
+    let inv_view = view.inverse();
+    let tan_half_fovy = (fov_y * 0.5).tan();
+    let mut corners = [Vec3::ZERO; 8];
+    for (i, corner) in corners.iter_mut().enumerate() {
+        let depth = if i < 4 { near } else { far };
+        let y = if i % 4 < 2 { -1.0 } else { 1.0 } * depth * tan_half_fovy;
+        let x = if i % 2 == 0 { -1.0 } else { 1.0 } * depth * tan_half_fovy * aspect;
+        let world = Vec4::new_vector(x, y, depth, 1.0) * inv_view;
+        *corner = Vec3::new_vector(world.x, world.y, world.z);
+    }
+    corners
+}
+
+/// return the light view-projection matrix of a shadow cascade that covers the given corners.
+/// the cascade is fitted to the bounding sphere of the corners, so its size does not change when the camera rotates.
+/// `light_dir` is the direction the light travels in. (from the light towards the scene)
+#[inline]
+pub fn cascade_view_projection(corners: &[Vec3; 8], light_dir: Vec3) -> Mat4x4 {
+    let center = centroid(corners);
+    let radius = corners.iter()
+        .map(|corner| corner.distance(&center))
+        .fold(0.0f32, f32::max)
+        .max(f32::EPSILON);
+
+    let light_dir = light_dir.try_normalized().unwrap_or(Vec3::Z);
+    let up = if light_dir.cross(&Vec3::Y).length_squared() > f32::EPSILON { Vec3::Y } else { Vec3::X };
+    let view = look_at_lh(center - light_dir * radius, center, up);
+    let projection = orthographic_lh_zo(-radius, radius, -radius, radius, 0.0, 2.0 * radius);
+    view * projection
+}
@@ -0,0 +1,98 @@
+use super::vec3::Vec3;
+use super::vec4::Vec4;
+
+/// A plane of the points `p` with `normal.dot(p) + distance == 0`.
+/// The normal points to the positive side of the plane.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Plane {
+    pub normal: Vec3,
+    pub distance: f32,
+}
+
+impl Plane {
+    #[inline]
+    pub fn new(normal: Vec3, distance: f32) -> Self {
+        Self { normal, distance }
+    }
+
+    /// Create a plane from the coefficients `(a, b, c, d)` of `ax + by + cz + d = 0`.
+    /// (e.g. a row combination of a view-projection matrix)
+    #[inline]
+    pub fn from_vec4(v: Vec4) -> Self {
+        Self { normal: Vec3::new_vector(v.x, v.y, v.z), distance: v.w }
+    }
+
+    /// Create a plane through three points.
+    /// The normal is `(b - a) x (c - a)`, normalized.
+    /// Returns `None` if the points are on a line.
+    pub fn from_points(a: Vec3, b: Vec3, c: Vec3) -> Option<Self> {
+        let normal = (b - a).cross(&(c - a)).try_normalized()?;
+        Some(Self { normal, distance: -normal.dot(&a) })
+    }
+
+    /// Returns the plane with a unit-length normal.
+    /// The signed distances of the returned plane are in world units.
+    #[inline]
+    pub fn normalize(&self) -> Self {
+        let length = self.normal.length();
+        Self { normal: self.normal / length, distance: self.distance / length }
+    }
+
+    /// Returns the signed distance from the plane to the point.
+    /// Positive on the side the normal points to. The plane must be normalized.
+    #[inline]
+    pub fn signed_distance(&self, point: Vec3) -> f32 {
+        self.normal.dot(&point) + self.distance
+    }
+
+    /// Returns the dot product of the plane coefficients and the homogeneous point.
+    /// The same as `signed_distance` if `point.w` is `1.0`.
+    #[inline]
+    pub fn dot_vec4(&self, point: Vec4) -> f32 {
+        self.normal.x * point.x + self.normal.y * point.y + self.normal.z * point.z + self.distance * point.w
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signed_distance_to_the_ground_plane_is_the_height() {
+        let plane = Plane::new(Vec3::Y, 0.0);
+        assert_eq!(plane.signed_distance(Vec3::new_vector(3.0, 2.0, -1.0)), 2.0);
+        assert_eq!(plane.signed_distance(Vec3::new_vector(-4.0, -5.0, 7.0)), -5.0);
+        assert_eq!(plane.signed_distance(Vec3::new_vector(1.0, 0.0, 1.0)), 0.0);
+
+        // the plane `y = 1`.
+        let plane = Plane::new(Vec3::Y, -1.0);
+        assert_eq!(plane.signed_distance(Vec3::new_vector(0.0, 3.0, 0.0)), 2.0);
+    }
+
+    #[test]
+    fn plane_from_points_contains_the_points() {
+        let (a, b, c) = (
+            Vec3::new_vector(0.0, 1.0, 0.0), 
+            Vec3::new_vector(0.0, 1.0, 1.0), 
+            Vec3::new_vector(1.0, 1.0, 0.0)
+        );
+        let plane = Plane::from_points(a, b, c).unwrap();
+        assert!((plane.normal - Vec3::Y).length() <= 1e-6, "{:?}", plane);
+        assert!((plane.distance + 1.0).abs() <= 1e-6, "{:?}", plane);
+        for point in [a, b, c] {
+            assert!(plane.signed_distance(point).abs() <= 1e-6);
+        }
+
+        // the opposite winding flips the normal.
+        let flipped = Plane::from_points(a, c, b).unwrap();
+        assert!((flipped.normal + Vec3::Y).length() <= 1e-6, "{:?}", flipped);
+    }
+
+    #[test]
+    fn plane_from_points_on_a_line_is_none() {
+        let plane = Plane::from_points(Vec3::ZERO, Vec3::X, Vec3::X * 2.0);
+        assert!(plane.is_none());
+    }
+}
@@ -79,6 +79,70 @@ impl Quat {
         }
     }
 
+    /// the rotation angle of the quaternion. (radian)
+    /// the quaternion must be normalized.
+    #[inline]
+    pub fn angle(&self) -> f32 {
+        2.0 * self.w.clamp(-1.0, 1.0).acos()
+    }
+
+    /// the rotation axis of the quaternion.
+    /// returns `Vec3::X` if the rotation is near the identity and the axis is undefined.
+    /// the quaternion must be normalized.
+    #[inline]
+    pub fn axis(&self) -> Vec3 {
+        let v = Vec3::new_vector(self.x, self.y, self.z);
+        let length = v.length();
+        if length <= f32::EPSILON {
+            return Vec3::X;
+        }
+        v / length
+    }
+
+    /// create a quaternion with the given euler angles. (radian)
+    /// - `pitch`: rotation around the x-axis.
+    /// - `yaw`: rotation around the y-axis.
+    /// - `roll`: rotation around the z-axis.
+    /// 
+    /// the rotation order is ZYX (intrinsic) with the y-axis up: yaw, then pitch, then roll.
+    /// this is the same as `from_angle_axis(yaw, Y) * from_angle_axis(pitch, X) * from_angle_axis(roll, Z)`.
+    #[inline]
+    pub fn from_euler(pitch: f32, yaw: f32, roll: f32) -> Self {
+        let (sp, cp) = (pitch * 0.5).sin_cos();
+        let (sy, cy) = (yaw * 0.5).sin_cos();
+        let (sr, cr) = (roll * 0.5).sin_cos();
+        Self {
+            x: cr * sp * cy + sr * cp * sy,
+            y: cr * cp * sy - sr * sp * cy,
+            z: sr * cp * cy - cr * sp * sy,
+            w: cr * cp * cy + sr * sp * sy
+        }
+    }
+
+    /// convert a quaternion to euler angles. `(pitch, yaw, roll)` (radian)
+    /// see `Quat::from_euler` for the convention used.
+    /// at the gimbal lock, pitch is exactly `±π/2`, roll is `0` and the whole rotation around the y-axis is yaw.
+    /// the quaternion must be normalized.
+    #[inline]
+    pub fn to_euler(self) -> (f32, f32, f32) {
+        const GIMBAL_LOCK_THRESHOLD: f32 = 1.0 - 1e-6;
+
+        let sin_pitch = (2.0 * (self.w * self.x - self.y * self.z)).clamp(-1.0, 1.0);
+        if sin_pitch.abs() >= GIMBAL_LOCK_THRESHOLD {
+            let pitch = std::f32::consts::FRAC_PI_2.copysign(sin_pitch);
+            let yaw = (2.0 * (self.w * self.y - self.x * self.z))
+                .atan2(1.0 - 2.0 * (self.y * self.y + self.z * self.z));
+            return (pitch, yaw, 0.0);
+        }
+
+        let pitch = sin_pitch.asin();
+        let yaw = (2.0 * (self.w * self.y + self.x * self.z))
+            .atan2(1.0 - 2.0 * (self.x * self.x + self.y * self.y));
+        let roll = (2.0 * (self.w * self.z + self.x * self.y))
+            .atan2(1.0 - 2.0 * (self.x * self.x + self.z * self.z));
+        (pitch, yaw, roll)
+    }
+
     /// create a quaternion with a given matrix.
     #[inline]
     pub fn from_matrix3x3(m: Mat3x3) -> Self {
@@ -189,6 +253,17 @@ impl Quat {
         Mat4x4::from_quat(self)
     }
 
+    /// rotate the vector by the quaternion. (`v + 2w(q × v) + 2(q × (q × v))`)
+    /// cheaper than converting the quaternion to a matrix for a single vector.
+    /// the quaternion must be normalized.
+    #[inline]
+    pub fn rotate_vector(self, v: Vec3) -> Vec3 {
+        debug_assert!((self.length_squared() - 1.0).abs() <= 1e-3, "the quaternion must be normalized.");
+        let q = Vec3::new_vector(self.x, self.y, self.z);
+        let t = q.cross(&v) * 2.0;
+        v + t * self.w + q.cross(&t)
+    }
+
     #[inline]
     pub fn add_quat(self, rhs: Self) -> Self {
         Self {
@@ -276,14 +351,17 @@ impl Quat {
     }
 
     /// return inverse quaternion.
+    /// the conjugate divided by the square of the length, so that `q * q.inverse()` is the identity.
     #[inline]
     pub fn inverse(&self) -> Self {
-        self.conjugate().div_scalar(self.length())
+        self.conjugate().div_scalar(self.length_squared())
     }
 
     /// return inverse quaternion.
+    /// the quaternion must be normalized. the inverse of a unit quaternion is its conjugate.
+    #[inline]
     pub fn fast_inverse(&self) -> Self {
-        self.conjugate().div_scalar(self.length_squared())
+        self.conjugate()
     }
 
     /// dot product.
@@ -326,6 +404,40 @@ impl Quat {
         return None;
     }
 
+    /// normalized linear interpolation between two quaternions along the shortest path.
+    /// cheaper than `slerp`, but the angular velocity is not constant.
+    #[inline]
+    pub fn nlerp(self, other: Self, t: f32) -> Self {
+        let other = if self.dot(other) < 0.0 { other.mul_scalar(-1.0) } else { other };
+        self.add_quat(other.sub_quat(self).mul_scalar(t)).normalize()
+    }
+
+    /// spherical linear interpolation between two quaternions along the shortest path.
+    /// falls back to `nlerp` if the two quaternions are nearly colinear.
+    /// the quaternions must be normalized.
+    #[inline]
+    pub fn slerp(self, other: Self, t: f32) -> Self {
+        const COLINEAR_THRESHOLD: f32 = 0.9995;
+
+        let mut dot = self.dot(other);
+        let other = if dot < 0.0 {
+            dot = -dot;
+            other.mul_scalar(-1.0)
+        } else {
+            other
+        };
+
+        if dot > COLINEAR_THRESHOLD {
+            return self.nlerp(other, t);
+        }
+
+        let theta = dot.acos();
+        let sin_theta = theta.sin();
+        let a = ((1.0 - t) * theta).sin() / sin_theta;
+        let b = (t * theta).sin() / sin_theta;
+        self.mul_scalar(a).add_quat(other.mul_scalar(b)).normalize()
+    }
+
     /// return `true` if any element of the quaternion has the value of infinity.
     #[inline]
     pub fn is_infinite(&self) -> bool {
@@ -345,6 +457,8 @@ impl Quat {
     }
 
     /// return `true` if the two quaternions are equal.
+    /// the comparison is approximate (each element within `f32::EPSILON`),
+    /// so it is not transitive and never `true` for a quaternion containing NaN.
     #[inline]
     pub fn equal(&self, other: &Self) -> bool {
         let mut flag = true;
@@ -354,6 +468,16 @@ impl Quat {
         return flag
     }
 
+    /// return `true` if the two quaternions have exactly the same bit pattern.
+    /// unlike `equal`, this is reflexive even for NaN, but `0.0` and `-0.0` are different.
+    #[inline]
+    pub fn bitwise_eq(&self, other: &Self) -> bool {
+        self.x.to_bits() == other.x.to_bits()
+        && self.y.to_bits() == other.y.to_bits()
+        && self.z.to_bits() == other.z.to_bits()
+        && self.w.to_bits() == other.w.to_bits()
+    }
+
     /// return the smaller of the elements of two quaternion.
     #[inline]
     pub fn min(self, other: Self) -> Self {
@@ -525,6 +649,8 @@ impl ops::IndexMut<usize> for Quat {
     }
 }
 
+/// approximate equality. see `Quat::equal`.
+/// it is not reflexive for NaN, use `Quat::bitwise_eq` for exact comparison.
 impl cmp::PartialEq for Quat {
     #[inline]
     fn eq(&self, other: &Self) -> bool {
@@ -602,6 +728,10 @@ impl Into<Mat4x4> for Quat {
     }
 }
 
+// `AsRef<[f32; 4]>` and `AsMut<[f32; 4]>` rely on `Quat` having the same layout as `[f32; 4]`.
+const _: () = assert!(std::mem::size_of::<Quat>() == std::mem::size_of::<[f32; 4]>());
+const _: () = assert!(std::mem::align_of::<Quat>() == std::mem::align_of::<[f32; 4]>());
+
 impl AsRef<[f32; 4]> for Quat {
     #[inline]
     fn as_ref(&self) -> &[f32; 4] {
@@ -621,3 +751,63 @@ impl fmt::Display for Quat {
         write!(f, "({}, {}, {}, {})", self.x, self.y, self.z, self.w)
     }
 }
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bitwise_identical_quats_are_equal() {
+        let q = Quat::new(0.1, -0.2, 0.3, 0.9);
+        let copy = Quat::new(0.1, -0.2, 0.3, 0.9);
+        assert!(q.bitwise_eq(&copy));
+        assert_eq!(q, copy);
+
+        // unlike `==`, NaN is bitwise equal to itself.
+        let nan = Quat::new(f32::NAN, 0.0, 0.0, 1.0);
+        assert!(nan.bitwise_eq(&nan));
+        assert_ne!(nan, nan);
+        assert!(!Quat::new(0.0, 0.0, 0.0, 1.0).bitwise_eq(&Quat::new(-0.0, 0.0, 0.0, 1.0)));
+    }
+
+    #[test]
+    fn approximate_equality_tolerates_rounding() {
+        let q = Quat::new(0.1, -0.2, 0.3, 0.9);
+        let nudged = Quat::new(0.1 + 1e-7, -0.2, 0.3, 0.9 - 1e-7);
+        assert_eq!(q, nudged);
+        assert!(!q.bitwise_eq(&nudged));
+        assert_ne!(q, Quat::new(0.1 + 1e-5, -0.2, 0.3, 0.9));
+    }
+
+    #[test]
+    fn angle_and_axis_round_trip() {
+        let axis = Vec3::new_vector(1.0, 2.0, -2.0).normalize();
+        for angle in [0.25, 1.0, 2.5, 3.0] {
+            let q = Quat::from_angle_axis(angle, axis);
+            assert!((q.angle() - angle).abs() <= 1e-5, "{} != {}", q.angle(), angle);
+            assert!((q.axis() - axis).length() <= 1e-5, "{:?} != {:?}", q.axis(), axis);
+
+            let round_trip = Quat::from_angle_axis(q.angle(), q.axis());
+            assert!(round_trip.dot(q).abs() >= 1.0 - 1e-5, "{:?} != {:?}", round_trip, q);
+        }
+    }
+
+    #[test]
+    fn identity_has_zero_angle_and_the_x_axis() {
+        assert_eq!(Quat::IDENTITY.angle(), 0.0);
+        assert_eq!(Quat::IDENTITY.axis(), Vec3::X);
+        assert_eq!(Quat::from_angle_axis(0.0, Vec3::Y).axis(), Vec3::X);
+    }
+
+    #[test]
+    fn rotating_x_about_z_gives_y_and_matches_the_matrix() {
+        let q = Quat::from_angle_axis(std::f32::consts::FRAC_PI_2, Vec3::Z);
+        let rotated = q.rotate_vector(Vec3::X);
+        assert!((rotated - Vec3::Y).length() <= 1e-5, "{:?} != {:?}", rotated, Vec3::Y);
+
+        let by_matrix = Vec3::X * q.into_matrix3x3();
+        assert!((rotated - by_matrix).length() <= 1e-5, "{:?} != {:?}", rotated, by_matrix);
+    }
+}
@@ -0,0 +1,339 @@
+//! property checks of the math types over deterministic random inputs.
+
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+
+use super::*;
+
+const SAMPLES: usize = 1000;
+const TOLERANCE: f32 = 1e-4;
+
+
+fn rng() -> StdRng {
+    StdRng::seed_from_u64(0x5eed)
+}
+
+fn random_vec3(rng: &mut StdRng) -> Vec3 {
+    Vec3::new_vector(rng.gen_range(-10.0..10.0), rng.gen_range(-10.0..10.0), rng.gen_range(-10.0..10.0))
+}
+
+fn random_vec4(rng: &mut StdRng) -> Vec4 {
+    Vec4::new_vector(
+        rng.gen_range(-10.0..10.0),
+        rng.gen_range(-10.0..10.0),
+        rng.gen_range(-10.0..10.0),
+        rng.gen_range(-10.0..10.0)
+    )
+}
+
+fn random_quat(rng: &mut StdRng) -> Quat {
+    loop {
+        let q = Quat::new(
+            rng.gen_range(-1.0..1.0),
+            rng.gen_range(-1.0..1.0),
+            rng.gen_range(-1.0..1.0),
+            rng.gen_range(-1.0..1.0)
+        );
+        if q.length_squared() > 0.01 {
+            return q;
+        }
+    }
+}
+
+/// a diagonally dominant matrix, which is always invertible.
+fn random_invertible_matrix(rng: &mut StdRng) -> Mat4x4 {
+    let mut m = Mat4x4::ZERO;
+    for (i, value) in m.as_mut().iter_mut().enumerate() {
+        *value = rng.gen_range(-1.0..1.0);
+        if i % 5 == 0 {
+            *value += 4.0f32.copysign(*value);
+        }
+    }
+    m
+}
+
+fn assert_near<const N: usize>(actual: &[f32; N], expected: &[f32; N], tolerance: f32) {
+    for (a, e) in actual.iter().zip(expected.iter()) {
+        assert!((a - e).abs() <= tolerance, "{:?} is not near {:?}", actual, expected);
+    }
+}
+
+
+#[test]
+fn normalize_has_unit_length() {
+    let mut rng = rng();
+    for _ in 0..SAMPLES {
+        assert!((random_quat(&mut rng).normalize().length() - 1.0).abs() <= TOLERANCE);
+        assert!((random_vec3(&mut rng).normalize().length() - 1.0).abs() <= TOLERANCE);
+        assert!((random_vec4(&mut rng).normalize().length() - 1.0).abs() <= TOLERANCE);
+    }
+}
+
+#[test]
+fn dot_is_symmetric() {
+    let mut rng = rng();
+    for _ in 0..SAMPLES {
+        let (a, b) = (random_vec3(&mut rng), random_vec3(&mut rng));
+        assert_eq!(a.dot(&b), b.dot(&a));
+        let (a, b) = (random_vec4(&mut rng), random_vec4(&mut rng));
+        assert_eq!(a.dot(&b), b.dot(&a));
+        let (a, b) = (random_quat(&mut rng), random_quat(&mut rng));
+        assert_eq!(a.dot(b), b.dot(a));
+    }
+}
+
+#[test]
+fn matrix_times_inverse_is_identity() {
+    let mut rng = rng();
+    for _ in 0..SAMPLES {
+        let m = random_invertible_matrix(&mut rng);
+        assert_near((m * m.inverse()).as_ref(), Mat4x4::IDENTITY.as_ref(), TOLERANCE);
+        assert_near((m.inverse() * m).as_ref(), Mat4x4::IDENTITY.as_ref(), TOLERANCE);
+    }
+}
+
+#[test]
+fn rotation_matrix_round_trips_through_quat() {
+    let mut rng = rng();
+    for _ in 0..SAMPLES {
+        let m = Mat4x4::from_quat(random_quat(&mut rng).normalize());
+        assert_near(Mat4x4::from_quat(m.into_quat()).as_ref(), m.as_ref(), TOLERANCE);
+    }
+}
+
+#[test]
+fn quat_times_inverse_is_identity() {
+    // regression: `Quat::inverse` divided the conjugate by the length instead of the square of the length,
+    // which is only correct for unit quaternions.
+    let mut rng = rng();
+    for _ in 0..SAMPLES {
+        let q = random_quat(&mut rng).mul_scalar(rng.gen_range(0.5..3.0));
+        assert_near((q * q.inverse()).as_ref(), Quat::IDENTITY.as_ref(), TOLERANCE);
+        assert_near((q.inverse() * q).as_ref(), Quat::IDENTITY.as_ref(), TOLERANCE);
+    }
+
+    let q = Quat::new(0.0, 0.0, 0.0, 2.0);
+    assert_eq!(q.inverse(), Quat::new(0.0, 0.0, 0.0, 0.5));
+}
+
+#[test]
+fn inverse_of_unit_quat_is_conjugate() {
+    let mut rng = rng();
+    for _ in 0..SAMPLES {
+        let q = random_quat(&mut rng).normalize();
+        assert_near(q.inverse().as_ref(), q.conjugate().as_ref(), TOLERANCE);
+        assert_eq!(q.fast_inverse(), q.conjugate());
+        assert_near((q * q.fast_inverse()).as_ref(), Quat::IDENTITY.as_ref(), TOLERANCE);
+    }
+}
+
+#[test]
+fn slerp_hits_endpoints() {
+    let mut rng = rng();
+    for _ in 0..SAMPLES {
+        let a = random_quat(&mut rng).normalize();
+        let b = random_quat(&mut rng).normalize();
+        // `slerp` takes the shortest path, so the end may be `-b`. (the same rotation)
+        let b = if a.dot(b) < 0.0 { -b } else { b };
+        assert_near(a.slerp(b, 0.0).as_ref(), a.as_ref(), TOLERANCE);
+        assert_near(a.slerp(b, 1.0).as_ref(), b.as_ref(), TOLERANCE);
+        assert_near(a.nlerp(b, 0.0).as_ref(), a.as_ref(), TOLERANCE);
+        assert_near(a.nlerp(b, 1.0).as_ref(), b.as_ref(), TOLERANCE);
+    }
+}
+
+#[test]
+fn lerp_hits_endpoints_exactly() {
+    let mut rng = rng();
+    for _ in 0..SAMPLES {
+        let (a, b) = (random_vec3(&mut rng), random_vec3(&mut rng));
+        assert_eq!(a.lerp(b, 0.0).as_ref(), a.as_ref());
+        assert_eq!(a.lerp(b, 1.0).as_ref(), b.as_ref());
+        let (a, b) = (random_vec4(&mut rng), random_vec4(&mut rng));
+        assert_eq!(a.lerp(b, 0.0).as_ref(), a.as_ref());
+        assert_eq!(a.lerp(b, 1.0).as_ref(), b.as_ref());
+    }
+
+    assert_eq!(lerp(0.1, 1.0e8, 1.0), 1.0e8);
+}
+
+#[test]
+fn euler_angles_rotate_around_the_y_up_axes() {
+    let angle = 0.7;
+    assert_near(Quat::from_euler(angle, 0.0, 0.0).as_ref(), Quat::from_angle_axis(angle, Vec3::X).as_ref(), TOLERANCE);
+    assert_near(Quat::from_euler(0.0, angle, 0.0).as_ref(), Quat::from_angle_axis(angle, Vec3::Y).as_ref(), TOLERANCE);
+    assert_near(Quat::from_euler(0.0, 0.0, angle).as_ref(), Quat::from_angle_axis(angle, Vec3::Z).as_ref(), TOLERANCE);
+
+    let (pitch, yaw, roll) = (0.3, -1.2, 2.1);
+    let expected = Quat::from_angle_axis(yaw, Vec3::Y) * Quat::from_angle_axis(pitch, Vec3::X) * Quat::from_angle_axis(roll, Vec3::Z);
+    assert_near(Quat::from_euler(pitch, yaw, roll).as_ref(), expected.as_ref(), TOLERANCE);
+}
+
+#[test]
+fn euler_angles_round_trip_through_quat() {
+    const EULER_TOLERANCE: f32 = f32::EPSILON * 10.0;
+
+    // away from the gimbal lock.
+    let mut rng = rng();
+    for _ in 0..SAMPLES {
+        let pitch = rng.gen_range(-1.0..1.0);
+        let yaw = rng.gen_range(-3.0..3.0);
+        let roll = rng.gen_range(-3.0..3.0);
+        let (p, y, r) = Quat::from_euler(pitch, yaw, roll).to_euler();
+        assert_near(&[p, y, r], &[pitch, yaw, roll], EULER_TOLERANCE);
+    }
+}
+
+#[test]
+fn euler_angles_at_gimbal_lock_keep_the_rotation() {
+    use std::f32::consts::FRAC_PI_2;
+
+    for pitch in [FRAC_PI_2, -FRAC_PI_2] {
+        let q = Quat::from_euler(pitch, 0.5, 0.25);
+        let (p, y, r) = q.to_euler();
+        assert_eq!(p, pitch);
+        assert_eq!(r, 0.0);
+
+        // the roll is folded into the yaw, so the rotation is the same.
+        let round_trip = Quat::from_euler(p, y, r);
+        let round_trip = if round_trip.dot(q) < 0.0 { -round_trip } else { round_trip };
+        assert_near(round_trip.as_ref(), q.as_ref(), TOLERANCE);
+    }
+}
+
+#[test]
+fn map_applies_to_each_element() {
+    let mut rng = rng();
+    for _ in 0..SAMPLES {
+        let v = random_vec3(&mut rng);
+        assert_eq!(v.map(|x| x * 2.0).into_array(), (v * 2.0).into_array());
+        let v = random_vec4(&mut rng);
+        assert_eq!(v.map(|x| x * 2.0).into_array(), (v * 2.0).into_array());
+    }
+}
+
+#[test]
+fn zip_with_max_is_max() {
+    let mut rng = rng();
+    for _ in 0..SAMPLES {
+        let (a, b) = (random_vec3(&mut rng), random_vec3(&mut rng));
+        assert_eq!(a.zip_with(b, f32::max).into_array(), a.max(b).into_array());
+        let (a, b) = (random_vec4(&mut rng), random_vec4(&mut rng));
+        assert_eq!(a.zip_with(b, f32::max).into_array(), a.max(b).into_array());
+    }
+}
+
+#[test]
+fn translation_moves_points_but_not_directions() {
+    let mut rng = rng();
+    for _ in 0..SAMPLES {
+        let (t, p) = (random_vec3(&mut rng), random_vec3(&mut rng));
+        let m = Mat4x4::from_translation(t);
+        let moved = Vec4::new_vector(p.x, p.y, p.z, 1.0) * m;
+        assert_near(moved.as_ref(), &[p.x + t.x, p.y + t.y, p.z + t.z, 1.0], TOLERANCE);
+
+        let direction = Vec4::new_vector(p.x, p.y, p.z, 0.0);
+        assert_eq!((direction * m).into_array(), direction.into_array());
+    }
+}
+
+#[test]
+fn trs_is_scale_then_rotation_then_translation() {
+    let mut rng = rng();
+    for _ in 0..SAMPLES {
+        let (t, r, s) = (random_vec3(&mut rng), random_quat(&mut rng).normalize(), random_vec3(&mut rng));
+        let expected = Mat4x4::from_scale(s) * Mat4x4::from_rotation(r) * Mat4x4::from_translation(t);
+        assert_near(Mat4x4::from_trs(t, r, s).as_ref(), expected.as_ref(), TOLERANCE);
+    }
+
+    // a point is scaled by two, turned a quarter about the y-axis, then moved along the x-axis.
+    let m = Mat4x4::from_trs(Vec3::X, Quat::from_angle_axis(std::f32::consts::FRAC_PI_2, Vec3::Y), Vec3::ONE * 2.0);
+    let p = Vec4::new_vector(1.0, 0.0, 0.0, 1.0) * m;
+    let rotated = Vec4::new_vector(2.0, 0.0, 0.0, 0.0) * Mat4x4::from_rotation(Quat::from_angle_axis(std::f32::consts::FRAC_PI_2, Vec3::Y));
+    assert_near(p.as_ref(), &[rotated.x + 1.0, rotated.y, rotated.z, 1.0], TOLERANCE);
+}
+
+#[test]
+fn decompose_round_trips_through_trs() {
+    let mut rng = rng();
+    for _ in 0..SAMPLES {
+        let t = random_vec3(&mut rng);
+        let r = random_quat(&mut rng).normalize();
+        let s = Vec3::new_vector(rng.gen_range(0.1..10.0), rng.gen_range(0.1..10.0), rng.gen_range(0.1..10.0));
+        let m = Mat4x4::from_trs(t, r, s);
+
+        let (t2, r2, s2) = m.decompose();
+        assert_near(t2.as_ref(), t.as_ref(), TOLERANCE);
+        assert_near(s2.as_ref(), s.as_ref(), 1e-3);
+        // `q` and `-q` are the same rotation.
+        let r2 = if r2.dot(r) < 0.0 { -r2 } else { r2 };
+        assert_near(r2.as_ref(), r.as_ref(), 1e-3);
+        assert_near(Mat4x4::from_trs(t2, r2, s2).as_ref(), m.as_ref(), 1e-3);
+    }
+}
+
+#[test]
+fn clamp_keeps_elements_inside_and_moves_the_rest_to_the_bounds() {
+    let (min, max) = (Vec3::new_vector(-1.0, -1.0, -1.0), Vec3::ONE);
+    // below, inside and above the range.
+    let v = Vec3::new_vector(-5.0, 0.25, 5.0);
+    assert_eq!(v.clamp(min, max), Vec3::new_vector(-1.0, 0.25, 1.0));
+    assert_eq!(Vec2::new_vector(-5.0, 5.0).clamp(Vec2::ZERO, Vec2::ONE), Vec2::new_vector(0.0, 1.0));
+    assert_eq!(Vec4::new_vector(-5.0, 0.5, 5.0, 1.0).clamp(Vec4::ZERO, Vec4::ONE), Vec4::new_vector(0.0, 0.5, 1.0, 1.0));
+
+    let mut rng = rng();
+    for _ in 0..SAMPLES {
+        let c = random_vec3(&mut rng).clamp(min, max);
+        assert!(c.into_array().iter().all(|x| (-1.0..=1.0).contains(x)));
+    }
+}
+
+#[test]
+fn abs_and_signum_split_the_magnitude_and_the_sign() {
+    let v = Vec3::new_vector(-2.0, 0.5, 3.0);
+    assert_eq!(v.abs(), Vec3::new_vector(2.0, 0.5, 3.0));
+    assert_eq!(v.signum(), Vec3::new_vector(-1.0, 1.0, 1.0));
+    assert_eq!(Vec2::new_vector(-2.0, 3.0).abs(), Vec2::new_vector(2.0, 3.0));
+    assert_eq!(Vec4::new_vector(-2.0, 0.5, -3.0, 1.0).signum(), Vec4::new_vector(-1.0, 1.0, -1.0, 1.0));
+
+    let mut rng = rng();
+    for _ in 0..SAMPLES {
+        let v = random_vec3(&mut rng);
+        assert_eq!((v.abs() * v.signum()).into_array(), v.into_array());
+    }
+}
+
+#[test]
+fn element_sum_adds_every_element() {
+    assert_eq!(Vec3::new_vector(1.0, -2.0, 4.0).element_sum(), 3.0);
+    assert_eq!(Vec4::new_vector(1.0, -2.0, 4.0, 0.5).element_sum(), 3.5);
+
+    let mut rng = rng();
+    for _ in 0..SAMPLES {
+        let v = random_vec3(&mut rng);
+        assert!((v.element_sum() - v.dot(&Vec3::ONE)).abs() <= TOLERANCE);
+    }
+}
+
+#[test]
+fn centroid_is_the_average_of_the_points() {
+    assert_eq!(Vec3::centroid(&[]), Vec3::ZERO);
+    assert_eq!(Vec4::centroid(&[]), Vec4::ZERO);
+
+    let triangle = [Vec3::ZERO, Vec3::new_vector(3.0, 0.0, 0.0), Vec3::new_vector(0.0, 6.0, 3.0)];
+    assert_eq!(centroid(&triangle), Vec3::new_vector(1.0, 2.0, 1.0));
+
+    let mut rng = rng();
+    for _ in 0..SAMPLES {
+        let v = random_vec4(&mut rng);
+        assert_near(&Vec4::centroid(&[v, -v]).into_array(), &[0.0; 4], TOLERANCE);
+        assert_eq!(Vec4::centroid(&[v]), v);
+    }
+}
+
+#[test]
+fn halton_2_3_starts_with_the_expected_values() {
+    assert_near(&[1, 2, 3, 4].map(|index| halton(index, 2)), &[1.0 / 2.0, 1.0 / 4.0, 3.0 / 4.0, 1.0 / 8.0], 1e-6);
+    assert_near(&[1, 2, 3, 4].map(|index| halton(index, 3)), &[1.0 / 3.0, 2.0 / 3.0, 1.0 / 9.0, 4.0 / 9.0], 1e-6);
+    assert_eq!(halton(0, 2), 0.0);
+}
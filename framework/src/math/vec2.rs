@@ -2,6 +2,7 @@ use std::fmt;
 use std::ops;
 use std::cmp;
 use bytemuck::{Zeroable, Pod};
+use super::madd;
 use super::mat2::Mat2x2;
 
 /// 2-dimensional vector.
@@ -196,6 +197,41 @@ impl Vec2 {
         self.x * rhs.x + self.y * rhs.y
     }
 
+    /// sum of all elements of the vector.
+    #[inline]
+    pub fn element_sum(&self) -> f32 {
+        self.x + self.y
+    }
+
+    /// average of the given vectors. returns `ZERO` if the slice is empty.
+    #[inline]
+    pub fn centroid(points: &[Self]) -> Self {
+        if points.is_empty() {
+            return Self::ZERO;
+        }
+        points.iter().fold(Self::ZERO, |sum, &point| sum + point) / points.len() as f32
+    }
+
+    /// 2d cross product of two vectors. (`self.x * rhs.y - self.y * rhs.x`)
+    /// positive if `rhs` is counter-clockwise from `self`.
+    #[inline]
+    pub fn perp_dot(&self, rhs: Self) -> f32 {
+        self.x * rhs.y - self.y * rhs.x
+    }
+
+    /// return the vector rotated 90 degrees counter-clockwise. (`(-y, x)`)
+    #[inline]
+    pub fn perp(&self) -> Self {
+        Self::new_vector(-self.y, self.x)
+    }
+
+    /// reflect the vector off a surface with the given normal.
+    /// the normal must be normalized.
+    #[inline]
+    pub fn reflect(self, normal: Self) -> Self {
+        self - normal * (2.0 * self.dot(normal))
+    }
+
     /// the length of the vector.
     #[inline]
     pub fn length(&self) -> f32 {
@@ -205,7 +241,20 @@ impl Vec2 {
     /// the square of the length of the vector.
     #[inline]
     pub fn length_squared(&self) -> f32 {
-        self.x * self.x + self.y * self.y
+        madd(self.y, self.y, self.x * self.x)
+    }
+
+    /// the distance between two vectors. (`distance_squared().sqrt()`)
+    #[inline]
+    pub fn distance(&self, other: &Self) -> f32 {
+        self.distance_squared(other).sqrt()
+    }
+
+    /// the square of the distance between two vectors.
+    /// prefer this to `distance` when only comparing distances.
+    #[inline]
+    pub fn distance_squared(&self, other: &Self) -> f32 {
+        (*self - *other).length_squared()
     }
 
     /// return normalized vector.
@@ -277,6 +326,55 @@ impl Vec2 {
         }
     }
 
+    /// clamp each element of a vector into the range of the elements of `min` and `max`.
+    #[inline]
+    pub fn clamp(self, min: Self, max: Self) -> Self {
+        debug_assert!(min.x <= max.x && min.y <= max.y, "min must be less than or equal to max.");
+        self.max(min).min(max)
+    }
+
+    /// return the absolute value of each element of a vector.
+    #[inline]
+    pub fn abs(self) -> Self {
+        self.map(f32::abs)
+    }
+
+    /// return the sign of each element of a vector. (see `f32::signum`)
+    #[inline]
+    pub fn signum(self) -> Self {
+        self.map(f32::signum)
+    }
+
+    /// apply the function to each element of a vector.
+    #[inline]
+    pub fn map(self, f: impl Fn(f32) -> f32) -> Self {
+        Self {
+            x: f(self.x),
+            y: f(self.y)
+        }
+    }
+
+    /// apply the function to each pair of elements of two vectors.
+    #[inline]
+    pub fn zip_with(self, other: Self, f: impl Fn(f32, f32) -> f32) -> Self {
+        Self {
+            x: f(self.x, other.x),
+            y: f(self.y, other.y)
+        }
+    }
+
+    /// linear interpolation between two vectors. (`self + (other - self) * t`)
+    #[inline]
+    pub fn lerp(self, other: Self, t: f32) -> Self {
+        self.zip_with(other, |a, b| super::lerp(a, b, t))
+    }
+
+    /// linear interpolation between two vectors with `t` clamped into `0.0..=1.0`.
+    #[inline]
+    pub fn lerp_clamped(self, other: Self, t: f32) -> Self {
+        self.lerp(other, t.clamp(0.0, 1.0))
+    }
+
     /// round up the decimal places of the elements of a vector.
     #[inline]
     pub fn ceil(self) -> Self {
@@ -573,3 +671,47 @@ impl fmt::Display for Vec2 {
         write!(f, "({}, {})", self.x, self.y)
     }
 }
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lerp_hits_midpoint_and_endpoints() {
+        let (a, b) = (Vec2::new_vector(-1.0, 2.0), Vec2::new_vector(3.0, 6.0));
+        assert_eq!(a.lerp(b, 0.0), a);
+        assert_eq!(a.lerp(b, 0.5), Vec2::new_vector(1.0, 4.0));
+        assert_eq!(a.lerp(b, 1.0), b);
+    }
+
+    #[test]
+    fn lerp_of_same_vectors_is_the_vector() {
+        let a = Vec2::new_vector(0.1, -7.5);
+        assert_eq!(a.lerp(a, 0.3), a);
+        assert_eq!(a.lerp(a, 42.0), a);
+    }
+
+    #[test]
+    fn lerp_clamped_stays_between_endpoints() {
+        let (a, b) = (Vec2::ZERO, Vec2::ONE);
+        assert_eq!(a.lerp_clamped(b, -1.0), a);
+        assert_eq!(a.lerp_clamped(b, 2.0), b);
+    }
+
+
+    #[test]
+    fn perp_turns_the_axes_counter_clockwise() {
+        assert_eq!(Vec2::X.perp(), Vec2::Y);
+        assert_eq!(Vec2::Y.perp(), -Vec2::X);
+        assert_eq!(Vec2::X.perp_dot(Vec2::Y), 1.0);
+        assert_eq!(Vec2::Y.perp_dot(Vec2::X), -1.0);
+        assert_eq!(Vec2::X.perp_dot(Vec2::X), 0.0);
+
+        // the 2d cross product is the dot product with the perpendicular vector.
+        let (a, b) = (Vec2::new_vector(3.0, -2.0), Vec2::new_vector(0.5, 4.0));
+        assert_eq!(a.perp().dot(a), 0.0);
+        assert_eq!(a.perp_dot(b), a.perp().dot(b));
+    }
+}
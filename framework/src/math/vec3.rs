@@ -2,6 +2,7 @@ use std::cmp;
 use std::fmt;
 use std::ops;
 use bytemuck::{Zeroable, Pod};
+use super::madd;
 use super::mat3::Mat3x3;
 
 /// 3-dimensional vector.
@@ -77,6 +78,35 @@ impl Vec3 {
         (self.x, self.y, self.z)
     }
 
+    /// create a vector with the given spherical coordinates.
+    /// - `azimuth`: angle around the y-axis, measured from the z-axis toward the x-axis. (radian)
+    /// - `elevation`: angle from the xz-plane toward the y-axis. (radian)
+    #[inline]
+    pub fn from_spherical(radius: f32, azimuth: f32, elevation: f32) -> Self {
+        let (sin_a, cos_a) = azimuth.sin_cos();
+        let (sin_e, cos_e) = elevation.sin_cos();
+        Self {
+            x: radius * cos_e * sin_a,
+            y: radius * sin_e,
+            z: radius * cos_e * cos_a
+        }
+    }
+
+    /// convert a vector to spherical coordinates. `(radius, azimuth, elevation)`
+    ///
+    /// see `Vec3::from_spherical` for the convention used.
+    /// returns `(0, 0, 0)` for a zero-length vector.
+    #[inline]
+    pub fn to_spherical(&self) -> (f32, f32, f32) {
+        let radius = self.length();
+        if radius <= f32::EPSILON {
+            return (0.0, 0.0, 0.0);
+        }
+        let azimuth = self.x.atan2(self.z);
+        let elevation = (self.y / radius).clamp(-1.0, 1.0).asin();
+        return (radius, azimuth, elevation);
+    }
+
     #[inline]
     pub fn add_scalar(self, rhs: f32) -> Self {
         Self {
@@ -209,6 +239,21 @@ impl Vec3 {
         self.x * rhs.x + self.y * rhs.y + self.z * rhs.z
     }
 
+    /// sum of all elements of the vector.
+    #[inline]
+    pub fn element_sum(&self) -> f32 {
+        self.x + self.y + self.z
+    }
+
+    /// average of the given vectors. returns `ZERO` if the slice is empty.
+    #[inline]
+    pub fn centroid(points: &[Self]) -> Self {
+        if points.is_empty() {
+            return Self::ZERO;
+        }
+        points.iter().fold(Self::ZERO, |sum, &point| sum + point) / points.len() as f32
+    }
+
     /// cross product of two vectors.
     #[inline]
     pub fn cross(&self, rhs: &Self) -> Self {
@@ -219,6 +264,27 @@ impl Vec3 {
         }
     }
 
+    /// reflect the vector off a surface with the given normal.
+    /// the normal must be normalized.
+    #[inline]
+    pub fn reflect(self, normal: Self) -> Self {
+        self - normal * (2.0 * self.dot(&normal))
+    }
+
+    /// refract the vector through a surface with the given normal.
+    /// `eta` is the ratio of the refractive indices. (incident / transmitted)
+    /// the vector and the normal must be normalized, and the normal must face against the vector.
+    /// returns `None` on total internal reflection.
+    #[inline]
+    pub fn refract(self, normal: Self, eta: f32) -> Option<Self> {
+        let cos_i = -self.dot(&normal);
+        let k = 1.0 - eta * eta * (1.0 - cos_i * cos_i);
+        if k < 0.0 {
+            return None;
+        }
+        Some(self * eta + normal * (eta * cos_i - k.sqrt()))
+    }
+
     /// the length of the vector.
     #[inline]
     pub fn length(&self) -> f32 {
@@ -228,7 +294,20 @@ impl Vec3 {
     /// the square of the length of the vector.
     #[inline]
     pub fn length_squared(&self) -> f32 {
-        self.x * self.x + self.y * self.y + self.z * self.z
+        madd(self.z, self.z, madd(self.y, self.y, self.x * self.x))
+    }
+
+    /// the distance between two vectors. (`distance_squared().sqrt()`)
+    #[inline]
+    pub fn distance(&self, other: &Self) -> f32 {
+        self.distance_squared(other).sqrt()
+    }
+
+    /// the square of the distance between two vectors.
+    /// prefer this to `distance` when only comparing distances.
+    #[inline]
+    pub fn distance_squared(&self, other: &Self) -> f32 {
+        (*self - *other).length_squared()
     }
 
     /// return normalized vector.
@@ -301,6 +380,57 @@ impl Vec3 {
         }
     }
 
+    /// clamp each element of a vector into the range of the elements of `min` and `max`.
+    #[inline]
+    pub fn clamp(self, min: Self, max: Self) -> Self {
+        debug_assert!(min.x <= max.x && min.y <= max.y && min.z <= max.z, "min must be less than or equal to max.");
+        self.max(min).min(max)
+    }
+
+    /// return the absolute value of each element of a vector.
+    #[inline]
+    pub fn abs(self) -> Self {
+        self.map(f32::abs)
+    }
+
+    /// return the sign of each element of a vector. (see `f32::signum`)
+    #[inline]
+    pub fn signum(self) -> Self {
+        self.map(f32::signum)
+    }
+
+    /// apply the function to each element of a vector.
+    #[inline]
+    pub fn map(self, f: impl Fn(f32) -> f32) -> Self {
+        Self {
+            x: f(self.x),
+            y: f(self.y),
+            z: f(self.z)
+        }
+    }
+
+    /// apply the function to each pair of elements of two vectors.
+    #[inline]
+    pub fn zip_with(self, other: Self, f: impl Fn(f32, f32) -> f32) -> Self {
+        Self {
+            x: f(self.x, other.x),
+            y: f(self.y, other.y),
+            z: f(self.z, other.z)
+        }
+    }
+
+    /// linear interpolation between two vectors. (`self + (other - self) * t`)
+    #[inline]
+    pub fn lerp(self, other: Self, t: f32) -> Self {
+        self.zip_with(other, |a, b| super::lerp(a, b, t))
+    }
+
+    /// linear interpolation between two vectors with `t` clamped into `0.0..=1.0`.
+    #[inline]
+    pub fn lerp_clamped(self, other: Self, t: f32) -> Self {
+        self.lerp(other, t.clamp(0.0, 1.0))
+    }
+
     /// round up the decimal places of the elements of a vector.
     #[inline]
     pub fn ceil(self) -> Self {
@@ -607,3 +737,82 @@ impl fmt::Display for Vec3 {
         write!(f, "({}, {}, {})", self.x, self.y, self.z)
     }
 }
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lerp_hits_midpoint_and_endpoints() {
+        let (a, b) = (Vec3::new_vector(-1.0, 2.0, 0.0), Vec3::new_vector(3.0, 6.0, -8.0));
+        assert_eq!(a.lerp(b, 0.0), a);
+        assert_eq!(a.lerp(b, 0.5), Vec3::new_vector(1.0, 4.0, -4.0));
+        assert_eq!(a.lerp(b, 1.0), b);
+    }
+
+    #[test]
+    fn lerp_of_same_vectors_is_the_vector() {
+        let a = Vec3::new_vector(0.1, -7.5, 1.0e6);
+        assert_eq!(a.lerp(a, 0.3), a);
+        assert_eq!(a.lerp(a, 42.0), a);
+    }
+
+    #[test]
+    fn lerp_clamped_stays_between_endpoints() {
+        let (a, b) = (Vec3::ZERO, Vec3::ONE);
+        assert_eq!(a.lerp_clamped(b, -1.0), a);
+        assert_eq!(a.lerp_clamped(b, 2.0), b);
+    }
+
+    #[test]
+    fn spherical_with_zero_angles_is_the_unit_z_axis() {
+        let v = Vec3::from_spherical(1.0, 0.0, 0.0);
+        assert_eq!(v, Vec3::Z);
+        assert!((v.length() - 1.0).abs() <= f32::EPSILON);
+        // a zero elevation stays on the xz-plane.
+        assert_eq!(Vec3::from_spherical(1.0, 1.2, 0.0).y, 0.0);
+    }
+
+    #[test]
+    fn spherical_round_trips_away_from_the_poles() {
+        for (radius, azimuth, elevation) in [(1.0, 0.5, 0.25), (2.5, -2.0, -1.0), (10.0, 3.0, 1.2)] {
+            let (r, a, e) = Vec3::from_spherical(radius, azimuth, elevation).to_spherical();
+            assert!((r - radius).abs() <= 1e-5 * radius, "{} != {}", r, radius);
+            assert!((a - azimuth).abs() <= 1e-5, "{} != {}", a, azimuth);
+            assert!((e - elevation).abs() <= 1e-5, "{} != {}", e, elevation);
+        }
+    }
+
+
+    #[test]
+    fn reflect_off_axis_planes_negates_the_normal_component() {
+        let v = Vec3::new_vector(1.0, -2.0, 3.0);
+        assert_eq!(v.reflect(Vec3::Y), Vec3::new_vector(1.0, 2.0, 3.0));
+        assert_eq!(v.reflect(Vec3::X), Vec3::new_vector(-1.0, -2.0, 3.0));
+        assert_eq!(v.reflect(-Vec3::Z), Vec3::new_vector(1.0, -2.0, -3.0));
+        // a vector on the plane is unchanged.
+        assert_eq!(Vec3::new_vector(1.0, 0.0, 3.0).reflect(Vec3::Y), Vec3::new_vector(1.0, 0.0, 3.0));
+    }
+
+    #[test]
+    fn refract_without_index_change_goes_straight() {
+        let v = Vec3::new_vector(1.0, -1.0, 0.0).normalize();
+        let refracted = v.refract(Vec3::Y, 1.0).unwrap();
+        assert!((refracted - v).length() <= 1e-6);
+
+        // from glass into air at a grazing angle.
+        assert!(v.refract(Vec3::Y, 1.5).is_none());
+    }
+
+
+    #[test]
+    fn distance_of_a_3_4_5_triangle() {
+        let (a, b) = (Vec3::new_vector(1.0, 2.0, -1.0), Vec3::new_vector(4.0, 6.0, -1.0));
+        assert_eq!(a.distance_squared(&b), 25.0);
+        assert_eq!(a.distance(&b), 5.0);
+        assert_eq!(b.distance(&a), 5.0);
+        assert_eq!(a.distance(&a), 0.0);
+    }
+}
@@ -2,6 +2,7 @@ use std::ops;
 use std::fmt;
 use std::cmp;
 use bytemuck::{Zeroable, Pod};
+use super::madd;
 use super::mat4::Mat4x4;
 
 /// 4-dimensional vector.
@@ -222,6 +223,21 @@ impl Vec4 {
         self.x * rhs.x + self.y * rhs.y + self.z * rhs.z + self.w * rhs.w
     }
 
+    /// sum of all elements of the vector.
+    #[inline]
+    pub fn element_sum(&self) -> f32 {
+        self.x + self.y + self.z + self.w
+    }
+
+    /// average of the given vectors. returns `ZERO` if the slice is empty.
+    #[inline]
+    pub fn centroid(points: &[Self]) -> Self {
+        if points.is_empty() {
+            return Self::ZERO;
+        }
+        points.iter().fold(Self::ZERO, |sum, &point| sum + point) / points.len() as f32
+    }
+
     /// the length of the vector.
     #[inline]
     pub fn length(&self) -> f32 {
@@ -231,7 +247,20 @@ impl Vec4 {
     /// the square of the length of the vector.
     #[inline]
     pub fn length_squared(&self) -> f32 {
-        self.x * self.x + self.y * self.y + self.z * self.z + self.w * self.w
+        madd(self.w, self.w, madd(self.z, self.z, madd(self.y, self.y, self.x * self.x)))
+    }
+
+    /// the distance between two vectors. (`distance_squared().sqrt()`)
+    #[inline]
+    pub fn distance(&self, other: &Self) -> f32 {
+        self.distance_squared(other).sqrt()
+    }
+
+    /// the square of the distance between two vectors.
+    /// prefer this to `distance` when only comparing distances.
+    #[inline]
+    pub fn distance_squared(&self, other: &Self) -> f32 {
+        (*self - *other).length_squared()
     }
 
     /// return normalized vector.
@@ -306,6 +335,59 @@ impl Vec4 {
         }
     }
 
+    /// clamp each element of a vector into the range of the elements of `min` and `max`.
+    #[inline]
+    pub fn clamp(self, min: Self, max: Self) -> Self {
+        debug_assert!(min.x <= max.x && min.y <= max.y && min.z <= max.z && min.w <= max.w, "min must be less than or equal to max.");
+        self.max(min).min(max)
+    }
+
+    /// return the absolute value of each element of a vector.
+    #[inline]
+    pub fn abs(self) -> Self {
+        self.map(f32::abs)
+    }
+
+    /// return the sign of each element of a vector. (see `f32::signum`)
+    #[inline]
+    pub fn signum(self) -> Self {
+        self.map(f32::signum)
+    }
+
+    /// apply the function to each element of a vector.
+    #[inline]
+    pub fn map(self, f: impl Fn(f32) -> f32) -> Self {
+        Self {
+            x: f(self.x),
+            y: f(self.y),
+            z: f(self.z),
+            w: f(self.w)
+        }
+    }
+
+    /// apply the function to each pair of elements of two vectors.
+    #[inline]
+    pub fn zip_with(self, other: Self, f: impl Fn(f32, f32) -> f32) -> Self {
+        Self {
+            x: f(self.x, other.x),
+            y: f(self.y, other.y),
+            z: f(self.z, other.z),
+            w: f(self.w, other.w)
+        }
+    }
+
+    /// linear interpolation between two vectors. (`self + (other - self) * t`)
+    #[inline]
+    pub fn lerp(self, other: Self, t: f32) -> Self {
+        self.zip_with(other, |a, b| super::lerp(a, b, t))
+    }
+
+    /// linear interpolation between two vectors with `t` clamped into `0.0..=1.0`.
+    #[inline]
+    pub fn lerp_clamped(self, other: Self, t: f32) -> Self {
+        self.lerp(other, t.clamp(0.0, 1.0))
+    }
+
     /// round up the decimal places of the elements of a vector.
     #[inline]
     pub fn ceil(self) -> Self {
@@ -622,3 +704,32 @@ impl fmt::Display for Vec4 {
         write!(f, "({}, {}, {}, {})", self.x, self.y, self.z, self.w)
     }
 }
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lerp_hits_midpoint_and_endpoints() {
+        let (a, b) = (Vec4::new_vector(-1.0, 2.0, 0.0, 1.0), Vec4::new_vector(3.0, 6.0, -8.0, 1.0));
+        assert_eq!(a.lerp(b, 0.0), a);
+        assert_eq!(a.lerp(b, 0.5), Vec4::new_vector(1.0, 4.0, -4.0, 1.0));
+        assert_eq!(a.lerp(b, 1.0), b);
+    }
+
+    #[test]
+    fn lerp_of_same_vectors_is_the_vector() {
+        let a = Vec4::new_vector(0.1, -7.5, 1.0e6, 0.0);
+        assert_eq!(a.lerp(a, 0.3), a);
+        assert_eq!(a.lerp(a, 42.0), a);
+    }
+
+    #[test]
+    fn lerp_clamped_stays_between_endpoints() {
+        let (a, b) = (Vec4::ZERO, Vec4::ONE);
+        assert_eq!(a.lerp_clamped(b, -1.0), a);
+        assert_eq!(a.lerp_clamped(b, 2.0), b);
+    }
+}
@@ -0,0 +1,178 @@
+use std::sync::Arc;
+
+use vulkano::buffer::{Buffer, BufferCreateInfo, BufferUsage, Subbuffer};
+use vulkano::command_buffer::{AutoCommandBufferBuilder, BufferImageCopy, CopyImageToBufferInfo};
+use vulkano::command_buffer::allocator::CommandBufferAllocator;
+use vulkano::format::Format;
+use vulkano::image::{ImageAccess, SwapchainImage};
+use vulkano::memory::allocator::{AllocationCreateInfo, MemoryUsage};
+
+use super::context::RenderContext;
+use crate::{err, error::RuntimeError};
+
+
+
+/// A copy of a swapchain image in host memory.
+/// The copy is recorded after the frame is rendered and before the image is presented.
+#[derive(Debug)]
+pub struct FrameCapture {
+    buffer: Subbuffer<[u8]>,
+    width: u32,
+    height: u32,
+    row_pitch: u32,
+    bgra: bool,
+}
+
+impl FrameCapture {
+    /// Create a readback buffer and record a copy of the swapchain image into it.
+    /// 
+    /// # Runtime Errors
+    /// - Returns a runtime error message if the image format is not an 8-bit RGBA or BGRA format.
+    /// - Returns a runtime error message if readback buffer creation fails.
+    /// - Returns a runtime error message if copy command recording fails.
+    /// 
+    pub fn record<L, A: CommandBufferAllocator>(
+        image: Arc<SwapchainImage>,
+        render_ctx: &Arc<RenderContext>,
+        command_buffer_builder: &mut AutoCommandBufferBuilder<L, A>
+    ) -> Result<Self, RuntimeError> {
+        let bgra = is_bgra_capture_format(image.format())?;
+
+        // the buffer rows are padded to the alignment preferred by the device.
+        let [width, height, _] = image.dimensions().width_height_depth();
+        let alignment = render_ctx.ref_device().physical_device().properties().optimal_buffer_copy_row_pitch_alignment.as_devicesize();
+        let row_pitch = get_capture_row_pitch(width, alignment);
+
+        let buffer = Buffer::new_slice::<u8>(
+            render_ctx.ref_memory_allocator(),
+            BufferCreateInfo {
+                usage: BufferUsage::TRANSFER_DST,
+                ..Default::default()
+            },
+            AllocationCreateInfo {
+                usage: MemoryUsage::Download,
+                ..Default::default()
+            },
+            row_pitch as u64 * height as u64
+        ).map_err(|e| err!("Readback buffer creation failed: {}", e.to_string()))?;
+
+        command_buffer_builder
+            .copy_image_to_buffer(CopyImageToBufferInfo {
+                regions: [BufferImageCopy {
+                    buffer_row_length: row_pitch / 4,
+                    buffer_image_height: height,
+                    image_subresource: image.subresource_layers(),
+                    image_extent: [width, height, 1],
+                    ..Default::default()
+                }].into(),
+                ..CopyImageToBufferInfo::image_buffer(image, buffer.clone())
+            })
+            .map_err(|e| err!("Frame capture copy failed: {}", e.to_string()))?;
+
+        Ok(Self { buffer, width, height, row_pitch, bgra })
+    }
+
+
+    /// Read the width, height and tightly-packed RGBA8 pixels of the captured image.
+    /// The copy command must have finished executing.
+    /// 
+    /// # Runtime Errors
+    /// - Returns a runtime error message if the readback buffer cannot be read.
+    /// 
+    pub fn read(&self) -> Result<(u32, u32, Vec<u8>), RuntimeError> {
+        let data = self.buffer.read()
+            .map_err(|e| err!("Readback buffer read failed: {}", e.to_string()))?;
+        Ok((self.width, self.height, pack_rgba8(&data, self.width, self.height, self.row_pitch, self.bgra)))
+    }
+}
+
+
+/// Returns `true` if the red and blue channels of the format must be swapped to get RGBA8.
+/// 
+/// # Runtime Errors
+/// - Returns a runtime error message if the format is not an 8-bit RGBA or BGRA format.
+/// 
+#[inline]
+pub fn is_bgra_capture_format(format: Format) -> Result<bool, RuntimeError> {
+    match format {
+        Format::B8G8R8A8_SRGB | Format::B8G8R8A8_UNORM => Ok(true),
+        Format::R8G8B8A8_SRGB | Format::R8G8B8A8_UNORM => Ok(false),
+        format => Err(err!("Unsupported capture format: {:?}", format)),
+    }
+}
+
+
+/// Get the row pitch of a captured RGBA8 image in bytes.
+/// The row pitch is the tightly-packed row size rounded up to the copy row pitch alignment reported by the device.
+#[inline]
+pub fn get_capture_row_pitch(width: u32, alignment: u64) -> u32 {
+    // the alignment is a power of two, and a row must hold whole texels.
+    let alignment = alignment.max(1).next_power_of_two().max(4) as u32;
+    (width * 4 + alignment - 1) / alignment * alignment
+}
+
+
+/// Remove the row padding of a captured image and convert it to tightly-packed RGBA8.
+/// If `bgra` is `true`, the red and blue channels of each texel are swapped.
+/// 
+/// # Panics
+/// - Stop program execution if the data is smaller than `row_pitch * height` bytes.
+/// 
+pub fn pack_rgba8(data: &[u8], width: u32, height: u32, row_pitch: u32, bgra: bool) -> Vec<u8> {
+    let row_size = width as usize * 4;
+    let row_pitch = row_pitch as usize;
+    assert!(data.len() >= row_pitch * height as usize, "The captured data is smaller than the image.");
+
+    let mut pixels = Vec::with_capacity(row_size * height as usize);
+    for row in data.chunks(row_pitch).take(height as usize) {
+        pixels.extend_from_slice(&row[..row_size]);
+    }
+
+    if bgra {
+        for texel in pixels.chunks_exact_mut(4) {
+            texel.swap(0, 2);
+        }
+    }
+    pixels
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cleared_frame_is_clear_color() {
+        // a cleared 5x3 BGRA frame read back with rows padded to 32 bytes.
+        const CLEAR_COLOR: [u8; 4] = [32, 64, 128, 255];
+        let (width, height) = (5, 3);
+        let row_pitch = get_capture_row_pitch(width, 32);
+        assert_eq!(row_pitch, 32);
+
+        let mut data = vec![0xCD; (row_pitch * height) as usize];
+        for row in data.chunks_mut(row_pitch as usize) {
+            for texel in row[..(width * 4) as usize].chunks_exact_mut(4) {
+                texel.copy_from_slice(&[CLEAR_COLOR[2], CLEAR_COLOR[1], CLEAR_COLOR[0], CLEAR_COLOR[3]]);
+            }
+        }
+
+        let pixels = pack_rgba8(&data, width, height, row_pitch, is_bgra_capture_format(Format::B8G8R8A8_SRGB).unwrap());
+        assert_eq!(pixels.len(), (width * height * 4) as usize);
+        assert!(pixels.chunks_exact(4).all(|texel| texel == CLEAR_COLOR));
+    }
+
+    #[test]
+    fn row_pitch_is_aligned() {
+        assert_eq!(get_capture_row_pitch(5, 1), 20);
+        assert_eq!(get_capture_row_pitch(5, 64), 64);
+        assert_eq!(get_capture_row_pitch(16, 64), 64);
+        assert_eq!(get_capture_row_pitch(17, 64), 128);
+    }
+
+    #[test]
+    fn rejects_non_rgba8_formats() {
+        assert!(!is_bgra_capture_format(Format::R8G8B8A8_UNORM).unwrap());
+        assert!(is_bgra_capture_format(Format::R16G16B16A16_SFLOAT).is_err());
+    }
+}
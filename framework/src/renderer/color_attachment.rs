@@ -0,0 +1,169 @@
+use std::sync::Arc;
+
+use vulkano::format::Format;
+use vulkano::image::{AttachmentImage, ImageUsage, SampleCount, ImageViewType, ImageSubresourceRange, ImageAspects};
+use vulkano::image::view::{ImageView, ImageViewCreateInfo};
+use vulkano::memory::allocator::MemoryAllocator;
+use vulkano::sampler::ComponentMapping;
+
+use super::context::RenderContext;
+use crate::{err, error::RuntimeError};
+
+
+
+/// An additional color attachment rendered alongside the swapchain image.
+/// (e.g. a data buffer for outlines or bloom)
+#[derive(Debug)]
+pub struct RenderColorAttachment {
+    format: Format,
+    samples: SampleCount,
+    image: Arc<AttachmentImage>,
+    view: Arc<ImageView<AttachmentImage>>,
+    render_ctx: Arc<RenderContext>,
+}
+
+
+impl RenderColorAttachment {
+    /// Create a new `RenderColorAttachment`
+    /// 
+    /// # Runtime Errors
+    /// - Returns a runtime error message if the format cannot be used as a color attachment.
+    /// - Returns a runtime error message if color attachment image creation fails.
+    /// - Returns a runtime error message if color attachment image view creation fails.
+    /// 
+    #[inline]
+    pub fn new(
+        width: u32,
+        height: u32,
+        format: Format,
+        render_ctx: Arc<RenderContext>
+    ) -> Result<Self, RuntimeError> {
+        Self::with_samples(width, height, format, SampleCount::Sample1, render_ctx)
+    }
+
+
+    /// Create a new `RenderColorAttachment` with the given sample count.
+    /// A multisampled attachment is only rendered to and then resolved,
+    /// so it is created as a transient attachment that cannot be sampled.
+    /// 
+    /// # Runtime Errors
+    /// - Returns a runtime error message if the format cannot be used as a color attachment.
+    /// - Returns a runtime error message if color attachment image creation fails.
+    /// - Returns a runtime error message if color attachment image view creation fails.
+    /// 
+    pub fn with_samples(
+        width: u32,
+        height: u32,
+        format: Format,
+        samples: SampleCount,
+        render_ctx: Arc<RenderContext>
+    ) -> Result<Self, RuntimeError> {
+        if !render_ctx.supports_color_attachment(format) {
+            return Err(err!("Unsupported color attachment format: {:?}", format));
+        }
+
+        let (image, view) = create_color_attachment(
+            width,
+            height,
+            format,
+            samples,
+            render_ctx.ref_memory_allocator()
+        )?;
+
+        Ok(Self { format, samples, image, view, render_ctx })
+    }
+
+
+    /// Create a new color attachment based on the existing color attachment.
+    /// 
+    /// # Runtime Errors
+    /// - Returns a runtime error message if color attachment image creation fails.
+    /// - Returns a runtime error message if color attachment image view creation fails.
+    /// 
+    pub fn recreate(&mut self, width: u32, height: u32) -> Result<(), RuntimeError> {
+        let (image, view) = create_color_attachment(
+            width,
+            height,
+            self.format,
+            self.samples,
+            self.render_ctx.ref_memory_allocator()
+        )?;
+
+        self.image = image;
+        self.view = view;
+
+        Ok(())
+    }
+
+
+    /// Get the color attachment format. (reference)
+    #[inline]
+    pub fn ref_format(&self) -> &Format {
+        &self.format
+    }
+
+
+    #[inline]
+    pub fn get_samples(&self) -> SampleCount {
+        self.samples
+    }
+
+
+    /// Get the color attachment image. (reference)
+    #[inline]
+    pub fn ref_image(&self) -> &Arc<AttachmentImage> {
+        &self.image
+    }
+
+
+    /// Get the color attachment image view. (reference)
+    #[inline]
+    pub fn ref_image_view(&self) -> &Arc<ImageView<AttachmentImage>> {
+        &self.view
+    }
+}
+
+
+/// Create a color attachment image and view.
+/// 
+/// # Runtime Errors
+/// - Returns a runtime error message if color attachment image creation fails.
+/// - Returns a runtime error message if color attachment image view creation fails.
+/// 
+#[inline]
+fn create_color_attachment(
+    width: u32,
+    height: u32,
+    format: Format,
+    samples: SampleCount,
+    allocator: &impl MemoryAllocator
+) -> Result<(Arc<AttachmentImage>, Arc<ImageView<AttachmentImage>>), RuntimeError> {
+    let usage = match samples {
+        SampleCount::Sample1 => ImageUsage::COLOR_ATTACHMENT | ImageUsage::SAMPLED | ImageUsage::INPUT_ATTACHMENT,
+        _ => ImageUsage::COLOR_ATTACHMENT | ImageUsage::TRANSIENT_ATTACHMENT,
+    };
+    let image = AttachmentImage::multisampled_with_usage(
+        allocator,
+        [width, height],
+        samples,
+        format,
+        usage
+    ).map_err(|e| err!("Failed to create color attachment image: {}", e.to_string()))?;
+
+    let view = ImageView::new(
+        image.clone(),
+        ImageViewCreateInfo {
+            view_type: ImageViewType::Dim2d,
+            format: Some(format),
+            component_mapping: ComponentMapping::identity(),
+            subresource_range: ImageSubresourceRange {
+                aspects: ImageAspects::COLOR,
+                mip_levels: (0..1),
+                array_layers: (0..1)
+            },
+            ..Default::default()
+        }
+    ).map_err(|e| err!("Failed to create color attachment image view: {}", e.to_string()))?;
+
+    Ok((image, view))
+}
@@ -2,17 +2,19 @@ use std::sync::Arc;
 
 use vulkano::VulkanLibrary;
 use vulkano::command_buffer::allocator::{StandardCommandBufferAllocator, StandardCommandBufferAllocatorCreateInfo};
-use vulkano::format::{Format, FormatProperties};
-use vulkano::memory::MemoryProperties;
+use vulkano::format::{Format, FormatFeatures, FormatProperties, NumericType};
+use vulkano::image::{ImageAspects, ImageTiling, SampleCount};
+use vulkano::memory::{MemoryProperties, MemoryPropertyFlags};
 use vulkano::memory::allocator::StandardMemoryAllocator;
 use vulkano::descriptor_set::allocator::StandardDescriptorSetAllocator;
 use vulkano::device::physical::PhysicalDeviceType;
 use vulkano::instance::{Instance, InstanceExtensions, InstanceCreateInfo};
 use vulkano::device::{Device, Queue, Features, DeviceExtensions, QueueFlags, DeviceCreateInfo, QueueCreateInfo};
+use vulkano::render_pass::ResolveModes;
 use vulkano::swapchain::{Surface, SurfaceInfo, SurfaceCapabilities, PresentMode, ColorSpace};
 
 use crate::renderer::platform::*;
-use crate::{err, error::RuntimeError};
+use crate::{err, error::{ErrorKind, RuntimeError}};
 
 
 
@@ -21,26 +23,50 @@ pub struct RenderContext {
     device: Arc<Device>,
     surface: Arc<Surface>,
     instance: Arc<Instance>,
-    integrated_queue: Arc<Queue>, // <Graphics | Present | Compute>
+    integrated_queue: Arc<Queue>, // <Graphics | Present | (Compute)>
     memory_allocator: StandardMemoryAllocator,
     descriptor_allocator: StandardDescriptorSetAllocator
 }
 
 impl RenderContext {
-    /// Create a new `RenderContext`.
+    /// Create a new `RenderContext` with the default device features.
     /// 
     /// # Runtime Errors
     /// - Returns a runtime error message if the Vulkan library fails to load.
     /// - Returns a runtime error message if Vulkan instance creation fails.
     /// - Returns a runtime error message if no suitable device is found.
+    /// - Returns a runtime error message if the device does not support the required features.
     /// - Returns a runtime error message if logical device creation fails.
     /// 
+    #[inline]
     pub fn new(handle: &AppHandle) -> Result<Arc<Self>, RuntimeError> {
+        Self::with_features(handle, &get_device_features(), &get_optional_device_features())
+    }
+
+
+    /// Create a new `RenderContext` with the given device features.
+    /// The required features must be supported by the device,
+    /// and the optional features are enabled only if the device supports them.
+    /// 
+    /// # Runtime Errors
+    /// - Returns a runtime error message if the Vulkan library fails to load.
+    /// - Returns a runtime error message if Vulkan instance creation fails.
+    /// - Returns a runtime error message if no suitable device is found.
+    /// - Returns a runtime error message if the device does not support the required features.
+    /// - Returns a runtime error message if logical device creation fails.
+    /// 
+    pub fn with_features(
+        handle: &AppHandle,
+        required_features: &Features,
+        optional_features: &Features,
+    ) -> Result<Arc<Self>, RuntimeError> {
         let instance = create_vulkan_instance()?;
         let surface = create_vulkan_surface(handle, &instance)?;
         let (device, integrated_queue) = create_vulkan_device_and_integrated_queue(
             &instance, 
-            &surface
+            &surface,
+            required_features,
+            optional_features
         )?;
 
         let memory_allocator = StandardMemoryAllocator::new_default(device.clone());
@@ -86,6 +112,33 @@ impl RenderContext {
     }
 
 
+    /// Returns `true` if the device has a lazily-allocated memory type.
+    /// Tile-based GPUs can keep transient attachments in tile memory with it.
+    #[inline]
+    pub fn supports_lazily_allocated_memory(&self) -> bool {
+        self.ref_device_memory_properties()
+            .memory_types
+            .iter()
+            .any(|memory_type| memory_type.property_flags.intersects(MemoryPropertyFlags::LAZILY_ALLOCATED))
+    }
+
+
+    /// Get the sample count the framebuffers can use for the requested sample count.
+    /// Returns the largest count supported by both the color and depth-stencil attachments that does not exceed the request.
+    #[inline]
+    pub fn get_supported_sample_count(&self, requested: SampleCount) -> SampleCount {
+        let properties = self.device.physical_device().properties();
+        let supported = properties.framebuffer_color_sample_counts
+            .intersection(properties.framebuffer_depth_sample_counts)
+            .intersection(properties.framebuffer_stencil_sample_counts);
+
+        [SampleCount::Sample64, SampleCount::Sample32, SampleCount::Sample16, SampleCount::Sample8, SampleCount::Sample4, SampleCount::Sample2]
+            .into_iter()
+            .find(|count| *count as u32 <= requested as u32 && supported.contains_enum(*count))
+            .unwrap_or(SampleCount::Sample1)
+    }
+
+
     /// Get the format properties of the device.
     /// 
     /// # Runtime Errors
@@ -100,6 +153,77 @@ impl RenderContext {
     }
 
 
+    /// Returns `true` if the format supports all of the given features with optimal tiling.
+    /// Returns `false` if getting format properties fails.
+    #[inline]
+    pub fn supports_optimal_tiling_features(&self, format: Format, features: FormatFeatures) -> bool {
+        self.get_format_properties(format)
+            .map(|properties| format_supports(&properties, ImageTiling::Optimal, features))
+            .unwrap_or(false)
+    }
+
+
+    /// Returns `true` if the format supports all of the given features with linear tiling.
+    /// Returns `false` if getting format properties fails.
+    #[inline]
+    pub fn supports_linear_tiling_features(&self, format: Format, features: FormatFeatures) -> bool {
+        self.get_format_properties(format)
+            .map(|properties| format_supports(&properties, ImageTiling::Linear, features))
+            .unwrap_or(false)
+    }
+
+
+    /// Returns `true` if the format can be used as a sampled image.
+    #[inline]
+    pub fn supports_sampled(&self, format: Format) -> bool {
+        self.supports_optimal_tiling_features(format, FormatFeatures::SAMPLED_IMAGE)
+    }
+
+
+    /// Returns `true` if the format can be used as a color attachment.
+    #[inline]
+    pub fn supports_color_attachment(&self, format: Format) -> bool {
+        self.supports_optimal_tiling_features(format, FormatFeatures::COLOR_ATTACHMENT)
+    }
+
+
+    /// Returns `true` if the format can be used as the source of a blit command.
+    #[inline]
+    pub fn supports_blit_src(&self, format: Format) -> bool {
+        self.supports_optimal_tiling_features(format, FormatFeatures::BLIT_SRC)
+    }
+
+
+    /// Returns `true` if the format can be sampled with a linear filter.
+    #[inline]
+    pub fn supports_linear_filter(&self, format: Format) -> bool {
+        self.supports_optimal_tiling_features(format, FormatFeatures::SAMPLED_IMAGE_FILTER_LINEAR)
+    }
+
+
+    /// Get the multisample resolve modes supported for attachments of the format.
+    /// - Color attachments with an integer format support only `SampleZero`.
+    /// - Other color attachments support only `Average`.
+    /// - Depth-stencil attachments support the modes reported by the device.
+    #[inline]
+    pub fn get_supported_resolve_modes(&self, format: Format) -> ResolveModes {
+        let aspects = format.aspects();
+        if aspects.intersects(ImageAspects::DEPTH | ImageAspects::STENCIL) {
+            let properties = self.device.physical_device().properties();
+            let mut modes = ResolveModes::SAMPLE_ZERO | ResolveModes::AVERAGE | ResolveModes::MIN | ResolveModes::MAX;
+            if aspects.intersects(ImageAspects::DEPTH) {
+                modes = modes.intersection(properties.supported_depth_resolve_modes.unwrap_or(ResolveModes::SAMPLE_ZERO));
+            }
+            if aspects.intersects(ImageAspects::STENCIL) {
+                modes = modes.intersection(properties.supported_stencil_resolve_modes.unwrap_or(ResolveModes::SAMPLE_ZERO));
+            }
+            return modes;
+        }
+
+        get_color_resolve_modes(format)
+    }
+
+
     /// Get the vulkan surface. (reference)
     #[inline]
     pub fn ref_surface(&self) -> &Arc<Surface> {
@@ -147,12 +271,74 @@ impl RenderContext {
 
 
     /// Get the vulkan queue. (Graphics, Present and Compute are integrated)
+    /// 
+    /// ### Note
+    /// - Compute is only integrated if the device has such a queue family. See `supports_compute`.
+    /// 
     #[inline]
     pub fn ref_integrated_queue(&self) -> &Arc<Queue> {
         &self.integrated_queue
     }
 
 
+    /// Returns `true` if the device can present only the updated regions of the swapchain image.
+    #[inline]
+    pub fn supports_incremental_present(&self) -> bool {
+        self.device.enabled_extensions().khr_incremental_present
+    }
+
+
+    /// Returns `true` if the device enables the `fill_mode_non_solid` feature required by the line and point polygon modes.
+    #[inline]
+    pub fn supports_non_solid_fill_mode(&self) -> bool {
+        self.device.enabled_features().fill_mode_non_solid
+    }
+
+
+    /// Returns `true` if the device enables the descriptor indexing features used by bindless texture arrays.
+    #[inline]
+    pub fn supports_bindless(&self) -> bool {
+        features_support_bindless(self.device.enabled_features())
+    }
+
+
+    /// Get the maximum number of textures in a bindless texture array.
+    #[inline]
+    pub fn get_max_bindless_textures(&self) -> u32 {
+        let properties = self.device.physical_device().properties();
+        properties.max_per_stage_descriptor_sampled_images
+            .min(properties.max_per_stage_descriptor_samplers)
+            .min(properties.max_descriptor_set_sampled_images)
+    }
+
+
+    /// Returns `true` if the integrated queue supports compute operations.
+    #[inline]
+    pub fn supports_compute(&self) -> bool {
+        queue_flags_support_compute(
+            self.device.physical_device()
+                .queue_family_properties()[self.get_queue_fmaily_index() as usize]
+                .queue_flags
+        )
+    }
+
+
+    /// Check that compute operations can be submitted to the integrated queue.
+    /// Compute-dependent features should call this before creating their pipelines.
+    /// 
+    /// # Runtime Errors
+    /// - Returns an `ErrorKind::Unsupported` error if the integrated queue does not support compute operations.
+    /// 
+    #[inline]
+    pub fn require_compute(&self) -> Result<(), RuntimeError> {
+        check_compute_support(
+            self.device.physical_device()
+                .queue_family_properties()[self.get_queue_fmaily_index() as usize]
+                .queue_flags
+        )
+    }
+
+
     /// Get the queue family index of the queue.
     #[inline]
     pub fn get_queue_fmaily_index(&self) -> u32 {
@@ -213,6 +399,7 @@ fn get_instance_extensions() -> InstanceExtensions {
         ext_metal_surface: true,
         khr_get_physical_device_properties2: true,
         khr_get_surface_capabilities2: true,
+        ext_swapchain_colorspace: true,
         ..Default::default()
     }
 }
@@ -260,7 +447,24 @@ fn get_device_extensions() -> DeviceExtensions {
 }
 
 
-/// Get the enabled device features.
+/// Get the optional device extension.
+/// These extensions are enabled only if the device supports them.
+/// 
+/// Note: Modify this function to change which optional device extension you want to use...
+/// 
+#[inline]
+fn get_optional_device_extensions() -> DeviceExtensions {
+    DeviceExtensions {
+        khr_incremental_present: true,
+        khr_multiview: true,
+        khr_maintenance3: true,
+        ext_descriptor_indexing: true,
+        ..Default::default()
+    }
+}
+
+
+/// Get the required device features.
 /// If the device does not support features, it will not create the device.
 /// 
 /// Note: Modify this function to change which device feature you want to use...
@@ -273,55 +477,157 @@ fn get_device_features() -> Features {
 }
 
 
+/// Get the optional device features.
+/// These features are enabled only if the device supports them.
+/// 
+/// Note: Modify this function to change which optional device feature you want to use...
+/// 
+#[inline]
+fn get_optional_device_features() -> Features {
+    Features {
+        fill_mode_non_solid: true,
+        sampler_anisotropy: true,
+        sample_rate_shading: true,
+        depth_clamp: true,
+        multiview: true,
+        runtime_descriptor_array: true,
+        descriptor_binding_variable_descriptor_count: true,
+        shader_sampled_image_array_non_uniform_indexing: true,
+        ..Default::default()
+    }
+}
+
+
+/// Select the device features to enable.
+/// All of the required features and the optional features supported by the device are enabled.
+/// 
+/// # Runtime Errors
+/// - Returns a runtime error message if the device does not support the required features.
+/// 
+#[inline]
+fn select_device_features(
+    supported_features: &Features,
+    required_features: &Features,
+    optional_features: &Features,
+) -> Result<Features, RuntimeError> {
+    if !supported_features.contains(required_features) {
+        return Err(err!(
+            "Required device features not supported: {:?}", 
+            required_features.difference(supported_features)
+        ));
+    }
+
+    Ok(required_features.union(&supported_features.intersection(optional_features)))
+}
+
+
+/// Returns `true` if the format properties include all of the given features with the tiling.
+#[inline]
+pub(super) fn format_supports(properties: &FormatProperties, tiling: ImageTiling, features: FormatFeatures) -> bool {
+    match tiling {
+        ImageTiling::Optimal => properties.optimal_tiling_features.contains(features),
+        ImageTiling::Linear => properties.linear_tiling_features.contains(features),
+        _ => false,
+    }
+}
+
+
+/// Returns `true` if the features include the descriptor indexing features used by bindless texture arrays.
+#[inline]
+fn features_support_bindless(features: &Features) -> bool {
+    features.runtime_descriptor_array
+        && features.descriptor_binding_variable_descriptor_count
+        && features.shader_sampled_image_array_non_uniform_indexing
+}
+
+
+/// Returns `true` if the queue flags include compute operations.
+#[inline]
+fn queue_flags_support_compute(queue_flags: QueueFlags) -> bool {
+    queue_flags.intersects(QueueFlags::COMPUTE)
+}
+
+
+/// Check that the queue flags include compute operations.
+/// 
+/// # Runtime Errors
+/// - Returns an `ErrorKind::Unsupported` error if the queue flags do not include compute operations.
+/// 
+#[inline]
+fn check_compute_support(queue_flags: QueueFlags) -> Result<(), RuntimeError> {
+    match queue_flags_support_compute(queue_flags) {
+        true => Ok(()),
+        false => Err(err!("Unsupported: the integrated queue does not support compute operations.").with_kind(ErrorKind::Unsupported)),
+    }
+}
+
+
 /// Create a Vulkan logical device and integrated queue.
+/// A queue family that also supports compute operations is preferred,
+/// and a device that supports the required features is preferred.
 /// 
 /// # Runtime Errors
 /// - Returns a runtime error message if no suitable device is found.
+/// - Returns a runtime error message if the device does not support the required features.
 /// - Returns a runtime error message if logical device creation fails.
 /// 
 #[inline]
 fn create_vulkan_device_and_integrated_queue(
-    instance: &Arc<Instance>, surface: &Arc<Surface>,
+    instance: &Arc<Instance>, 
+    surface: &Arc<Surface>,
+    required_features: &Features,
+    optional_features: &Features,
 ) -> Result<(Arc<Device>, Arc<Queue>), RuntimeError> {
     // get the enabled device extensions.
     let enabled_extensions = get_device_extensions();
 
-    // get the enabled device features.
-    let enabled_features = get_device_features();
-
     // get the suitable physical device and queue family index.
     let (physical_device, queue_family_index) = match instance
         .enumerate_physical_devices()
         .map_err(|e| err!("Physical device query failed: {}", e.to_string()))?
         .filter(|physical_device| {
             physical_device.supported_extensions().contains(&enabled_extensions)
-            && physical_device.supported_features().contains(&enabled_features)
         })
         .filter_map(|physical_device| {
             physical_device.queue_family_properties()
                 .iter()
                 .enumerate()
-                .position(|(idx, properties)| {
+                .filter(|(idx, properties)| {
                     properties.queue_flags.intersects(QueueFlags::GRAPHICS)
-                    && physical_device.surface_support(idx as u32, surface).unwrap_or(false)
+                    && physical_device.surface_support(*idx as u32, surface).unwrap_or(false)
                 })
+                .min_by_key(|(_, properties)| !queue_flags_support_compute(properties.queue_flags))
+                .map(|(idx, _)| idx)
                 .map(|idx| (physical_device, idx as u32))
         })
         .min_by_key(|(physical_device, _)| {
-            match physical_device.properties().device_type {
+            let device_type = match physical_device.properties().device_type {
                 PhysicalDeviceType::DiscreteGpu => 0,
                 PhysicalDeviceType::IntegratedGpu => 1,
                 PhysicalDeviceType::VirtualGpu => 2,
                 PhysicalDeviceType::Cpu => 3,
                 PhysicalDeviceType::Other => 4,
                 _ => 5,
-            }
+            };
+            (!physical_device.supported_features().contains(required_features), device_type)
         })
     {
         Some(it) => it,
         None => return Err(err!("No suitable physical device found."))
     };
 
+    // enable the optional device extensions supported by the device.
+    let enabled_extensions = enabled_extensions.union(
+        &physical_device.supported_extensions().intersection(&get_optional_device_extensions())
+    );
+
+    // enable the required device features and the optional device features supported by the device.
+    let enabled_features = select_device_features(
+        physical_device.supported_features(), 
+        required_features, 
+        optional_features
+    )?;
+
     // create Vulkan logical device and queues.
     let (device, mut queues) = Device::new(
         physical_device, 
@@ -338,3 +644,95 @@ fn create_vulkan_device_and_integrated_queue(
 
     Ok((device, queues.next().unwrap()))
 }
+
+
+/// Get the multisample resolve modes of a render pass for color attachments of the format.
+/// The resolve mode of a color attachment is fixed by its format.
+/// - Color attachments with an integer format are resolved with `SampleZero`.
+/// - Other color attachments are resolved with `Average`.
+#[inline]
+pub fn get_color_resolve_modes(format: Format) -> ResolveModes {
+    match format.type_color() {
+        Some(NumericType::SINT) | Some(NumericType::UINT) => ResolveModes::SAMPLE_ZERO,
+        _ => ResolveModes::AVERAGE,
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn queue_family_without_compute_is_unsupported() {
+        let graphics_only = QueueFlags::GRAPHICS | QueueFlags::TRANSFER;
+        assert!(!queue_flags_support_compute(graphics_only));
+
+        let error = check_compute_support(graphics_only).unwrap_err();
+        assert!(error.is_unsupported(), "{:?}", error);
+        assert_eq!(error.kind(), ErrorKind::Unsupported);
+    }
+
+    #[test]
+    fn queue_family_with_compute_is_supported() {
+        let integrated = QueueFlags::GRAPHICS | QueueFlags::COMPUTE | QueueFlags::TRANSFER;
+        assert!(queue_flags_support_compute(integrated));
+        assert!(check_compute_support(integrated).is_ok());
+    }
+
+    #[test]
+    fn common_color_format_supports_sampling_and_color_attachment() {
+        // the properties reported for `R8G8B8A8_UNORM` by a typical device.
+        let properties = FormatProperties {
+            optimal_tiling_features: FormatFeatures::SAMPLED_IMAGE
+                | FormatFeatures::SAMPLED_IMAGE_FILTER_LINEAR
+                | FormatFeatures::COLOR_ATTACHMENT
+                | FormatFeatures::COLOR_ATTACHMENT_BLEND
+                | FormatFeatures::BLIT_SRC
+                | FormatFeatures::BLIT_DST,
+            linear_tiling_features: FormatFeatures::TRANSFER_SRC | FormatFeatures::TRANSFER_DST,
+            ..Default::default()
+        };
+
+        assert!(format_supports(&properties, ImageTiling::Optimal, FormatFeatures::SAMPLED_IMAGE));
+        assert!(format_supports(&properties, ImageTiling::Optimal, FormatFeatures::COLOR_ATTACHMENT));
+        assert!(format_supports(&properties, ImageTiling::Optimal, FormatFeatures::SAMPLED_IMAGE | FormatFeatures::COLOR_ATTACHMENT));
+        assert!(!format_supports(&properties, ImageTiling::Linear, FormatFeatures::SAMPLED_IMAGE));
+        assert!(!format_supports(&properties, ImageTiling::Optimal, FormatFeatures::STORAGE_IMAGE));
+    }
+
+
+    #[test]
+    fn missing_optional_feature_is_dropped() {
+        // a device without wireframe rendering.
+        let supported = Features { sampler_anisotropy: true, depth_clamp: true, ..Features::empty() };
+        let required = Features { depth_clamp: true, ..Features::empty() };
+        let optional = Features { fill_mode_non_solid: true, sampler_anisotropy: true, ..Features::empty() };
+
+        let enabled = select_device_features(&supported, &required, &optional).unwrap();
+        assert_eq!(enabled, Features { sampler_anisotropy: true, depth_clamp: true, ..Features::empty() });
+    }
+
+    #[test]
+    fn missing_required_feature_is_an_error() {
+        let supported = Features { sampler_anisotropy: true, ..Features::empty() };
+        let required = Features { fill_mode_non_solid: true, ..Features::empty() };
+
+        let msg = select_device_features(&supported, &required, &Features::empty()).unwrap_err();
+        assert!(msg.what().contains("fillModeNonSolid"), "{}", msg.what());
+    }
+
+
+    #[test]
+    fn descriptor_indexing_features_support_bindless() {
+        let features = Features {
+            runtime_descriptor_array: true,
+            descriptor_binding_variable_descriptor_count: true,
+            shader_sampled_image_array_non_uniform_indexing: true,
+            ..Features::empty()
+        };
+        assert!(features_support_bindless(&features));
+        assert!(!features_support_bindless(&Features { runtime_descriptor_array: false, ..features }));
+    }
+}
@@ -0,0 +1,98 @@
+/// The default number of frames the screen size must stay the same before the swapchain is recreated.
+pub const DEFAULT_RESIZE_DEBOUNCE_FRAMES: u32 = 3;
+
+
+
+/// Defers a resize until the requested size has stayed the same for a number of frames.
+/// This avoids recreating the swapchain every frame during a continuous resize. (e.g. a rotation animation)
+#[derive(Debug, Clone, Copy)]
+pub struct ResizeDebounce {
+    num_frames: u32,
+    pending: Option<((u32, u32), u32)>,
+}
+
+impl ResizeDebounce {
+    /// Create a new `ResizeDebounce`. `0` frames applies a resize on the next frame.
+    #[inline]
+    pub fn new(num_frames: u32) -> Self {
+        Self { num_frames, pending: None }
+    }
+
+    /// Request a resize. The frame count restarts, so only the last of rapid requests is applied.
+    #[inline]
+    pub fn request(&mut self, size: (u32, u32)) {
+        self.pending = Some((size, 0));
+    }
+
+    /// Advance by a frame.
+    /// Returns the requested size once it has stayed the same for the debounce window.
+    #[inline]
+    pub fn advance(&mut self) -> Option<(u32, u32)> {
+        let (size, stable_frames) = self.pending.as_mut()?;
+        if *stable_frames < self.num_frames {
+            *stable_frames += 1;
+            return None;
+        }
+
+        let size = *size;
+        self.pending = None;
+        Some(size)
+    }
+
+    /// Returns `true` if a requested resize has not been applied yet.
+    #[inline]
+    pub fn is_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    #[inline]
+    pub fn set_num_frames(&mut self, num_frames: u32) {
+        self.num_frames = num_frames;
+    }
+
+    #[inline]
+    pub fn get_num_frames(&self) -> u32 {
+        self.num_frames
+    }
+}
+
+impl Default for ResizeDebounce {
+    #[inline]
+    fn default() -> Self {
+        Self::new(DEFAULT_RESIZE_DEBOUNCE_FRAMES)
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rapid_resizes_recreate_once_with_the_final_size() {
+        let mut debounce = ResizeDebounce::new(3);
+
+        // a rotation animation resizes the view on every frame.
+        let mut applied = Vec::new();
+        for size in [(390, 844), (500, 700), (700, 500), (844, 390)] {
+            debounce.request(size);
+            applied.extend(debounce.advance());
+        }
+        assert!(applied.is_empty());
+
+        for _ in 0..10 {
+            applied.extend(debounce.advance());
+        }
+        assert_eq!(applied, vec![(844, 390)]);
+        assert!(!debounce.is_pending());
+    }
+
+    #[test]
+    fn zero_frames_resizes_on_the_next_frame() {
+        let mut debounce = ResizeDebounce::new(0);
+        debounce.request((390, 844));
+        assert_eq!(debounce.advance(), Some((390, 844)));
+        assert_eq!(debounce.advance(), None);
+    }
+}
@@ -1,50 +1,128 @@
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 
-use vulkano::format::{Format, FormatFeatures};
-use vulkano::image::{AttachmentImage, ImageUsage, ImageViewType, ImageSubresourceRange, ImageAspects};
+use vulkano::device::{Device, DeviceOwned};
+use vulkano::format::{Format, FormatFeatures, FormatProperties};
+use vulkano::image::{ImageAccess, ImageInner, ImageDescriptorLayouts, ImageLayout, ImageDimensions, ImageTiling, ImageUsage, SampleCount, ImageViewType, ImageSubresourceRange, ImageAspects};
+use vulkano::image::sys::{Image, RawImage, ImageCreateInfo};
 use vulkano::image::view::{ImageView, ImageViewCreateInfo};
-use vulkano::memory::allocator::MemoryAllocator;
+use vulkano::memory::allocator::{MemoryAllocator, AllocationCreateInfo, AllocationType, MemoryUsage};
+use vulkano::memory::DedicatedAllocation;
 use vulkano::sampler::ComponentMapping;
 
-use super::context::RenderContext;
+use super::context::{RenderContext, format_supports};
 use crate::{err, error::RuntimeError};
 
 
 
+/// The candidate depth-stencil formats in order of preference.
+const DEPTH_STENCIL_FORMATS: [Format; 3] = [
+    Format::D32_SFLOAT_S8_UINT,
+    Format::D24_UNORM_S8_UINT,
+    Format::D16_UNORM_S8_UINT,
+];
+
+
+
+/// A depth-stencil attachment image with the given tiling.
+/// Unlike `AttachmentImage`, it can be created with linear tiling.
+#[derive(Debug)]
+pub struct DepthStencilImage {
+    inner: Arc<Image>,
+    layout_initialized: AtomicBool,
+}
+
+unsafe impl ImageAccess for DepthStencilImage {
+    #[inline]
+    fn inner(&self) -> ImageInner<'_> {
+        ImageInner {
+            image: &self.inner,
+            first_layer: 0,
+            num_layers: 1,
+            first_mipmap_level: 0,
+            num_mipmap_levels: 1,
+        }
+    }
+
+    #[inline]
+    fn initial_layout_requirement(&self) -> ImageLayout {
+        ImageLayout::DepthStencilAttachmentOptimal
+    }
+
+    #[inline]
+    fn final_layout_requirement(&self) -> ImageLayout {
+        ImageLayout::DepthStencilAttachmentOptimal
+    }
+
+    #[inline]
+    fn descriptor_layouts(&self) -> Option<ImageDescriptorLayouts> {
+        None
+    }
+
+    #[inline]
+    unsafe fn layout_initialized(&self) {
+        self.layout_initialized.store(true, Ordering::SeqCst);
+    }
+
+    #[inline]
+    fn is_layout_initialized(&self) -> bool {
+        self.layout_initialized.load(Ordering::SeqCst)
+    }
+}
+
+unsafe impl DeviceOwned for DepthStencilImage {
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        self.inner.device()
+    }
+}
+
+
+
 #[derive(Debug)]
 pub struct RenderDepthStencil {
     format: Format,
-    image: Arc<AttachmentImage>,
-    view: Arc<ImageView<AttachmentImage>>,
+    tiling: ImageTiling,
+    samples: SampleCount,
+    transient: bool,
+    image: Arc<DepthStencilImage>,
+    view: Arc<ImageView<DepthStencilImage>>,
     render_ctx: Arc<RenderContext>,
 }
 
 
 impl RenderDepthStencil {
     /// Create a new `RenderDepthStencil`
+    /// The sample count must match the color attachments of the render pass.
+    /// If no candidate format supports the depth-stencil attachment with optimal tiling, linear tiling is used.
     /// 
     /// # Runtime Errors
-    /// - Returns a runtime error message if there is no format supported by the device.
+    /// - Returns a runtime error message if there is no format supported by the device with either tiling.
     /// - Returns a runtime error message if depth-stencil image creation fails.
     /// - Returns a runtime error message if depth-stencil image view creation fails.
     /// 
     pub fn new(
         width: u32, 
         height: u32, 
+        samples: SampleCount,
         render_ctx: Arc<RenderContext>
     ) -> Result<Self, RuntimeError> {
-        if let Some(format) = get_depth_stencil_format(&render_ctx) {
+        if let Some((format, tiling)) = get_depth_stencil_format_and_tiling(|format| render_ctx.get_format_properties(format).ok()) {
+            let transient = is_transient_depth_stencil(tiling, render_ctx.supports_lazily_allocated_memory());
             let (image, view) = create_depth_stencil(
                 width, 
                 height, 
                 format, 
+                tiling,
+                samples,
+                transient,
                 render_ctx.ref_memory_allocator()
             )?;
 
-            Ok(Self { format, image, view, render_ctx })
+            Ok(Self { format, tiling, samples, transient, image, view, render_ctx })
         }
         else {
-            Err(err!("No suitable depth-stencil format found."))
+            Err(err!("No suitable depth-stencil format found with optimal or linear tiling."))
         }
     }
 
@@ -60,6 +138,9 @@ impl RenderDepthStencil {
             width, 
             height, 
             self.format, 
+            self.tiling,
+            self.samples,
+            self.transient,
             self.render_ctx.ref_memory_allocator()
         )?;
 
@@ -77,16 +158,36 @@ impl RenderDepthStencil {
     }
 
 
+    /// Get the tiling of the depth-stencil image.
+    #[inline]
+    pub fn get_tiling(&self) -> ImageTiling {
+        self.tiling
+    }
+
+
+    #[inline]
+    pub fn get_samples(&self) -> SampleCount {
+        self.samples
+    }
+
+
+    /// Returns `true` if the depth-stencil image is a transient attachment.
+    #[inline]
+    pub fn is_transient(&self) -> bool {
+        self.transient
+    }
+
+
     /// Get the depth-stencil image. (reference)
     #[inline]
-    pub fn ref_image(&self) -> &Arc<AttachmentImage> {
+    pub fn ref_image(&self) -> &Arc<DepthStencilImage> {
         &self.image
     }
 
 
     /// Get the depth-stencil image view. (reference)
     #[inline]
-    pub fn ref_image_view(&self) -> &Arc<ImageView<AttachmentImage>> {
+    pub fn ref_image_view(&self) -> &Arc<ImageView<DepthStencilImage>> {
         &self.view
     }
 }
@@ -95,30 +196,59 @@ impl RenderDepthStencil {
 /// Get the depth-stencil format from the candidates.
 /// Returns `None` if there is no format supported by the device.
 /// 
-/// Note: Modify this function to change which depth-stencil format you want to use...
+/// Note: Modify `DEPTH_STENCIL_FORMATS` to change which depth-stencil format you want to use...
 /// 
 #[inline]
-fn get_depth_stencil_format(render_ctx: &RenderContext) -> Option<Format> {
-    const CANDIDATE_FORMATS: [Format; 3] = [
-        Format::D32_SFLOAT_S8_UINT,
-        Format::D24_UNORM_S8_UINT,
-        Format::D16_UNORM_S8_UINT,
-    ];
-
+pub(super) fn get_depth_stencil_format(render_ctx: &RenderContext) -> Option<Format> {
     // checking that the candidate format is supported by the device.
-    for format in CANDIDATE_FORMATS.into_iter() {
-        if let Ok(properties) = render_ctx.get_format_properties(format) {
-            if properties.optimal_tiling_features.contains(FormatFeatures::DEPTH_STENCIL_ATTACHMENT) {
-                return Some(format)
-            }
-        }
+    DEPTH_STENCIL_FORMATS.into_iter()
+        .find(|format| render_ctx.supports_optimal_tiling_features(*format, FormatFeatures::DEPTH_STENCIL_ATTACHMENT))
+}
+
+
+/// Get the depth-stencil format and tiling from the candidates.
+/// Optimal tiling is preferred. Linear tiling is used only if no candidate format supports optimal tiling.
+/// `get_properties` returns the properties of a format on the device, or `None` if they cannot be queried.
+/// Returns `None` if there is no format supported by the device with either tiling.
+#[inline]
+fn get_depth_stencil_format_and_tiling<F>(get_properties: F) -> Option<(Format, ImageTiling)>
+where F: Fn(Format) -> Option<FormatProperties> {
+    let properties: Vec<_> = DEPTH_STENCIL_FORMATS.into_iter()
+        .filter_map(|format| get_properties(format).map(|properties| (format, properties)))
+        .collect();
+
+    [ImageTiling::Optimal, ImageTiling::Linear].into_iter().find_map(|tiling| {
+        properties.iter()
+            .find(|(_, properties)| format_supports(properties, tiling, FormatFeatures::DEPTH_STENCIL_ATTACHMENT))
+            .map(|&(format, _)| (format, tiling))
+    })
+}
+
+
+/// Returns `true` if the depth-stencil image should be a transient attachment.
+/// Lazily-allocated memory is only used for optimal tiling images.
+#[inline]
+fn is_transient_depth_stencil(tiling: ImageTiling, has_lazily_allocated_memory: bool) -> bool {
+    tiling == ImageTiling::Optimal && has_lazily_allocated_memory
+}
+
+
+/// Get the usage of the depth-stencil image.
+#[inline]
+fn get_depth_stencil_usage(transient: bool) -> ImageUsage {
+    match transient {
+        true => ImageUsage::DEPTH_STENCIL_ATTACHMENT | ImageUsage::TRANSIENT_ATTACHMENT,
+        false => ImageUsage::DEPTH_STENCIL_ATTACHMENT,
     }
-    return None;
 }
 
 
 /// Create a depth-stencil image and view.
 /// 
+/// If `transient` is `true`, the image is created with the transient attachment usage
+/// so that the implementation may back it with lazily-allocated memory.
+/// A linear tiling image is allocated as a linear resource.
+/// 
 /// # Runtime Errors 
 /// - Returns a runtime error message if depth-stencil image creation fails.
 /// - Returns a runtime error message if depth-stencil image view creation fails.
@@ -128,15 +258,44 @@ fn create_depth_stencil(
     width: u32, 
     height: u32, 
     format: Format, 
+    tiling: ImageTiling,
+    samples: SampleCount,
+    transient: bool,
     allocator: &impl MemoryAllocator
-) -> Result<(Arc<AttachmentImage>, Arc<ImageView<AttachmentImage>>), RuntimeError> {
-    let image = AttachmentImage::with_usage(
-        allocator, 
-        [width, height], 
-        format, 
-        ImageUsage::DEPTH_STENCIL_ATTACHMENT
+) -> Result<(Arc<DepthStencilImage>, Arc<ImageView<DepthStencilImage>>), RuntimeError> {
+    let usage = get_depth_stencil_usage(transient);
+    let raw_image = RawImage::new(
+        allocator.device().clone(),
+        ImageCreateInfo {
+            dimensions: ImageDimensions::Dim2d { width, height, array_layers: 1 },
+            format: Some(format),
+            samples,
+            tiling,
+            usage,
+            ..Default::default()
+        }
     ).map_err(|e| err!("Failed to create depth-stencil image: {}", e.to_string()))?;
 
+    let allocation_type = match tiling {
+        ImageTiling::Linear => AllocationType::Linear,
+        _ => AllocationType::NonLinear,
+    };
+    let allocation = allocator.allocate(
+        raw_image.memory_requirements()[0],
+        allocation_type,
+        AllocationCreateInfo {
+            usage: MemoryUsage::DeviceOnly,
+            ..Default::default()
+        },
+        Some(DedicatedAllocation::Image(&raw_image))
+    ).map_err(|e| err!("Failed to allocate depth-stencil image memory: {}", e.to_string()))?;
+
+    let image = Arc::new(DepthStencilImage {
+        inner: Arc::new(raw_image.bind_memory([allocation])
+            .map_err(|(e, _, _)| err!("Failed to bind depth-stencil image memory: {}", e.to_string()))?),
+        layout_initialized: AtomicBool::new(false),
+    });
+
     let view = ImageView::new(
         image.clone(),
         ImageViewCreateInfo {
@@ -153,4 +312,61 @@ fn create_depth_stencil(
     ).map_err(|e| err!("Failed to create depth-stencil image view: {}", e.to_string()))?;
 
     Ok((image, view))
-}
\ No newline at end of file
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn depth_stencil_is_transient_with_lazily_allocated_memory() {
+        let transient = is_transient_depth_stencil(ImageTiling::Optimal, true);
+        assert!(transient);
+        assert!(get_depth_stencil_usage(transient).contains(ImageUsage::DEPTH_STENCIL_ATTACHMENT | ImageUsage::TRANSIENT_ATTACHMENT));
+    }
+
+    #[test]
+    fn depth_stencil_is_not_transient_without_lazily_allocated_memory() {
+        for (tiling, lazily_allocated) in [(ImageTiling::Optimal, false), (ImageTiling::Linear, true)] {
+            let transient = is_transient_depth_stencil(tiling, lazily_allocated);
+            assert!(!transient);
+            assert_eq!(get_depth_stencil_usage(transient), ImageUsage::DEPTH_STENCIL_ATTACHMENT);
+        }
+    }
+
+    #[test]
+    fn linear_tiling_is_used_when_only_linear_tiling_supports_depth() {
+        // a device where every candidate supports the depth-stencil attachment only with linear tiling.
+        let linear_only = |_| Some(FormatProperties {
+            linear_tiling_features: FormatFeatures::DEPTH_STENCIL_ATTACHMENT,
+            ..Default::default()
+        });
+        let (format, tiling) = get_depth_stencil_format_and_tiling(linear_only).unwrap();
+        assert_eq!(format, DEPTH_STENCIL_FORMATS[0]);
+        assert_eq!(tiling, ImageTiling::Linear);
+        assert!(!is_transient_depth_stencil(tiling, true));
+    }
+
+    #[test]
+    fn optimal_tiling_of_any_format_is_preferred_over_linear_tiling() {
+        let get_properties = |format| Some(match format {
+            Format::D24_UNORM_S8_UINT => FormatProperties {
+                optimal_tiling_features: FormatFeatures::DEPTH_STENCIL_ATTACHMENT,
+                ..Default::default()
+            },
+            _ => FormatProperties {
+                linear_tiling_features: FormatFeatures::DEPTH_STENCIL_ATTACHMENT,
+                ..Default::default()
+            },
+        });
+        assert_eq!(get_depth_stencil_format_and_tiling(get_properties), Some((Format::D24_UNORM_S8_UINT, ImageTiling::Optimal)));
+    }
+
+    #[test]
+    fn no_format_is_found_without_depth_support() {
+        assert_eq!(get_depth_stencil_format_and_tiling(|_| Some(FormatProperties::default())), None);
+        assert_eq!(get_depth_stencil_format_and_tiling(|_| None), None);
+    }
+}
@@ -1,77 +1,132 @@
 use std::fmt;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
-use vulkano::command_buffer::{PrimaryAutoCommandBuffer, RenderPassBeginInfo};
+use vulkano::command_buffer::{PrimaryAutoCommandBuffer, AutoCommandBufferBuilder, CommandBufferUsage, RenderPassBeginInfo};
 use vulkano::command_buffer::allocator::CommandBufferAlloc;
 use vulkano::format::Format;
-use vulkano::image::{SampleCount, ImageLayout};
-use vulkano::render_pass::{Framebuffer, RenderPass, RenderPassCreateInfo, AttachmentDescription, LoadOp, StoreOp, SubpassDescription, AttachmentReference, SubpassDependency, FramebufferCreateInfo};
-use vulkano::swapchain::{SwapchainAcquireFuture, SwapchainPresentInfo};
+use vulkano::image::{SampleCount, ImageAccess, ImageLayout, ImageViewAbstract, SwapchainImage, AttachmentImage};
+use vulkano::image::view::ImageView;
+use vulkano::render_pass::{ResolveMode, ResolveModes, Framebuffer, RenderPass, RenderPassCreateInfo, AttachmentDescription, LoadOp, StoreOp, SubpassDescription, AttachmentReference, SubpassDependency, FramebufferCreateInfo};
+use vulkano::swapchain::{SwapchainAcquireFuture, SwapchainPresentInfo, PresentMode, ColorSpace, RectangleLayer};
 use vulkano::sync::{now, GpuFuture, PipelineStages, AccessFlags, FlushError}; 
 
-use super::context::RenderContext;
+use super::context::{RenderContext, get_color_resolve_modes};
 use super::swapchain::RenderSwapchain;
 use super::depth_stencil::RenderDepthStencil;
+use super::color_attachment::RenderColorAttachment;
+use super::history::RenderHistory;
+use super::capture::{FrameCapture, is_bgra_capture_format};
 use crate::{err, error::RuntimeError};
 
 
+/// The maximum number of swapchain recreation attempts per frame.
+pub const MAX_RECREATE_ATTEMPTS: u32 = 3;
+
+
 pub struct RenderFrame {
     recreate_swapchain: bool,
     swapchain: RenderSwapchain,
     depth_stencil: RenderDepthStencil,
+    color_attachments: Vec<RenderColorAttachment>,
+    samples: SampleCount,
+    msaa_attachments: Vec<RenderColorAttachment>,
     render_pass: Arc<RenderPass>,
     framebuffers: Vec<Arc<Framebuffer>>,
+    history: Option<RenderHistory>,
+    resolve_modes: Vec<ResolveMode>,
+    capture_requested: bool,
+    capture: Option<FrameCapture>,
     previous_frame_end: Option<Box<dyn GpuFuture>>,
 }
 
 impl RenderFrame {
     /// Create a new `RenderFrame`.
     /// 
+    /// The sample count is clamped to the counts supported by the device.
+    /// If it is greater than one, the scene is rendered to multisampled attachments
+    /// that are resolved into the swapchain image and the additional color attachments.
+    /// If `wide_color` is `true`, the Display-P3 color space is used when the surface supports it. (see `select_surface_format`)
+    /// 
     /// # Runtime Errors
     /// - Returns a runtime error message if Vulkan swapchain creation fails.
     /// - Returns a runtime error message if Vulkan image view creation fails.
     /// - Returns a runtime error message if there is no format supported by the device.
     /// - Returns a runtime error message if depth-stencil image creation fails.
     /// - Returns a runtime error message if depth-stencil image view creation fails.
+    /// - Returns a runtime error message if color attachment creation fails.
     /// - Returns a runtime error message if render pass creation fails.
     /// - Returns a runtime error message if framebuffer creation fails.
     /// 
     pub fn new(
         width: u32,
         height: u32,
+        render_scale: f32,
+        color_attachment_formats: &[Format],
+        samples: SampleCount,
+        wide_color: bool,
         render_ctx: &Arc<RenderContext>,
     ) -> Result<Arc<Mutex<Self>>, RuntimeError> {
+        let samples = render_ctx.get_supported_sample_count(samples);
+
         // create a `RenderSwapchain`.
         let swapchain = RenderSwapchain::new(
             width, 
             height,
+            render_scale,
+            wide_color,
             render_ctx.clone()
         )?;
 
+        // the attachments must match the swapchain image extent,
+        // which may differ from the screen size if the surface is rotated.
+        let image_extent = swapchain.get_image_extent();
+
         // create a `RenderDepthStencil`
         let depth_stencil = RenderDepthStencil::new(
-            width, 
-            height, 
+            image_extent[0], 
+            image_extent[1], 
+            samples,
             render_ctx.clone()
         )?;
 
+        // create additional color attachments.
+        let color_attachments = color_attachment_formats.iter()
+            .map(|format| RenderColorAttachment::new(image_extent[0], image_extent[1], *format, render_ctx.clone()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // create the multisampled attachments of the swapchain image and the additional color attachments.
+        let msaa_attachments = match samples {
+            SampleCount::Sample1 => Vec::new(),
+            _ => [swapchain.ref_swapchain().image_format()].iter()
+                .chain(color_attachment_formats.iter())
+                .map(|format| RenderColorAttachment::with_samples(image_extent[0], image_extent[1], *format, samples, render_ctx.clone()))
+                .collect::<Result<Vec<_>, _>>()?,
+        };
+
         // create a vulkan render pass.
         let render_pass = create_vulkan_render_pass(
             &render_ctx,
             swapchain.ref_swapchain().image_format(), 
-            depth_stencil.ref_format().clone()
+            color_attachment_formats,
+            depth_stencil.ref_format().clone(),
+            samples
         )?;
 
         // create a vulkan framebuffers.
-        let image_extent = swapchain.ref_swapchain().image_extent();
         let framebuffers = create_vulkan_framebuffers(
             image_extent[0], 
             image_extent[1], 
             &swapchain, 
+            &color_attachments,
+            &msaa_attachments,
             &depth_stencil, 
             &render_pass
         )?;
 
+        // the multisample resolve modes the render pass uses for the color attachments.
+        let resolve_modes = get_subpass_resolve_modes(swapchain.ref_swapchain().image_format(), color_attachment_formats);
+
         // create a waiting future.
         let previous_frame_end = Some(now(render_ctx.ref_device().clone()).boxed());
 
@@ -79,8 +134,15 @@ impl RenderFrame {
             recreate_swapchain: false,
             swapchain,
             depth_stencil,
+            color_attachments,
+            samples,
+            msaa_attachments,
             render_pass,
             framebuffers,
+            history: None,
+            resolve_modes,
+            capture_requested: false,
+            capture: None,
             previous_frame_end
         })))
     }
@@ -91,13 +153,16 @@ impl RenderFrame {
     /// # Results
     /// - Returns `SwapchainAcquireFuture` if the next frame image is fetched successfully.
     /// - Returns `None` if `AcquireError::OutOfDate` occurs.
+    /// - Returns `None` if acquiring the next frame image times out.
+    /// - Returns `None` if swapchain recreation fails `MAX_RECREATE_ATTEMPTS` times in a row.
+    ///   The swapchain recreation is tried again on the next frame.
     /// 
     /// # Runtime Errors
     /// - Returns a runtime error message if getting the next frame image fails.
-    /// - Returns a runtime error message if Vulkan swapchain recreation fails.
-    /// - Returns a runtime error message if Vulkan image view creation fails.
+    /// - Returns a runtime error message if history image creation fails.
     /// - Returns a runtime error message if depth-stencil image creation fails.
     /// - Returns a runtime error message if depth-stencil image view creation fails.
+    /// - Returns a runtime error message if color attachment creation fails.
     /// - Returns a runtime error message if framebuffer creation fails.
     /// 
     pub fn wait_for_next_frame(
@@ -110,16 +175,35 @@ impl RenderFrame {
 
         if self.recreate_swapchain {
             // recreate a swapchain.
-            self.swapchain.recreate(width, height)?;
+            // the surface may be momentarily unavailable (e.g. during rotation), so retry a few times.
+            if let Err(_e) = retry(MAX_RECREATE_ATTEMPTS, || self.swapchain.recreate(width, height)) {
+                #[cfg(debug_assertions)]
+                println!("swapchain recreation failed! ({})", _e.what());
+
+                return Ok(None);
+            }
+            let image_extent = self.swapchain.get_image_extent();
 
             // recreate a depth-stencil.
-            self.depth_stencil.recreate(width, height)?;
+            self.depth_stencil.recreate(image_extent[0], image_extent[1])?;
+
+            // recreate additional color attachments.
+            for color_attachment in self.color_attachments.iter_mut().chain(self.msaa_attachments.iter_mut()) {
+                color_attachment.recreate(image_extent[0], image_extent[1])?;
+            }
+
+            // recreate the history images. the retained frames no longer match the new extent.
+            if let Some(history) = self.history.as_mut() {
+                history.recreate(image_extent[0], image_extent[1])?;
+            }
 
             // recreate a framebuffers
             self.framebuffers = create_vulkan_framebuffers(
-                width, 
-                height, 
+                image_extent[0], 
+                image_extent[1], 
                 &self.swapchain, 
+                &self.color_attachments,
+                &self.msaa_attachments,
                 &self.depth_stencil, 
                 &self.render_pass
             )?;
@@ -127,7 +211,7 @@ impl RenderFrame {
             self.recreate_swapchain = false;
 
             #[cfg(feature = "monitor")]
-            println!("<monitor> swapchain recreated. ({:?}, {:?})", &width, &height);
+            println!("<monitor> swapchain recreated. ({:?}, {:?})", &image_extent[0], &image_extent[1]);
         }
 
         if let Some((image_index, suboptimal, acquire_future)) = self.swapchain.acquire_next_image()? {
@@ -141,7 +225,16 @@ impl RenderFrame {
 
     /// Submit commands to the queue and print them to the screen.
     /// 
+    /// If `present_regions` is not empty and the device supports incremental present,
+    /// only the given regions of the swapchain image are presented.
+    /// Regions are clamped to the swapchain image extent.
+    /// 
+    /// If the frame history is enabled, the swapchain image is copied into it after the commands.
+    /// If a capture was requested, the swapchain image is also copied into host memory before it is presented.
+    /// 
     /// # Runtime Errors
+    /// - Returns a runtime error message if history copy command recording fails.
+    /// - Returns a runtime error message if capture copy command recording fails.
     /// - Returns a runtime error message if command buffer execution fails.
     /// - Returns a runtime error message if presentation fails.
     /// 
@@ -149,9 +242,46 @@ impl RenderFrame {
         &mut self,
         render_ctx: &Arc<RenderContext>,
         acquire_future: SwapchainAcquireFuture,
-        command_buffer: PrimaryAutoCommandBuffer<A>
+        command_buffer: PrimaryAutoCommandBuffer<A>,
+        present_regions: &[RectangleLayer],
     ) -> Result<(), RuntimeError> {
-        let future = self.previous_frame_end
+        let mut present_info = SwapchainPresentInfo::swapchain_image_index(
+            self.swapchain.ref_swapchain().clone(), 
+            self.swapchain.get_current_frame()
+        );
+        if render_ctx.supports_incremental_present() {
+            let image_extent = self.swapchain.get_image_extent();
+            present_info.present_regions = present_regions.iter()
+                .filter_map(|region| clamp_present_region(region, image_extent))
+                .collect();
+        }
+
+        // the copies of the rendered image run after the commands and before the image is presented.
+        let capture_requested = std::mem::take(&mut self.capture_requested);
+        let copy_command_buffer = match self.history.is_some() || capture_requested {
+            true => {
+                let allocator = render_ctx.get_command_buffer_allocator();
+                let mut command_buffer_builder = AutoCommandBufferBuilder::primary(
+                    &allocator, 
+                    render_ctx.get_queue_fmaily_index(), 
+                    CommandBufferUsage::OneTimeSubmit
+                ).map_err(|e| err!("Command buffer begining failed: {}", e.to_string()))?;
+
+                let image = self.swapchain.ref_swapchain_image_views()[self.swapchain.get_current_frame() as usize].image().clone();
+                if let Some(history) = self.history.as_mut() {
+                    history.record_copy(image.clone(), &mut command_buffer_builder)?;
+                }
+                if capture_requested {
+                    self.capture = Some(FrameCapture::record(image, render_ctx, &mut command_buffer_builder)?);
+                }
+
+                Some(command_buffer_builder.build()
+                    .map_err(|e| err!("Primary command buffer building failed: {}", e.to_string()))?)
+            },
+            false => None,
+        };
+
+        let mut future = self.previous_frame_end
             .take()
             .unwrap()
             .join(acquire_future)
@@ -159,12 +289,20 @@ impl RenderFrame {
                 render_ctx.ref_integrated_queue().clone(), 
                 command_buffer
             ).map_err(|e| err!("Command buffer execution failed: {}", e.to_string()))?
+            .boxed();
+        if let Some(copy_command_buffer) = copy_command_buffer {
+            future = future
+                .then_execute(
+                    render_ctx.ref_integrated_queue().clone(), 
+                    copy_command_buffer
+                ).map_err(|e| err!("Command buffer execution failed: {}", e.to_string()))?
+                .boxed();
+        }
+
+        let future = future
             .then_swapchain_present(
                 render_ctx.ref_integrated_queue().clone(), 
-                SwapchainPresentInfo::swapchain_image_index(
-                    self.swapchain.ref_swapchain().clone(), 
-                    self.swapchain.get_current_frame()
-                )
+                present_info
             ).then_signal_fence_and_flush();
         
         match future {
@@ -175,10 +313,13 @@ impl RenderFrame {
                 #[cfg(debug_assertions)]
                 println!("flush error! (out of date)");
 
+                // the frame cannot be waited for, so its capture is discarded.
                 self.recreate_swapchain = true;
+                self.capture = None;
                 self.previous_frame_end = Some(now(render_ctx.ref_device().clone()).boxed());
             },
             Err(e) => {
+                self.capture = None;
                 return Err(err!("Presentation failed: {}", e.to_string()));
             }
         };
@@ -186,15 +327,225 @@ impl RenderFrame {
         Ok(())
     }
 
+    /// Set the present mode. The swapchain is recreated with it on the next frame.
+    /// If the present mode is not supported by the surface, `PresentMode::Fifo` is used instead.
+    /// Returns the present mode selected.
+    /// 
+    /// # Runtime Errors
+    /// - Returns a runtime error message if getting the surface present modes fails.
+    /// 
+    pub fn set_present_mode(&mut self, present_mode: PresentMode) -> Result<PresentMode, RuntimeError> {
+        let present_mode = self.swapchain.request_present_mode(present_mode)?;
+        if self.swapchain.has_pending_present_mode() {
+            self.recreate_swapchain = true;
+        }
+
+        #[cfg(feature = "monitor")]
+        println!("<monitor> present mode selected. ({:?})", present_mode);
+
+        Ok(present_mode)
+    }
+
+    /// Get the present mode of the current swapchain.
+    /// A present mode set by `set_present_mode` is reported once the swapchain is recreated.
+    #[inline]
+    pub fn get_present_mode(&self) -> PresentMode {
+        self.swapchain.ref_swapchain().present_mode()
+    }
+
+    /// Get the color space of the swapchain images.
+    #[inline]
+    pub fn get_color_space(&self) -> ColorSpace {
+        self.swapchain.get_color_space()
+    }
+
+    /// Set the timeout for acquiring the next frame image. `None` waits indefinitely.
+    #[inline]
+    pub fn set_acquire_timeout(&mut self, timeout: Option<Duration>) {
+        self.swapchain.set_acquire_timeout(timeout)
+    }
+
+    #[inline]
+    pub fn get_acquire_timeout(&self) -> Option<Duration> {
+        self.swapchain.get_acquire_timeout()
+    }
+
+    /// Capture the next submitted frame. The swapchain image is copied into host memory before it is presented,
+    /// and the pixels can be read with `capture_frame` after the frame is submitted.
+    /// 
+    /// # Runtime Errors
+    /// - Returns a runtime error message if the swapchain images cannot be copied from.
+    /// - Returns a runtime error message if the swapchain format is not an 8-bit RGBA or BGRA format.
+    /// 
+    pub fn request_capture(&mut self) -> Result<(), RuntimeError> {
+        if !self.swapchain.supports_transfer_src() {
+            return Err(err!("The swapchain images do not support transfer source usage."));
+        }
+        is_bgra_capture_format(self.swapchain.get_image_format())?;
+
+        self.capture_requested = true;
+        Ok(())
+    }
+
+    /// Get the frame captured by the last `request_capture`.
+    /// Waits until the frame has finished rendering.
+    /// Returns the width, height and tightly-packed RGBA8 pixels of the image.
+    /// The pixels of an sRGB swapchain are returned sRGB-encoded, as they are displayed.
+    /// 
+    /// # Runtime Errors
+    /// - Returns a runtime error message if no frame was captured since the last call.
+    ///   (e.g. no capture was requested, or the presentation of the frame was out of date)
+    /// - Returns a runtime error message if waiting for the frame fails.
+    /// - Returns a runtime error message if the readback buffer cannot be read.
+    /// 
+    pub fn capture_frame(&mut self, render_ctx: &Arc<RenderContext>) -> Result<(u32, u32, Vec<u8>), RuntimeError> {
+        let capture = self.capture.take()
+            .ok_or_else(|| err!("No frame has been captured. The capture must be requested before the frame is submitted."))?;
+
+        self.previous_frame_end
+            .take()
+            .unwrap()
+            .then_signal_fence_and_flush()
+            .map_err(|e| err!("Primary command buffer flush failed: {}", e.to_string()))?
+            .wait(None)
+            .map_err(|e| err!("Primary command buffer flush failed: {}", e.to_string()))?;
+        self.previous_frame_end = Some(now(render_ctx.ref_device().clone()).boxed());
+
+        capture.read()
+    }
+
+    /// Recreate the swapchain on the next frame.
+    #[inline]
+    pub fn request_recreate(&mut self) {
+        self.recreate_swapchain = true;
+    }
+
+    /// Set the render scale. The swapchain is recreated on the next frame.
+    #[inline]
+    pub fn set_render_scale(&mut self, render_scale: f32) {
+        if self.swapchain.get_render_scale() != render_scale {
+            self.swapchain.set_render_scale(render_scale);
+            self.recreate_swapchain = true;
+        }
+    }
+
+    /// Get the extent of the framebuffers. (pixel)
+    #[inline]
+    pub fn get_image_extent(&self) -> [u32; 2] {
+        self.swapchain.get_image_extent()
+    }
+
+    /// Get the format of the swapchain images.
+    #[inline]
+    pub fn get_swapchain_format(&self) -> Format {
+        self.swapchain.get_image_format()
+    }
+
+    /// Get the index of the current swapchain image.
+    #[inline]
+    pub fn get_current_frame(&self) -> u32 {
+        self.swapchain.get_current_frame()
+    }
+
+    /// Get the number of swapchain images.
+    #[inline]
+    pub fn get_max_frame_in_flight(&self) -> u32 {
+        self.swapchain.get_max_frame_in_flight()
+    }
+
     #[inline]
     pub fn ref_current_framebuffer(&self) -> &Arc<Framebuffer> {
         &self.framebuffers[self.swapchain.get_current_frame() as usize]
     }
 
+    /// Returns `true` if compute shaders can write to the swapchain images.
+    #[inline]
+    pub fn supports_storage_present(&self) -> bool {
+        self.swapchain.supports_storage()
+    }
+
+    /// Get the image view of the current swapchain image. (reference)
+    #[inline]
+    pub fn ref_current_swapchain_image_view(&self) -> &Arc<ImageView<SwapchainImage>> {
+        &self.swapchain.ref_swapchain_image_views()[self.swapchain.get_current_frame() as usize]
+    }
+
+    /// Set the number of previous frames retained for temporal effects. `0` disables the history.
+    /// The retained frames are discarded.
+    /// 
+    /// # Runtime Errors
+    /// - Returns a runtime error message if the swapchain images cannot be copied from.
+    /// - Returns a runtime error message if history image creation fails.
+    /// - Returns a runtime error message if history image view creation fails.
+    /// 
+    pub fn set_history_length(&mut self, render_ctx: &Arc<RenderContext>, num_frames: usize) -> Result<(), RuntimeError> {
+        if num_frames == 0 {
+            self.history = None;
+            return Ok(());
+        }
+
+        if !self.swapchain.supports_transfer_src() {
+            return Err(err!("The swapchain images do not support transfer source usage."));
+        }
+
+        let image_extent = self.swapchain.get_image_extent();
+        self.history = Some(RenderHistory::new(
+            num_frames, 
+            image_extent[0], 
+            image_extent[1], 
+            self.swapchain.ref_swapchain().image_format(), 
+            render_ctx.clone()
+        )?);
+
+        Ok(())
+    }
+
+    #[inline]
+    pub fn get_history_length(&self) -> usize {
+        self.history.as_ref().map_or(0, |history| history.get_num_frames())
+    }
+
+    /// Get the image view of the frame rendered `index + 1` frames ago.
+    /// See `RenderHistory::ref_frame`.
+    #[inline]
+    pub fn get_history_frame(&self, index: usize) -> Option<Arc<ImageView<AttachmentImage>>> {
+        self.history.as_ref().and_then(|history| history.ref_frame(index).cloned())
+    }
+
+    /// Get the multisample resolve mode the render pass uses for the color attachment with the given index.
+    /// The index `0` is the swapchain image. (see `get_subpass_resolve_modes`)
+    #[inline]
+    pub fn get_resolve_mode(&self, index: usize) -> ResolveMode {
+        self.resolve_modes[index]
+    }
+
     #[inline]
     pub fn ref_render_pass(&self) -> &Arc<RenderPass> {
         &self.render_pass
     }
+
+    /// Get the number of color attachments, including the swapchain image.
+    #[inline]
+    pub fn get_num_color_attachments(&self) -> usize {
+        1 + self.color_attachments.len()
+    }
+
+    #[inline]
+    pub fn ref_color_attachments(&self) -> &[RenderColorAttachment] {
+        &self.color_attachments
+    }
+
+    /// Get the sample count of the color and depth-stencil attachments.
+    #[inline]
+    pub fn get_samples(&self) -> SampleCount {
+        self.samples
+    }
+
+    /// Get the number of attachments of the render pass, including the depth-stencil and the resolve attachments.
+    #[inline]
+    pub fn get_num_attachments(&self) -> usize {
+        self.render_pass.attachments().len()
+    }
 }
 
 
@@ -204,14 +555,84 @@ impl fmt::Debug for RenderFrame {
             .field("recreate_swapchain", &self.recreate_swapchain)
             .field("swapchain", &self.swapchain)
             .field("depth_stencil", &self.depth_stencil)
+            .field("color_attachments", &self.color_attachments)
+            .field("samples", &self.samples)
+            .field("msaa_attachments", &self.msaa_attachments)
             .field("render_pass", &self.render_pass)
             .field("framebuffers", &self.framebuffers)
+            .field("history", &self.history)
+            .field("resolve_modes", &self.resolve_modes)
+            .field("capture_requested", &self.capture_requested)
+            .field("capture", &self.capture)
             .finish()
     }
 }
 
 
+/// Call the function until it succeeds, at most `max_attempts` times.
+/// Returns the last error if all attempts fail.
+#[inline]
+fn retry<T, E>(max_attempts: u32, mut f: impl FnMut() -> Result<T, E>) -> Result<T, E> {
+    let mut attempts = 1;
+    loop {
+        match f() {
+            Ok(value) => return Ok(value),
+            Err(e) if attempts >= max_attempts => return Err(e),
+            Err(_) => attempts += 1,
+        }
+    }
+}
+
+
+/// Get the multisample resolve modes of the subpass for the swapchain image and the additional color attachments.
+/// The resolve mode of a color attachment is fixed by its format, so an integer attachment (e.g. an object ID)
+/// is resolved with `SampleZero` while the other attachments are averaged. (see `get_color_resolve_modes`)
+#[inline]
+fn get_subpass_resolve_modes(swapchain_format: Format, color_attachment_formats: &[Format]) -> Vec<ResolveMode> {
+    [swapchain_format].iter()
+        .chain(color_attachment_formats.iter())
+        .map(|format| select_resolve_mode(ResolveMode::Average, get_color_resolve_modes(*format)))
+        .collect()
+}
+
+
+/// Returns the requested resolve mode if it is supported.
+/// Otherwise, returns the first supported mode in the order of `Average`, `SampleZero`, `Min` and `Max`.
+#[inline]
+fn select_resolve_mode(requested: ResolveMode, supported: ResolveModes) -> ResolveMode {
+    if supported.contains_enum(requested) {
+        return requested;
+    }
+
+    [ResolveMode::Average, ResolveMode::SampleZero, ResolveMode::Min, ResolveMode::Max]
+        .into_iter()
+        .find(|mode| supported.contains_enum(*mode))
+        .unwrap_or(ResolveMode::SampleZero)
+}
+
+
+/// Clamp the present region to the swapchain image extent.
+/// Returns `None` if the region lies outside the image.
+#[inline]
+fn clamp_present_region(region: &RectangleLayer, image_extent: [u32; 2]) -> Option<RectangleLayer> {
+    if region.offset[0] >= image_extent[0] || region.offset[1] >= image_extent[1] {
+        return None;
+    }
+
+    let extent = [
+        region.extent[0].min(image_extent[0] - region.offset[0]),
+        region.extent[1].min(image_extent[1] - region.offset[1]),
+    ];
+    if extent[0] == 0 || extent[1] == 0 {
+        return None;
+    }
+
+    Some(RectangleLayer { offset: region.offset, extent, layer: 0 })
+}
+
+
 /// Create a vulkan render pass.
+/// The attachments are described in `get_render_pass_create_info`.
 /// 
 /// # Runtime Errors 
 /// - Returns a runtime error message if render pass creation fails.
@@ -220,81 +641,145 @@ impl fmt::Debug for RenderFrame {
 fn create_vulkan_render_pass(
     render_ctx: &Arc<RenderContext>,
     swapchain_format: Format,
+    color_attachment_formats: &[Format],
     depth_stencil_format: Format,
+    samples: SampleCount,
 ) -> Result<Arc<RenderPass>, RuntimeError> {
     RenderPass::new(
         render_ctx.ref_device().clone(), 
-        RenderPassCreateInfo {
-            attachments: vec![
-                AttachmentDescription {
-                    format: Some(swapchain_format),
-                    samples: SampleCount::Sample1,
-                    load_op: LoadOp::Clear,
-                    store_op: StoreOp::Store,
-                    stencil_load_op: LoadOp::DontCare,
-                    stencil_store_op: StoreOp::DontCare,
-                    initial_layout: ImageLayout::Undefined,
-                    final_layout: ImageLayout::PresentSrc,
-                    ..Default::default()
-                },
-                AttachmentDescription {
-                    format: Some(depth_stencil_format),
-                    samples: SampleCount::Sample1,
-                    load_op: LoadOp::Clear,
-                    store_op: StoreOp::Store,
-                    stencil_load_op: LoadOp::Clear,
-                    stencil_store_op: StoreOp::DontCare,
-                    initial_layout: ImageLayout::Undefined,
-                    final_layout: ImageLayout::DepthStencilAttachmentOptimal,
-                    ..Default::default()
-                }
-            ],
-            subpasses: vec![
-                SubpassDescription {
-                    color_attachments: vec![
-                        Some(AttachmentReference {
-                            attachment: 0,
-                            layout: ImageLayout::ColorAttachmentOptimal,
-                            ..Default::default()
-                        })
-                    ],
-                    depth_stencil_attachment: Some(
-                        AttachmentReference {
-                            attachment: 1,
-                            layout: ImageLayout::DepthStencilAttachmentOptimal,
-                            ..Default::default()
-                        }
-                    ),
-                    ..Default::default()
-                }
-            ],
-            dependencies: vec![
-                SubpassDependency {
-                    src_subpass: None,
-                    dst_subpass: Some(0),
-                    src_stages: PipelineStages::EARLY_FRAGMENT_TESTS | PipelineStages::LATE_FRAGMENT_TESTS,
-                    dst_stages: PipelineStages::EARLY_FRAGMENT_TESTS | PipelineStages::LATE_FRAGMENT_TESTS,
-                    src_access: AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
-                    dst_access: AccessFlags::DEPTH_STENCIL_ATTACHMENT_READ | AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
-                    ..Default::default()
-                },
-                SubpassDependency {
-                    src_subpass: None,
-                    dst_subpass: Some(0),
-                    src_stages: PipelineStages::COLOR_ATTACHMENT_OUTPUT,
-                    dst_stages: PipelineStages::COLOR_ATTACHMENT_OUTPUT,
-                    src_access: AccessFlags::default(),
-                    dst_access: AccessFlags::COLOR_ATTACHMENT_READ | AccessFlags::COLOR_ATTACHMENT_WRITE,
-                    ..Default::default()
-                }
-            ],
+        get_render_pass_create_info(swapchain_format, color_attachment_formats, depth_stencil_format, samples)
+    ).map_err(|e| err!("Vulkan render pass creation failed: {}", e.to_string()))
+}
+
+
+/// Get the create info of the render pass.
+/// 
+/// The attachments are ordered as the swapchain image, the additional color attachments
+/// and the depth-stencil.
+/// If the sample count is greater than one, they are multisampled and followed by
+/// the resolve attachments of the swapchain image and the additional color attachments,
+/// which are resolved with the modes of `get_subpass_resolve_modes`.
+/// 
+fn get_render_pass_create_info(
+    swapchain_format: Format,
+    color_attachment_formats: &[Format],
+    depth_stencil_format: Format,
+    samples: SampleCount,
+) -> RenderPassCreateInfo {
+    let multisampled = samples != SampleCount::Sample1;
+    let color_formats: Vec<Format> = [swapchain_format].into_iter()
+        .chain(color_attachment_formats.iter().copied())
+        .collect();
+    let final_color_layout = |index: usize| match index {
+        0 => ImageLayout::PresentSrc,
+        _ => ImageLayout::ShaderReadOnlyOptimal,
+    };
+
+    let mut attachments = Vec::with_capacity(2 * color_formats.len() + 1);
+    for (index, format) in color_formats.iter().enumerate() {
+        // the multisampled images are discarded once they are resolved.
+        attachments.push(AttachmentDescription {
+            format: Some(*format),
+            samples,
+            load_op: LoadOp::Clear,
+            store_op: if multisampled { StoreOp::DontCare } else { StoreOp::Store },
+            stencil_load_op: LoadOp::DontCare,
+            stencil_store_op: StoreOp::DontCare,
+            initial_layout: ImageLayout::Undefined,
+            final_layout: if multisampled { ImageLayout::ColorAttachmentOptimal } else { final_color_layout(index) },
             ..Default::default()
+        });
+    }
+    // the depth-stencil is not read after the render pass, so it does not need to be stored.
+    attachments.push(AttachmentDescription {
+        format: Some(depth_stencil_format),
+        samples,
+        load_op: LoadOp::Clear,
+        store_op: StoreOp::DontCare,
+        stencil_load_op: LoadOp::Clear,
+        stencil_store_op: StoreOp::DontCare,
+        initial_layout: ImageLayout::Undefined,
+        final_layout: ImageLayout::DepthStencilAttachmentOptimal,
+        ..Default::default()
+    });
+    if multisampled {
+        for (index, format) in color_formats.iter().enumerate() {
+            attachments.push(AttachmentDescription {
+                format: Some(*format),
+                samples: SampleCount::Sample1,
+                load_op: LoadOp::DontCare,
+                store_op: StoreOp::Store,
+                stencil_load_op: LoadOp::DontCare,
+                stencil_store_op: StoreOp::DontCare,
+                initial_layout: ImageLayout::Undefined,
+                final_layout: final_color_layout(index),
+                ..Default::default()
+            });
         }
-    ).map_err(|e| err!("Vulkan render pass creation failed: {}", e.to_string()))
+    }
+
+    let num_color_attachments = 1 + color_attachment_formats.len() as u32;
+    let color_attachments = (0..num_color_attachments)
+        .map(|attachment| Some(AttachmentReference {
+            attachment,
+            layout: ImageLayout::ColorAttachmentOptimal,
+            ..Default::default()
+        }))
+        .collect();
+    let resolve_attachments = match multisampled {
+        true => (0..num_color_attachments)
+            .map(|index| Some(AttachmentReference {
+                attachment: num_color_attachments + 1 + index,
+                layout: ImageLayout::ColorAttachmentOptimal,
+                ..Default::default()
+            }))
+            .collect(),
+        false => Vec::new(),
+    };
+
+    RenderPassCreateInfo {
+        attachments,
+        subpasses: vec![
+            SubpassDescription {
+                color_attachments,
+                resolve_attachments,
+                depth_stencil_attachment: Some(
+                    AttachmentReference {
+                        attachment: num_color_attachments,
+                        layout: ImageLayout::DepthStencilAttachmentOptimal,
+                        ..Default::default()
+                    }
+                ),
+                ..Default::default()
+            }
+        ],
+        dependencies: vec![
+            SubpassDependency {
+                src_subpass: None,
+                dst_subpass: Some(0),
+                src_stages: PipelineStages::EARLY_FRAGMENT_TESTS | PipelineStages::LATE_FRAGMENT_TESTS,
+                dst_stages: PipelineStages::EARLY_FRAGMENT_TESTS | PipelineStages::LATE_FRAGMENT_TESTS,
+                src_access: AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
+                dst_access: AccessFlags::DEPTH_STENCIL_ATTACHMENT_READ | AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
+                ..Default::default()
+            },
+            SubpassDependency {
+                src_subpass: None,
+                dst_subpass: Some(0),
+                src_stages: PipelineStages::COLOR_ATTACHMENT_OUTPUT,
+                dst_stages: PipelineStages::COLOR_ATTACHMENT_OUTPUT,
+                src_access: AccessFlags::default(),
+                dst_access: AccessFlags::COLOR_ATTACHMENT_READ | AccessFlags::COLOR_ATTACHMENT_WRITE,
+                ..Default::default()
+            }
+        ],
+        ..Default::default()
+    }
 }
 
 
 /// Create a framebuffers.
+/// The attachments are ordered as described in `create_vulkan_render_pass`.
 /// 
 /// # Runtime Errors 
 /// - Returns a runtime error message if framebuffer creation fails.
@@ -304,19 +789,30 @@ fn create_vulkan_framebuffers(
     width: u32,
     height: u32,
     swapchain: &RenderSwapchain,
+    color_attachments: &[RenderColorAttachment],
+    msaa_attachments: &[RenderColorAttachment],
     depth_stencil: &RenderDepthStencil,
     render_pass: &Arc<RenderPass>
 ) -> Result<Vec<Arc<Framebuffer>>, RuntimeError> {
     let mut framebuffers = Vec::with_capacity(swapchain.get_max_frame_in_flight() as usize);
     for view in swapchain.ref_swapchain_image_views().iter() {
+        let mut resolve_attachments: Vec<Arc<dyn ImageViewAbstract>> = Vec::with_capacity(color_attachments.len() + 1);
+        resolve_attachments.push(view.clone());
+        for color_attachment in color_attachments.iter() {
+            resolve_attachments.push(color_attachment.ref_image_view().clone());
+        }
+
+        let attachments = order_framebuffer_attachments(
+            resolve_attachments,
+            msaa_attachments.iter().map(|attachment| attachment.ref_image_view().clone() as Arc<dyn ImageViewAbstract>).collect(),
+            depth_stencil.ref_image_view().clone()
+        );
+
         framebuffers.push(
             Framebuffer::new(
                 render_pass.clone(),
                 FramebufferCreateInfo {
-                    attachments: vec![
-                        view.clone(),
-                        depth_stencil.ref_image_view().clone()
-                    ],
+                    attachments,
                     extent: [width, height],
                     layers: 1,
                     ..Default::default()
@@ -326,3 +822,97 @@ fn create_vulkan_framebuffers(
     }
     return Ok(framebuffers);
 }
+
+
+
+/// Order the framebuffer attachments as described in `get_render_pass_create_info`.
+/// Without multisampling, `resolve_attachments` are rendered to directly.
+#[inline]
+fn order_framebuffer_attachments<T>(resolve_attachments: Vec<T>, msaa_attachments: Vec<T>, depth_stencil: T) -> Vec<T> {
+    let mut attachments = Vec::with_capacity(resolve_attachments.len() + msaa_attachments.len() + 1);
+    match msaa_attachments.is_empty() {
+        true => {
+            attachments.extend(resolve_attachments);
+            attachments.push(depth_stencil);
+        },
+        false => {
+            attachments.extend(msaa_attachments);
+            attachments.push(depth_stencil);
+            attachments.extend(resolve_attachments);
+        }
+    }
+    attachments
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn integer_attachment_resolves_sample_zero() {
+        let modes = get_subpass_resolve_modes(Format::B8G8R8A8_UNORM, &[Format::R32_UINT, Format::R16G16B16A16_SFLOAT]);
+        assert_eq!(modes, vec![ResolveMode::Average, ResolveMode::SampleZero, ResolveMode::Average]);
+    }
+
+    #[test]
+    fn unsupported_resolve_mode_falls_back() {
+        assert_eq!(select_resolve_mode(ResolveMode::SampleZero, ResolveModes::SAMPLE_ZERO | ResolveModes::AVERAGE), ResolveMode::SampleZero);
+        assert_eq!(select_resolve_mode(ResolveMode::SampleZero, ResolveModes::AVERAGE), ResolveMode::Average);
+        assert_eq!(select_resolve_mode(ResolveMode::Min, ResolveModes::SAMPLE_ZERO | ResolveModes::MAX), ResolveMode::SampleZero);
+        assert_eq!(select_resolve_mode(ResolveMode::Average, ResolveModes::empty()), ResolveMode::SampleZero);
+    }
+
+    #[test]
+    fn two_color_attachments_give_two_subpass_references() {
+        let info = get_render_pass_create_info(Format::B8G8R8A8_UNORM, &[Format::R32_UINT], Format::D32_SFLOAT, SampleCount::Sample1);
+        let subpass = &info.subpasses[0];
+        let references: Vec<u32> = subpass.color_attachments.iter()
+            .map(|reference| reference.as_ref().unwrap().attachment)
+            .collect();
+        assert_eq!(references, vec![0, 1]);
+        assert!(subpass.resolve_attachments.is_empty());
+        assert_eq!(subpass.depth_stencil_attachment.as_ref().unwrap().attachment, 2);
+        assert_eq!(info.attachments.len(), 3);
+
+        let attachments = order_framebuffer_attachments(vec!["swapchain", "object id"], Vec::new(), "depth");
+        assert_eq!(attachments, vec!["swapchain", "object id", "depth"]);
+        assert_eq!(attachments.len(), info.attachments.len());
+    }
+
+    #[test]
+    fn multisampled_color_attachments_are_followed_by_resolve_attachments() {
+        let info = get_render_pass_create_info(Format::B8G8R8A8_UNORM, &[Format::R32_UINT], Format::D32_SFLOAT, SampleCount::Sample4);
+        let subpass = &info.subpasses[0];
+        assert_eq!(subpass.color_attachments.len(), 2);
+        let resolves: Vec<u32> = subpass.resolve_attachments.iter()
+            .map(|reference| reference.as_ref().unwrap().attachment)
+            .collect();
+        assert_eq!(resolves, vec![3, 4]);
+        assert_eq!(info.attachments.len(), 5);
+
+        let attachments = order_framebuffer_attachments(vec!["swapchain", "object id"], vec!["msaa swapchain", "msaa object id"], "depth");
+        assert_eq!(attachments, vec!["msaa swapchain", "msaa object id", "depth", "swapchain", "object id"]);
+    }
+
+
+    #[test]
+    fn retry_succeeds_after_two_failures() {
+        let mut calls = 0;
+        let result = retry(MAX_RECREATE_ATTEMPTS, || {
+            calls += 1;
+            if calls <= 2 { Err("swapchain out of date") } else { Ok(calls) }
+        });
+        assert_eq!(result, Ok(3));
+        assert_eq!(calls, 3);
+
+        // all attempts fail, so the last error is returned.
+        let mut calls = 0;
+        let result: Result<(), _> = retry(2, || {
+            calls += 1;
+            Err(calls)
+        });
+        assert_eq!(result, Err(2));
+    }
+}
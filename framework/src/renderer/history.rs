@@ -0,0 +1,159 @@
+use std::sync::Arc;
+use std::collections::VecDeque;
+
+use vulkano::command_buffer::{AutoCommandBufferBuilder, CopyImageInfo};
+use vulkano::command_buffer::allocator::CommandBufferAllocator;
+use vulkano::format::Format;
+use vulkano::image::{AttachmentImage, ImageAccess, ImageUsage, ImageViewType, ImageSubresourceRange, ImageAspects};
+use vulkano::image::view::{ImageView, ImageViewCreateInfo};
+use vulkano::memory::allocator::MemoryAllocator;
+use vulkano::sampler::ComponentMapping;
+
+use super::context::RenderContext;
+use crate::{err, error::RuntimeError};
+
+
+
+/// The color images of the previously rendered frames, retained for temporal effects.
+/// (e.g. temporal anti-aliasing or motion blur)
+#[derive(Debug)]
+pub struct RenderHistory {
+    format: Format,
+    images: VecDeque<(Arc<AttachmentImage>, Arc<ImageView<AttachmentImage>>)>,
+    num_retained: usize,
+    render_ctx: Arc<RenderContext>,
+}
+
+
+impl RenderHistory {
+    /// Create a new `RenderHistory` that retains the given number of frames.
+    /// 
+    /// # Runtime Errors
+    /// - Returns a runtime error message if the format cannot be sampled.
+    /// - Returns a runtime error message if history image creation fails.
+    /// - Returns a runtime error message if history image view creation fails.
+    /// 
+    /// # Panics
+    /// - Stop program execution if `num_frames` is zero.
+    /// 
+    pub fn new(
+        num_frames: usize,
+        width: u32,
+        height: u32,
+        format: Format,
+        render_ctx: Arc<RenderContext>
+    ) -> Result<Self, RuntimeError> {
+        assert!(num_frames > 0, "The number of history frames cannot be zero.");
+        if !render_ctx.supports_sampled(format) {
+            return Err(err!("Unsupported history image format: {:?}", format));
+        }
+
+        let images = (0..num_frames)
+            .map(|_| create_history_image(width, height, format, render_ctx.ref_memory_allocator()))
+            .collect::<Result<VecDeque<_>, _>>()?;
+
+        Ok(Self { format, images, num_retained: 0, render_ctx })
+    }
+
+
+    /// Recreate the history images with the given size. The retained frames are discarded.
+    /// 
+    /// # Runtime Errors
+    /// - Returns a runtime error message if history image creation fails.
+    /// - Returns a runtime error message if history image view creation fails.
+    /// 
+    pub fn recreate(&mut self, width: u32, height: u32) -> Result<(), RuntimeError> {
+        for image in self.images.iter_mut() {
+            *image = create_history_image(width, height, self.format, self.render_ctx.ref_memory_allocator())?;
+        }
+        self.num_retained = 0;
+
+        Ok(())
+    }
+
+
+    /// Record a copy of the rendered frame image into the history, replacing the oldest frame.
+    /// 
+    /// # Runtime Errors
+    /// - Returns a runtime error message if copy command recording fails.
+    /// 
+    pub fn record_copy<L, A: CommandBufferAllocator>(
+        &mut self,
+        src_image: Arc<dyn ImageAccess>,
+        command_buffer_builder: &mut AutoCommandBufferBuilder<L, A>
+    ) -> Result<(), RuntimeError> {
+        self.images.rotate_right(1);
+        let (dst_image, _) = self.images.front().unwrap();
+        command_buffer_builder
+            .copy_image(CopyImageInfo::images(src_image, dst_image.clone()))
+            .map_err(|e| err!("History image copy failed: {}", e.to_string()))?;
+        self.num_retained = self.images.len().min(self.num_retained + 1);
+
+        Ok(())
+    }
+
+
+    /// Get the number of frames the history can retain.
+    #[inline]
+    pub fn get_num_frames(&self) -> usize {
+        self.images.len()
+    }
+
+
+    /// Get the number of frames currently retained.
+    #[inline]
+    pub fn get_num_retained(&self) -> usize {
+        self.num_retained
+    }
+
+
+    /// Get the image view of the frame rendered `index + 1` frames ago. (reference)
+    /// If fewer frames are retained, the oldest retained frame is returned.
+    /// Returns `None` if no frame has been retained yet.
+    #[inline]
+    pub fn ref_frame(&self, index: usize) -> Option<&Arc<ImageView<AttachmentImage>>> {
+        if self.num_retained == 0 {
+            return None;
+        }
+        self.images.get(index.min(self.num_retained - 1)).map(|(_, view)| view)
+    }
+}
+
+
+/// Create a history image and view.
+/// 
+/// # Runtime Errors
+/// - Returns a runtime error message if history image creation fails.
+/// - Returns a runtime error message if history image view creation fails.
+/// 
+#[inline]
+fn create_history_image(
+    width: u32,
+    height: u32,
+    format: Format,
+    allocator: &impl MemoryAllocator
+) -> Result<(Arc<AttachmentImage>, Arc<ImageView<AttachmentImage>>), RuntimeError> {
+    let image = AttachmentImage::with_usage(
+        allocator,
+        [width, height],
+        format,
+        ImageUsage::TRANSFER_DST | ImageUsage::SAMPLED
+    ).map_err(|e| err!("Failed to create history image: {}", e.to_string()))?;
+
+    let view = ImageView::new(
+        image.clone(),
+        ImageViewCreateInfo {
+            view_type: ImageViewType::Dim2d,
+            format: Some(format),
+            component_mapping: ComponentMapping::identity(),
+            subresource_range: ImageSubresourceRange {
+                aspects: ImageAspects::COLOR,
+                mip_levels: (0..1),
+                array_layers: (0..1)
+            },
+            ..Default::default()
+        }
+    ).map_err(|e| err!("Failed to create history image view: {}", e.to_string()))?;
+
+    Ok((image, view))
+}
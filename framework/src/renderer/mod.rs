@@ -4,35 +4,62 @@ mod frame;
 mod context;
 mod swapchain;
 mod depth_stencil;
+mod color_attachment;
+mod history;
+mod capture;
+mod multiview;
+mod shadow;
+mod ssao;
+mod debounce;
+mod reload;
+mod token;
 
 use std::{fs, thread};
 use std::io::Read;
 use std::sync::{Arc, Mutex, MutexGuard, Once};
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-use vulkano::command_buffer::{PrimaryAutoCommandBuffer, AutoCommandBufferBuilder, RenderPassBeginInfo};
+use vulkano::command_buffer::{PrimaryAutoCommandBuffer, AutoCommandBufferBuilder, CommandBufferUsage, RenderPassBeginInfo};
+use vulkano::descriptor_set::{PersistentDescriptorSet, WriteDescriptorSet};
 use vulkano::command_buffer::allocator::{CommandBufferAlloc, CommandBufferAllocator};
 use vulkano::format::Format;
-use vulkano::pipeline::GraphicsPipeline;
+use vulkano::image::{AttachmentImage, SampleCount};
+use vulkano::image::view::ImageView;
+use vulkano::pipeline::{GraphicsPipeline, ComputePipeline, Pipeline, PipelineBindPoint};
 use vulkano::pipeline::cache::PipelineCache;
 use vulkano::pipeline::graphics::multisample::MultisampleState;
 use vulkano::pipeline::graphics::rasterization::RasterizationState;
 use vulkano::pipeline::graphics::render_pass::{PipelineRenderingCreateInfo, PipelineRenderPassType};
-use vulkano::pipeline::graphics::viewport::{ViewportState, Viewport};
+use vulkano::pipeline::graphics::viewport::{ViewportState, Viewport, Scissor};
 use vulkano::pipeline::graphics::{GraphicsPipelineBuilder, rasterization};
 use vulkano::pipeline::graphics::color_blend::ColorBlendState;
 use vulkano::pipeline::graphics::depth_stencil::DepthStencilState;
 use vulkano::pipeline::graphics::input_assembly::InputAssemblyState;
 use vulkano::pipeline::graphics::vertex_input::VertexInputState;
-use vulkano::render_pass::{Subpass, Framebuffer};
+use vulkano::render_pass::{Subpass, Framebuffer, ResolveMode};
 use vulkano::shader::{ShaderModule, EntryPoint, SpecializationConstants};
-use vulkano::swapchain::SwapchainAcquireFuture;
+use vulkano::swapchain::{SwapchainAcquireFuture, PresentMode, ColorSpace, RectangleLayer};
 
 use self::frame::RenderFrame;
+use crate::math::*;
 use crate::{err, error::RuntimeError};
 
-pub use self::platform::AppHandle;
+pub use self::platform::{AppHandle, DrawableSizeProvider, reconcile_drawable_size};
 pub use self::context::RenderContext;
+pub use self::multiview::RenderMultiview;
+pub use self::shadow::{CascadeData, CameraFrustum, CascadedShadowMap, MAX_CASCADES, compute_cascade_data};
+pub use self::swapchain::{RenderPresentMode, SRGB_TO_DISPLAY_P3};
+pub use self::ssao::*;
+pub use self::debounce::*;
+pub use self::reload::*;
+pub use self::token::FrameToken;
+
+/// The minimum render scale.
+pub const MIN_RENDER_SCALE: f32 = 0.25;
+
+/// The maximum render scale.
+pub const MAX_RENDER_SCALE: f32 = 2.0;
 
 
 
@@ -43,29 +70,50 @@ pub struct Renderer {
     handle: AppHandle,
     assets_dir: PathBuf,
     scale_factor: f32,
+    render_scale: f32,
     screen_size: (u32, u32),
     viewer_area: (i32, i32, i32, i32),
+    present_region: Option<Scissor>,
+    resize_debounce: ResizeDebounce,
     
     render_ctx: Arc<RenderContext>,
     render_frame: Arc<Mutex<RenderFrame>>,
     pipeline_cache: Arc<PipelineCache>,
+    shader_reloads: ShaderReloads<Arc<ShaderModule>>,
+
+    frame_id: u64,
+    wide_color: bool,
 }
 
 impl Renderer {
+    /// Create a new `Renderer`.
+    /// `render_scale` is clamped to the range from `MIN_RENDER_SCALE` to `MAX_RENDER_SCALE`.
+    /// `samples` is clamped to the sample counts supported by the device. See `RenderFrame::new`.
+    /// If `wide_color` is `true`, the Display-P3 color space is used when the surface supports it.
     pub fn new(
         handle: AppHandle, 
         assets_dir: &Path,
         scale_factor: f32,
+        render_scale: f32,
         screen_size: (u32, u32),
         viewer_area: (i32, i32, i32, i32),
+        color_attachment_formats: &[Format],
+        samples: SampleCount,
+        wide_color: bool,
     ) -> Result<Self, RuntimeError> {
         // create a new `RenderContext`
         let render_ctx = RenderContext::new(&handle)?;
 
         // create a new `RenderFrame`
+        let render_scale = render_scale.clamp(MIN_RENDER_SCALE, MAX_RENDER_SCALE);
+        let (width, height) = reconcile_drawable_size(&handle, screen_size, scale_factor);
         let render_frame = RenderFrame::new(
-            (screen_size.0 as f32 * scale_factor) as u32, 
-            (screen_size.1 as f32 * scale_factor) as u32, 
+            width, 
+            height, 
+            render_scale,
+            color_attachment_formats,
+            samples,
+            wide_color,
             &render_ctx
         )?;
 
@@ -85,11 +133,17 @@ impl Renderer {
             handle,
             assets_dir: assets_dir.to_path_buf(),
             scale_factor,
+            render_scale,
             screen_size,
             viewer_area,
+            present_region: None,
+            resize_debounce: ResizeDebounce::default(),
             render_ctx,
             render_frame,
             pipeline_cache,
+            shader_reloads: ShaderReloads::new(),
+            frame_id: 0,
+            wide_color,
         })
     }
 
@@ -99,6 +153,12 @@ impl Renderer {
         self.num_threads
     }
 
+    #[inline]
+    pub fn get_scale_factor(&self) -> f32 {
+        self.scale_factor
+    }
+
+
     #[inline]
     pub fn get_screen_size(&self) -> (u32, u32) {
         (
@@ -107,11 +167,126 @@ impl Renderer {
         )
     }
 
+    /// Get the size of the drawable the swapchain presents to. (pixel)
+    /// The drawable size of the Metal layer is preferred over `screen_size * scale_factor`. See `reconcile_drawable_size`.
+    #[inline]
+    pub fn get_drawable_size(&self) -> (u32, u32) {
+        reconcile_drawable_size(&self.handle, self.screen_size, self.scale_factor)
+    }
+
+    /// Request a new screen size. (iOS logical points)
+    /// The swapchain is recreated once the size has stayed the same for the resize debounce window.
+    #[inline]
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.resize_debounce.request((width, height));
+    }
+
+    /// Set the number of frames the requested screen size must stay the same before the swapchain is recreated.
+    #[inline]
+    pub fn set_resize_debounce_frames(&mut self, num_frames: u32) {
+        self.resize_debounce.set_num_frames(num_frames);
+    }
+
+    #[inline]
+    pub fn get_resize_debounce_frames(&self) -> u32 {
+        self.resize_debounce.get_num_frames()
+    }
+
+    /// Set the render scale applied on top of the scale factor.
+    /// The value is clamped to the range from `MIN_RENDER_SCALE` to `MAX_RENDER_SCALE`,
+    /// and the swapchain is recreated on the next frame.
+    pub fn set_render_scale(&mut self, render_scale: f32) {
+        self.render_scale = render_scale.clamp(MIN_RENDER_SCALE, MAX_RENDER_SCALE);
+        self.render_frame.lock().unwrap().set_render_scale(self.render_scale);
+    }
+
+    #[inline]
+    pub fn get_render_scale(&self) -> f32 {
+        self.render_scale
+    }
+
+    /// Get the size of the render target. (pixel)
+    #[inline]
+    pub fn get_render_size(&self) -> (u32, u32) {
+        let extent = self.render_frame.lock().unwrap().get_image_extent();
+        (extent[0], extent[1])
+    }
+
+    /// Get the format of the swapchain images.
+    /// Pipelines that draw to the swapchain images directly must use this format.
+    #[inline]
+    pub fn get_swapchain_format(&self) -> Format {
+        self.render_frame.lock().unwrap().get_swapchain_format()
+    }
+
+    /// Get the index of the swapchain image acquired by the last `wait_for_next_frame`.
+    /// Resources written every frame can be indexed with it to avoid overwriting data still in use.
+    #[inline]
+    pub fn get_current_frame(&self) -> u32 {
+        self.render_frame.lock().unwrap().get_current_frame()
+    }
+
+    /// Get the number of swapchain images.
+    /// Every value of `get_current_frame` is less than this number.
+    #[inline]
+    pub fn get_max_frame_in_flight(&self) -> u32 {
+        self.render_frame.lock().unwrap().get_max_frame_in_flight()
+    }
+
+    /// Constrain rendering to the given region of the render target. (pixel)
+    /// The rest of the render target is cleared to transparent. (e.g. a preview over native UI)
+    /// `None` renders to the whole render target.
+    /// 
+    /// # Runtime Errors
+    /// - Returns a runtime error message if the width or height of the region is zero.
+    /// - Returns a runtime error message if the region does not fit in the `i32` range required by Vulkan.
+    /// 
+    pub fn set_present_region(&mut self, region: Option<Scissor>) -> Result<(), RuntimeError> {
+        if let Some(region) = region {
+            if region.dimensions[0] == 0 || region.dimensions[1] == 0 {
+                return Err(err!("The present region cannot be empty. ({:?})", region));
+            }
+            let fits = (0..2).all(|axis| region.origin[axis].checked_add(region.dimensions[axis])
+                .is_some_and(|end| end <= i32::MAX as u32));
+            if !fits {
+                return Err(err!("The present region is out of range. ({:?})", region));
+            }
+        }
+        self.present_region = region;
+        Ok(())
+    }
+
+    #[inline]
+    pub fn get_present_region(&self) -> Option<Scissor> {
+        self.present_region
+    }
+
+    /// Get the region to draw to. (pixel) See `clip_present_region`.
+    /// Returns `None` if the present region lies outside the render target, in which case nothing is drawn.
+    #[inline]
+    pub fn get_present_scissor(&self) -> Option<Scissor> {
+        clip_present_region(self.present_region, self.get_render_size())
+    }
+
+    /// Get the viewport that covers the region to draw to. See `get_present_scissor`.
+    #[inline]
+    pub fn get_present_viewport(&self) -> Option<Viewport> {
+        self.get_present_scissor().map(|scissor| scissor_to_viewport(&scissor))
+    }
+
     #[inline]
     pub fn get_viewer_area(&self) -> (i32, i32, i32, i32) {
         self.viewer_area
     }
 
+    /// Convert a touch point (iOS logical points) into physical pixel coordinates
+    /// relative to the origin of the content region inside the viewer insets.
+    /// Returns `None` if the touch falls outside the content region.
+    #[inline]
+    pub fn touch_to_content(&self, point_x: f32, point_y: f32) -> Option<(f32, f32)> {
+        point_to_content((point_x, point_y), self.screen_size, self.viewer_area, self.scale_factor)
+    }
+
     #[inline]
     pub fn ref_assets_dir(&self) -> &Path {
         &self.assets_dir
@@ -124,29 +299,400 @@ impl Renderer {
     }
 
 
+    /// Rebuild the render context, render frame and pipeline cache from the stored `AppHandle`.
+    /// (e.g. after the device is lost)
+    /// 
+    /// The sample count, present mode, acquire timeout and frame history are carried over.
+    /// Reloaded shader modules are discarded, so the scene must recreate its GPU resources afterwards.
+    /// 
+    /// # Runtime Errors
+    /// - Returns a runtime error message if render context creation fails.
+    /// - Returns a runtime error message if render frame creation fails.
+    /// - Returns a runtime error message if pipeline cache creation fails.
+    /// - Returns a runtime error message if the carried over settings cannot be applied.
+    /// 
+    pub fn recreate(&mut self) -> Result<(), RuntimeError> {
+        let (color_attachment_formats, samples, present_mode, acquire_timeout, history_length) = {
+            let render_frame = self.render_frame.lock().unwrap();
+            (
+                render_frame.ref_color_attachments().iter().map(|attachment| *attachment.ref_format()).collect::<Vec<_>>(),
+                render_frame.get_samples(),
+                render_frame.get_present_mode(),
+                render_frame.get_acquire_timeout(),
+                render_frame.get_history_length(),
+            )
+        };
+
+        // the old device may be lost, in which case there is nothing left to wait for.
+        unsafe { self.render_ctx.ref_device().wait_idle().ok() };
+
+        let render_ctx = RenderContext::new(&self.handle)?;
+        let (width, height) = self.get_drawable_size();
+        let render_frame = RenderFrame::new(
+            width,
+            height,
+            self.render_scale,
+            &color_attachment_formats,
+            samples,
+            self.wide_color,
+            &render_ctx
+        )?;
+        let pipeline_cache = PipelineCache::empty(
+            render_ctx.ref_device().clone()
+        ).map_err(|e| err!("Pipeline creation failed: {}", e.to_string()))?;
+
+        {
+            let mut frame = render_frame.lock().unwrap();
+            frame.set_present_mode(present_mode)?;
+            frame.set_acquire_timeout(acquire_timeout);
+            frame.set_history_length(&render_ctx, history_length)?;
+        }
+
+        self.render_ctx = render_ctx;
+        self.render_frame = render_frame;
+        self.pipeline_cache = pipeline_cache;
+        self.shader_reloads.clear();
+
+        #[cfg(feature = "monitor")]
+        println!("<monitor> renderer recreated.");
+
+        Ok(())
+    }
+
+
+    /// Re-read a SPIR-V file and replace the shader module loaded from it.
+    /// A relative path is resolved against the assets directory.
+    /// The scene can collect the reloaded paths with `take_reloaded_shaders` to rebuild its pipelines.
+    /// (see `SceneNode::reload_shaders`)
+    /// 
+    /// # Runtime Errors
+    /// - Returns a runtime error message if reading the file or creating the shader module fails.
+    ///   The previously loaded module is kept in that case.
+    /// 
+    pub fn reload_shader(&mut self, path: &Path) -> Result<Arc<ShaderModule>, RuntimeError> {
+        let render_ctx = self.render_ctx.clone();
+        let module = self.shader_reloads.reload(
+            self.assets_dir.join(path), 
+            |path| load_from_spv_file(path, &render_ctx)
+        )?;
+
+        #[cfg(feature = "monitor")]
+        println!("<monitor> shader reloaded. ({:?})", path);
+
+        Ok(module)
+    }
+
+
+    /// Get the most recently reloaded shader module of the given path.
+    /// A relative path is resolved against the assets directory.
+    #[inline]
+    pub fn get_reloaded_shader(&self, path: &Path) -> Option<Arc<ShaderModule>> {
+        self.shader_reloads.get(&self.assets_dir.join(path))
+    }
+
+
+    /// Take the paths of the shaders reloaded since the last call. (resolved against the assets directory)
+    #[inline]
+    pub fn take_reloaded_shaders(&mut self) -> Vec<PathBuf> {
+        self.shader_reloads.take_reloaded()
+    }
+
+
+    /// Wait for the next frame image. See `RenderFrame::wait_for_next_frame`.
+    /// Returns the `FrameToken` of the new frame, which must be submitted in the same frame.
+    /// Acquiring a new frame invalidates the tokens of the earlier frames.
     #[inline]
-    pub fn wait_for_next_frame(&mut self) -> Result<Option<(SwapchainAcquireFuture, Arc<Framebuffer>)>, RuntimeError> {
-        self.render_frame.lock().unwrap().wait_for_next_frame(
+    pub fn wait_for_next_frame(&mut self) -> Result<Option<FrameToken>, RuntimeError> {
+        self.frame_id = self.frame_id.wrapping_add(1);
+        let mut render_frame = self.render_frame.lock().unwrap();
+        if let Some(screen_size) = self.resize_debounce.advance() {
+            if self.screen_size != screen_size {
+                self.screen_size = screen_size;
+                render_frame.request_recreate();
+            }
+        }
+
+        let (width, height) = self.get_drawable_size();
+        let frame_id = self.frame_id;
+        Ok(render_frame.wait_for_next_frame(
             self.scale_factor, 
-            self.screen_size.0, 
-            self.screen_size.1
-        )
+            width, 
+            height
+        )?.map(|(acquire_future, framebuffer)| FrameToken::new(frame_id, acquire_future, framebuffer)))
     }
 
 
+    /// Submit commands to the queue and present the frame of the token.
+    /// 
+    /// # Runtime Errors
+    /// - Returns a runtime error message if the token is from a different frame.
+    /// - Returns a runtime error message if command buffer execution or presentation fails.
+    /// 
     #[inline]
     pub fn queue_submit_and_present<A: CommandBufferAlloc>(
         &mut self,
-        acquire_future: SwapchainAcquireFuture,
+        token: FrameToken,
         command_buffer: PrimaryAutoCommandBuffer<A>
     ) -> Result<(), RuntimeError> {
+        let acquire_future = token.into_acquire_future(self.frame_id)?;
+        self.render_frame.lock().unwrap().queue_submit_and_present(
+            &self.render_ctx, 
+            acquire_future,
+            command_buffer,
+            &[]
+        )
+    }
+
+    /// Submit commands to the queue and present only the given regions of the screen. (pixel)
+    /// The whole screen is presented if the device does not support incremental present.
+    #[inline]
+    pub fn queue_submit_and_present_regions<A: CommandBufferAlloc>(
+        &mut self,
+        token: FrameToken,
+        command_buffer: PrimaryAutoCommandBuffer<A>,
+        present_regions: &[RectangleLayer],
+    ) -> Result<(), RuntimeError> {
+        let acquire_future = token.into_acquire_future(self.frame_id)?;
         self.render_frame.lock().unwrap().queue_submit_and_present(
             &self.render_ctx, 
             acquire_future,
-            command_buffer
+            command_buffer,
+            present_regions
         )
     }
 
+    /// Set the timeout for acquiring the next frame image.
+    /// The frame is skipped if it times out. `None` waits indefinitely.
+    #[inline]
+    pub fn set_acquire_timeout(&mut self, timeout: Option<Duration>) {
+        self.render_frame.lock().unwrap().set_acquire_timeout(timeout)
+    }
+
+    #[inline]
+    pub fn get_acquire_timeout(&self) -> Option<Duration> {
+        self.render_frame.lock().unwrap().get_acquire_timeout()
+    }
+
+    /// Returns `true` if compute shaders can write to the swapchain images.
+    /// If not, the final image must be drawn with the render pass.
+    #[inline]
+    pub fn supports_storage_present(&self) -> bool {
+        self.render_frame.lock().unwrap().supports_storage_present()
+    }
+
+    /// Dispatch a compute shader that writes the final image to the current swapchain image,
+    /// then present it without a render pass.
+    /// 
+    /// The swapchain image is bound as a storage image to `binding` of descriptor set 0,
+    /// and enough work groups of `local_size` are dispatched to cover the whole image.
+    /// 
+    /// # Runtime Errors
+    /// - Returns a runtime error message if the token is from a different frame.
+    /// - Returns a runtime error message if the integrated queue does not support compute operations.
+    /// - Returns a runtime error message if the swapchain images do not support storage usage.
+    /// - Returns a runtime error message if descriptor set creation fails.
+    /// - Returns a runtime error message if command buffer recording fails.
+    /// - Returns a runtime error message if command buffer execution fails.
+    /// - Returns a runtime error message if presentation fails.
+    /// 
+    /// # Panics
+    /// - Stop program execution if `local_size` has a zero component.
+    /// 
+    pub fn dispatch_and_present(
+        &mut self,
+        token: FrameToken,
+        pipeline: &Arc<ComputePipeline>,
+        binding: u32,
+        local_size: [u32; 2],
+    ) -> Result<(), RuntimeError> {
+        assert!(local_size[0] > 0 && local_size[1] > 0, "The local size cannot be zero.");
+        token::validate_frame_id(token.get_frame_id(), self.frame_id)?;
+        self.render_ctx.require_compute()?;
+        if !self.supports_storage_present() {
+            return Err(err!("The swapchain images do not support storage usage."));
+        }
+
+        let view = self.render_frame.lock().unwrap().ref_current_swapchain_image_view().clone();
+        let extent = view.image().swapchain().image_extent();
+
+        let layout = pipeline.layout().set_layouts().get(0).unwrap().clone();
+        let descriptor_set = PersistentDescriptorSet::new(
+            self.render_ctx.ref_descriptor_allocator(),
+            layout,
+            [WriteDescriptorSet::image_view(binding, view)]
+        ).map_err(|e| err!("Descriptor set creation failed: {}", e.to_string()))?;
+
+        let allocator = self.render_ctx.get_command_buffer_allocator();
+        let mut command_buffer_builder = AutoCommandBufferBuilder::primary(
+            &allocator, 
+            self.render_ctx.get_queue_fmaily_index(), 
+            CommandBufferUsage::OneTimeSubmit
+        ).map_err(|e| err!("Command buffer begining failed: {}", e.to_string()))?;
+
+        command_buffer_builder
+            .bind_pipeline_compute(pipeline.clone())
+            .bind_descriptor_sets(
+                PipelineBindPoint::Compute, 
+                pipeline.layout().clone(), 
+                0, 
+                descriptor_set
+            )
+            .dispatch([
+                (extent[0] + local_size[0] - 1) / local_size[0],
+                (extent[1] + local_size[1] - 1) / local_size[1],
+                1
+            ])
+            .map_err(|e| err!("Compute dispatch failed: {}", e.to_string()))?;
+
+        let command_buffer = command_buffer_builder.build()
+            .map_err(|e| err!("Primary command buffer building failed: {}", e.to_string()))?;
+
+        self.queue_submit_and_present(token, command_buffer)
+    }
+
+    /// Change the present mode of the swapchain. (e.g. to toggle vsync)
+    /// The change takes effect when the swapchain is recreated on the next `wait_for_next_frame`.
+    /// If the present mode is not supported by the surface, `RenderPresentMode::Fifo` is used instead.
+    /// Returns the present mode selected.
+    /// 
+    /// # Runtime Errors
+    /// - Returns a runtime error message if getting the surface present modes fails.
+    /// 
+    #[inline]
+    pub fn set_present_mode(&mut self, present_mode: RenderPresentMode) -> Result<RenderPresentMode, RuntimeError> {
+        let present_mode = self.render_frame.lock().unwrap().set_present_mode(present_mode.into_present_mode())?;
+        Ok(RenderPresentMode::from_present_mode(present_mode).unwrap_or(RenderPresentMode::Fifo))
+    }
+
+    /// Get the present mode of the current swapchain.
+    #[inline]
+    pub fn get_present_mode(&self) -> RenderPresentMode {
+        let present_mode = self.render_frame.lock().unwrap().get_present_mode();
+        RenderPresentMode::from_present_mode(present_mode).unwrap_or(RenderPresentMode::Fifo)
+    }
+
+    /// Capture the next submitted frame. See `RenderFrame::request_capture`.
+    /// 
+    /// # Runtime Errors
+    /// - Returns a runtime error message if the swapchain images cannot be copied from.
+    /// - Returns a runtime error message if the swapchain format is not an 8-bit RGBA or BGRA format.
+    /// 
+    #[inline]
+    pub fn request_capture(&mut self) -> Result<(), RuntimeError> {
+        self.render_frame.lock().unwrap().request_capture()
+    }
+
+    /// Get the frame captured by the last `request_capture`. See `RenderFrame::capture_frame`.
+    /// Returns the width, height and tightly-packed RGBA8 pixels of the frame.
+    /// 
+    /// # Runtime Errors
+    /// - Returns a runtime error message if no frame was captured since the last call.
+    /// - Returns a runtime error message if waiting for the frame fails.
+    /// - Returns a runtime error message if the readback buffer cannot be read.
+    /// 
+    #[inline]
+    pub fn capture_frame(&mut self) -> Result<(u32, u32, Vec<u8>), RuntimeError> {
+        self.render_frame.lock().unwrap().capture_frame(&self.render_ctx)
+    }
+
+    /// Set the number of previous frames retained for temporal effects. `0` disables the history.
+    /// 
+    /// # Runtime Errors
+    /// - Returns a runtime error message if the swapchain images cannot be copied from.
+    /// - Returns a runtime error message if history image creation fails.
+    /// - Returns a runtime error message if history image view creation fails.
+    /// 
+    #[inline]
+    pub fn set_history_length(&mut self, num_frames: usize) -> Result<(), RuntimeError> {
+        self.render_frame.lock().unwrap().set_history_length(&self.render_ctx, num_frames)
+    }
+
+    #[inline]
+    pub fn get_history_length(&self) -> usize {
+        self.render_frame.lock().unwrap().get_history_length()
+    }
+
+    /// Get the color image of the frame rendered `index + 1` frames ago, to be bound as a sampled image.
+    /// If fewer frames are retained, the oldest retained frame is returned.
+    /// Returns `None` on the first frame, in which case the current frame should be used instead.
+    #[inline]
+    pub fn get_history_frame(&self, index: usize) -> Option<Arc<ImageView<AttachmentImage>>> {
+        self.render_frame.lock().unwrap().get_history_frame(index)
+    }
+
+    /// Get the multisample resolve mode the render pass uses for the color attachment with the given index.
+    /// See `RenderFrame::get_resolve_mode`.
+    #[inline]
+    pub fn get_resolve_mode(&self, index: usize) -> ResolveMode {
+        self.render_frame.lock().unwrap().get_resolve_mode(index)
+    }
+
+    /// Get the number of color attachments of the render pass, including the swapchain image.
+    #[inline]
+    pub fn get_num_color_attachments(&self) -> usize {
+        self.render_frame.lock().unwrap().get_num_color_attachments()
+    }
+
+    /// Get the number of attachments of the render pass, including the depth-stencil and the resolve attachments.
+    /// A clear value is needed for each of them when the render pass begins.
+    #[inline]
+    pub fn get_num_attachments(&self) -> usize {
+        self.render_frame.lock().unwrap().get_num_attachments()
+    }
+
+    /// Get the sample count of the render pass. Graphics pipelines must use the same rasterization sample count.
+    #[inline]
+    pub fn get_sample_count(&self) -> SampleCount {
+        self.render_frame.lock().unwrap().get_samples()
+    }
+
+    /// Get the color space of the swapchain images.
+    /// It is `ColorSpace::DisplayP3NonLinear` if wide color was requested and is supported by the surface.
+    #[inline]
+    pub fn get_color_space(&self) -> ColorSpace {
+        self.render_frame.lock().unwrap().get_color_space()
+    }
+
+    /// Create an offscreen multiview render target with the render size.
+    /// See `RenderMultiview::new`.
+    /// 
+    /// # Runtime Errors
+    /// - Returns a runtime error message if the device does not enable the multiview feature.
+    /// - Returns a runtime error message if the view mask is invalid.
+    /// - Returns a runtime error message if render target creation fails.
+    /// 
+    #[inline]
+    pub fn create_multiview(&self, view_mask: u32, color_format: Format) -> Result<RenderMultiview, RuntimeError> {
+        let (width, height) = self.get_render_size();
+        RenderMultiview::new(width, height, view_mask, color_format, self.render_ctx.clone())
+    }
+
+    /// Create a cascaded shadow map with square cascades of the given size.
+    /// See `CascadedShadowMap::new`.
+    /// 
+    /// # Runtime Errors
+    /// - Returns a runtime error message if the size or the number of cascades is invalid.
+    /// - Returns a runtime error message if there is no depth format that can be rendered and sampled on the device.
+    /// - Returns a runtime error message if render target creation fails.
+    /// 
+    #[inline]
+    pub fn create_cascaded_shadow_map(&self, size: u32, num_cascades: usize) -> Result<CascadedShadowMap, RuntimeError> {
+        CascadedShadowMap::new(size, num_cascades, self.render_ctx.clone())
+    }
+
+    /// Create the offscreen targets of the screen-space ambient occlusion with the render size.
+    /// See `SsaoTargets::new`.
+    /// 
+    /// # Runtime Errors
+    /// - Returns a runtime error message if the target formats are not supported by the device.
+    /// - Returns a runtime error message if render target creation fails.
+    /// 
+    #[inline]
+    pub fn create_ssao_targets(&self) -> Result<SsaoTargets, RuntimeError> {
+        let (width, height) = self.get_render_size();
+        SsaoTargets::new(width, height, self.render_ctx.clone())
+    }
+
     #[inline]
     pub fn ref_pipeline_cache(&self) -> &Arc<PipelineCache> {
         &self.pipeline_cache
@@ -212,8 +758,121 @@ pub fn load_from_spv_file(
         .map_err(|e| err!("Failed to read file: {}", e.to_string()))?;
 
     // create shader module.
-    unsafe { ShaderModule::from_bytes(
+    let words = read_spirv_words(&buf)?;
+    unsafe { ShaderModule::from_words(
         render_ctx.ref_device().clone(), 
-        &buf
+        &words
     )}.map_err(|e| err!("Shader module creation failed: {}", e.to_string()))
 }
+
+
+/// Convert a point on the screen (logical points) into physical pixel coordinates
+/// relative to the origin of the content region inside the viewer insets. `(top, left, bottom, right)`
+/// Returns `None` if the point falls outside the content region.
+#[inline]
+fn point_to_content(point: (f32, f32), screen_size: (u32, u32), viewer_area: (i32, i32, i32, i32), scale_factor: f32) -> Option<(f32, f32)> {
+    let (top, left, bottom, right) = viewer_area;
+    let min_x = left as f32;
+    let min_y = top as f32;
+    let max_x = screen_size.0 as f32 - right as f32;
+    let max_y = screen_size.1 as f32 - bottom as f32;
+    if point.0 < min_x || point.0 >= max_x || point.1 < min_y || point.1 >= max_y {
+        return None;
+    }
+
+    Some((
+        (point.0 - min_x) * scale_factor,
+        (point.1 - min_y) * scale_factor,
+    ))
+}
+
+
+/// Clip the present region to the render target of the given size. (pixel)
+/// Returns the whole render target if there is no present region,
+/// and `None` if the clipped region is empty. (e.g. the render target shrank on rotation)
+#[inline]
+fn clip_present_region(region: Option<Scissor>, render_size: (u32, u32)) -> Option<Scissor> {
+    let (width, height) = render_size;
+    let region = match region {
+        Some(region) => region,
+        None => return Some(Scissor { origin: [0, 0], dimensions: [width, height] }),
+    };
+
+    let origin = [region.origin[0].min(width), region.origin[1].min(height)];
+    let dimensions = [
+        region.dimensions[0].min(width - origin[0]),
+        region.dimensions[1].min(height - origin[1]),
+    ];
+    match dimensions[0] == 0 || dimensions[1] == 0 {
+        true => None,
+        false => Some(Scissor { origin, dimensions }),
+    }
+}
+
+
+/// Get the viewport that covers the scissor.
+#[inline]
+fn scissor_to_viewport(scissor: &Scissor) -> Viewport {
+    Viewport {
+        origin: [scissor.origin[0] as f32, scissor.origin[1] as f32],
+        dimensions: [scissor.dimensions[0] as f32, scissor.dimensions[1] as f32],
+        depth_range: (0.0..1.0)
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn top_left_quarter_region_limits_viewport_and_scissor() {
+        let quarter = Scissor { origin: [0, 0], dimensions: [400, 300] };
+        let scissor = clip_present_region(Some(quarter), (800, 600)).unwrap();
+        assert_eq!(scissor, quarter);
+
+        let viewport = scissor_to_viewport(&scissor);
+        assert_eq!(viewport.origin, [0.0, 0.0]);
+        assert_eq!(viewport.dimensions, [400.0, 300.0]);
+    }
+
+    #[test]
+    fn no_region_covers_the_whole_render_target() {
+        let scissor = clip_present_region(None, (800, 600)).unwrap();
+        assert_eq!(scissor, Scissor { origin: [0, 0], dimensions: [800, 600] });
+    }
+
+    #[test]
+    fn region_is_clipped_to_the_render_target() {
+        let region = Scissor { origin: [600, 500], dimensions: [400, 300] };
+        assert_eq!(
+            clip_present_region(Some(region), (800, 600)),
+            Some(Scissor { origin: [600, 500], dimensions: [200, 100] })
+        );
+    }
+
+    #[test]
+    fn region_outside_the_render_target_is_empty() {
+        let region = Scissor { origin: [800, 0], dimensions: [100, 100] };
+        assert_eq!(clip_present_region(Some(region), (800, 600)), None);
+        let region = Scissor { origin: [0, 700], dimensions: [100, 100] };
+        assert_eq!(clip_present_region(Some(region), (800, 600)), None);
+    }
+
+    #[test]
+    fn inset_corner_is_the_content_origin() {
+        // a 390x844 point screen with the notch and home indicator insets, at 3x.
+        let (screen_size, viewer_area, scale_factor) = ((390, 844), (47, 0, 34, 0), 3.0);
+        assert_eq!(point_to_content((0.0, 47.0), screen_size, viewer_area, scale_factor), Some((0.0, 0.0)));
+        assert_eq!(point_to_content((10.0, 57.0), screen_size, viewer_area, scale_factor), Some((30.0, 30.0)));
+    }
+
+    #[test]
+    fn touch_inside_the_inset_is_outside_the_content() {
+        let (screen_size, viewer_area, scale_factor) = ((390, 844), (47, 0, 34, 0), 3.0);
+        assert_eq!(point_to_content((100.0, 20.0), screen_size, viewer_area, scale_factor), None);
+        assert_eq!(point_to_content((100.0, 820.0), screen_size, viewer_area, scale_factor), None);
+        assert_eq!(point_to_content((390.0, 400.0), screen_size, viewer_area, scale_factor), None);
+    }
+}
@@ -0,0 +1,359 @@
+use std::sync::Arc;
+
+use vulkano::format::Format;
+use vulkano::image::{StorageImage, ImageDimensions, ImageCreateFlags, ImageUsage, ImageLayout, SampleCount, ImageViewType, ImageSubresourceRange, ImageAspects};
+use vulkano::image::view::{ImageView, ImageViewCreateInfo};
+use vulkano::pipeline::graphics::render_pass::PipelineRenderPassType;
+use vulkano::render_pass::{Subpass, Framebuffer, RenderPass, RenderPassCreateInfo, AttachmentDescription, LoadOp, StoreOp, SubpassDescription, AttachmentReference, FramebufferCreateInfo};
+use vulkano::sampler::ComponentMapping;
+
+use super::context::RenderContext;
+use super::depth_stencil::get_depth_stencil_format;
+use crate::{err, error::RuntimeError};
+
+
+
+/// An offscreen render target that renders to several views in a single draw using multiview.
+/// (e.g. the left and right eyes of a stereo display)
+/// 
+/// Each bit of the view mask selects a layer of the color and depth-stencil images.
+/// Shaders select the per-view data with `gl_ViewIndex`.
+#[derive(Debug)]
+pub struct RenderMultiview {
+    view_mask: u32,
+    color_format: Format,
+    depth_stencil_format: Format,
+    color_view: Arc<ImageView<StorageImage>>,
+    depth_stencil_view: Arc<ImageView<StorageImage>>,
+    render_pass: Arc<RenderPass>,
+    framebuffer: Arc<Framebuffer>,
+    render_ctx: Arc<RenderContext>,
+}
+
+
+impl RenderMultiview {
+    /// Create a new `RenderMultiview`.
+    /// 
+    /// # Runtime Errors
+    /// - Returns a runtime error message if the device does not enable the multiview feature.
+    /// - Returns a runtime error message if the view mask is zero.
+    /// - Returns a runtime error message if the view mask exceeds the maximum number of views.
+    /// - Returns a runtime error message if the format cannot be used as a color attachment.
+    /// - Returns a runtime error message if there is no depth-stencil format supported by the device.
+    /// - Returns a runtime error message if image creation fails.
+    /// - Returns a runtime error message if image view creation fails.
+    /// - Returns a runtime error message if render pass creation fails.
+    /// - Returns a runtime error message if framebuffer creation fails.
+    /// 
+    pub fn new(
+        width: u32,
+        height: u32,
+        view_mask: u32,
+        color_format: Format,
+        render_ctx: Arc<RenderContext>
+    ) -> Result<Self, RuntimeError> {
+        if !render_ctx.ref_device_enabled_features().multiview {
+            return Err(err!("The multiview feature is not enabled on the device."));
+        }
+
+        if view_mask == 0 {
+            return Err(err!("The view mask cannot be zero."));
+        }
+
+        let max_views = render_ctx.ref_device().physical_device().properties().max_multiview_view_count.unwrap_or(0);
+        if get_num_views(view_mask) > max_views {
+            return Err(err!("The view mask exceeds the maximum number of views. (view mask: {:#b}, max views: {})", view_mask, max_views));
+        }
+
+        if !render_ctx.supports_color_attachment(color_format) {
+            return Err(err!("Unsupported color attachment format: {:?}", color_format));
+        }
+
+        let depth_stencil_format = match get_depth_stencil_format(&render_ctx) {
+            Some(format) => format,
+            None => return Err(err!("No suitable depth-stencil format found.")),
+        };
+
+        let render_pass = create_multiview_render_pass(&render_ctx, view_mask, color_format, depth_stencil_format)?;
+        let (color_view, depth_stencil_view, framebuffer) = create_multiview_framebuffer(
+            width,
+            height,
+            view_mask,
+            color_format,
+            depth_stencil_format,
+            &render_pass,
+            &render_ctx
+        )?;
+
+        Ok(Self {
+            view_mask,
+            color_format,
+            depth_stencil_format,
+            color_view,
+            depth_stencil_view,
+            render_pass,
+            framebuffer,
+            render_ctx,
+        })
+    }
+
+
+    /// Recreate the images and framebuffer with the given size.
+    /// 
+    /// # Runtime Errors
+    /// - Returns a runtime error message if image creation fails.
+    /// - Returns a runtime error message if image view creation fails.
+    /// - Returns a runtime error message if framebuffer creation fails.
+    /// 
+    pub fn recreate(&mut self, width: u32, height: u32) -> Result<(), RuntimeError> {
+        let (color_view, depth_stencil_view, framebuffer) = create_multiview_framebuffer(
+            width,
+            height,
+            self.view_mask,
+            self.color_format,
+            self.depth_stencil_format,
+            &self.render_pass,
+            &self.render_ctx
+        )?;
+
+        self.color_view = color_view;
+        self.depth_stencil_view = depth_stencil_view;
+        self.framebuffer = framebuffer;
+
+        Ok(())
+    }
+
+
+    #[inline]
+    pub fn get_view_mask(&self) -> u32 {
+        self.view_mask
+    }
+
+
+    /// Get the number of views rendered by a single draw.
+    #[inline]
+    pub fn get_num_views(&self) -> u32 {
+        get_num_views(self.view_mask)
+    }
+
+
+    #[inline]
+    pub fn ref_render_pass(&self) -> &Arc<RenderPass> {
+        &self.render_pass
+    }
+
+
+    /// Get the render pass type for graphics pipelines that draw to this target.
+    #[inline]
+    pub fn pipeline_render_pass_type(&self) -> PipelineRenderPassType {
+        PipelineRenderPassType::BeginRenderPass(Subpass::from(self.render_pass.clone(), 0).unwrap())
+    }
+
+
+    #[inline]
+    pub fn ref_framebuffer(&self) -> &Arc<Framebuffer> {
+        &self.framebuffer
+    }
+
+
+    /// Get the color image view. It has one layer per view and can be sampled after the render pass. (reference)
+    #[inline]
+    pub fn ref_color_image_view(&self) -> &Arc<ImageView<StorageImage>> {
+        &self.color_view
+    }
+}
+
+
+/// Get the number of image layers needed by the view mask.
+#[inline]
+fn get_num_views(view_mask: u32) -> u32 {
+    32 - view_mask.leading_zeros()
+}
+
+
+/// Create a render pass whose subpass renders to every view in the view mask.
+/// 
+/// # Runtime Errors
+/// - Returns a runtime error message if render pass creation fails.
+/// 
+#[inline]
+fn create_multiview_render_pass(
+    render_ctx: &Arc<RenderContext>,
+    view_mask: u32,
+    color_format: Format,
+    depth_stencil_format: Format,
+) -> Result<Arc<RenderPass>, RuntimeError> {
+    RenderPass::new(
+        render_ctx.ref_device().clone(),
+        get_multiview_render_pass_create_info(view_mask, color_format, depth_stencil_format)
+    ).map_err(|e| err!("Vulkan render pass creation failed: {}", e.to_string()))
+}
+
+
+/// Get the create info of a render pass whose subpass renders to every view in the view mask.
+#[inline]
+fn get_multiview_render_pass_create_info(
+    view_mask: u32,
+    color_format: Format,
+    depth_stencil_format: Format,
+) -> RenderPassCreateInfo {
+    RenderPassCreateInfo {
+        attachments: vec![
+            AttachmentDescription {
+                format: Some(color_format),
+                samples: SampleCount::Sample1,
+                load_op: LoadOp::Clear,
+                store_op: StoreOp::Store,
+                stencil_load_op: LoadOp::DontCare,
+                stencil_store_op: StoreOp::DontCare,
+                initial_layout: ImageLayout::Undefined,
+                final_layout: ImageLayout::ShaderReadOnlyOptimal,
+                ..Default::default()
+            },
+            AttachmentDescription {
+                format: Some(depth_stencil_format),
+                samples: SampleCount::Sample1,
+                load_op: LoadOp::Clear,
+                store_op: StoreOp::DontCare,
+                stencil_load_op: LoadOp::Clear,
+                stencil_store_op: StoreOp::DontCare,
+                initial_layout: ImageLayout::Undefined,
+                final_layout: ImageLayout::DepthStencilAttachmentOptimal,
+                ..Default::default()
+            },
+        ],
+        subpasses: vec![
+            SubpassDescription {
+                view_mask,
+                color_attachments: vec![Some(AttachmentReference {
+                    attachment: 0,
+                    layout: ImageLayout::ColorAttachmentOptimal,
+                    ..Default::default()
+                })],
+                depth_stencil_attachment: Some(AttachmentReference {
+                    attachment: 1,
+                    layout: ImageLayout::DepthStencilAttachmentOptimal,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }
+        ],
+        // the views are spatially correlated, so the implementation may render them concurrently.
+        correlated_view_masks: vec![view_mask],
+        ..Default::default()
+    }
+}
+
+
+/// Create the layered images and the framebuffer.
+/// The framebuffer has a single layer, as required by multiview.
+/// 
+/// # Runtime Errors
+/// - Returns a runtime error message if image creation fails.
+/// - Returns a runtime error message if image view creation fails.
+/// - Returns a runtime error message if framebuffer creation fails.
+/// 
+#[inline]
+fn create_multiview_framebuffer(
+    width: u32,
+    height: u32,
+    view_mask: u32,
+    color_format: Format,
+    depth_stencil_format: Format,
+    render_pass: &Arc<RenderPass>,
+    render_ctx: &Arc<RenderContext>,
+) -> Result<(Arc<ImageView<StorageImage>>, Arc<ImageView<StorageImage>>, Arc<Framebuffer>), RuntimeError> {
+    let color_view = create_layered_image(
+        width,
+        height,
+        get_num_views(view_mask),
+        color_format,
+        ImageUsage::COLOR_ATTACHMENT | ImageUsage::SAMPLED,
+        ImageAspects::COLOR,
+        render_ctx
+    )?;
+
+    let depth_stencil_view = create_layered_image(
+        width,
+        height,
+        get_num_views(view_mask),
+        depth_stencil_format,
+        ImageUsage::DEPTH_STENCIL_ATTACHMENT,
+        ImageAspects::DEPTH | ImageAspects::STENCIL,
+        render_ctx
+    )?;
+
+    let framebuffer = Framebuffer::new(
+        render_pass.clone(),
+        FramebufferCreateInfo {
+            attachments: vec![color_view.clone(), depth_stencil_view.clone()],
+            extent: [width, height],
+            layers: 1,
+            ..Default::default()
+        }
+    ).map_err(|e| err!("Framebuffer creation failed: {}", e.to_string()))?;
+
+    Ok((color_view, depth_stencil_view, framebuffer))
+}
+
+
+/// Create a 2d array image and its view.
+/// 
+/// # Runtime Errors
+/// - Returns a runtime error message if image creation fails.
+/// - Returns a runtime error message if image view creation fails.
+/// 
+#[inline]
+fn create_layered_image(
+    width: u32,
+    height: u32,
+    array_layers: u32,
+    format: Format,
+    usage: ImageUsage,
+    aspects: ImageAspects,
+    render_ctx: &Arc<RenderContext>,
+) -> Result<Arc<ImageView<StorageImage>>, RuntimeError> {
+    let image = StorageImage::with_usage(
+        render_ctx.ref_memory_allocator(),
+        ImageDimensions::Dim2d { width, height, array_layers },
+        format,
+        usage,
+        ImageCreateFlags::empty(),
+        [render_ctx.get_queue_fmaily_index()]
+    ).map_err(|e| err!("Failed to create multiview image: {}", e.to_string()))?;
+
+    ImageView::new(
+        image,
+        ImageViewCreateInfo {
+            view_type: ImageViewType::Dim2dArray,
+            format: Some(format),
+            component_mapping: ComponentMapping::identity(),
+            subresource_range: ImageSubresourceRange {
+                aspects,
+                mip_levels: (0..1),
+                array_layers: (0..array_layers)
+            },
+            ..Default::default()
+        }
+    ).map_err(|e| err!("Failed to create multiview image view: {}", e.to_string()))
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn two_view_render_pass_renders_both_views() {
+        let view_mask = 0b11;
+        assert_eq!(get_num_views(view_mask), 2);
+
+        let create_info = get_multiview_render_pass_create_info(view_mask, Format::R8G8B8A8_UNORM, Format::D24_UNORM_S8_UINT);
+        assert_eq!(create_info.subpasses.len(), 1);
+        assert_eq!(create_info.subpasses[0].view_mask, view_mask);
+        assert_eq!(create_info.correlated_view_masks, vec![view_mask]);
+        assert_eq!(create_info.attachments[0].format, Some(Format::R8G8B8A8_UNORM));
+        assert_eq!(create_info.attachments[1].format, Some(Format::D24_UNORM_S8_UINT));
+    }
+}
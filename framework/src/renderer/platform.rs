@@ -50,6 +50,49 @@ unsafe impl Send for AppHandle { }
 unsafe impl Sync for AppHandle { }
 
 
+/// Provides the size of the drawable that the swapchain presents to. (pixel)
+pub trait DrawableSizeProvider {
+    /// Returns `None` if the drawable size is not available.
+    fn get_drawable_size(&self) -> Option<(u32, u32)>;
+}
+
+impl DrawableSizeProvider for AppHandle {
+    /// (iOS) Returns the size of the bounds of the `CAMetalLayer` of the UIView in pixels. (`bounds * contentsScale`)
+    #[inline]
+    fn get_drawable_size(&self) -> Option<(u32, u32)> {
+        match self {
+            #[cfg(target_os = "ios")]
+            &AppHandle::IOS { ui_view } => {
+                unsafe { get_drawable_size_ios(ui_view) }
+            },
+            _ => None
+        }
+    }
+}
+
+
+/// Reconcile the drawable size reported by the provider with the screen size given by the host.
+/// 
+/// The drawable size of the provider is preferred, because the layer may render at a different resolution
+/// than `screen_size * scale_factor`. (e.g. a reduced `contentsScale`)
+/// If the provider has no drawable size, `screen_size * scale_factor` is used.
+/// 
+#[inline]
+pub fn reconcile_drawable_size(
+    provider: &impl DrawableSizeProvider,
+    screen_size: (u32, u32),
+    scale_factor: f32
+) -> (u32, u32) {
+    match provider.get_drawable_size() {
+        Some((width, height)) if width > 0 && height > 0 => (width, height),
+        _ => (
+            (screen_size.0 as f32 * scale_factor) as u32,
+            (screen_size.1 as f32 * scale_factor) as u32,
+        )
+    }
+}
+
+
 /// Creates a vulkan surface with the given application handle.
 /// 
 /// # Runtime Errors
@@ -96,6 +139,35 @@ unsafe fn create_vulkan_surface_ios(
 }
 
 
+/// A function that gets the drawable size of the `CAMetalLayer` for iOS.
+/// The size is computed from the `bounds` and `contentsScale` of the layer, which follow the view.
+/// The `drawableSize` of the layer is not used, because MoltenVK sets it to the extent of the swapchain,
+/// so it would keep the old size after a resize or rotation.
+/// Returns `None` if the view has no layer or the drawable size is not a positive finite size.
+/// 
+/// # Panics
+/// - Abort program execution if the pointer is not valid.
+/// 
+#[inline]
+#[cfg(target_os = "ios")]
+unsafe fn get_drawable_size_ios(ui_view: *mut Object) -> Option<(u32, u32)> {
+    let layer: *mut Object = msg_send![ui_view, layer];
+    if layer.is_null() {
+        return None;
+    }
+
+    let bounds: CGRect = msg_send![layer, bounds];
+    let scale: CGFloat = msg_send![layer, contentsScale];
+    let (width, height) = (bounds.size.width * scale, bounds.size.height * scale);
+    if width.is_finite() && height.is_finite() && width >= 1.0 && height >= 1.0 {
+        Some((width.round() as u32, height.round() as u32))
+    }
+    else {
+        None
+    }
+}
+
+
 /// A function that creates a vulkan surface for macOS.
 /// 
 /// # Runtime Errors
@@ -135,6 +207,50 @@ unsafe fn create_vulkan_surface_metal(
 
 
 
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use super::*;
+
+    /// A layer whose drawable size follows its bounds, like `bounds * contentsScale` of a `CAMetalLayer`.
+    struct MockLayer {
+        bounds: Cell<Option<(u32, u32)>>,
+        contents_scale: u32,
+    }
+
+    impl DrawableSizeProvider for MockLayer {
+        fn get_drawable_size(&self) -> Option<(u32, u32)> {
+            self.bounds.get().map(|(width, height)| (width * self.contents_scale, height * self.contents_scale))
+        }
+    }
+
+    #[test]
+    fn prefers_layer_size_when_sizes_disagree() {
+        let layer = MockLayer { bounds: Cell::new(Some((390, 844))), contents_scale: 2 };
+        assert_eq!(reconcile_drawable_size(&layer, (390, 844), 3.0), (780, 1688));
+    }
+
+    #[test]
+    fn falls_back_to_host_size_without_layer() {
+        let layer = MockLayer { bounds: Cell::new(None), contents_scale: 2 };
+        assert_eq!(reconcile_drawable_size(&layer, (390, 844), 3.0), (1170, 2532));
+
+        let layer = MockLayer { bounds: Cell::new(Some((0, 844))), contents_scale: 2 };
+        assert_eq!(reconcile_drawable_size(&layer, (390, 844), 3.0), (1170, 2532));
+    }
+
+    #[test]
+    fn follows_resized_layer() {
+        let layer = MockLayer { bounds: Cell::new(Some((390, 844))), contents_scale: 3 };
+        assert_eq!(reconcile_drawable_size(&layer, (390, 844), 3.0), (1170, 2532));
+
+        // rotation.
+        layer.bounds.set(Some((844, 390)));
+        assert_eq!(reconcile_drawable_size(&layer, (844, 390), 3.0), (2532, 1170));
+    }
+}
+
+
 #[cfg(any(target_os = "ios", target_os = "macos"))]
 mod apple {
     use std::ffi;
@@ -0,0 +1,142 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::{err, error::RuntimeError};
+
+
+
+/// The magic number at the start of a SPIR-V module.
+pub const SPIRV_MAGIC_NUMBER: u32 = 0x0723_0203;
+
+/// The number of words in the header of a SPIR-V module.
+const SPIRV_HEADER_WORDS: usize = 5;
+
+
+
+/// The shader modules reloaded at runtime, keyed by their file path.
+/// A failed reload keeps the previously loaded module.
+#[derive(Debug, Clone)]
+pub struct ShaderReloads<M: Clone> {
+    modules: HashMap<PathBuf, M>,
+    reloaded: Vec<PathBuf>,
+}
+
+impl<M: Clone> ShaderReloads<M> {
+    #[inline]
+    pub fn new() -> Self {
+        Self { modules: HashMap::new(), reloaded: Vec::new() }
+    }
+
+    /// Load the module of the given path with `load` and replace the module loaded from it.
+    /// 
+    /// # Runtime Errors
+    /// - Returns the runtime error of `load`. The previously loaded module is kept in that case.
+    /// 
+    pub fn reload(&mut self, path: PathBuf, load: impl FnOnce(&Path) -> Result<M, RuntimeError>) -> Result<M, RuntimeError> {
+        let module = load(&path)?;
+        self.modules.insert(path.clone(), module.clone());
+        if !self.reloaded.contains(&path) {
+            self.reloaded.push(path);
+        }
+        Ok(module)
+    }
+
+    /// Get the most recently reloaded module of the given path.
+    #[inline]
+    pub fn get(&self, path: &Path) -> Option<M> {
+        self.modules.get(path).cloned()
+    }
+
+    /// Take the paths of the modules reloaded since the last call.
+    #[inline]
+    pub fn take_reloaded(&mut self) -> Vec<PathBuf> {
+        std::mem::take(&mut self.reloaded)
+    }
+
+    /// Discard all reloaded modules. (e.g. when the device is recreated)
+    #[inline]
+    pub fn clear(&mut self) {
+        self.modules.clear();
+        self.reloaded.clear();
+    }
+}
+
+impl<M: Clone> Default for ShaderReloads<M> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
+/// Convert the bytes of a SPIR-V file into words.
+/// 
+/// # Runtime Errors
+/// - Returns a runtime error message if the size is not a multiple of four bytes or is smaller than the header.
+/// - Returns a runtime error message if the module does not start with the SPIR-V magic number.
+/// 
+pub fn read_spirv_words(bytes: &[u8]) -> Result<Vec<u32>, RuntimeError> {
+    if bytes.len() % 4 != 0 || bytes.len() < SPIRV_HEADER_WORDS * 4 {
+        return Err(err!("Invalid SPIR-V module: The size is not a whole SPIR-V module. ({} bytes)", bytes.len()));
+    }
+
+    let words: Vec<u32> = bytes.chunks_exact(4)
+        .map(|word| u32::from_ne_bytes([word[0], word[1], word[2], word[3]]))
+        .collect();
+    if words[0] != SPIRV_MAGIC_NUMBER {
+        return Err(err!("Invalid SPIR-V module: The magic number is {:#010x}.", words[0]));
+    }
+
+    Ok(words)
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spirv_bytes(body: &[u32]) -> Vec<u8> {
+        [SPIRV_MAGIC_NUMBER, 0x0001_0000, 0, 1, 0].iter()
+            .chain(body.iter())
+            .flat_map(|word| word.to_ne_bytes())
+            .collect()
+    }
+
+    #[test]
+    fn reads_valid_spirv() {
+        let words = read_spirv_words(&spirv_bytes(&[7])).unwrap();
+        assert_eq!(words.len(), SPIRV_HEADER_WORDS + 1);
+        assert_eq!(words[0], SPIRV_MAGIC_NUMBER);
+    }
+
+    #[test]
+    fn rejects_invalid_spirv() {
+        assert!(read_spirv_words(&[]).is_err());
+        assert!(read_spirv_words(&spirv_bytes(&[])[..19]).is_err());
+        assert!(read_spirv_words(&[0u8; 20]).is_err());
+    }
+
+    #[test]
+    fn reload_with_valid_bytes_succeeds() {
+        let mut reloads = ShaderReloads::new();
+        let path = PathBuf::from("shaders/vert.spv");
+        let module = reloads.reload(path.clone(), |_| read_spirv_words(&spirv_bytes(&[1]))).unwrap();
+
+        assert_eq!(reloads.get(&path), Some(module));
+        assert_eq!(reloads.take_reloaded(), vec![path]);
+        assert!(reloads.take_reloaded().is_empty());
+    }
+
+    #[test]
+    fn reload_with_invalid_bytes_keeps_previous_module() {
+        let mut reloads = ShaderReloads::new();
+        let path = PathBuf::from("shaders/vert.spv");
+        let previous = reloads.reload(path.clone(), |_| read_spirv_words(&spirv_bytes(&[1]))).unwrap();
+        reloads.take_reloaded();
+
+        assert!(reloads.reload(path.clone(), |_| read_spirv_words(b"not a shader")).is_err());
+        assert_eq!(reloads.get(&path), Some(previous));
+        assert!(reloads.take_reloaded().is_empty());
+    }
+}
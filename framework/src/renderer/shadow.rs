@@ -0,0 +1,367 @@
+use std::sync::Arc;
+
+use bytemuck::{Pod, Zeroable};
+use vulkano::format::{Format, FormatFeatures};
+use vulkano::image::{StorageImage, ImageDimensions, ImageCreateFlags, ImageUsage, ImageLayout, SampleCount, ImageViewType, ImageSubresourceRange, ImageAspects};
+use vulkano::image::view::{ImageView, ImageViewCreateInfo};
+use vulkano::pipeline::graphics::render_pass::PipelineRenderPassType;
+use vulkano::pipeline::graphics::viewport::Viewport;
+use vulkano::render_pass::{Subpass, Framebuffer, RenderPass, RenderPassCreateInfo, AttachmentDescription, LoadOp, StoreOp, SubpassDescription, AttachmentReference, FramebufferCreateInfo};
+use vulkano::sampler::ComponentMapping;
+
+use super::context::RenderContext;
+use crate::math::*;
+use crate::{err, error::RuntimeError};
+
+
+
+/// The maximum number of cascades of a cascaded shadow map.
+pub const MAX_CASCADES: usize = 4;
+
+/// The candidate depth formats of a cascaded shadow map in order of preference.
+const SHADOW_MAP_FORMATS: [Format; 2] = [
+    Format::D32_SFLOAT,
+    Format::D16_UNORM,
+];
+
+/// The per-cascade data of a cascaded shadow map, to be written to a uniform buffer.
+/// Shaders select the cascade of a fragment by comparing its view depth with `split_distances`,
+/// and sample the layer of the same index with the matching `view_projection`.
+#[repr(C, align(16))]
+#[derive(Debug, Clone, Copy, PartialEq, Pod, Zeroable)]
+pub struct CascadeData {
+    pub view_projection: [Mat4x4; MAX_CASCADES],
+    /// the far distance of each cascade. the unused cascades are set to the far distance of the last cascade.
+    pub split_distances: Vec4,
+}
+
+
+
+/// The perspective frustum of the camera that the cascades are fitted to.
+/// `view` is the left-handed view matrix of the camera and `fov_y` is the vertical field of view in radians.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CameraFrustum {
+    pub view: Mat4x4,
+    pub fov_y: f32,
+    pub aspect: f32,
+    pub near: f32,
+    pub far: f32,
+}
+
+
+
+/// Compute the split distances and the light view-projection matrices of the cascades.
+/// The split distances are given by `cascade_split_distances`, and each cascade covers
+/// the slice of the camera frustum between the previous split distance and its own.
+/// 
+/// # Panics
+/// - Stop program execution if the number of cascades is zero or greater than `MAX_CASCADES`.
+/// - Stop program execution if `near` of the frustum is not positive or is not less than `far`.
+/// 
+pub fn compute_cascade_data(frustum: &CameraFrustum, light_dir: Vec3, num_cascades: usize, lambda: f32) -> CascadeData {
+    assert!(0 < num_cascades && num_cascades <= MAX_CASCADES, "the number of cascades must be between 1 and {}.", MAX_CASCADES);
+
+    let splits = cascade_split_distances(frustum.near, frustum.far, num_cascades, lambda);
+    let mut data = CascadeData {
+        view_projection: [Mat4x4::IDENTITY; MAX_CASCADES],
+        split_distances: Vec4::new_scalar(splits[num_cascades - 1]),
+    };
+
+    let mut slice_near = frustum.near;
+    for (index, slice_far) in splits.into_iter().enumerate() {
+        let corners = frustum_slice_corners(frustum.view, frustum.fov_y, frustum.aspect, slice_near, slice_far);
+        data.view_projection[index] = cascade_view_projection(&corners, light_dir);
+        data.split_distances.as_mut()[index] = slice_far;
+        slice_near = slice_far;
+    }
+    data
+}
+
+
+
+/// The depth render target of a cascaded shadow map.
+/// 
+/// Each cascade is rendered to a layer of a 2d array depth image with its own framebuffer.
+/// After the shadow passes, shaders sample every cascade through the array view
+/// and select the layer with the `CascadeData` of the frame.
+#[derive(Debug)]
+pub struct CascadedShadowMap {
+    size: u32,
+    num_cascades: usize,
+    format: Format,
+    array_view: Arc<ImageView<StorageImage>>,
+    render_pass: Arc<RenderPass>,
+    framebuffers: Vec<Arc<Framebuffer>>,
+}
+
+
+impl CascadedShadowMap {
+    /// Create a new `CascadedShadowMap` with square cascades of the given size.
+    /// 
+    /// # Runtime Errors
+    /// - Returns a runtime error message if the size is zero.
+    /// - Returns a runtime error message if the number of cascades is zero or greater than `MAX_CASCADES`.
+    /// - Returns a runtime error message if there is no depth format that can be rendered and sampled on the device.
+    /// - Returns a runtime error message if image creation fails.
+    /// - Returns a runtime error message if image view creation fails.
+    /// - Returns a runtime error message if render pass creation fails.
+    /// - Returns a runtime error message if framebuffer creation fails.
+    /// 
+    pub fn new(size: u32, num_cascades: usize, render_ctx: Arc<RenderContext>) -> Result<Self, RuntimeError> {
+        if size == 0 {
+            return Err(err!("The shadow map size cannot be zero."));
+        }
+
+        if num_cascades == 0 || num_cascades > MAX_CASCADES {
+            return Err(err!("The number of cascades must be between 1 and {}. (given: {})", MAX_CASCADES, num_cascades));
+        }
+
+        let format = match get_shadow_map_format(|format| {
+            render_ctx.supports_optimal_tiling_features(format, FormatFeatures::DEPTH_STENCIL_ATTACHMENT | FormatFeatures::SAMPLED_IMAGE)
+        }) {
+            Some(format) => format,
+            None => return Err(err!("No suitable shadow map format found.")),
+        };
+
+        let image = StorageImage::with_usage(
+            render_ctx.ref_memory_allocator(),
+            ImageDimensions::Dim2d { width: size, height: size, array_layers: num_cascades as u32 },
+            format,
+            ImageUsage::DEPTH_STENCIL_ATTACHMENT | ImageUsage::SAMPLED,
+            ImageCreateFlags::empty(),
+            [render_ctx.get_queue_fmaily_index()]
+        ).map_err(|e| err!("Failed to create shadow map image: {}", e.to_string()))?;
+
+        let array_view = create_shadow_map_view(&image, format, ImageViewType::Dim2dArray, 0..num_cascades as u32)?;
+
+        let render_pass = RenderPass::new(
+            render_ctx.ref_device().clone(),
+            get_shadow_render_pass_create_info(format)
+        ).map_err(|e| err!("Vulkan render pass creation failed: {}", e.to_string()))?;
+
+        // each cascade renders to its own layer of the image.
+        let framebuffers = (0..num_cascades as u32)
+            .map(|layer| {
+                let layer_view = create_shadow_map_view(&image, format, ImageViewType::Dim2d, layer..layer + 1)?;
+                Framebuffer::new(
+                    render_pass.clone(),
+                    FramebufferCreateInfo {
+                        attachments: vec![layer_view],
+                        extent: [size, size],
+                        layers: 1,
+                        ..Default::default()
+                    }
+                ).map_err(|e| err!("Framebuffer creation failed: {}", e.to_string()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            size,
+            num_cascades,
+            format,
+            array_view,
+            render_pass,
+            framebuffers,
+        })
+    }
+
+
+    /// Get the width and height of each cascade.
+    #[inline]
+    pub fn get_size(&self) -> u32 {
+        self.size
+    }
+
+
+    #[inline]
+    pub fn get_num_cascades(&self) -> usize {
+        self.num_cascades
+    }
+
+
+    #[inline]
+    pub fn get_format(&self) -> Format {
+        self.format
+    }
+
+
+    /// Get the viewport that covers a cascade.
+    #[inline]
+    pub fn get_viewport(&self) -> Viewport {
+        Viewport {
+            origin: [0.0, 0.0],
+            dimensions: [self.size as f32, self.size as f32],
+            depth_range: 0.0..1.0,
+        }
+    }
+
+
+    #[inline]
+    pub fn ref_render_pass(&self) -> &Arc<RenderPass> {
+        &self.render_pass
+    }
+
+
+    /// Get the render pass type for graphics pipelines that draw to the cascades.
+    #[inline]
+    pub fn pipeline_render_pass_type(&self) -> PipelineRenderPassType {
+        PipelineRenderPassType::BeginRenderPass(Subpass::from(self.render_pass.clone(), 0).unwrap())
+    }
+
+
+    /// Get the framebuffer of the cascade with the given index.
+    /// Returns `None` if the index is not less than the number of cascades.
+    #[inline]
+    pub fn ref_framebuffer(&self, cascade: usize) -> Option<&Arc<Framebuffer>> {
+        self.framebuffers.get(cascade)
+    }
+
+
+    /// Get the array view of all cascades. It can be sampled after the shadow passes. (reference)
+    #[inline]
+    pub fn ref_array_view(&self) -> &Arc<ImageView<StorageImage>> {
+        &self.array_view
+    }
+}
+
+
+/// Get the first shadow map format for which `is_supported` returns `true`.
+#[inline]
+fn get_shadow_map_format<F>(is_supported: F) -> Option<Format>
+where F: Fn(Format) -> bool {
+    SHADOW_MAP_FORMATS.into_iter().find(|&format| is_supported(format))
+}
+
+
+/// Get the create info of a render pass that only writes the depth of a cascade.
+/// The depth is stored and left in a read-only layout, so it can be sampled by the passes that follow.
+#[inline]
+fn get_shadow_render_pass_create_info(format: Format) -> RenderPassCreateInfo {
+    RenderPassCreateInfo {
+        attachments: vec![
+            AttachmentDescription {
+                format: Some(format),
+                samples: SampleCount::Sample1,
+                load_op: LoadOp::Clear,
+                store_op: StoreOp::Store,
+                stencil_load_op: LoadOp::DontCare,
+                stencil_store_op: StoreOp::DontCare,
+                initial_layout: ImageLayout::Undefined,
+                final_layout: ImageLayout::DepthStencilReadOnlyOptimal,
+                ..Default::default()
+            },
+        ],
+        subpasses: vec![
+            SubpassDescription {
+                depth_stencil_attachment: Some(AttachmentReference {
+                    attachment: 0,
+                    layout: ImageLayout::DepthStencilAttachmentOptimal,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }
+        ],
+        ..Default::default()
+    }
+}
+
+
+/// Create a depth view of the given layers of the shadow map image.
+/// 
+/// # Runtime Errors
+/// - Returns a runtime error message if image view creation fails.
+/// 
+#[inline]
+fn create_shadow_map_view(
+    image: &Arc<StorageImage>,
+    format: Format,
+    view_type: ImageViewType,
+    array_layers: std::ops::Range<u32>,
+) -> Result<Arc<ImageView<StorageImage>>, RuntimeError> {
+    ImageView::new(
+        image.clone(),
+        ImageViewCreateInfo {
+            view_type,
+            format: Some(format),
+            component_mapping: ComponentMapping::identity(),
+            subresource_range: ImageSubresourceRange {
+                aspects: ImageAspects::DEPTH,
+                mip_levels: (0..1),
+                array_layers,
+            },
+            ..Default::default()
+        }
+    ).map_err(|e| err!("Failed to create shadow map image view: {}", e.to_string()))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FOV_Y: f32 = 60.0 * std::f32::consts::PI / 180.0;
+
+    fn camera_frustum(aspect: f32) -> CameraFrustum {
+        CameraFrustum {
+            view: look_at_lh(Vec3::new_vector(0.0, 2.0, -10.0), Vec3::ZERO, Vec3::Y),
+            fov_y: FOV_Y,
+            aspect,
+            near: 0.1,
+            far: 100.0,
+        }
+    }
+
+    #[test]
+    fn split_distances_are_increasing_and_bounded() {
+        let splits = cascade_split_distances(0.1, 100.0, 3, 0.5);
+        assert_eq!(splits.len(), 3);
+        assert!(splits.windows(2).all(|pair| pair[0] < pair[1]), "{:?}", splits);
+        assert!(splits.iter().all(|split| 0.1 < *split && *split <= 100.0), "{:?}", splits);
+        assert_eq!(splits[2], 100.0);
+    }
+
+    #[test]
+    fn cascades_cover_their_frustum_slice() {
+        let light_dir = Vec3::new_vector(0.3, -1.0, 0.2);
+        let frustum = camera_frustum(16.0 / 9.0);
+        let data = compute_cascade_data(&frustum, light_dir, 3, 0.5);
+
+        let mut slice_near = 0.1;
+        for index in 0..3 {
+            let slice_far = data.split_distances.as_ref()[index];
+            let corners = frustum_slice_corners(frustum.view, frustum.fov_y, frustum.aspect, slice_near, slice_far);
+            for corner in corners {
+                let clip = Vec4::new_vector(corner.x, corner.y, corner.z, 1.0) * data.view_projection[index];
+                let ndc = [clip.x / clip.w, clip.y / clip.w, clip.z / clip.w];
+                assert!(ndc[0].abs() <= 1.0 + 1e-4 && ndc[1].abs() <= 1.0 + 1e-4, "{:?}", ndc);
+                assert!(-1e-4 <= ndc[2] && ndc[2] <= 1.0 + 1e-4, "{:?}", ndc);
+            }
+            slice_near = slice_far;
+        }
+    }
+
+    #[test]
+    fn unused_cascades_use_the_last_split() {
+        let data = compute_cascade_data(&camera_frustum(1.0), -Vec3::Y, 2, 0.5);
+        assert_eq!(data.split_distances.as_ref()[2], 100.0);
+        assert_eq!(data.split_distances.as_ref()[3], 100.0);
+        assert_eq!(data.view_projection[2], Mat4x4::IDENTITY);
+    }
+
+    #[test]
+    fn shadow_render_pass_only_stores_the_depth() {
+        let create_info = get_shadow_render_pass_create_info(Format::D32_SFLOAT);
+        assert_eq!(create_info.attachments.len(), 1);
+        assert_eq!(create_info.attachments[0].store_op, StoreOp::Store);
+        assert_eq!(create_info.attachments[0].final_layout, ImageLayout::DepthStencilReadOnlyOptimal);
+        assert!(create_info.subpasses[0].color_attachments.is_empty());
+        assert_eq!(create_info.subpasses[0].depth_stencil_attachment.as_ref().map(|reference| reference.attachment), Some(0));
+    }
+
+    #[test]
+    fn shadow_map_format_falls_back_to_16_bit_depth() {
+        assert_eq!(get_shadow_map_format(|_| true), Some(Format::D32_SFLOAT));
+        assert_eq!(get_shadow_map_format(|format| format == Format::D16_UNORM), Some(Format::D16_UNORM));
+        assert_eq!(get_shadow_map_format(|_| false), None);
+    }
+}
@@ -0,0 +1,513 @@
+use std::sync::Arc;
+
+use rand::Rng;
+use bytemuck::{Pod, Zeroable};
+use vulkano::format::Format;
+use vulkano::image::{StorageImage, ImageDimensions, ImageCreateFlags, ImageUsage, ImageLayout, SampleCount};
+use vulkano::image::view::ImageView;
+use vulkano::pipeline::graphics::render_pass::PipelineRenderPassType;
+use vulkano::render_pass::{Subpass, Framebuffer, RenderPass, RenderPassCreateInfo, AttachmentDescription, LoadOp, StoreOp, SubpassDescription, AttachmentReference, FramebufferCreateInfo};
+
+use super::context::RenderContext;
+use super::depth_stencil::get_depth_stencil_format;
+use crate::math::*;
+use crate::{err, error::RuntimeError};
+
+
+
+/// The number of sample vectors in the screen-space ambient occlusion kernel.
+pub const SSAO_KERNEL_SIZE: usize = 32;
+
+/// The width and height of the screen-space ambient occlusion noise texture. (texel)
+pub const SSAO_NOISE_SIZE: usize = 4;
+
+/// The format of the geometry target. It holds the view-space normal (xyz) and the view depth (w) of each pixel.
+pub const SSAO_GEOMETRY_FORMAT: Format = Format::R16G16B16A16_SFLOAT;
+
+/// The format of the occlusion target. It holds the ambient light that reaches each pixel. (`1.0` is not occluded)
+pub const SSAO_OCCLUSION_FORMAT: Format = Format::R8_UNORM;
+
+
+
+/// The settings of the screen-space ambient occlusion.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SsaoSettings {
+    /// The radius of the kernel in view space.
+    pub radius: f32,
+    /// The scale of the occlusion. `1.0` darkens the fully occluded pixels to black.
+    pub intensity: f32,
+}
+
+impl SsaoSettings {
+    pub const DEFAULT: Self = Self {
+        radius: 0.5,
+        intensity: 1.0,
+    };
+}
+
+impl Default for SsaoSettings {
+    #[inline]
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+
+
+/// The data of the occlusion pass, to be written to a uniform buffer.
+#[repr(C, align(16))]
+#[derive(Debug, Clone, Copy, PartialEq, Pod, Zeroable)]
+pub struct SsaoData {
+    /// The sample vectors of the kernel. (xyz) See `generate_ssao_kernel`.
+    pub kernel: [Vec4; SSAO_KERNEL_SIZE],
+    /// The rotation vectors of the kernel, tiled over the screen. (xy) See `generate_ssao_noise`.
+    pub noise: [Vec4; SSAO_NOISE_SIZE * SSAO_NOISE_SIZE],
+    /// The radius (x) and the intensity (y) of the settings.
+    pub settings: Vec4,
+    /// The origin (xy) and the size (zw) of the viewport the scene is drawn to. (pixel)
+    pub viewport: Vec4,
+}
+
+impl SsaoData {
+    /// Create the data with the given kernel and noise.
+    /// 
+    /// # Panics
+    /// - Stop program execution if the kernel does not have `SSAO_KERNEL_SIZE` samples.
+    /// - Stop program execution if the noise does not have `SSAO_NOISE_SIZE * SSAO_NOISE_SIZE` vectors.
+    /// 
+    pub fn new(kernel: &[Vec3], noise: &[Vec3], settings: &SsaoSettings, viewport: Vec4) -> Self {
+        assert_eq!(kernel.len(), SSAO_KERNEL_SIZE, "the kernel must have {} samples.", SSAO_KERNEL_SIZE);
+        assert_eq!(noise.len(), SSAO_NOISE_SIZE * SSAO_NOISE_SIZE, "the noise must have {} vectors.", SSAO_NOISE_SIZE * SSAO_NOISE_SIZE);
+
+        let mut data = Self::zeroed();
+        for (dst, src) in data.kernel.iter_mut().zip(kernel) {
+            *dst = Vec4::new_vector(src.x, src.y, src.z, 0.0);
+        }
+        for (dst, src) in data.noise.iter_mut().zip(noise) {
+            *dst = Vec4::new_vector(src.x, src.y, src.z, 0.0);
+        }
+        data.settings = Vec4::new_vector(settings.radius, settings.intensity, 0.0, 0.0);
+        data.viewport = viewport;
+        data
+    }
+}
+
+
+
+/// Generate the sample vectors of the screen-space ambient occlusion kernel.
+/// 
+/// The vectors are distributed in the unit hemisphere around the positive z-axis. (tangent space)
+/// Their length is at most `1.0`, and more samples are placed close to the origin
+/// so that nearby geometry contributes more to the occlusion.
+/// The occlusion pass reads the kernel from the `SsaoData`.
+/// 
+pub fn generate_ssao_kernel(num_samples: usize, rng: &mut impl Rng) -> Vec<Vec3> {
+    (0..num_samples)
+        .map(|i| {
+            let direction = loop {
+                let v = Vec3::new_vector(
+                    rng.gen_range(-1.0..=1.0),
+                    rng.gen_range(-1.0..=1.0),
+                    rng.gen_range(0.0..=1.0),
+                );
+                if let Some(v) = v.try_normalized() {
+                    break v;
+                }
+            };
+
+            let scale = i as f32 / num_samples as f32;
+            let scale = lerp(0.1, 1.0, scale * scale);
+            direction * (rng.gen_range(0.0..=1.0) * scale)
+        })
+        .collect()
+}
+
+
+/// Generate the random rotation vectors of the screen-space ambient occlusion noise texture.
+/// The vectors lie in the xy-plane and rotate the kernel around the surface normal.
+pub fn generate_ssao_noise(rng: &mut impl Rng) -> Vec<Vec3> {
+    (0..SSAO_NOISE_SIZE * SSAO_NOISE_SIZE)
+        .map(|_| Vec3::new_vector(
+            rng.gen_range(-1.0..=1.0),
+            rng.gen_range(-1.0..=1.0),
+            0.0,
+        ))
+        .collect()
+}
+
+
+
+/// The offscreen render targets of the screen-space ambient occlusion.
+/// 
+/// The geometry pass writes the view-space normal and depth of each pixel,
+/// and the occlusion pass estimates the ambient light of each pixel from the geometry target.
+/// Both targets have the size of the render target, so their texels match the pixels of the main pass.
+#[derive(Debug)]
+pub struct SsaoTargets {
+    width: u32,
+    height: u32,
+    depth_stencil_format: Format,
+    geometry_view: Arc<ImageView<StorageImage>>,
+    occlusion_view: Arc<ImageView<StorageImage>>,
+    geometry_render_pass: Arc<RenderPass>,
+    occlusion_render_pass: Arc<RenderPass>,
+    geometry_framebuffer: Arc<Framebuffer>,
+    occlusion_framebuffer: Arc<Framebuffer>,
+    render_ctx: Arc<RenderContext>,
+}
+
+
+impl SsaoTargets {
+    /// Create a new `SsaoTargets`.
+    /// 
+    /// # Runtime Errors
+    /// - Returns a runtime error message if the target formats cannot be used as color attachments.
+    /// - Returns a runtime error message if there is no depth-stencil format supported by the device.
+    /// - Returns a runtime error message if image creation fails.
+    /// - Returns a runtime error message if image view creation fails.
+    /// - Returns a runtime error message if render pass creation fails.
+    /// - Returns a runtime error message if framebuffer creation fails.
+    /// 
+    pub fn new(width: u32, height: u32, render_ctx: Arc<RenderContext>) -> Result<Self, RuntimeError> {
+        for format in [SSAO_GEOMETRY_FORMAT, SSAO_OCCLUSION_FORMAT] {
+            if !render_ctx.supports_color_attachment(format) || !render_ctx.supports_sampled(format) {
+                return Err(err!("Unsupported ambient occlusion target format: {:?}", format));
+            }
+        }
+
+        let depth_stencil_format = match get_depth_stencil_format(&render_ctx) {
+            Some(format) => format,
+            None => return Err(err!("No suitable depth-stencil format found.")),
+        };
+
+        let geometry_render_pass = RenderPass::new(
+            render_ctx.ref_device().clone(),
+            get_geometry_render_pass_create_info(SSAO_GEOMETRY_FORMAT, depth_stencil_format)
+        ).map_err(|e| err!("Vulkan render pass creation failed: {}", e.to_string()))?;
+        let occlusion_render_pass = RenderPass::new(
+            render_ctx.ref_device().clone(),
+            get_occlusion_render_pass_create_info(SSAO_OCCLUSION_FORMAT)
+        ).map_err(|e| err!("Vulkan render pass creation failed: {}", e.to_string()))?;
+
+        let (geometry_view, geometry_framebuffer, occlusion_view, occlusion_framebuffer) = create_ssao_framebuffers(
+            width, 
+            height, 
+            depth_stencil_format, 
+            &geometry_render_pass, 
+            &occlusion_render_pass, 
+            &render_ctx
+        )?;
+
+        Ok(Self {
+            width,
+            height,
+            depth_stencil_format,
+            geometry_view,
+            occlusion_view,
+            geometry_render_pass,
+            occlusion_render_pass,
+            geometry_framebuffer,
+            occlusion_framebuffer,
+            render_ctx,
+        })
+    }
+
+
+    /// Recreate the images and framebuffers with the given size.
+    /// The image views are replaced, so the descriptor sets that sample them must be created again.
+    /// 
+    /// # Runtime Errors
+    /// - Returns a runtime error message if image creation fails.
+    /// - Returns a runtime error message if image view creation fails.
+    /// - Returns a runtime error message if framebuffer creation fails.
+    /// 
+    pub fn recreate(&mut self, width: u32, height: u32) -> Result<(), RuntimeError> {
+        let (geometry_view, geometry_framebuffer, occlusion_view, occlusion_framebuffer) = create_ssao_framebuffers(
+            width, 
+            height, 
+            self.depth_stencil_format, 
+            &self.geometry_render_pass, 
+            &self.occlusion_render_pass, 
+            &self.render_ctx
+        )?;
+
+        self.width = width;
+        self.height = height;
+        self.geometry_view = geometry_view;
+        self.geometry_framebuffer = geometry_framebuffer;
+        self.occlusion_view = occlusion_view;
+        self.occlusion_framebuffer = occlusion_framebuffer;
+
+        Ok(())
+    }
+
+
+    #[inline]
+    pub fn get_size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+
+    /// Get the render pass type for graphics pipelines that draw to the geometry target.
+    #[inline]
+    pub fn geometry_render_pass_type(&self) -> PipelineRenderPassType {
+        PipelineRenderPassType::BeginRenderPass(Subpass::from(self.geometry_render_pass.clone(), 0).unwrap())
+    }
+
+
+    /// Get the render pass type for graphics pipelines that draw to the occlusion target.
+    #[inline]
+    pub fn occlusion_render_pass_type(&self) -> PipelineRenderPassType {
+        PipelineRenderPassType::BeginRenderPass(Subpass::from(self.occlusion_render_pass.clone(), 0).unwrap())
+    }
+
+
+    #[inline]
+    pub fn ref_geometry_framebuffer(&self) -> &Arc<Framebuffer> {
+        &self.geometry_framebuffer
+    }
+
+
+    #[inline]
+    pub fn ref_occlusion_framebuffer(&self) -> &Arc<Framebuffer> {
+        &self.occlusion_framebuffer
+    }
+
+
+    /// Get the view of the geometry target. It can be sampled after the geometry pass. (reference)
+    #[inline]
+    pub fn ref_geometry_view(&self) -> &Arc<ImageView<StorageImage>> {
+        &self.geometry_view
+    }
+
+
+    /// Get the view of the occlusion target. It can be sampled after the occlusion pass. (reference)
+    #[inline]
+    pub fn ref_occlusion_view(&self) -> &Arc<ImageView<StorageImage>> {
+        &self.occlusion_view
+    }
+}
+
+
+/// Get the create info of a render pass that writes the geometry target with a depth test.
+/// The depth is only used by the pass, so it is not stored.
+#[inline]
+fn get_geometry_render_pass_create_info(color_format: Format, depth_stencil_format: Format) -> RenderPassCreateInfo {
+    RenderPassCreateInfo {
+        attachments: vec![
+            get_sampled_color_attachment(color_format),
+            AttachmentDescription {
+                format: Some(depth_stencil_format),
+                samples: SampleCount::Sample1,
+                load_op: LoadOp::Clear,
+                store_op: StoreOp::DontCare,
+                stencil_load_op: LoadOp::Clear,
+                stencil_store_op: StoreOp::DontCare,
+                initial_layout: ImageLayout::Undefined,
+                final_layout: ImageLayout::DepthStencilAttachmentOptimal,
+                ..Default::default()
+            },
+        ],
+        subpasses: vec![
+            SubpassDescription {
+                color_attachments: vec![Some(AttachmentReference {
+                    attachment: 0,
+                    layout: ImageLayout::ColorAttachmentOptimal,
+                    ..Default::default()
+                })],
+                depth_stencil_attachment: Some(AttachmentReference {
+                    attachment: 1,
+                    layout: ImageLayout::DepthStencilAttachmentOptimal,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }
+        ],
+        ..Default::default()
+    }
+}
+
+
+/// Get the create info of a render pass that writes the occlusion target with a fullscreen draw.
+#[inline]
+fn get_occlusion_render_pass_create_info(color_format: Format) -> RenderPassCreateInfo {
+    RenderPassCreateInfo {
+        attachments: vec![get_sampled_color_attachment(color_format)],
+        subpasses: vec![
+            SubpassDescription {
+                color_attachments: vec![Some(AttachmentReference {
+                    attachment: 0,
+                    layout: ImageLayout::ColorAttachmentOptimal,
+                    ..Default::default()
+                })],
+                ..Default::default()
+            }
+        ],
+        ..Default::default()
+    }
+}
+
+
+/// Get a color attachment that is cleared, stored and left in a layout that can be sampled by the passes that follow.
+#[inline]
+fn get_sampled_color_attachment(format: Format) -> AttachmentDescription {
+    AttachmentDescription {
+        format: Some(format),
+        samples: SampleCount::Sample1,
+        load_op: LoadOp::Clear,
+        store_op: StoreOp::Store,
+        stencil_load_op: LoadOp::DontCare,
+        stencil_store_op: StoreOp::DontCare,
+        initial_layout: ImageLayout::Undefined,
+        final_layout: ImageLayout::ShaderReadOnlyOptimal,
+        ..Default::default()
+    }
+}
+
+
+/// Create the images and framebuffers of the geometry and occlusion targets.
+/// 
+/// # Runtime Errors
+/// - Returns a runtime error message if image creation fails.
+/// - Returns a runtime error message if image view creation fails.
+/// - Returns a runtime error message if framebuffer creation fails.
+/// 
+#[inline]
+fn create_ssao_framebuffers(
+    width: u32,
+    height: u32,
+    depth_stencil_format: Format,
+    geometry_render_pass: &Arc<RenderPass>,
+    occlusion_render_pass: &Arc<RenderPass>,
+    render_ctx: &Arc<RenderContext>,
+) -> Result<(Arc<ImageView<StorageImage>>, Arc<Framebuffer>, Arc<ImageView<StorageImage>>, Arc<Framebuffer>), RuntimeError> {
+    let geometry_view = create_ssao_image(width, height, SSAO_GEOMETRY_FORMAT, ImageUsage::COLOR_ATTACHMENT | ImageUsage::SAMPLED, render_ctx)?;
+    let depth_stencil_view = create_ssao_image(width, height, depth_stencil_format, ImageUsage::DEPTH_STENCIL_ATTACHMENT, render_ctx)?;
+    let occlusion_view = create_ssao_image(width, height, SSAO_OCCLUSION_FORMAT, ImageUsage::COLOR_ATTACHMENT | ImageUsage::SAMPLED, render_ctx)?;
+
+    let geometry_framebuffer = Framebuffer::new(
+        geometry_render_pass.clone(),
+        FramebufferCreateInfo {
+            attachments: vec![geometry_view.clone(), depth_stencil_view],
+            extent: [width, height],
+            layers: 1,
+            ..Default::default()
+        }
+    ).map_err(|e| err!("Framebuffer creation failed: {}", e.to_string()))?;
+
+    let occlusion_framebuffer = Framebuffer::new(
+        occlusion_render_pass.clone(),
+        FramebufferCreateInfo {
+            attachments: vec![occlusion_view.clone()],
+            extent: [width, height],
+            layers: 1,
+            ..Default::default()
+        }
+    ).map_err(|e| err!("Framebuffer creation failed: {}", e.to_string()))?;
+
+    Ok((geometry_view, geometry_framebuffer, occlusion_view, occlusion_framebuffer))
+}
+
+
+/// Create a 2d image and its view.
+/// 
+/// # Runtime Errors
+/// - Returns a runtime error message if image creation fails.
+/// - Returns a runtime error message if image view creation fails.
+/// 
+#[inline]
+fn create_ssao_image(
+    width: u32,
+    height: u32,
+    format: Format,
+    usage: ImageUsage,
+    render_ctx: &Arc<RenderContext>,
+) -> Result<Arc<ImageView<StorageImage>>, RuntimeError> {
+    let image = StorageImage::with_usage(
+        render_ctx.ref_memory_allocator(),
+        ImageDimensions::Dim2d { width, height, array_layers: 1 },
+        format,
+        usage,
+        ImageCreateFlags::empty(),
+        [render_ctx.get_queue_fmaily_index()]
+    ).map_err(|e| err!("Failed to create ambient occlusion image: {}", e.to_string()))?;
+
+    ImageView::new_default(image)
+        .map_err(|e| err!("Failed to create ambient occlusion image view: {}", e.to_string()))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    use super::*;
+
+    fn rng() -> StdRng {
+        StdRng::seed_from_u64(0x55a0)
+    }
+
+    #[test]
+    fn kernel_is_in_unit_hemisphere() {
+        let kernel = generate_ssao_kernel(SSAO_KERNEL_SIZE, &mut rng());
+        assert_eq!(kernel.len(), SSAO_KERNEL_SIZE);
+        for sample in kernel.iter() {
+            assert!(sample.z >= 0.0, "{:?} is below the surface.", sample);
+            assert!(sample.length() <= 1.0 + f32::EPSILON, "{:?} is longer than one.", sample);
+        }
+    }
+
+    #[test]
+    fn kernel_samples_grow_toward_the_end() {
+        // the scale of the i-th sample is at most `lerp(0.1, 1.0, (i / n)^2)`.
+        let kernel = generate_ssao_kernel(SSAO_KERNEL_SIZE, &mut rng());
+        for (i, sample) in kernel.iter().enumerate() {
+            let scale = i as f32 / SSAO_KERNEL_SIZE as f32;
+            assert!(sample.length() <= lerp(0.1, 1.0, scale * scale) + 1e-6);
+        }
+    }
+
+    #[test]
+    fn noise_lies_in_xy_plane() {
+        let noise = generate_ssao_noise(&mut rng());
+        assert_eq!(noise.len(), SSAO_NOISE_SIZE * SSAO_NOISE_SIZE);
+        assert!(noise.iter().all(|v| v.z == 0.0 && v.x.abs() <= 1.0 && v.y.abs() <= 1.0));
+    }
+
+    #[test]
+    fn data_pads_the_kernel_and_noise_to_vec4() {
+        let kernel = generate_ssao_kernel(SSAO_KERNEL_SIZE, &mut rng());
+        let noise = generate_ssao_noise(&mut rng());
+        let viewport = Vec4::new_vector(0.0, 0.0, 640.0, 480.0);
+        let data = SsaoData::new(&kernel, &noise, &SsaoSettings { radius: 0.75, intensity: 2.0 }, viewport);
+
+        assert_eq!(std::mem::size_of::<SsaoData>(), (SSAO_KERNEL_SIZE + SSAO_NOISE_SIZE * SSAO_NOISE_SIZE + 2) * 16);
+        assert_eq!(data.kernel[3], Vec4::new_vector(kernel[3].x, kernel[3].y, kernel[3].z, 0.0));
+        assert_eq!(data.noise[5], Vec4::new_vector(noise[5].x, noise[5].y, 0.0, 0.0));
+        assert_eq!(data.settings, Vec4::new_vector(0.75, 2.0, 0.0, 0.0));
+        assert_eq!(data.viewport, viewport);
+    }
+
+    #[test]
+    fn occlusion_is_sampled_after_its_passes() {
+        let geometry = get_geometry_render_pass_create_info(SSAO_GEOMETRY_FORMAT, Format::D24_UNORM_S8_UINT);
+        assert_eq!(geometry.attachments[0].final_layout, ImageLayout::ShaderReadOnlyOptimal);
+        assert_eq!(geometry.attachments[1].store_op, StoreOp::DontCare);
+        assert_eq!(geometry.subpasses[0].depth_stencil_attachment.as_ref().map(|reference| reference.attachment), Some(1));
+
+        let occlusion = get_occlusion_render_pass_create_info(SSAO_OCCLUSION_FORMAT);
+        assert_eq!(occlusion.attachments.len(), 1);
+        assert_eq!(occlusion.attachments[0].format, Some(SSAO_OCCLUSION_FORMAT));
+        assert_eq!(occlusion.attachments[0].final_layout, ImageLayout::ShaderReadOnlyOptimal);
+    }
+
+    #[test]
+    fn kernel_and_noise_sizes_match_the_shaders() {
+        let occlusion = include_str!("../../shaders/ssao_frag.glsl");
+        let composite = include_str!("../../shaders/ssao_composite_frag.glsl");
+        assert!(occlusion.contains(&format!("const int KERNEL_SIZE = {};", SSAO_KERNEL_SIZE)));
+        assert!(occlusion.contains(&format!("const int NOISE_SIZE = {};", SSAO_NOISE_SIZE)));
+        assert!(composite.contains(&format!("const int NOISE_SIZE = {};", SSAO_NOISE_SIZE)));
+    }
+}
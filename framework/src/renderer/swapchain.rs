@@ -1,10 +1,11 @@
 use std::sync::Arc;
+use std::time::Duration;
 
-use vulkano::format::Format;
+use vulkano::format::{Format, FormatFeatures};
 use vulkano::sampler::ComponentMapping;
 use vulkano::image::view::{ImageView, ImageViewCreateInfo};
 use vulkano::image::{SwapchainImage, ImageUsage, ImageViewType, ImageSubresourceRange, ImageAspects};
-use vulkano::swapchain::{self, Swapchain, SwapchainCreateInfo, SwapchainAcquireFuture, AcquireError, PresentMode, ColorSpace, CompositeAlpha};
+use vulkano::swapchain::{self, Swapchain, SwapchainCreateInfo, SwapchainAcquireFuture, AcquireError, PresentMode, ColorSpace, CompositeAlpha, SurfaceCapabilities, SurfaceTransform};
 use vulkano::sync::Sharing;
 
 use super::context::RenderContext;
@@ -12,10 +13,98 @@ use crate::{err, error::RuntimeError};
 
 
 
+/// The default timeout for acquiring the next swapchain image. (about three frames at 60 fps)
+pub const DEFAULT_ACQUIRE_TIMEOUT: Duration = Duration::from_millis(50);
+
+/// The swapchain image formats in order of preference. Each is paired with `ColorSpace::SrgbNonLinear`.
+pub const PREFERRED_SURFACE_FORMATS: [Format; 3] = [
+    Format::B8G8R8A8_SRGB,
+    Format::R8G8B8A8_SRGB,
+    Format::B8G8R8A8_UNORM,
+];
+
+/// The swapchain image formats in order of preference when wide color is requested.
+/// Each is paired with `ColorSpace::DisplayP3NonLinear`.
+/// 
+/// # Color Authoring
+/// - Display-P3 uses the sRGB transfer function, so the shaders still write linear colors to the sRGB formats.
+/// - The color values are interpreted with the Display-P3 primaries, which are more saturated than the sRGB primaries.
+///   Colors authored in sRGB look oversaturated unless they are converted to Display-P3 first.
+///   (e.g. convert the textures at build time, or convert the shader output with `SRGB_TO_DISPLAY_P3`)
+/// - 8-bit formats have the same number of steps over a wider gamut, so gradients may band more.
+/// 
+pub const PREFERRED_WIDE_COLOR_SURFACE_FORMATS: [Format; 2] = [
+    Format::B8G8R8A8_SRGB,
+    Format::R8G8B8A8_SRGB,
+];
+
+/// The matrix that converts linear sRGB colors to linear Display-P3 colors. (row-major, `p3 = M * srgb`)
+pub const SRGB_TO_DISPLAY_P3: [[f32; 3]; 3] = [
+    [0.822_462, 0.177_538, 0.000_000],
+    [0.033_194, 0.966_806, 0.000_000],
+    [0.017_083, 0.072_397, 0.910_520],
+];
+
+
+/// The present modes that can be selected at runtime.
+/// The values are the same as the `VkPresentModeKHR` values.
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderPresentMode {
+    /// Frames are presented immediately and may tear. (vsync off)
+    Immediate = 0,
+    /// Frames are presented on the vertical blank, replacing the waiting frame. (vsync on, low latency)
+    Mailbox = 1,
+    /// Frames are queued and presented on the vertical blank. Always supported. (vsync on)
+    Fifo = 2,
+    /// Like `Fifo`, but a late frame is presented immediately.
+    FifoRelaxed = 3,
+}
+
+impl RenderPresentMode {
+    /// Convert a `VkPresentModeKHR` value. Returns `None` if the value is not a selectable mode.
+    #[inline]
+    pub fn from_raw(value: u32) -> Option<Self> {
+        match value {
+            0 => Some(Self::Immediate),
+            1 => Some(Self::Mailbox),
+            2 => Some(Self::Fifo),
+            3 => Some(Self::FifoRelaxed),
+            _ => None,
+        }
+    }
+
+    /// Returns `None` if the present mode is not a selectable mode.
+    #[inline]
+    pub fn from_present_mode(present_mode: PresentMode) -> Option<Self> {
+        match present_mode {
+            PresentMode::Immediate => Some(Self::Immediate),
+            PresentMode::Mailbox => Some(Self::Mailbox),
+            PresentMode::Fifo => Some(Self::Fifo),
+            PresentMode::FifoRelaxed => Some(Self::FifoRelaxed),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn into_present_mode(self) -> PresentMode {
+        match self {
+            Self::Immediate => PresentMode::Immediate,
+            Self::Mailbox => PresentMode::Mailbox,
+            Self::Fifo => PresentMode::Fifo,
+            Self::FifoRelaxed => PresentMode::FifoRelaxed,
+        }
+    }
+}
+
+
 #[derive(Debug)]
 pub struct RenderSwapchain {
     current_frame: u32,
     max_frame_in_flight: u32,
+    render_scale: f32,
+    acquire_timeout: Option<Duration>,
+    requested_present_mode: Option<PresentMode>,
     swapchain: Arc<Swapchain>,
     images: Vec<Arc<SwapchainImage>>,
     views: Vec<Arc<ImageView<SwapchainImage>>>,
@@ -24,6 +113,7 @@ pub struct RenderSwapchain {
 
 impl RenderSwapchain {
     /// Create a new `RenderSwapchain`.
+    /// The swapchain images are sized to the content size multiplied by `render_scale`.
     /// 
     /// ### Note
     /// - If there is an existing swap chain, do not create a new swap chain by calling this function.
@@ -35,14 +125,19 @@ impl RenderSwapchain {
     pub fn new(
         width: u32, 
         height: u32, 
+        render_scale: f32,
+        wide_color: bool,
         render_ctx: Arc<RenderContext>
     ) -> Result<Self, RuntimeError> {
         let (max_frame_in_flight, swapchain, images, views) 
-            = create_vulkan_swapchain(width, height, &render_ctx)?;
+            = create_vulkan_swapchain(width, height, render_scale, wide_color, &render_ctx)?;
 
         Ok(Self {
             current_frame: 0,
             max_frame_in_flight,
+            render_scale,
+            acquire_timeout: Some(DEFAULT_ACQUIRE_TIMEOUT),
+            requested_present_mode: None,
             render_ctx,
             swapchain,
             images,
@@ -52,6 +147,7 @@ impl RenderSwapchain {
 
 
     /// Create a new swapchain based on the existing swapchain.
+    /// The present mode requested by `request_present_mode` is applied.
     /// 
     /// # Runtime Errors
     /// 
@@ -60,12 +156,13 @@ impl RenderSwapchain {
     /// 
     pub fn recreate(&mut self, width: u32, height: u32) -> Result<(), RuntimeError> {
         let surface_capabilities = self.render_ctx.get_surface_capabilities()?;
-        let image_extent = surface_capabilities.current_extent.unwrap_or([width, height]);
+        let image_extent = get_image_extent(width, height, self.render_scale, &surface_capabilities);
 
         // recreate a swapchain and swapchain images.
         let (swapchain, images) = self.swapchain.recreate(
             SwapchainCreateInfo {
                 image_extent,
+                present_mode: self.requested_present_mode.unwrap_or(self.swapchain.present_mode()),
                 ..self.swapchain.create_info()
             }
         ).map_err(|e| err!("Swapchain recreation failed: {}", e.to_string()))?;
@@ -78,33 +175,97 @@ impl RenderSwapchain {
         self.swapchain = swapchain;
         self.images = images;
         self.views = views;
+        self.requested_present_mode = None;
 
         Ok(())
     }
 
 
+    /// Request the present mode used the next time the swapchain is recreated.
+    /// If the present mode is not supported by the surface, `PresentMode::Fifo` is used instead.
+    /// Returns the present mode selected.
+    /// 
+    /// # Runtime Errors
+    /// - Returns a runtime error message if getting the surface present modes fails.
+    /// 
+    pub fn request_present_mode(&mut self, present_mode: PresentMode) -> Result<PresentMode, RuntimeError> {
+        let present_mode = match self.render_ctx.get_surface_present_modes()?.any(|mode| mode == present_mode) {
+            true => present_mode,
+            false => PresentMode::Fifo,
+        };
+
+        self.requested_present_mode = match self.swapchain.present_mode() == present_mode {
+            true => None,
+            false => Some(present_mode),
+        };
+
+        Ok(present_mode)
+    }
+
+
+    /// Returns `true` if a requested present mode has not been applied yet.
+    #[inline]
+    pub fn has_pending_present_mode(&self) -> bool {
+        self.requested_present_mode.is_some()
+    }
+
+
+    /// Set the render scale used the next time the swapchain is recreated.
+    #[inline]
+    pub fn set_render_scale(&mut self, render_scale: f32) {
+        self.render_scale = render_scale;
+    }
+
+
+    #[inline]
+    pub fn get_render_scale(&self) -> f32 {
+        self.render_scale
+    }
+
+
+    /// Set the timeout for acquiring the next swapchain image.
+    /// `None` waits indefinitely.
+    #[inline]
+    pub fn set_acquire_timeout(&mut self, timeout: Option<Duration>) {
+        self.acquire_timeout = timeout;
+    }
+
+
+    #[inline]
+    pub fn get_acquire_timeout(&self) -> Option<Duration> {
+        self.acquire_timeout
+    }
+
+
     /// Get the next frame image.
     /// 
     /// ## Results
     /// - Returns `None` if `AcquireError::OutOfDate` occurs.
+    /// - Returns `None` if `AcquireError::Timeout` occurs.
     /// 
     /// # Runtime Errors
     /// - Returns a runtime error message if getting the next frame image fails.
     /// 
     pub fn acquire_next_image(&mut self) -> Result<Option<(u32, bool, SwapchainAcquireFuture)>, RuntimeError> {
-        let (image_index, suboptimal, acquire_future) =
-            match swapchain::acquire_next_image(self.swapchain.clone(), None) {
-                Ok(it) => it,
-                Err(AcquireError::OutOfDate) => {
-                    return Ok(None);
-                },
-                Err(e) => {
-                    return Err(err!("Failed to get swapchain next image: {}", e.to_string()))
-                }
-            };
+        let acquired = handle_acquire_result(swapchain::acquire_next_image(self.swapchain.clone(), self.acquire_timeout))?;
+        if let Some((image_index, _, _)) = acquired.as_ref() {
+            self.current_frame = *image_index;
+        }
+        Ok(acquired)
+    }
 
-        self.current_frame = image_index;
-        Ok(Some((image_index, suboptimal, acquire_future)))
+
+    /// Returns `true` if compute shaders can write to the swapchain images.
+    #[inline]
+    pub fn supports_storage(&self) -> bool {
+        self.swapchain.image_usage().intersects(ImageUsage::STORAGE)
+    }
+
+
+    /// Returns `true` if the swapchain images can be copied from.
+    #[inline]
+    pub fn supports_transfer_src(&self) -> bool {
+        self.swapchain.image_usage().intersects(ImageUsage::TRANSFER_SRC)
     }
 
 
@@ -121,7 +282,31 @@ impl RenderSwapchain {
     }
 
 
+    /// Get the extent of the swapchain images.
+    /// 
+    /// ### Note
+    /// - This may differ from the screen size if the surface is rotated.
+    /// 
+    #[inline]
+    pub fn get_image_extent(&self) -> [u32; 2] {
+        self.swapchain.image_extent()
+    }
+
+
     /// Get the vulkan swapchain. (reference)
+    /// Get the format of the swapchain images.
+    /// Pipelines that draw to the swapchain images directly must use this format.
+    #[inline]
+    pub fn get_image_format(&self) -> Format {
+        self.swapchain.image_format()
+    }
+
+    /// Get the color space of the swapchain images.
+    #[inline]
+    pub fn get_color_space(&self) -> ColorSpace {
+        self.swapchain.image_color_space()
+    }
+
     #[inline]
     pub fn ref_swapchain(&self) -> &Arc<Swapchain> {
         &self.swapchain
@@ -143,6 +328,67 @@ impl RenderSwapchain {
 }
 
 
+/// Get the extent of the swapchain images.
+/// See `compute_image_extent`.
+#[inline]
+fn get_image_extent(
+    width: u32, 
+    height: u32, 
+    render_scale: f32,
+    surface_capabilities: &SurfaceCapabilities
+) -> [u32; 2] {
+    compute_image_extent(
+        width, 
+        height, 
+        render_scale, 
+        surface_capabilities.current_extent, 
+        surface_capabilities.current_transform, 
+        [surface_capabilities.min_image_extent, surface_capabilities.max_image_extent]
+    )
+}
+
+
+/// Compute the extent of the swapchain images.
+/// 
+/// If the surface does not report its current extent, the screen size is used.
+/// The width and height are swapped when the surface is rotated by 90 or 270 degrees.
+/// The extent is then multiplied by `render_scale` and clamped to the extent range supported by the surface.
+/// 
+#[inline]
+fn compute_image_extent(
+    width: u32, 
+    height: u32, 
+    render_scale: f32,
+    current_extent: Option<[u32; 2]>,
+    current_transform: SurfaceTransform,
+    extent_range: [[u32; 2]; 2]
+) -> [u32; 2] {
+    let extent = match current_extent {
+        Some(current_extent) => current_extent,
+        None => match current_transform {
+            SurfaceTransform::Rotate90
+            | SurfaceTransform::Rotate270
+            | SurfaceTransform::HorizontalMirrorRotate90
+            | SurfaceTransform::HorizontalMirrorRotate270 => [height, width],
+            _ => [width, height],
+        },
+    };
+
+    let [min_extent, max_extent] = extent_range;
+    [
+        scale_extent(extent[0], render_scale).clamp(min_extent[0], max_extent[0]),
+        scale_extent(extent[1], render_scale).clamp(min_extent[1], max_extent[1]),
+    ]
+}
+
+
+/// Multiply the extent by the scale, rounding to the nearest pixel.
+#[inline]
+fn scale_extent(extent: u32, scale: f32) -> u32 {
+    ((extent as f32 * scale).round() as u32).max(1)
+}
+
+
 /// Create a vulkan swapchain, swapchain images, and image views.
 /// 
 /// # Runtime Errors
@@ -153,10 +399,12 @@ impl RenderSwapchain {
 fn create_vulkan_swapchain(
     width: u32,
     height: u32,
+    render_scale: f32,
+    wide_color: bool,
     render_ctx: &RenderContext
 ) -> Result<(u32, Arc<Swapchain>, Vec<Arc<SwapchainImage>>, Vec<Arc<ImageView<SwapchainImage>>>), RuntimeError> {
     let surface_capabilities = render_ctx.get_surface_capabilities()?;
-    let image_extent = surface_capabilities.current_extent.unwrap_or([width, height]);
+    let image_extent = get_image_extent(width, height, render_scale, &surface_capabilities);
 
     // set the present mode. (default = `PresentMode::Fifo`)
     let present_mode = render_ctx
@@ -172,16 +420,9 @@ fn create_vulkan_swapchain(
         })
         .unwrap_or(PresentMode::Fifo);
 
-    // finds surfaces of a specific type.
-    // if not found, the device's default settings are used.
-    let (image_format, image_color_space) = render_ctx
-        .get_surface_formats()?
-        .into_iter()
-        .find(|(format, color_space)| {
-            format.clone() == Format::B8G8R8A8_UNORM 
-            && color_space.clone() == ColorSpace::SrgbNonLinear
-        })
-        .unzip();
+    // select the surface format. (see `select_surface_format`)
+    let surface_formats = render_ctx.get_surface_formats()?;
+    let (image_format, image_color_space) = select_surface_format(&surface_formats, wide_color).unzip();
     
     // set the number of swap chain buffers.
     //
@@ -194,13 +435,10 @@ fn create_vulkan_swapchain(
     );
 
     // set the image usage flags.
-    let mut image_usage = ImageUsage::COLOR_ATTACHMENT;
-    if surface_capabilities.supported_usage_flags.contains(ImageUsage::TRANSFER_SRC) {
-        image_usage |= ImageUsage::TRANSFER_SRC;
-    }
-    if surface_capabilities.supported_usage_flags.contains(ImageUsage::TRANSFER_DST) {
-        image_usage |= ImageUsage::TRANSFER_DST;
-    }
+    let image_usage = get_swapchain_image_usage(
+        surface_capabilities.supported_usage_flags, 
+        image_format.map_or(false, |format| render_ctx.supports_optimal_tiling_features(format, FormatFeatures::STORAGE_IMAGE))
+    );
 
     // create a swapchain and swapchain images.
     let (swapchain, images) = Swapchain::new(
@@ -232,6 +470,76 @@ fn create_vulkan_swapchain(
 }
 
 
+/// Returns the image usage of the swapchain images.
+/// 
+/// The images are always color attachments. The transfer usages are added if the surface supports them.
+/// The storage usage is added only if both the surface and the image format support it,
+/// otherwise the frame is presented through the render pass.
+/// 
+fn get_swapchain_image_usage(supported_usage: ImageUsage, storage_format: bool) -> ImageUsage {
+    let mut image_usage = ImageUsage::COLOR_ATTACHMENT;
+    if supported_usage.contains(ImageUsage::TRANSFER_SRC) {
+        image_usage |= ImageUsage::TRANSFER_SRC;
+    }
+    if supported_usage.contains(ImageUsage::TRANSFER_DST) {
+        image_usage |= ImageUsage::TRANSFER_DST;
+    }
+    if supported_usage.contains(ImageUsage::STORAGE) && storage_format {
+        image_usage |= ImageUsage::STORAGE;
+    }
+    image_usage
+}
+
+
+/// Handle the result of acquiring the next swapchain image.
+/// 
+/// ## Results
+/// - Returns `None` if `AcquireError::OutOfDate` occurs.
+/// - Returns `None` if `AcquireError::Timeout` occurs, so the frame is skipped.
+/// 
+/// # Runtime Errors
+/// - Returns a runtime error message if any other acquire error occurs.
+/// 
+fn handle_acquire_result<F>(result: Result<(u32, bool, F), AcquireError>) -> Result<Option<(u32, bool, F)>, RuntimeError> {
+    match result {
+        Ok(it) => Ok(Some(it)),
+        Err(AcquireError::OutOfDate) => Ok(None),
+        Err(AcquireError::Timeout) => {
+            #[cfg(debug_assertions)]
+            println!("acquire error! (timeout)");
+
+            Ok(None)
+        },
+        Err(e) => Err(err!("Failed to get swapchain next image: {}", e.to_string())),
+    }
+}
+
+
+/// Select the swapchain image format and color space from the formats supported by the surface.
+/// 
+/// If `wide_color` is `true`, the formats of `PREFERRED_WIDE_COLOR_SURFACE_FORMATS` paired with
+/// the Display-P3 color space are tried first. Otherwise, or if none is supported, the formats of
+/// `PREFERRED_SURFACE_FORMATS` paired with the sRGB color space are tried.
+/// The sRGB formats encode the linear shader output when writing, so the colors are not washed out.
+/// If none is found, the first surface format is used.
+/// 
+pub fn select_surface_format(surface_formats: &[(Format, ColorSpace)], wide_color: bool) -> Option<(Format, ColorSpace)> {
+    let find = |preferred_formats: &[Format], preferred_color_space: ColorSpace| {
+        preferred_formats.iter().find_map(|preferred| {
+            surface_formats.iter().find(|(format, color_space)| {
+                *format == *preferred && *color_space == preferred_color_space
+            })
+        })
+    };
+
+    wide_color.then(|| find(&PREFERRED_WIDE_COLOR_SURFACE_FORMATS, ColorSpace::DisplayP3NonLinear))
+        .flatten()
+        .or_else(|| find(&PREFERRED_SURFACE_FORMATS, ColorSpace::SrgbNonLinear))
+        .or(surface_formats.first())
+        .cloned()
+}
+
+
 /// Create a image views from swapchain images.
 /// 
 /// # Runtime Errors
@@ -266,3 +574,95 @@ fn create_vulkan_swapchain_image_views(
         }
     ).collect::<Result<_, RuntimeError>>()
 }
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ANY_EXTENT: [[u32; 2]; 2] = [[1, 1], [16384, 16384]];
+
+    #[test]
+    fn rotated_surface_swaps_the_attachment_extent() {
+        // the depth-stencil and color attachments are sized from the swapchain image extent,
+        // so they follow the rotated surface instead of the portrait screen size.
+        let extent = compute_image_extent(1170, 2532, 1.0, None, SurfaceTransform::Rotate90, ANY_EXTENT);
+        assert_eq!(extent, [2532, 1170]);
+        let extent = compute_image_extent(1170, 2532, 1.0, None, SurfaceTransform::Identity, ANY_EXTENT);
+        assert_eq!(extent, [1170, 2532]);
+
+        // the current extent of the surface is already rotated.
+        let extent = compute_image_extent(1170, 2532, 1.0, Some([2532, 1170]), SurfaceTransform::Rotate90, ANY_EXTENT);
+        assert_eq!(extent, [2532, 1170]);
+    }
+
+    #[test]
+    fn half_render_scale_halves_the_extent() {
+        let extent = compute_image_extent(1170, 2532, 0.5, None, SurfaceTransform::Identity, ANY_EXTENT);
+        assert_eq!(extent, [585, 1266]);
+
+        // the odd extent is rounded to the nearest pixel.
+        let extent = compute_image_extent(1171, 2533, 0.5, None, SurfaceTransform::Identity, ANY_EXTENT);
+        assert_eq!(extent, [586, 1267]);
+
+        // the scaled extent stays within the range supported by the surface.
+        let extent = compute_image_extent(1170, 2532, 0.5, None, SurfaceTransform::Identity, [[600, 600], [16384, 16384]]);
+        assert_eq!(extent, [600, 1266]);
+    }
+
+
+    #[test]
+    fn storage_usage_is_added_only_when_supported() {
+        let supported = ImageUsage::COLOR_ATTACHMENT | ImageUsage::TRANSFER_SRC | ImageUsage::STORAGE;
+        let usage = get_swapchain_image_usage(supported, true);
+        assert!(usage.contains(ImageUsage::COLOR_ATTACHMENT | ImageUsage::TRANSFER_SRC | ImageUsage::STORAGE));
+        assert!(!usage.intersects(ImageUsage::TRANSFER_DST));
+
+        // the format can't be written from a compute shader, so the render pass path is used.
+        assert!(!get_swapchain_image_usage(supported, false).intersects(ImageUsage::STORAGE));
+
+        // the surface doesn't support storage images.
+        let usage = get_swapchain_image_usage(ImageUsage::COLOR_ATTACHMENT, true);
+        assert_eq!(usage, ImageUsage::COLOR_ATTACHMENT);
+    }
+
+
+    #[test]
+    fn acquire_timeout_skips_the_frame() {
+        // the compositor stalls for two frames and then recovers.
+        let results = [Err(AcquireError::Timeout), Err(AcquireError::Timeout), Ok((1, false, ()))];
+        let frames: Vec<_> = results.into_iter()
+            .map(|result| handle_acquire_result(result).unwrap())
+            .collect();
+        assert_eq!(frames, vec![None, None, Some((1, false, ()))]);
+
+        assert_eq!(handle_acquire_result::<()>(Err(AcquireError::OutOfDate)), Ok(None));
+        assert!(handle_acquire_result::<()>(Err(AcquireError::SurfaceLost)).is_err());
+    }
+
+    #[test]
+    fn wide_color_picks_display_p3_when_the_surface_supports_it() {
+        let surface_formats = [
+            (Format::B8G8R8A8_UNORM, ColorSpace::SrgbNonLinear),
+            (Format::B8G8R8A8_SRGB, ColorSpace::SrgbNonLinear),
+            (Format::R8G8B8A8_SRGB, ColorSpace::DisplayP3NonLinear),
+        ];
+        assert_eq!(select_surface_format(&surface_formats, true), Some((Format::R8G8B8A8_SRGB, ColorSpace::DisplayP3NonLinear)));
+        assert_eq!(select_surface_format(&surface_formats, false), Some((Format::B8G8R8A8_SRGB, ColorSpace::SrgbNonLinear)));
+    }
+
+    #[test]
+    fn wide_color_falls_back_to_srgb_without_display_p3() {
+        let surface_formats = [
+            (Format::B8G8R8A8_UNORM, ColorSpace::SrgbNonLinear),
+            (Format::B8G8R8A8_SRGB, ColorSpace::SrgbNonLinear),
+        ];
+        assert_eq!(select_surface_format(&surface_formats, true), Some((Format::B8G8R8A8_SRGB, ColorSpace::SrgbNonLinear)));
+
+        // without a preferred pairing, the first surface format is used.
+        let surface_formats = [(Format::A2B10G10R10_UNORM_PACK32, ColorSpace::ExtendedSrgbLinear)];
+        assert_eq!(select_surface_format(&surface_formats, true), Some(surface_formats[0]));
+        assert_eq!(select_surface_format(&[], true), None);
+    }
+}
@@ -0,0 +1,93 @@
+use std::fmt;
+use std::sync::Arc;
+
+use vulkano::render_pass::Framebuffer;
+use vulkano::swapchain::SwapchainAcquireFuture;
+
+use crate::{err, error::RuntimeError};
+
+
+
+/// The acquired swapchain image of a frame, returned by `Renderer::wait_for_next_frame`.
+/// The token is consumed when the frame is submitted, so each acquired image is presented at most once.
+/// A token of an earlier frame is rejected. (e.g. a token kept from a skipped frame)
+pub struct FrameToken {
+    frame_id: u64,
+    acquire_future: SwapchainAcquireFuture,
+    framebuffer: Arc<Framebuffer>,
+}
+
+impl FrameToken {
+    #[inline]
+    pub(super) fn new(frame_id: u64, acquire_future: SwapchainAcquireFuture, framebuffer: Arc<Framebuffer>) -> Self {
+        Self { frame_id, acquire_future, framebuffer }
+    }
+
+    /// Get the id of the frame the token was acquired for.
+    #[inline]
+    pub fn get_frame_id(&self) -> u64 {
+        self.frame_id
+    }
+
+    /// Borrow the framebuffer of the acquired swapchain image.
+    #[inline]
+    pub fn ref_framebuffer(&self) -> &Arc<Framebuffer> {
+        &self.framebuffer
+    }
+
+    /// Take the acquire future if the token belongs to the current frame.
+    /// 
+    /// # Runtime Errors
+    /// - Returns a runtime error message if the token was acquired for a different frame.
+    /// 
+    #[inline]
+    pub(super) fn into_acquire_future(self, current_frame_id: u64) -> Result<SwapchainAcquireFuture, RuntimeError> {
+        validate_frame_id(self.frame_id, current_frame_id)?;
+        Ok(self.acquire_future)
+    }
+}
+
+impl fmt::Debug for FrameToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FrameToken")
+            .field("frame_id", &self.frame_id)
+            .field("image_index", &self.acquire_future.image_index())
+            .finish()
+    }
+}
+
+
+/// Check that a frame token of `frame_id` can be submitted in the frame of `current_frame_id`.
+/// 
+/// # Runtime Errors
+/// - Returns a runtime error message if the frame ids differ.
+/// 
+#[inline]
+pub fn validate_frame_id(frame_id: u64, current_frame_id: u64) -> Result<(), RuntimeError> {
+    if frame_id != current_frame_id {
+        return Err(err!(
+            "Logic Error: The frame token is from a different frame. (token frame: {}, current frame: {})",
+            frame_id, current_frame_id
+        ));
+    }
+    Ok(())
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stale_frame_token_is_rejected() {
+        let msg = validate_frame_id(4, 5).unwrap_err();
+        assert!(msg.what().contains("different frame"), "{}", msg.what());
+        assert!(msg.what().contains("token frame: 4"), "{}", msg.what());
+    }
+
+    #[test]
+    fn frame_token_of_the_current_frame_is_accepted() {
+        assert!(validate_frame_id(5, 5).is_ok());
+    }
+}
@@ -11,6 +11,7 @@ pub struct Timer<const N_CNT: usize = 50> {
     
     fps_elapsed_time: f32,
     elapsed_time_in_sec: f32,
+    max_delta_in_sec: f32,
 
     frame_times: [f32; N_CNT],
     sample_count: usize,
@@ -22,6 +23,8 @@ pub struct Timer<const N_CNT: usize = 50> {
 }
 
 impl<const N_CNT: usize> Timer<N_CNT> {
+    pub const DEFAULT_MAX_DELTA_IN_SEC: f32 = 0.1;
+
     #[inline]
     pub fn new() -> Self {
         let time_point = Instant::now();
@@ -32,6 +35,7 @@ impl<const N_CNT: usize> Timer<N_CNT> {
             stop_time_point: None,
             fps_elapsed_time: 0.0,
             elapsed_time_in_sec: 0.0,
+            max_delta_in_sec: Self::DEFAULT_MAX_DELTA_IN_SEC,
             frame_times: [0.0; N_CNT],
             sample_count: 0,
             curr_frame_rate: 0,
@@ -46,20 +50,14 @@ impl<const N_CNT: usize> Timer<N_CNT> {
             return;
         }
 
-        self.curr_time_point = Instant::now();
-        let mut elapsed_time_in_sec = self.curr_time_point
-            .saturating_duration_since(self.prev_time_point)
-            .as_secs_f32();
+        let mut elapsed_time_in_sec = self.advance_time_point(Instant::now());
 
         if let Some(vsync) = vsync {
             while elapsed_time_in_sec < (1.0 / vsync as f32) {
                 if (1.0 / vsync as f32) - elapsed_time_in_sec > Duration::from_millis(64).as_secs_f32() {
                     thread::yield_now();
                 }
-                self.curr_time_point = Instant::now();
-                elapsed_time_in_sec = self.curr_time_point
-                    .saturating_duration_since(self.prev_time_point)
-                    .as_secs_f32();
+                elapsed_time_in_sec = self.advance_time_point(Instant::now());
             }
         }
         self.prev_time_point = self.curr_time_point;
@@ -82,9 +80,35 @@ impl<const N_CNT: usize> Timer<N_CNT> {
         self.elapsed_time_in_sec /= 1_f32.max(self.sample_count as f32);
     }
 
+    /// set the current time point and returns the elapsed time since the previous time point.
+    /// a time point earlier than the previous one (clock skew) is clamped to the previous one,
+    /// so the elapsed time is never negative.
+    #[inline]
+    fn advance_time_point(&mut self, time_point: Instant) -> f32 {
+        self.curr_time_point = time_point.max(self.prev_time_point);
+        self.curr_time_point
+            .duration_since(self.prev_time_point)
+            .as_secs_f32()
+    }
+
     #[inline]
     pub fn reset(&mut self) {
+        let max_delta_in_sec = self.max_delta_in_sec;
         *self = Self::new();
+        self.max_delta_in_sec = max_delta_in_sec;
+    }
+
+    /// set the ceiling of the reported elapsed time.
+    /// prevents large simulation steps after the app returns from the background.
+    #[inline]
+    pub fn set_max_delta(&mut self, seconds: f32) {
+        assert!(seconds > 0.0, "the maximum delta must be greater than zero.");
+        self.max_delta_in_sec = seconds;
+    }
+
+    #[inline]
+    pub fn get_max_delta(&self) -> f32 {
+        self.max_delta_in_sec
     }
 
     #[inline]
@@ -132,7 +156,7 @@ impl<const N_CNT: usize> Timer<N_CNT> {
 
     #[inline]
     pub fn get_elapsed_time_in_sec(&self) -> f32 {
-        self.elapsed_time_in_sec
+        self.elapsed_time_in_sec.min(self.max_delta_in_sec)
     }
 
     #[inline]
@@ -141,4 +165,39 @@ impl<const N_CNT: usize> Timer<N_CNT> {
             .saturating_duration_since(self.base_time_point)
             .as_secs_f32()
     }
-}
\ No newline at end of file
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn long_raw_delta_is_clamped_to_the_max_delta() {
+        let mut timer: Timer = Timer::new();
+        timer.set_max_delta(0.25);
+        // e.g. the first frame after the app returns from the background.
+        timer.elapsed_time_in_sec = 5.0;
+        assert_eq!(timer.get_elapsed_time_in_sec(), 0.25);
+
+        timer.reset();
+        assert_eq!(timer.get_max_delta(), 0.25);
+        timer.elapsed_time_in_sec = 0.2;
+        assert_eq!(timer.get_elapsed_time_in_sec(), 0.2);
+    }
+
+
+    #[test]
+    fn backwards_time_point_gives_a_zero_delta() {
+        let mut timer: Timer = Timer::new();
+        let start = timer.prev_time_point;
+
+        let forward = start + Duration::from_millis(16);
+        assert!((timer.advance_time_point(forward) - 0.016).abs() < 1e-6);
+        timer.prev_time_point = timer.curr_time_point;
+
+        // the clock went backwards. (e.g. clock skew)
+        assert_eq!(timer.advance_time_point(start), 0.0);
+        assert_eq!(timer.curr_time_point, forward);
+    }
+}
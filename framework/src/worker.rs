@@ -0,0 +1,125 @@
+use std::thread::{self, JoinHandle};
+use std::sync::atomic::{AtomicU32, Ordering};
+
+
+
+/// The quality-of-service class of the worker threads.
+/// The values are the same as the `qos_class_t` values of Darwin.
+/// 
+/// On iOS, the class decides how the threads are scheduled compared to the main thread,
+/// and which threads are slowed down first under thermal pressure.
+/// Other platforms ignore it.
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorkerQos {
+    UserInteractive = 0x21,
+    UserInitiated = 0x19,
+    Default = 0x15,
+    Utility = 0x11,
+    Background = 0x09,
+}
+
+impl WorkerQos {
+    /// Convert a `qos_class_t` value. Returns `None` if the value is not a known class.
+    #[inline]
+    pub fn from_raw(value: u32) -> Option<Self> {
+        match value {
+            0x21 => Some(Self::UserInteractive),
+            0x19 => Some(Self::UserInitiated),
+            0x15 => Some(Self::Default),
+            0x11 => Some(Self::Utility),
+            0x09 => Some(Self::Background),
+            _ => None,
+        }
+    }
+}
+
+
+/// The quality-of-service class of the worker threads spawned after it is set.
+static WORKER_QOS: AtomicU32 = AtomicU32::new(WorkerQos::UserInitiated as u32);
+
+/// Set the quality-of-service class of the worker threads.
+/// Threads that are already running keep their class.
+#[inline]
+pub fn set_worker_qos(qos: WorkerQos) {
+    WORKER_QOS.store(qos as u32, Ordering::Relaxed);
+}
+
+#[inline]
+pub fn get_worker_qos() -> WorkerQos {
+    WorkerQos::from_raw(WORKER_QOS.load(Ordering::Relaxed)).unwrap()
+}
+
+
+/// Spawn a named worker thread running at the configured quality-of-service class.
+/// 
+/// iOS does not allow pinning threads to cores, so the class is the only scheduling hint.
+/// 
+/// # Panics
+/// - Stop program execution if the operating system fails to create the thread. (same as `thread::spawn`)
+/// 
+pub fn spawn_worker<T, F>(name: String, task: F) -> JoinHandle<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    let qos = get_worker_qos();
+    thread::Builder::new()
+        .name(name)
+        .spawn(move || {
+            apply_qos(qos);
+            task()
+        })
+        .expect("failed to spawn worker thread")
+}
+
+
+#[cfg(target_os = "ios")]
+extern "C" {
+    fn pthread_set_qos_class_self_np(qos_class: u32, relative_priority: i32) -> i32;
+}
+
+/// Set the quality-of-service class of the calling thread.
+#[inline]
+#[cfg(target_os = "ios")]
+fn apply_qos(qos: WorkerQos) {
+    let _result = unsafe { pthread_set_qos_class_self_np(qos as u32, 0) };
+
+    #[cfg(debug_assertions)]
+    if _result != 0 {
+        println!("failed to set the worker thread qos class! ({:?}, error code: {})", qos, _result);
+    }
+}
+
+#[inline]
+#[cfg(not(target_os = "ios"))]
+fn apply_qos(_qos: WorkerQos) { }
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(not(target_os = "ios"))]
+    fn workers_get_the_configured_names() {
+        let handles: Vec<_> = (0..3)
+            .map(|index| spawn_worker(format!("render-worker-{}", index), || thread::current().name().map(str::to_string)))
+            .collect();
+        let names: Vec<_> = handles.into_iter().map(|handle| handle.join().unwrap()).collect();
+        assert_eq!(names, vec![
+            Some("render-worker-0".to_string()),
+            Some("render-worker-1".to_string()),
+            Some("render-worker-2".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn qos_class_round_trips_through_the_raw_value() {
+        for qos in [WorkerQos::UserInteractive, WorkerQos::UserInitiated, WorkerQos::Default, WorkerQos::Utility, WorkerQos::Background] {
+            assert_eq!(WorkerQos::from_raw(qos as u32), Some(qos));
+        }
+        assert_eq!(WorkerQos::from_raw(0), None);
+    }
+}
@@ -0,0 +1,280 @@
+use std::fmt;
+use std::sync::Arc;
+
+use bytemuck::{Pod, Zeroable};
+use vulkano::descriptor_set::{PersistentDescriptorSet, WriteDescriptorSet};
+use vulkano::descriptor_set::layout::{DescriptorSetLayout, DescriptorSetLayoutCreateInfo, DescriptorSetLayoutBinding, DescriptorType};
+use vulkano::image::ImageViewAbstract;
+use vulkano::pipeline::{PipelineLayout, PipelineBindPoint};
+use vulkano::command_buffer::AutoCommandBufferBuilder;
+use vulkano::command_buffer::allocator::CommandBufferAllocator;
+use vulkano::sampler::{Sampler, SamplerCreateInfo};
+use vulkano::shader::ShaderStages;
+
+use crate::renderer::RenderContext;
+use crate::{err, error::RuntimeError};
+
+
+
+/// The descriptor set index of the bindless texture array.
+/// Set 0 holds the shader variables of each `GraphicsShader`.
+pub const BINDLESS_SET_INDEX: u32 = 1;
+
+/// The binding of the bindless texture array in its descriptor set.
+pub const BINDLESS_BINDING: u32 = 0;
+
+
+
+/// The push constants that select the texture of a draw in the bindless texture array.
+/// (e.g. `layout(push_constant) uniform PushConstants { ...; uint textureIndex; };`)
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Pod, Zeroable)]
+pub struct BindlessTextureIndex {
+    pub texture_index: u32,
+}
+
+
+
+/// A variable-size array of sampled textures shared by all materials.
+/// 
+/// All textures are bound once with a single descriptor set at `BINDLESS_SET_INDEX`,
+/// and each draw selects its texture with an index passed in the push constants.
+/// (e.g. `layout(set = 1, binding = 0) uniform sampler2D textures[];` indexed with `nonuniformEXT`)
+/// 
+/// Requires the descriptor indexing features. See `RenderContext::supports_bindless`.
+/// Without them, textures must be bound with per-object descriptor sets.
+pub struct BindlessTextures {
+    capacity: u32,
+    layout: Arc<DescriptorSetLayout>,
+    sampler: Arc<Sampler>,
+    textures: Vec<Arc<dyn ImageViewAbstract>>,
+    descriptor_set: Option<Arc<PersistentDescriptorSet>>,
+    render_ctx: Arc<RenderContext>,
+}
+
+impl BindlessTextures {
+    /// Create a new bindless texture array that holds up to `capacity` textures.
+    /// 
+    /// # Runtime Error
+    /// - Return the `RuntimeError` if the device does not support descriptor indexing.
+    /// - Return the `RuntimeError` if the capacity is zero or exceeds the device limit.
+    /// - Return the `RuntimeError` if descriptor set layout creation fails.
+    /// - Return the `RuntimeError` if sampler creation fails.
+    /// 
+    pub fn new(capacity: u32, render_ctx: Arc<RenderContext>) -> Result<Self, RuntimeError> {
+        if !render_ctx.supports_bindless() {
+            return Err(err!("Unsupported: the device does not support descriptor indexing."));
+        }
+
+        check_capacity(capacity, render_ctx.get_max_bindless_textures())?;
+
+        let layout = DescriptorSetLayout::new(
+            render_ctx.ref_device().clone(), 
+            DescriptorSetLayoutCreateInfo {
+                bindings: [(BINDLESS_BINDING, get_layout_binding(capacity))].into(),
+                ..Default::default()
+            }
+        ).map_err(|e| err!("Descriptor set layout creation failed: {}", e.to_string()))?;
+
+        let sampler = Sampler::new(
+            render_ctx.ref_device().clone(), 
+            SamplerCreateInfo::simple_repeat_linear()
+        ).map_err(|e| err!("Sampler creation failed: {}", e.to_string()))?;
+
+        Ok(Self { 
+            capacity, 
+            layout, 
+            sampler, 
+            textures: Vec::with_capacity(capacity as usize), 
+            descriptor_set: None, 
+            render_ctx 
+        })
+    }
+
+
+    /// Add a texture to the array and return its index.
+    /// The descriptor set is rebuilt the next time it is bound.
+    /// 
+    /// # Runtime Error
+    /// - Return the `RuntimeError` if the array is full.
+    /// 
+    pub fn add_texture(&mut self, texture: Arc<dyn ImageViewAbstract>) -> Result<u32, RuntimeError> {
+        if self.textures.len() as u32 >= self.capacity {
+            return Err(err!("The bindless texture array is full. (capacity: {})", self.capacity));
+        }
+
+        self.textures.push(texture);
+        self.descriptor_set = None;
+        Ok(self.textures.len() as u32 - 1)
+    }
+
+
+    /// Get the push constants that select the texture with the given index in a draw.
+    /// Push them with `GraphicsShader::push_constants` at the offset the shaders declare.
+    /// 
+    /// # Runtime Error
+    /// - Return the `RuntimeError` if no texture has been added at the index.
+    /// 
+    #[inline]
+    pub fn get_texture_index(&self, index: u32) -> Result<BindlessTextureIndex, RuntimeError> {
+        get_texture_index(index, self.len())
+    }
+
+
+    /// Get the descriptor set with all added textures.
+    /// Only the added textures are allocated, so no descriptor in the set is left unwritten.
+    /// 
+    /// # Runtime Error
+    /// - Return the `RuntimeError` if no texture has been added.
+    /// - Return the `RuntimeError` if descriptor set creation fails.
+    /// 
+    pub fn get_descriptor_set(&mut self) -> Result<Arc<PersistentDescriptorSet>, RuntimeError> {
+        if let Some(descriptor_set) = &self.descriptor_set {
+            return Ok(descriptor_set.clone());
+        }
+
+        if self.textures.is_empty() {
+            return Err(err!("The bindless texture array is empty."));
+        }
+
+        let descriptor_set = PersistentDescriptorSet::new_variable(
+            self.render_ctx.ref_descriptor_allocator(), 
+            self.layout.clone(), 
+            self.textures.len() as u32, 
+            [WriteDescriptorSet::image_view_sampler_array(
+                BINDLESS_BINDING, 
+                0, 
+                self.textures.iter().map(|texture| (texture.clone(), self.sampler.clone()))
+            )]
+        ).map_err(|e| err!("Descriptor set creation failed: {}", e.to_string()))?;
+
+        self.descriptor_set = Some(descriptor_set.clone());
+        Ok(descriptor_set)
+    }
+
+
+    /// Bind the descriptor set at `BINDLESS_SET_INDEX` of the pipeline layout.
+    /// 
+    /// # Runtime Error
+    /// - Return the `RuntimeError` if no texture has been added.
+    /// - Return the `RuntimeError` if descriptor set creation fails.
+    /// 
+    pub fn bind_descriptor_set<L, A: CommandBufferAllocator>(
+        &mut self,
+        pipeline_layout: &Arc<PipelineLayout>,
+        command_buffer_builder: &mut AutoCommandBufferBuilder<L, A>
+    ) -> Result<(), RuntimeError> {
+        let descriptor_set = self.get_descriptor_set()?;
+        command_buffer_builder.bind_descriptor_sets(
+            PipelineBindPoint::Graphics, 
+            pipeline_layout.clone(), 
+            BINDLESS_SET_INDEX, 
+            descriptor_set
+        );
+        Ok(())
+    }
+
+
+    /// Get the descriptor set layout to use at `BINDLESS_SET_INDEX` when creating pipeline layouts. (reference)
+    #[inline]
+    pub fn ref_layout(&self) -> &Arc<DescriptorSetLayout> {
+        &self.layout
+    }
+
+    #[inline]
+    pub fn get_capacity(&self) -> u32 {
+        self.capacity
+    }
+
+    /// Get the number of added textures.
+    #[inline]
+    pub fn len(&self) -> u32 {
+        self.textures.len() as u32
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.textures.is_empty()
+    }
+}
+
+impl fmt::Debug for BindlessTextures {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BindlessTextures")
+            .field("capacity", &self.capacity)
+            .field("len", &self.len())
+            .field("layout", &self.layout)
+            .field("sampler", &self.sampler)
+            .finish()
+    }
+}
+
+
+/// Check the capacity of a bindless texture array against the device limit.
+/// 
+/// # Runtime Error
+/// - Return the `RuntimeError` if the capacity is zero or exceeds the device limit.
+/// 
+#[inline]
+fn check_capacity(capacity: u32, max_capacity: u32) -> Result<(), RuntimeError> {
+    if capacity == 0 || capacity > max_capacity {
+        return Err(err!("Invalid bindless texture capacity. (capacity: {}, max: {})", capacity, max_capacity));
+    }
+    Ok(())
+}
+
+
+/// Get the layout binding of a variable-size texture array that holds up to `capacity` textures.
+#[inline]
+fn get_layout_binding(capacity: u32) -> DescriptorSetLayoutBinding {
+    DescriptorSetLayoutBinding {
+        descriptor_count: capacity,
+        variable_descriptor_count: true,
+        stages: ShaderStages::FRAGMENT,
+        ..DescriptorSetLayoutBinding::descriptor_type(DescriptorType::CombinedImageSampler)
+    }
+}
+
+
+/// Get the push constants that select the texture with the given index. `len` is the number of added textures.
+/// 
+/// # Runtime Error
+/// - Return the `RuntimeError` if the index is out of range.
+/// 
+#[inline]
+fn get_texture_index(index: u32, len: u32) -> Result<BindlessTextureIndex, RuntimeError> {
+    if index >= len {
+        return Err(err!("Bindless texture index out of range. (index: {}, len: {})", index, len));
+    }
+    Ok(BindlessTextureIndex { texture_index: index })
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bindless_layout_has_the_requested_capacity() {
+        assert!(check_capacity(1024, 500_000).is_ok());
+        assert!(check_capacity(0, 500_000).is_err());
+        assert!(check_capacity(1024, 512).is_err());
+
+        let binding = get_layout_binding(1024);
+        assert_eq!(binding.descriptor_count, 1024);
+        assert!(binding.variable_descriptor_count);
+        assert_eq!(binding.descriptor_type, DescriptorType::CombinedImageSampler);
+        assert_eq!(binding.stages, ShaderStages::FRAGMENT);
+    }
+
+    #[test]
+    fn texture_index_is_pushed_for_added_textures() {
+        let push_constants = get_texture_index(2, 3).unwrap();
+        assert_eq!(push_constants, BindlessTextureIndex { texture_index: 2 });
+        assert_eq!(bytemuck::bytes_of(&push_constants), &2u32.to_ne_bytes());
+
+        assert!(get_texture_index(3, 3).is_err());
+        assert!(get_texture_index(0, 0).is_err());
+    }
+}
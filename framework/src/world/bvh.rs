@@ -0,0 +1,314 @@
+use std::fmt;
+
+use crate::math::*;
+
+
+
+/// A ray with a normalized direction.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ray {
+    pub origin: Vec3,
+    pub direction: Vec3,
+}
+
+impl Ray {
+    /// Create a new ray. The direction is normalized.
+    #[inline]
+    pub fn new(origin: Vec3, direction: Vec3) -> Self {
+        Self { origin, direction: direction.normalize() }
+    }
+
+    /// Returns the point at the given distance along the ray.
+    #[inline]
+    pub fn at(&self, distance: f32) -> Vec3 {
+        self.origin + self.direction * distance
+    }
+}
+
+
+
+/// A bounding sphere in world space.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingSphere {
+    pub center: Vec3,
+    pub radius: f32,
+}
+
+impl BoundingSphere {
+    #[inline]
+    pub fn new(center: Vec3, radius: f32) -> Self {
+        Self { center, radius }
+    }
+
+    /// Returns the sphere transformed by the world matrix.
+    /// The radius is scaled by the largest axis scale of the matrix, so the sphere still encloses the object.
+    pub fn transformed(&self, mat: &Mat4x4) -> Self {
+        let center = Vec4::new_vector(self.center.x, self.center.y, self.center.z, 1.0) * *mat;
+        let scale = (0..3)
+            .map(|row| mat.row(row))
+            .map(|axis| Vec3::new_vector(axis.x, axis.y, axis.z).length())
+            .fold(0.0, f32::max);
+        Self { center: Vec3::new_vector(center.x, center.y, center.z), radius: self.radius * scale }
+    }
+
+    /// Returns the smallest sphere that encloses both spheres.
+    pub fn merge(&self, other: &Self) -> Self {
+        let offset = other.center - self.center;
+        let distance = offset.length();
+        if distance + other.radius <= self.radius {
+            return *self;
+        }
+        if distance + self.radius <= other.radius {
+            return *other;
+        }
+
+        let radius = (distance + self.radius + other.radius) * 0.5;
+        let center = self.center + offset * ((radius - self.radius) / distance);
+        Self { center, radius }
+    }
+
+    /// Returns the distance along the ray to the nearest intersection.
+    /// Returns `0.0` if the ray starts inside the sphere, and `None` if the ray misses.
+    pub fn intersect_ray(&self, ray: &Ray) -> Option<f32> {
+        let m = ray.origin - self.center;
+        let b = m.dot(&ray.direction);
+        let c = m.length_squared() - self.radius * self.radius;
+        if c > 0.0 && b > 0.0 {
+            return None;
+        }
+
+        let discriminant = b * b - c;
+        if discriminant < 0.0 {
+            return None;
+        }
+
+        Some((-b - discriminant.sqrt()).max(0.0))
+    }
+}
+
+
+
+impl Plane {
+    /// Returns the distance along the ray to the intersection.
+    /// Returns `None` if the ray is parallel to the plane or points away from it.
+    pub fn intersect_ray(&self, ray: &Ray) -> Option<f32> {
+        let denom = self.normal.dot(&ray.direction);
+        if denom.abs() <= f32::EPSILON {
+            return None;
+        }
+
+        let t = -self.signed_distance(ray.origin) / denom;
+        (t >= 0.0).then_some(t)
+    }
+}
+
+
+
+#[derive(Debug, Clone, Copy)]
+enum BvhNode {
+    Leaf { sphere: BoundingSphere, object: usize },
+    Branch { sphere: BoundingSphere, left: usize, right: usize },
+}
+
+impl BvhNode {
+    #[inline]
+    fn ref_sphere(&self) -> &BoundingSphere {
+        match self {
+            Self::Leaf { sphere, .. } => sphere,
+            Self::Branch { sphere, .. } => sphere,
+        }
+    }
+}
+
+
+
+/// A bounding volume hierarchy over object bounding spheres.
+/// The hierarchy is rebuilt lazily on the next query after objects are added, moved or removed.
+#[derive(Debug, Clone)]
+pub struct Bvh<ObjectID = usize>
+where ObjectID: fmt::Debug + Clone + PartialEq {
+    objects: Vec<(ObjectID, BoundingSphere)>,
+    nodes: Vec<BvhNode>,
+    root: Option<usize>,
+    dirty: bool,
+}
+
+impl<ObjectID> Bvh<ObjectID>
+where ObjectID: fmt::Debug + Clone + PartialEq {
+    #[inline]
+    pub fn new() -> Self {
+        Self { objects: Vec::new(), nodes: Vec::new(), root: None, dirty: false }
+    }
+
+    /// Add an object, or update its bounding sphere if it is already registered.
+    pub fn insert(&mut self, id: ObjectID, sphere: BoundingSphere) {
+        match self.objects.iter_mut().find(|(object_id, _)| *object_id == id) {
+            Some((_, object_sphere)) => *object_sphere = sphere,
+            None => self.objects.push((id, sphere)),
+        }
+        self.dirty = true;
+    }
+
+    /// Replace all objects with the given objects.
+    pub fn reset<I>(&mut self, objects: I)
+    where I: IntoIterator<Item = (ObjectID, BoundingSphere)> {
+        self.objects.clear();
+        self.objects.extend(objects);
+        self.dirty = true;
+    }
+
+    /// Remove an object. Returns `false` if the object is not registered.
+    pub fn remove(&mut self, id: &ObjectID) -> bool {
+        match self.objects.iter().position(|(object_id, _)| object_id == id) {
+            Some(index) => {
+                self.objects.swap_remove(index);
+                self.dirty = true;
+                true
+            },
+            None => false,
+        }
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.objects.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
+
+    /// Mark the hierarchy to be rebuilt on the next query.
+    #[inline]
+    pub fn mark_dirty(&mut self) {
+        self.dirty = true;
+    }
+
+    /// Rebuild the hierarchy if it is marked dirty.
+    pub fn rebuild(&mut self) {
+        if !self.dirty {
+            return;
+        }
+
+        self.nodes.clear();
+        self.nodes.reserve(self.objects.len() * 2);
+        let mut indices: Vec<usize> = (0..self.objects.len()).collect();
+        self.root = match indices.is_empty() {
+            true => None,
+            false => Some(self.build_recursion(&mut indices)),
+        };
+        self.dirty = false;
+    }
+
+    fn build_recursion(&mut self, indices: &mut [usize]) -> usize {
+        if indices.len() == 1 {
+            self.nodes.push(BvhNode::Leaf { sphere: self.objects[indices[0]].1, object: indices[0] });
+            return self.nodes.len() - 1;
+        }
+
+        // split at the median along the longest axis of the centers.
+        let (min, max) = indices.iter()
+            .map(|&i| self.objects[i].1.center)
+            .fold((Vec3::MAX, Vec3::MIN), |(min, max), center| (min.min(center), max.max(center)));
+        let extent = max - min;
+        let axis = if extent.x >= extent.y && extent.x >= extent.z { 0 } else if extent.y >= extent.z { 1 } else { 2 };
+        indices.sort_by(|&a, &b| {
+            self.objects[a].1.center[axis].total_cmp(&self.objects[b].1.center[axis])
+        });
+
+        let (left_indices, right_indices) = indices.split_at_mut(indices.len() / 2);
+        let left = self.build_recursion(left_indices);
+        let right = self.build_recursion(right_indices);
+        let sphere = self.nodes[left].ref_sphere().merge(self.nodes[right].ref_sphere());
+        self.nodes.push(BvhNode::Branch { sphere, left, right });
+        return self.nodes.len() - 1;
+    }
+
+    /// Returns the nearest object hit by the ray and the distance to it.
+    pub fn raycast(&mut self, ray: &Ray) -> Option<(ObjectID, f32)> {
+        self.rebuild();
+
+        let mut nearest: Option<(usize, f32)> = None;
+        let mut stack: Vec<usize> = self.root.into_iter().collect();
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            let distance = match node.ref_sphere().intersect_ray(ray) {
+                Some(distance) => distance,
+                None => continue,
+            };
+            if nearest.map_or(false, |(_, nearest)| nearest <= distance) {
+                continue;
+            }
+
+            match *node {
+                BvhNode::Leaf { object, .. } => nearest = Some((object, distance)),
+                BvhNode::Branch { left, right, .. } => {
+                    stack.push(left);
+                    stack.push(right);
+                },
+            }
+        }
+
+        nearest.map(|(object, distance)| (self.objects[object].0.clone(), distance))
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use rand::{Rng, SeedableRng};
+    use rand::rngs::StdRng;
+
+    use super::*;
+
+    fn random_vec3(rng: &mut StdRng, range: f32) -> Vec3 {
+        Vec3::new_vector(rng.gen_range(-range..range), rng.gen_range(-range..range), rng.gen_range(-range..range))
+    }
+
+    #[test]
+    fn nearest_hit_matches_brute_force() {
+        let mut rng = StdRng::seed_from_u64(0xb1f);
+        let spheres: Vec<BoundingSphere> = (0..200)
+            .map(|_| BoundingSphere::new(random_vec3(&mut rng, 50.0), rng.gen_range(0.5..3.0)))
+            .collect();
+
+        let mut bvh = Bvh::new();
+        for (id, sphere) in spheres.iter().enumerate() {
+            bvh.insert(id, *sphere);
+        }
+
+        let mut hits = 0;
+        for _ in 0..500 {
+            let ray = Ray::new(random_vec3(&mut rng, 60.0), random_vec3(&mut rng, 1.0));
+            let expected = spheres.iter()
+                .enumerate()
+                .filter_map(|(id, sphere)| sphere.intersect_ray(&ray).map(|distance| (id, distance)))
+                .min_by(|a, b| a.1.total_cmp(&b.1));
+
+            match (bvh.raycast(&ray), expected) {
+                (Some((id, distance)), Some((_, expected_distance))) => {
+                    // the distances are the same even if two spheres tie.
+                    assert_eq!(distance, expected_distance);
+                    assert_eq!(spheres[id].intersect_ray(&ray), Some(distance));
+                    hits += 1;
+                },
+                (None, None) => (),
+                (actual, expected) => panic!("{:?} != {:?} for {:?}", actual, expected, ray),
+            }
+        }
+        assert!(hits > 0, "no ray hit a sphere.");
+    }
+
+    #[test]
+    fn transformed_sphere_follows_the_translation_and_largest_scale() {
+        let mut mat = Mat4x4::from_translation(Vec3::new_vector(1.0, 2.0, 3.0));
+        mat.r1c1 = 2.0;
+        mat.r2c2 = 3.0;
+        let sphere = BoundingSphere::new(Vec3::X, 0.5).transformed(&mat);
+        assert_eq!(sphere.center, Vec3::new_vector(3.0, 2.0, 3.0));
+        assert_eq!(sphere.radius, 1.5);
+    }
+
+}
@@ -0,0 +1,214 @@
+use std::fmt;
+use std::hash::Hash;
+use std::sync::Arc;
+use std::collections::HashMap;
+
+use crate::error::RuntimeError;
+
+
+
+/// A loaded asset and its bookkeeping.
+struct CacheEntry<V> {
+    asset: Arc<V>,
+    size_in_bytes: u64,
+    last_used: u64,
+}
+
+
+
+/// A cache of loaded assets with a byte budget.
+/// 
+/// When adding an asset would exceed the budget, the least recently used assets that are not in use
+/// (their `Arc` strong count is 1, held only by the cache) are evicted first.
+/// Assets in use are never evicted, so the cache may stay over budget until they are released.
+pub struct AssetCache<K, V>
+where K: Clone + Eq + Hash {
+    entries: HashMap<K, CacheEntry<V>>,
+    budget: u64,
+    used_bytes: u64,
+    tick: u64,
+}
+
+impl<K, V> AssetCache<K, V>
+where K: Clone + Eq + Hash {
+    /// Create a new cache with the given budget in bytes.
+    #[inline]
+    pub fn new(budget: u64) -> Self {
+        Self { entries: HashMap::new(), budget, used_bytes: 0, tick: 0 }
+    }
+
+    /// Set the budget in bytes and evict the unused assets over the new budget.
+    #[inline]
+    pub fn set_budget(&mut self, budget: u64) {
+        self.budget = budget;
+        self.evict_unused(budget);
+    }
+
+    #[inline]
+    pub fn get_budget(&self) -> u64 {
+        self.budget
+    }
+
+    /// Get the total size in bytes of the cached assets.
+    #[inline]
+    pub fn get_used_bytes(&self) -> u64 {
+        self.used_bytes
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    #[inline]
+    pub fn contains(&self, key: &K) -> bool {
+        self.entries.contains_key(key)
+    }
+
+    /// Get the cached asset and mark it as the most recently used.
+    #[inline]
+    pub fn get(&mut self, key: &K) -> Option<Arc<V>> {
+        self.tick += 1;
+        let tick = self.tick;
+        self.entries.get_mut(key).map(|entry| {
+            entry.last_used = tick;
+            entry.asset.clone()
+        })
+    }
+
+    /// Add the asset to the cache, replacing the asset cached with the same key.
+    /// The least recently used unused assets are evicted first if the asset does not fit in the budget.
+    pub fn insert(&mut self, key: K, asset: Arc<V>, size_in_bytes: u64) -> Arc<V> {
+        if let Some(entry) = self.entries.remove(&key) {
+            self.used_bytes -= entry.size_in_bytes;
+        }
+        self.evict_unused(self.budget.saturating_sub(size_in_bytes));
+
+        self.tick += 1;
+        self.used_bytes += size_in_bytes;
+        self.entries.insert(key, CacheEntry { asset: asset.clone(), size_in_bytes, last_used: self.tick });
+        asset
+    }
+
+    /// Get the cached asset, or load it with `load` and add it to the cache.
+    /// `load` returns the asset and its size in bytes.
+    /// 
+    /// # Runtime Errors
+    /// - Returns the runtime error of `load`. Nothing is evicted in that case.
+    /// 
+    pub fn get_or_load(
+        &mut self,
+        key: K,
+        load: impl FnOnce() -> Result<(Arc<V>, u64), RuntimeError>
+    ) -> Result<Arc<V>, RuntimeError> {
+        if let Some(asset) = self.get(&key) {
+            return Ok(asset);
+        }
+
+        let (asset, size_in_bytes) = load()?;
+        Ok(self.insert(key, asset, size_in_bytes))
+    }
+
+    /// Remove all assets from the cache. (e.g. when the device is recreated)
+    #[inline]
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.used_bytes = 0;
+    }
+
+    /// Evict the least recently used unused assets until the cache uses at most `target` bytes
+    /// or every remaining asset is in use.
+    fn evict_unused(&mut self, target: u64) {
+        while self.used_bytes > target {
+            let lru = self.entries.iter()
+                .filter(|(_, entry)| Arc::strong_count(&entry.asset) == 1)
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone());
+
+            match lru.and_then(|key| self.entries.remove(&key)) {
+                Some(entry) => self.used_bytes -= entry.size_in_bytes,
+                None => break,
+            }
+        }
+    }
+}
+
+impl<K, V> fmt::Debug for AssetCache<K, V>
+where K: Clone + Eq + Hash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AssetCache")
+            .field("len", &self.len())
+            .field("used_bytes", &self.used_bytes)
+            .field("budget", &self.budget)
+            .finish()
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loading_past_the_budget_evicts_the_oldest_unused() {
+        let mut cache = AssetCache::new(300);
+        let in_use = cache.insert("a", Arc::new(1), 100);
+        cache.insert("b", Arc::new(2), 100);
+        cache.insert("c", Arc::new(3), 100);
+
+        // "a" is the oldest but in use, so "b" is evicted.
+        cache.insert("d", Arc::new(4), 100);
+        assert!(cache.contains(&"a"));
+        assert!(!cache.contains(&"b"));
+        assert!(cache.contains(&"c"));
+        assert!(cache.contains(&"d"));
+        assert_eq!(cache.get_used_bytes(), 300);
+
+        // once released, "a" is the least recently used.
+        drop(in_use);
+        cache.insert("e", Arc::new(5), 100);
+        assert!(!cache.contains(&"a"));
+        assert_eq!(cache.len(), 3);
+    }
+
+    #[test]
+    fn get_marks_the_asset_as_recently_used() {
+        let mut cache = AssetCache::new(200);
+        cache.insert("a", Arc::new(1), 100);
+        cache.insert("b", Arc::new(2), 100);
+        drop(cache.get(&"a"));
+
+        cache.insert("c", Arc::new(3), 100);
+        assert!(cache.contains(&"a"));
+        assert!(!cache.contains(&"b"));
+    }
+
+    #[test]
+    fn assets_in_use_are_kept_over_the_budget() {
+        let mut cache = AssetCache::new(100);
+        let a = cache.insert("a", Arc::new(1), 100);
+        let b = cache.insert("b", Arc::new(2), 100);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get_used_bytes(), 200);
+
+        drop((a, b));
+        cache.set_budget(100);
+        assert_eq!(cache.len(), 1);
+        assert!(cache.contains(&"b"));
+    }
+
+    #[test]
+    fn failed_load_evicts_nothing() {
+        let mut cache: AssetCache<&str, i32> = AssetCache::new(100);
+        cache.insert("a", Arc::new(1), 100);
+        assert!(cache.get_or_load("b", || Err(crate::err!("load failed"))).is_err());
+        assert!(cache.contains(&"a"));
+        assert_eq!(*cache.get_or_load("a", || unreachable!()).unwrap(), 1);
+    }
+}
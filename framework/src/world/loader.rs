@@ -0,0 +1,140 @@
+use std::fmt;
+use std::thread::JoinHandle;
+use std::sync::{Arc, Mutex, Condvar};
+
+use crate::worker::spawn_worker;
+
+
+
+/// Limits the number of asset uploads that run at the same time.
+#[derive(Debug)]
+struct UploadSlots {
+    in_flight: Mutex<usize>,
+    available: Condvar,
+    max_concurrent: usize,
+}
+
+impl UploadSlots {
+    /// Block the calling thread until a slot is available, then take it.
+    #[inline]
+    fn acquire(self: &Arc<Self>) -> UploadPermit {
+        let mut in_flight = self.in_flight.lock().unwrap();
+        while *in_flight >= self.max_concurrent {
+            in_flight = self.available.wait(in_flight).unwrap();
+        }
+        *in_flight += 1;
+        UploadPermit { slots: self.clone() }
+    }
+}
+
+
+
+/// A taken upload slot. The slot is released when the permit is dropped.
+struct UploadPermit {
+    slots: Arc<UploadSlots>,
+}
+
+impl Drop for UploadPermit {
+    #[inline]
+    fn drop(&mut self) {
+        *self.slots.in_flight.lock().unwrap() -= 1;
+        self.slots.available.notify_one();
+    }
+}
+
+
+
+/// Runs asset upload tasks on background threads with a limit on the number of tasks in flight.
+/// Tasks submitted beyond the limit are queued until a running task finishes.
+#[derive(Clone)]
+pub struct AssetLoader {
+    slots: Arc<UploadSlots>,
+}
+
+impl AssetLoader {
+    /// Create a new asset loader.
+    /// 
+    /// # Panics
+    /// - Stop program execution if `max_concurrent` is zero.
+    /// 
+    pub fn new(max_concurrent: usize) -> Self {
+        assert!(max_concurrent > 0, "The maximum number of concurrent uploads cannot be zero.");
+        Self {
+            slots: Arc::new(UploadSlots {
+                in_flight: Mutex::new(0),
+                available: Condvar::new(),
+                max_concurrent,
+            })
+        }
+    }
+
+    /// Run the task on a background thread once an upload slot is available.
+    /// The calling thread is not blocked.
+    pub fn spawn<T, F>(&self, task: F) -> JoinHandle<T>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> T + Send + 'static,
+    {
+        let slots = self.slots.clone();
+        spawn_worker("asset-loader".to_string(), move || {
+            let _permit = slots.acquire();
+            task()
+        })
+    }
+
+    /// Get the maximum number of tasks that run at the same time.
+    #[inline]
+    pub fn get_max_concurrent(&self) -> usize {
+        self.slots.max_concurrent
+    }
+
+    /// Get the number of tasks currently running.
+    #[inline]
+    pub fn get_in_flight(&self) -> usize {
+        *self.slots.in_flight.lock().unwrap()
+    }
+}
+
+impl fmt::Debug for AssetLoader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AssetLoader")
+            .field("max_concurrent", &self.get_max_concurrent())
+            .field("in_flight", &self.get_in_flight())
+            .finish()
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+    use std::time::Duration;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    #[test]
+    fn excess_uploads_are_queued() {
+        let loader = AssetLoader::new(2);
+        let running = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+
+        let handles: Vec<_> = (0..8).map(|index| {
+            let (running, peak) = (running.clone(), peak.clone());
+            loader.spawn(move || {
+                let count = running.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(count, Ordering::SeqCst);
+                thread::sleep(Duration::from_millis(20));
+                running.fetch_sub(1, Ordering::SeqCst);
+                index
+            })
+        }).collect();
+
+        let results: Vec<_> = handles.into_iter().map(|handle| handle.join().unwrap()).collect();
+        assert_eq!(results, (0..8).collect::<Vec<_>>());
+        let peak = peak.load(Ordering::SeqCst);
+        assert!(0 < peak && peak <= 2, "{}", peak);
+        assert_eq!(loader.get_in_flight(), 0);
+    }
+}
@@ -1,13 +1,17 @@
 use std::fmt;
+use std::fs;
 use std::mem;
+use std::path::Path;
 use std::sync::Arc;
 
-use bytemuck::offset_of;
+use bytemuck::{offset_of, Pod};
 use vulkano::format::Format;
 use vulkano::buffer::{Buffer, BufferUsage, BufferContents, BufferCreateInfo, Subbuffer};
 use vulkano::memory::allocator::{AllocationCreateInfo, MemoryAllocator, MemoryUsage};
-use vulkano::command_buffer::{AutoCommandBufferBuilder, CopyBufferInfo};
+use vulkano::command_buffer::{AutoCommandBufferBuilder, CommandBufferInheritanceInfo, CommandBufferUsage, CopyBufferInfo, SecondaryAutoCommandBuffer};
 use vulkano::command_buffer::allocator::CommandBufferAllocator;
+use vulkano::pipeline::{GraphicsPipeline, PartialStateMode, StateMode};
+use vulkano::pipeline::graphics::input_assembly::{InputAssemblyState, PrimitiveTopology};
 use vulkano::pipeline::graphics::vertex_input::{VertexInputAttributeDescription, VertexInputBindingDescription, VertexInputRate, VertexInputState};
 
 use crate::math::*;
@@ -142,18 +146,23 @@ pub trait VertexBufferAbstract : fmt::Debug + Send + Sync {
 
     /// buffer access
     fn buffer_access(&self) -> Subbuffer<[u8]>;
+
+    /// Minimum and maximum corners of the vertex positions, if the buffer holds positions.
+    #[inline]
+    fn bounds(&self) -> Option<(Vec3, Vec3)> { None }
 }
 
 
 
 /// A vertex buffer that creates a buffer in device local memory.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct GpuVertexBuffer<T> 
 where T: fmt::Debug, [T]: BufferContents {
     stride: u32,
     format: Vec<(Format, u32)>,
     input_rate: VertexInputRate,
-    buffer: Subbuffer<[T]>
+    buffer: Subbuffer<[T]>,
+    bounds: Option<(Vec3, Vec3)>,
 }
 
 impl GpuVertexBuffer<Vec2> {
@@ -210,6 +219,7 @@ impl GpuVertexBuffer<Vec2> {
             format: vec![(Format::R32G32_SFLOAT, 0)],
             input_rate,
             buffer,
+            bounds: None,
         }))
     }
 }
@@ -232,6 +242,15 @@ impl GpuVertexBuffer<Vec3> {
         I: IntoIterator<Item = Vec3>, 
         I::IntoIter: ExactSizeIterator 
     {
+        // the bounds are accumulated while the data is written to the staging buffer.
+        let mut bounds: Option<(Vec3, Vec3)> = None;
+        let iter = iter.into_iter().inspect(|&v| {
+            bounds = Some(match bounds {
+                Some((min, max)) => (min.min(v), max.max(v)),
+                None => (v, v),
+            });
+        });
+
         let staging_buffer = Buffer::from_iter(
             allocator,
             BufferCreateInfo {
@@ -268,6 +287,7 @@ impl GpuVertexBuffer<Vec3> {
             format: vec![(Format::R32G32B32_SFLOAT, 0)],
             input_rate,
             buffer,
+            bounds,
         }))
     }
 }    
@@ -326,6 +346,7 @@ impl GpuVertexBuffer<Vec4> {
             format: vec![(Format::R32G32B32A32_SFLOAT, 0)],
             input_rate,
             buffer,
+            bounds: None,
         }))
     }
 }
@@ -387,7 +408,8 @@ impl GpuVertexBuffer<Mat3x3> {
                 (Format::R32G32B32_SFLOAT, offset_of!(Mat3x3, r3c1) as u32),
             ],
             input_rate,
-            buffer, 
+            buffer,
+            bounds: None,
         }))
     }
 }
@@ -450,7 +472,8 @@ impl GpuVertexBuffer<Mat4x4> {
                 (Format::R32G32B32A32_SFLOAT, offset_of!(Mat4x4, r4c1) as u32),
             ],
             input_rate,
-            buffer, 
+            buffer,
+            bounds: None,
         }))
     }
 }
@@ -476,9 +499,121 @@ where T: fmt::Debug, [T]: BufferContents {
     fn buffer_access(&self) -> Subbuffer<[u8]> {
         self.buffer.as_bytes().clone()
     }
+
+    #[inline]
+    fn bounds(&self) -> Option<(Vec3, Vec3)> {
+        self.bounds
+    }
+}
+
+
+
+/// A vertex buffer that interleaves several attributes of a vertex in one buffer in device local memory.
+/// `T` is a `#[repr(C)]` vertex type, and each attribute is bound at the next location in the given order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InterleavedVertexBuffer<T>
+where T: fmt::Debug + Pod + Send + Sync {
+    stride: u32,
+    format: Vec<(Format, u32)>,
+    input_rate: VertexInputRate,
+    buffer: Subbuffer<[T]>
+}
+
+impl<T> InterleavedVertexBuffer<T>
+where T: fmt::Debug + Pod + Send + Sync {
+    /// Create an interleaved vertex buffer from vertex data.
+    /// `attributes` are the format and the byte offset of each attribute in `T`. (e.g. `offset_of!(Vertex, normal)`)
+    /// 
+    /// # Runtime Error
+    /// - Return the `RuntimeError` if there are no attributes.
+    /// - Return the `RuntimeError` if an attribute does not fit in `T`.
+    /// - Return the `RuntimeError` if an error occurs while creating the vertex buffer.
+    /// 
+    pub fn from_iter<L, A, I>(
+        iter: I,
+        attributes: &[(Format, u32)],
+        input_rate: VertexInputRate,
+        allocator: &impl MemoryAllocator,
+        command_buffer_builder: &mut AutoCommandBufferBuilder<L, A>
+    ) -> Result<Arc<Self>, RuntimeError> 
+    where 
+        A: CommandBufferAllocator, 
+        I: IntoIterator<Item = T>, 
+        I::IntoIter: ExactSizeIterator 
+    {
+        if attributes.is_empty() {
+            return Err(err!("The interleaved vertex buffer has no attributes."));
+        }
+
+        let stride = mem::size_of::<T>() as u32;
+        for &(format, offset) in attributes.iter() {
+            let size = format.block_size().unwrap_or(0) as u32;
+            if size == 0 || offset + size > stride {
+                return Err(err!("The vertex attribute does not fit in the vertex. (format: {:?}, offset: {}, stride: {})", format, offset, stride));
+            }
+        }
+
+        let staging_buffer = Buffer::from_iter(
+            allocator,
+            BufferCreateInfo {
+                usage: BufferUsage::VERTEX_BUFFER | BufferUsage::TRANSFER_SRC,
+                ..Default::default()
+            },
+            AllocationCreateInfo {
+                usage: MemoryUsage::Upload,
+                ..Default::default()
+            },
+            iter
+        ).map_err(|e| err!("Buffer creation failed: {}", e.to_string()))?;
+
+        let buffer = Buffer::new_unsized(
+            allocator, 
+            BufferCreateInfo {
+                usage: BufferUsage::VERTEX_BUFFER | BufferUsage::TRANSFER_DST,
+                ..Default::default()
+            }, 
+            AllocationCreateInfo {
+                usage: MemoryUsage::DeviceOnly,
+                ..Default::default()
+            }, 
+            staging_buffer.size()
+        ).map_err(|e| err!("Buffer creation failed: {}", e.to_string()))?;
+
+        command_buffer_builder.copy_buffer(CopyBufferInfo::buffers(
+            staging_buffer, 
+            buffer.clone()
+        )).map_err(|e| err!("Buffer copy failed: {}", e.to_string()))?;
+
+        Ok(Arc::new(Self {
+            stride,
+            format: attributes.to_vec(),
+            input_rate,
+            buffer,
+        }))
+    }
 }
 
+impl<T> VertexBufferAbstract for InterleavedVertexBuffer<T>
+where T: fmt::Debug + Pod + Send + Sync {
+    #[inline]
+    fn stride(&self) -> u32 {
+        self.stride
+    }
+
+    #[inline]
+    fn format(&self) -> &[(Format, u32)] {
+        &self.format
+    }
+
+    #[inline]
+    fn input_rate(&self) -> VertexInputRate {
+        self.input_rate
+    }
 
+    fn buffer_access(&self) -> Subbuffer<[u8]> {
+        self.buffer.as_bytes().clone()
+    }
+}
 
 /// `Mesh` object used in `Model`.
 #[derive(Debug, Clone)]
@@ -488,6 +623,9 @@ pub struct Mesh {
     index_buffer: Option<IndexBuffer>,
     vertex_buffers: Vec<Arc<dyn VertexBufferAbstract>>,
     vertex_input_state: VertexInputState,
+    topology: PrimitiveTopology,
+    primitive_restart: bool,
+    aabb: (Vec3, Vec3),
 }
 
 impl Mesh {
@@ -498,33 +636,8 @@ impl Mesh {
     ) -> Arc<Self>
     where Iter: IntoIterator<Item = Arc<dyn VertexBufferAbstract>>, Iter::IntoIter: ExactSizeIterator {
         let vertex_buffers: Vec<_> = vertex_buffers.into_iter().collect();
-        let (bindings, attributes): (Vec<_>, Vec<Vec<_>>) = vertex_buffers
-            .iter()
-            .enumerate()
-            .map(|(i, buffer)| {(
-                VertexInputBindingDescription {
-                    input_rate: buffer.input_rate(),
-                    stride: buffer.stride()
-                },
-                buffer.format().iter()
-                    .map(|&(format, offset)| {
-                        VertexInputAttributeDescription {
-                            binding: i as u32,
-                            format,
-                            offset
-                        }
-                    })
-                    .collect()
-            )})
-            .unzip();
-        
-        let vertex_input_state = VertexInputState::new()
-            .bindings(bindings.into_iter().enumerate().map(|(i, description)| {
-                (i as u32, description)
-            }))
-            .attributes(attributes.into_iter().flatten().enumerate().map(|(i, description)| {
-                (i as u32, description)
-            }));
+        let vertex_input_state = build_vertex_input_state(&vertex_buffers);
+        let aabb = get_position_bounds(&vertex_buffers);
 
         Arc::new(Self {
             index_count: 0,
@@ -532,6 +645,9 @@ impl Mesh {
             vertex_count,
             vertex_buffers,
             vertex_input_state,
+            topology: PrimitiveTopology::TriangleList,
+            primitive_restart: false,
+            aabb,
         })
     }
 
@@ -544,33 +660,8 @@ impl Mesh {
     ) -> Arc<Self>
     where Iter: IntoIterator<Item = Arc<dyn VertexBufferAbstract>>, Iter::IntoIter: ExactSizeIterator {
         let vertex_buffers: Vec<_> = vertex_buffers.into_iter().collect();
-        let (bindings, attributes): (Vec<_>, Vec<Vec<_>>) = vertex_buffers
-            .iter()
-            .enumerate()
-            .map(|(i, buffer)| {(
-                VertexInputBindingDescription {
-                    input_rate: buffer.input_rate(),
-                    stride: buffer.stride()
-                },
-                buffer.format().iter()
-                    .map(|&(format, offset)| {
-                        VertexInputAttributeDescription {
-                            binding: i as u32,
-                            format,
-                            offset
-                        }
-                    })
-                    .collect()
-            )})
-            .unzip();
-        
-        let vertex_input_state = VertexInputState::new()
-            .bindings(bindings.into_iter().enumerate().map(|(i, description)| {
-                (i as u32, description)
-            }))
-            .attributes(attributes.into_iter().flatten().enumerate().map(|(i, description)| {
-                (i as u32, description)
-            }));
+        let vertex_input_state = build_vertex_input_state(&vertex_buffers);
+        let aabb = get_position_bounds(&vertex_buffers);
 
         Arc::new(
             Self {
@@ -579,16 +670,227 @@ impl Mesh {
                 vertex_count,
                 vertex_buffers,
                 vertex_input_state,
+                topology: PrimitiveTopology::TriangleList,
+                primitive_restart: false,
+                aabb,
             }
         )
     }
 
+    /// Creates a new mesh drawn with the given primitive topology.
+    /// `index` is the index count and the index buffer of the mesh, if the mesh is indexed.
+    /// If `primitive_restart` is `true`, the maximum index value (`u16::MAX` or `u32::MAX`) ends the current strip or fan.
+    /// 
+    /// # Runtime Errors
+    /// - Returns a runtime error message if primitive restart is enabled for a mesh without an index buffer.
+    /// - Returns a runtime error message if primitive restart is enabled for a topology other than the strip and fan topologies.
+    /// - Returns a runtime error message if the topology is a patch list. (tessellation is not supported)
+    /// 
+    pub fn new_with_topology<Iter>(
+        topology: PrimitiveTopology,
+        primitive_restart: bool,
+        index: Option<(u32, IndexBuffer)>,
+        vertex_count: u32,
+        vertex_buffers: Iter
+    ) -> Result<Arc<Self>, RuntimeError>
+    where Iter: IntoIterator<Item = Arc<dyn VertexBufferAbstract>>, Iter::IntoIter: ExactSizeIterator {
+        if topology == PrimitiveTopology::PatchList {
+            return Err(err!("The patch list topology is not supported."));
+        }
+
+        if primitive_restart {
+            // the index buffer is either `u16` or `u32`, so the restart index is always valid.
+            if index.is_none() {
+                return Err(err!("Primitive restart requires an index buffer. (topology: {:?})", topology));
+            }
+
+            if !is_strip_or_fan_topology(topology) {
+                return Err(err!("Primitive restart is only supported for the strip and fan topologies. (topology: {:?})", topology));
+            }
+        }
+
+        let vertex_buffers: Vec<_> = vertex_buffers.into_iter().collect();
+        let vertex_input_state = build_vertex_input_state(&vertex_buffers);
+        let aabb = get_position_bounds(&vertex_buffers);
+        let (index_count, index_buffer) = match index {
+            Some((index_count, index_buffer)) => (index_count, Some(index_buffer)),
+            None => (0, None),
+        };
+
+        Ok(Arc::new(Self {
+            index_count,
+            index_buffer,
+            vertex_count,
+            vertex_buffers,
+            vertex_input_state,
+            topology,
+            primitive_restart,
+            aabb,
+        }))
+    }
+
+    /// Load an indexed triangle list mesh from a Wavefront `.obj` file. See `obj::parse_obj`.
+    /// The vertex buffers are the positions, the normals and the texture coordinates in that order,
+    /// and the normals and the texture coordinates are only present if the file has them.
+    /// Returns the secondary command buffer that uploads the buffers, so that the caller can batch the uploads.
+    /// 
+    /// # Runtime Errors
+    /// - Returns a runtime error message if reading the file fails.
+    /// - Returns a runtime error message if the file is not a valid `.obj` file.
+    /// - Returns a runtime error message if creating the buffers or the command buffer fails.
+    /// 
+    pub fn from_obj_file(
+        path: &Path,
+        render_ctx: &Arc<RenderContext>
+    ) -> Result<(Arc<Self>, SecondaryAutoCommandBuffer), RuntimeError> {
+        let source = fs::read_to_string(path)
+            .map_err(|e| err!("Failed to read file: {} ({:?})", e.to_string(), path))?;
+        let data = super::obj::parse_obj(&source)?;
+
+        // create secondary command buffer.
+        let allocator = render_ctx.get_command_buffer_allocator();
+        let mut command_buffer_builder = AutoCommandBufferBuilder::secondary(
+            &allocator, 
+            render_ctx.get_queue_fmaily_index(), 
+            CommandBufferUsage::OneTimeSubmit, 
+            CommandBufferInheritanceInfo::default()
+        ).map_err(|e| err!("Secondary command buffer begining failed: {}", e.to_string()))?;
+
+        // create index buffer.
+        let index_count = data.indices.len() as u32;
+        let index_buffer = IndexBuffer::from_iter_u32(
+            data.indices,
+            render_ctx.ref_memory_allocator(),
+            &mut command_buffer_builder
+        )?;
+
+        // create vertex buffers.
+        let vertex_count = data.positions.len() as u32;
+        let mut vertex_buffers: Vec<Arc<dyn VertexBufferAbstract>> = vec![GpuVertexBuffer::from_iter_vec3(
+            data.positions,
+            VertexInputRate::Vertex,
+            render_ctx.ref_memory_allocator(),
+            &mut command_buffer_builder
+        )?];
+
+        if let Some(normals) = data.normals {
+            vertex_buffers.push(GpuVertexBuffer::from_iter_vec3(
+                normals,
+                VertexInputRate::Vertex,
+                render_ctx.ref_memory_allocator(),
+                &mut command_buffer_builder
+            )?);
+        }
+
+        if let Some(uvs) = data.uvs {
+            vertex_buffers.push(GpuVertexBuffer::from_iter_vec2(
+                uvs,
+                VertexInputRate::Vertex,
+                render_ctx.ref_memory_allocator(),
+                &mut command_buffer_builder
+            )?);
+        }
+
+        // build command buffer.
+        let command_buffer = command_buffer_builder
+            .build()
+            .map_err(|e| err!("Secondary command buffer building failed: {}", e.to_string()))?;
+
+        Ok((
+            Self::new_with_index(index_count, index_buffer, vertex_count, vertex_buffers),
+            command_buffer
+        ))
+    }
+
+    /// Reduce the number of triangles of an indexed triangle list to about `target_ratio` of the original.
+    /// Returns the positions and indices of the simplified mesh. See `simplify::simplify`.
+    /// 
+    /// # Panics
+    /// - Stop program execution if an index is out of range.
+    /// 
+    #[inline]
+    pub fn simplify(positions: &[Vec3], indices: &[u32], target_ratio: f32) -> (Vec<Vec3>, Vec<u32>) {
+        super::simplify::simplify(positions, indices, target_ratio)
+    }
+
+    /// Get the axis-aligned bounding box of the mesh. (minimum and maximum corners)
+    /// The box is computed from the first per-vertex `Vec3` buffer (the positions) when the mesh is created,
+    /// and it is empty at the origin if the mesh has no such buffer.
+    #[inline]
+    pub fn aabb(&self) -> (Vec3, Vec3) {
+        self.aabb
+    }
+
+    /// Get the bounding sphere that encloses the bounding box of the mesh. (center and radius)
+    #[inline]
+    pub fn bounding_sphere(&self) -> (Vec3, f32) {
+        let (min, max) = self.aabb;
+        ((min + max) * 0.5, (max - min).length() * 0.5)
+    }
+
     /// Borrow the `VertexInputState`.
     #[inline]
     pub fn get_vertex_input_state(&self) -> &VertexInputState {
         &self.vertex_input_state
     }
 
+    /// Get the primitive topology of the mesh.
+    #[inline]
+    pub fn get_topology(&self) -> PrimitiveTopology {
+        self.topology
+    }
+
+    /// Returns `true` if the mesh is drawn with primitive restart.
+    #[inline]
+    pub fn is_primitive_restart_enabled(&self) -> bool {
+        self.primitive_restart
+    }
+
+    /// Get the `InputAssemblyState` of the pipelines that draw the mesh.
+    #[inline]
+    pub fn get_input_assembly_state(&self) -> InputAssemblyState {
+        let state = InputAssemblyState::new().topology(self.topology);
+        match self.primitive_restart {
+            true => state.primitive_restart_enable(),
+            false => state,
+        }
+    }
+
+    /// Check that the input assembly state of the pipeline matches the topology of the mesh.
+    /// 
+    /// # Runtime Errors
+    /// - Returns a runtime error message if the pipeline topology does not match the topology of the mesh.
+    /// - Returns a runtime error message if the pipeline primitive restart does not match the mesh.
+    /// 
+    #[inline]
+    pub fn validate_pipeline(&self, pipeline: &GraphicsPipeline) -> Result<(), RuntimeError> {
+        self.validate_input_assembly_state(pipeline.input_assembly_state())
+    }
+
+    /// Check that the input assembly state matches the topology of the mesh. See `Mesh::validate_pipeline`.
+    /// 
+    /// # Runtime Errors
+    /// - Returns a runtime error message if the topology of the state does not match the topology of the mesh.
+    /// - Returns a runtime error message if the primitive restart of the state does not match the mesh.
+    /// 
+    pub fn validate_input_assembly_state(&self, state: &InputAssemblyState) -> Result<(), RuntimeError> {
+        let matches_topology = match state.topology {
+            PartialStateMode::Fixed(topology) => topology == self.topology,
+            PartialStateMode::Dynamic(class) => class == self.topology.class(),
+        };
+        if !matches_topology {
+            return Err(err!("The pipeline topology does not match the mesh. (pipeline: {:?}, mesh: {:?})", state.topology, self.topology));
+        }
+
+        if let StateMode::Fixed(primitive_restart) = state.primitive_restart_enable {
+            if primitive_restart != self.primitive_restart {
+                return Err(err!("The pipeline primitive restart does not match the mesh. (pipeline: {}, mesh: {})", primitive_restart, self.primitive_restart));
+            }
+        }
+
+        Ok(())
+    }
+
     /// Bind the mesh's buffer to the command buffer.
     /// 
     /// # Unsafety
@@ -655,3 +957,422 @@ impl Mesh {
         Ok(())
     }
 }
+
+
+
+/// Returns `true` if primitive restart can be used with the topology.
+#[inline]
+fn is_strip_or_fan_topology(topology: PrimitiveTopology) -> bool {
+    matches!(
+        topology,
+        PrimitiveTopology::LineStrip
+        | PrimitiveTopology::TriangleStrip
+        | PrimitiveTopology::TriangleFan
+        | PrimitiveTopology::LineStripWithAdjacency
+        | PrimitiveTopology::TriangleStripWithAdjacency
+    )
+}
+
+
+/// Build the vertex input state of the given vertex buffers.
+/// 
+/// Each buffer is bound to the binding of its index, and the attribute locations are
+/// assigned in order across all buffers. An attribute that is larger than 16 bytes
+/// (e.g. a 64-bit `vec3` or `vec4`) consumes two locations.
+/// 
+pub fn build_vertex_input_state(vertex_buffers: &[Arc<dyn VertexBufferAbstract>]) -> VertexInputState {
+    let mut vertex_input_state = VertexInputState::new();
+    let mut location = 0;
+    for (binding, buffer) in vertex_buffers.iter().enumerate() {
+        let binding = binding as u32;
+        vertex_input_state = vertex_input_state.binding(binding, VertexInputBindingDescription {
+            input_rate: buffer.input_rate(),
+            stride: buffer.stride()
+        });
+
+        for &(format, offset) in buffer.format().iter() {
+            vertex_input_state = vertex_input_state.attribute(location, VertexInputAttributeDescription {
+                binding,
+                format,
+                offset
+            });
+            location += get_num_locations(format);
+        }
+    }
+    vertex_input_state
+}
+
+
+/// Returns the bounds of the first per-vertex buffer that has bounds.
+/// Returns an empty box at the origin if there is no such buffer.
+#[inline]
+fn get_position_bounds(vertex_buffers: &[Arc<dyn VertexBufferAbstract>]) -> (Vec3, Vec3) {
+    vertex_buffers.iter()
+        .filter(|buffer| buffer.input_rate() == VertexInputRate::Vertex)
+        .find_map(|buffer| buffer.bounds())
+        .unwrap_or((Vec3::ZERO, Vec3::ZERO))
+}
+
+
+/// Returns the number of vertex input locations consumed by an attribute of the given format.
+#[inline]
+fn get_num_locations(format: Format) -> u32 {
+    (format.block_size().unwrap_or(16) as u32 + 15) / 16
+}
+
+
+/// Computes per-face normals for flat shading.
+/// 
+/// The vertices are not shared between triangles, so each triangle gets its own three vertices
+/// with the normal of the triangle. Returns the positions and normals of the non-indexed triangle list.
+/// The normal points to the side from which the triangle is wound counter-clockwise.
+/// 
+/// # Panics
+/// - Stop program execution if an index is out of range.
+/// 
+pub fn compute_flat_normals(positions: &[Vec3], indices: &[u32]) -> (Vec<Vec3>, Vec<Vec3>) {
+    let mut flat_positions = Vec::with_capacity(indices.len());
+    let mut normals = Vec::with_capacity(indices.len());
+    for triangle in indices.chunks_exact(3) {
+        let (p0, p1, p2) = (positions[triangle[0] as usize], positions[triangle[1] as usize], positions[triangle[2] as usize]);
+        let normal = (p1 - p0).cross(&(p2 - p0))
+            .try_normalized()
+            .unwrap_or(Vec3::ZERO);
+
+        flat_positions.extend_from_slice(&[p0, p1, p2]);
+        normals.extend_from_slice(&[normal; 3]);
+    }
+    (flat_positions, normals)
+}
+
+
+/// Computes per-vertex tangents for normal mapping.
+/// 
+/// Tangents are accumulated from the edge and uv deltas of each triangle,
+/// then orthonormalized against the vertex normal. The `w` component holds the sign of the bitangent.
+/// Vertices with degenerate uvs get an arbitrary tangent perpendicular to the normal.
+/// 
+/// # Panics
+/// - Stop program execution if `normals` or `uvs` do not have the same length as `positions`.
+/// - Stop program execution if an index is out of range.
+/// 
+pub fn compute_tangents(positions: &[Vec3], normals: &[Vec3], uvs: &[Vec2], indices: &[u32]) -> Vec<Vec4> {
+    assert_eq!(positions.len(), normals.len(), "normals must have the same length as positions.");
+    assert_eq!(positions.len(), uvs.len(), "uvs must have the same length as positions.");
+
+    let mut tangents = vec![Vec3::ZERO; positions.len()];
+    let mut bitangents = vec![Vec3::ZERO; positions.len()];
+    for triangle in indices.chunks_exact(3) {
+        let (i0, i1, i2) = (triangle[0] as usize, triangle[1] as usize, triangle[2] as usize);
+        let edge1 = positions[i1] - positions[i0];
+        let edge2 = positions[i2] - positions[i0];
+        let delta1 = uvs[i1] - uvs[i0];
+        let delta2 = uvs[i2] - uvs[i0];
+
+        let det = delta1.x * delta2.y - delta2.x * delta1.y;
+        if det.abs() <= f32::EPSILON {
+            continue;
+        }
+
+        let r = 1.0 / det;
+        let tangent = (edge1 * delta2.y - edge2 * delta1.y) * r;
+        let bitangent = (edge2 * delta1.x - edge1 * delta2.x) * r;
+        for i in [i0, i1, i2] {
+            tangents[i] += tangent;
+            bitangents[i] += bitangent;
+        }
+    }
+
+    normals.iter()
+        .zip(tangents.into_iter().zip(bitangents.into_iter()))
+        .map(|(normal, (tangent, bitangent))| {
+            // Gram-Schmidt orthogonalize.
+            let tangent = (tangent - *normal * normal.dot(&tangent))
+                .try_normalized()
+                .unwrap_or_else(|| {
+                    let axis = if normal.x.abs() < 0.9 { Vec3::X } else { Vec3::Y };
+                    (axis - *normal * normal.dot(&axis)).normalize()
+                });
+
+            let sign = if normal.cross(&tangent).dot(&bitangent) < 0.0 { -1.0 } else { 1.0 };
+            Vec4::new_vector(tangent.x, tangent.y, tangent.z, sign)
+        })
+        .collect()
+}
+
+
+
+/// Creates an indexed mesh with positions and normals.
+/// The index buffer uses 16-bit indices unless the vertex count exceeds `u16::MAX`.
+/// 
+/// - binding 0, location 0: the position. (`Vec3`)
+/// - binding 1, location 1: the normal. (`Vec3`)
+/// 
+/// # Runtime Error
+/// Return the `RuntimeError` if an error occurs while creating the buffers.
+/// 
+pub fn create_lit_mesh(
+    render_ctx: Arc<RenderContext>,
+    positions: Vec<Vec3>,
+    normals: Vec<Vec3>,
+    indices: Vec<u32>
+) -> Result<(Arc<Mesh>, SecondaryAutoCommandBuffer), RuntimeError> {
+    debug_assert!(positions.len() == normals.len(), "The mesh must have a normal for each vertex.");
+    debug_assert!(
+        indices.len() % 3 == 0 && indices.iter().all(|&index| (index as usize) < positions.len()),
+        "The indices must form a triangle list within the vertex count."
+    );
+
+    // create secondary command buffer.
+    let allocator = render_ctx.get_command_buffer_allocator();
+    let mut command_buffer_builder = AutoCommandBufferBuilder::secondary(
+        &allocator, 
+        render_ctx.get_queue_fmaily_index(), 
+        CommandBufferUsage::OneTimeSubmit, 
+        CommandBufferInheritanceInfo::default()
+    ).map_err(|e| err!("Secondary command buffer begining failed: {}", e.to_string()))?;
+
+    // create index buffer.
+    let index_count = indices.len() as u32;
+    let vertex_count = positions.len() as u32;
+    let index_buffer = if positions.len() > u16::MAX as usize {
+        IndexBuffer::from_iter_u32(
+            indices,
+            render_ctx.ref_memory_allocator(),
+            &mut command_buffer_builder
+        )?
+    } else {
+        IndexBuffer::from_iter_u16(
+            indices.into_iter().map(|index| index as u16),
+            render_ctx.ref_memory_allocator(),
+            &mut command_buffer_builder
+        )?
+    };
+
+    // create vertex buffers.
+    let positions = GpuVertexBuffer::from_iter_vec3(
+        positions,
+        VertexInputRate::Vertex,
+        render_ctx.ref_memory_allocator(),
+        &mut command_buffer_builder
+    )? as _;
+
+    let normals = GpuVertexBuffer::from_iter_vec3(
+        normals,
+        VertexInputRate::Vertex,
+        render_ctx.ref_memory_allocator(),
+        &mut command_buffer_builder
+    )? as _;
+
+    // build command buffer.
+    let command_buffer = command_buffer_builder
+        .build()
+        .map_err(|e| err!("Secondary command buffer building failed: {}", e.to_string()))?;
+
+    Ok((
+        Mesh::new_with_index(index_count, index_buffer, vertex_count, [positions, normals]), 
+        command_buffer
+    ))
+}
+
+
+/// Returns the positions, normals and indices of a uv-sphere of radius `1`.
+/// The triangles touching the poles are skipped where two of their vertices meet at the pole.
+/// 
+/// # Panics
+/// - Stop program execution if `rings` is less than `2` or `sectors` is less than `3`.
+/// 
+pub fn generate_sphere(rings: u32, sectors: u32) -> (Vec<Vec3>, Vec<Vec3>, Vec<u32>) {
+    assert!(rings >= 2, "the sphere needs at least two rings.");
+    assert!(sectors >= 3, "the sphere needs at least three sectors.");
+
+    let mut positions = Vec::with_capacity(((rings + 1) * (sectors + 1)) as usize);
+    for ring in 0..=rings {
+        let polar = std::f32::consts::PI * ring as f32 / rings as f32;
+        for sector in 0..=sectors {
+            let azimuth = 2.0 * std::f32::consts::PI * sector as f32 / sectors as f32;
+            positions.push(Vec3::new_vector(
+                polar.sin() * azimuth.cos(),
+                polar.cos(),
+                polar.sin() * azimuth.sin()
+            ));
+        }
+    }
+
+    // each cell is split into two triangles wound counter-clockwise seen from the outside.
+    let mut indices = Vec::with_capacity((6 * rings * sectors) as usize);
+    for ring in 0..rings {
+        for sector in 0..sectors {
+            let top = ring * (sectors + 1) + sector;
+            let bottom = top + sectors + 1;
+            if ring != 0 {
+                indices.extend([top, top + 1, bottom]);
+            }
+            if ring != rings - 1 {
+                indices.extend([bottom, top + 1, bottom + 1]);
+            }
+        }
+    }
+
+    // the normal of a point on the unit sphere is the point itself.
+    let normals = positions.clone();
+    (positions, normals, indices)
+}
+
+
+/// Returns the positions, normals and indices of a plane of size `2` on the xz-plane facing the +y axis,
+/// divided into `subdivisions` cells on each side.
+/// 
+/// # Panics
+/// - Stop program execution if `subdivisions` is zero.
+/// 
+pub fn generate_plane(subdivisions: u32) -> (Vec<Vec3>, Vec<Vec3>, Vec<u32>) {
+    assert!(subdivisions > 0, "the plane needs at least one subdivision.");
+
+    let mut positions = Vec::with_capacity(((subdivisions + 1) * (subdivisions + 1)) as usize);
+    for row in 0..=subdivisions {
+        let z = 1.0 - 2.0 * row as f32 / subdivisions as f32;
+        for column in 0..=subdivisions {
+            let x = -1.0 + 2.0 * column as f32 / subdivisions as f32;
+            positions.push(Vec3::new_vector(x, 0.0, z));
+        }
+    }
+
+    // each cell is split into two triangles wound counter-clockwise seen from above.
+    let mut indices = Vec::with_capacity((6 * subdivisions * subdivisions) as usize);
+    for row in 0..subdivisions {
+        for column in 0..subdivisions {
+            let near = row * (subdivisions + 1) + column;
+            let far = near + subdivisions + 1;
+            indices.extend([near, near + 1, far, far, near + 1, far + 1]);
+        }
+    }
+
+    let normals = vec![Vec3::Y; positions.len()];
+    (positions, normals, indices)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generated_meshes_have_a_vertex_per_grid_point() {
+        for (rings, sectors) in [(2, 3), (8, 16), (16, 32)] {
+            let (positions, normals, indices) = generate_sphere(rings, sectors);
+            assert_eq!(positions.len(), ((rings + 1) * (sectors + 1)) as usize);
+            assert_eq!(normals.len(), positions.len());
+            // the cells touching the poles have one triangle.
+            assert_eq!(indices.len(), (6 * sectors * (rings - 1)) as usize);
+            assert!(indices.iter().all(|&index| (index as usize) < positions.len()));
+        }
+
+        for subdivisions in [1, 4, 10] {
+            let (positions, normals, indices) = generate_plane(subdivisions);
+            assert_eq!(positions.len(), ((subdivisions + 1) * (subdivisions + 1)) as usize);
+            assert_eq!(normals.len(), positions.len());
+            assert_eq!(indices.len(), (6 * subdivisions * subdivisions) as usize);
+            assert!(indices.iter().all(|&index| (index as usize) < positions.len()));
+        }
+    }
+
+    #[test]
+    fn flat_quad_tangents_follow_u() {
+        // the quad faces +z and its uvs start at the top-left corner. (`v` grows toward -y)
+        let positions = [
+            Vec3::new_vector(-1.0, 1.0, 0.0),
+            Vec3::new_vector(-1.0, -1.0, 0.0),
+            Vec3::new_vector(1.0, -1.0, 0.0),
+            Vec3::new_vector(1.0, 1.0, 0.0),
+        ];
+        let normals = [Vec3::Z; 4];
+        let uvs = [
+            Vec2::new_vector(0.0, 0.0),
+            Vec2::new_vector(0.0, 1.0),
+            Vec2::new_vector(1.0, 1.0),
+            Vec2::new_vector(1.0, 0.0),
+        ];
+        let indices = [0, 1, 2, 2, 3, 0];
+
+        let tangents = compute_tangents(&positions, &normals, &uvs, &indices);
+        assert_eq!(tangents.len(), 4);
+        for tangent in tangents.iter() {
+            assert!((Vec3::new_vector(tangent.x, tangent.y, tangent.z) - Vec3::X).length() <= 1e-6, "{:?}", tangent);
+            assert_eq!(tangent.w, tangents[0].w);
+        }
+        // the bitangent follows `v`, which points away from `normal x tangent`.
+        assert_eq!(tangents[0].w, -1.0);
+    }
+
+
+    #[derive(Debug)]
+    struct MockVertexBuffer {
+        stride: u32,
+        format: Vec<(Format, u32)>,
+        input_rate: VertexInputRate,
+    }
+
+    impl VertexBufferAbstract for MockVertexBuffer {
+        fn stride(&self) -> u32 { self.stride }
+        fn format(&self) -> &[(Format, u32)] { &self.format }
+        fn input_rate(&self) -> VertexInputRate { self.input_rate }
+        fn buffer_access(&self) -> Subbuffer<[u8]> { unimplemented!() }
+    }
+
+    #[test]
+    fn vertex_input_locations_continue_across_buffers() {
+        let positions: Arc<dyn VertexBufferAbstract> = Arc::new(MockVertexBuffer {
+            stride: mem::size_of::<Vec3>() as u32,
+            format: vec![(Format::R32G32B32_SFLOAT, 0)],
+            input_rate: VertexInputRate::Vertex,
+        });
+        let transforms: Arc<dyn VertexBufferAbstract> = Arc::new(MockVertexBuffer {
+            stride: mem::size_of::<Mat4x4>() as u32,
+            format: vec![
+                (Format::R32G32B32A32_SFLOAT, offset_of!(Mat4x4, r1c1) as u32),
+                (Format::R32G32B32A32_SFLOAT, offset_of!(Mat4x4, r2c1) as u32),
+                (Format::R32G32B32A32_SFLOAT, offset_of!(Mat4x4, r3c1) as u32),
+                (Format::R32G32B32A32_SFLOAT, offset_of!(Mat4x4, r4c1) as u32),
+            ],
+            input_rate: VertexInputRate::Instance { divisor: 1 },
+        });
+
+        let state = build_vertex_input_state(&[positions, transforms]);
+        assert_eq!(state.bindings.len(), 2);
+        assert_eq!(state.bindings[&0].stride, 12);
+        assert_eq!(state.bindings[&1].stride, 64);
+        assert_eq!(state.bindings[&1].input_rate, VertexInputRate::Instance { divisor: 1 });
+
+        assert_eq!(state.attributes.len(), 5);
+        assert_eq!(state.attributes[&0].binding, 0);
+        assert_eq!(state.attributes[&0].format, Format::R32G32B32_SFLOAT);
+        for location in 1..5 {
+            let attribute = &state.attributes[&location];
+            assert_eq!(attribute.binding, 1);
+            assert_eq!(attribute.format, Format::R32G32B32A32_SFLOAT);
+            assert_eq!(attribute.offset, (location - 1) * 16);
+        }
+    }
+
+    #[test]
+    fn line_list_mesh_builds_a_line_list_pipeline_state() {
+        let mesh = Mesh::new_with_topology(
+            PrimitiveTopology::LineList, 
+            false, 
+            None, 
+            2, 
+            Vec::<Arc<dyn VertexBufferAbstract>>::new()
+        ).unwrap();
+        assert_eq!(mesh.get_topology(), PrimitiveTopology::LineList);
+
+        let state = mesh.get_input_assembly_state();
+        assert_eq!(state.topology, PartialStateMode::Fixed(PrimitiveTopology::LineList));
+        assert_eq!(state.primitive_restart_enable, StateMode::Fixed(false));
+        assert!(mesh.validate_input_assembly_state(&state).is_ok());
+
+        let triangles = InputAssemblyState::new().topology(PrimitiveTopology::TriangleList);
+        let msg = mesh.validate_input_assembly_state(&triangles).unwrap_err();
+        assert!(msg.what().contains("topology"), "{}", msg.what());
+    }
+}
@@ -4,3 +4,11 @@ pub mod scene;
 pub mod shader;
 pub mod object;
 pub mod variable;
+pub mod texture;
+pub mod bvh;
+pub mod loader;
+pub mod simplify;
+pub mod obj;
+pub mod bindless;
+pub mod pool;
+pub mod cache;
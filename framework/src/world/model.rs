@@ -197,6 +197,14 @@ where NodeID: fmt::Debug + Clone + Eq + Hash {
             })
             .collect();
 
+        // the pipeline of each node must match the topology of its mesh.
+        for node in nodes.iter() {
+            if let (Some(mesh), Some(shader)) = (&node.mesh, &node.shader) {
+                shader.validate_mesh(mesh)
+                    .map_err(|e| err!("Invalid model node. (node id: {:?}, model name: {}): {}", node.id, name, e.what()))?;
+            }
+        }
+
         if id_index_map.get(&root_id).is_none() {
             Err(err!("Invalid root ID."))
         }
@@ -218,6 +226,19 @@ where NodeID: fmt::Debug + Clone + Eq + Hash {
         }
     }
 
+    /// Get the node's index with the given node's ID.
+    /// 
+    /// # Runtime Errors
+    /// - Returns a runtime error message if the ID of the given node does not belong to the set of nodes in the model.
+    /// 
+    #[inline]
+    fn try_get_index(&self, id: &NodeID) -> Result<usize, RuntimeError> {
+        match self.id_index_map.get(id) {
+            Some(&index) => Ok(index),
+            None => Err(err!("Node ID not found in Model. (node id: {:?}, model name: {})", id, self.name)),
+        }
+    }
+
     /// Borrow a model node with the given index.
     /// 
     /// # Panics
@@ -391,6 +412,62 @@ where NodeID: fmt::Debug + Clone + Eq + Hash {
         }
     }
 
+    /// Check that the world matrix of every node matches its transform multiplied by the parent's world matrix.
+    /// The world matrices of the root and its siblings are taken as they are.
+    /// 
+    /// # Runtime Error
+    /// - Return the `RuntimeError` if the world matrix of a node differs from the expected matrix by more than `epsilon`.
+    /// 
+    pub fn validate_transforms(&self, epsilon: f32) -> Result<(), RuntimeError> {
+        self.validate_transforms_recursion(&self.root_id, None, epsilon)
+    }
+
+    fn validate_transforms_recursion(&self, id: &NodeID, parent_matrix: Option<Mat4x4>, epsilon: f32) -> Result<(), RuntimeError> {
+        let node = self.ref_node(self.try_get_index(id)?);
+        if let Some(parent_matrix) = parent_matrix {
+            let expected = node.transform * parent_matrix;
+            let consistent = expected.as_ref().iter()
+                .zip(node.world_matrix.as_ref().iter())
+                .all(|(a, b)| (a - b).abs() <= epsilon);
+            if !consistent {
+                return Err(err!("Inconsistent world matrix. (node id: {:?}, model name: {})", id, self.name));
+            }
+        }
+
+        if let Some(sibling) = &node.sibling {
+            self.validate_transforms_recursion(sibling, parent_matrix, epsilon)?;
+        }
+
+        if let Some(child) = &node.child {
+            self.validate_transforms_recursion(child, Some(node.world_matrix), epsilon)?;
+        }
+
+        Ok(())
+    }
+
+    /// Replace the meshes and shaders of the nodes.
+    /// `meshes` and `shaders` return the new resource of a current one, or `None` to keep it.
+    pub fn rebind_resources(
+        &mut self,
+        meshes: &dyn Fn(&Arc<Mesh>) -> Option<Arc<Mesh>>,
+        shaders: &dyn Fn(&Arc<GraphicsShader>) -> Option<Arc<GraphicsShader>>
+    ) {
+        for node in self.nodes.iter_mut() {
+            if let Some(mesh) = node.mesh.as_ref().and_then(|mesh| meshes(mesh)) {
+                node.mesh = Some(mesh);
+            }
+            if let Some(shader) = node.shader.as_ref().and_then(|shader| shaders(shader)) {
+                node.shader = Some(shader);
+            }
+        }
+    }
+
+    /// Returns an iterator over the nodes in the same order as `ref_nodes`, without collecting them.
+    #[inline]
+    pub fn iter(&self) -> ModelNodeIter<'_, NodeID> {
+        ModelNodeIter { model: self, stack: vec![self.get_index(&self.root_id)] }
+    }
+
     #[inline]
     pub fn ref_nodes(&self) -> Vec<&ModelNode<NodeID>> {
         let mut nodes = Vec::with_capacity(self.nodes.capacity());
@@ -411,3 +488,233 @@ where NodeID: fmt::Debug + Clone + Eq + Hash {
         }
     }
 }
+
+
+/// The non-panicking variants of the node functions, for node IDs that come from outside the model.
+/// (e.g. from the host application)
+/// 
+/// # Runtime Errors
+/// - Each function returns a runtime error message if the ID of the given node does not belong to the set of nodes in the model.
+/// 
+impl<NodeID> Model<NodeID> 
+where NodeID: fmt::Debug + Clone + Eq + Hash {
+    /// Returns the relative position of a node with the given ID.
+    pub fn try_get_position(&self, id: &NodeID) -> Result<Vec3, RuntimeError> {
+        Ok(self.ref_node(self.try_get_index(id)?).get_position())
+    }
+
+    /// Returns the relative rotation of a node with the given ID.
+    pub fn try_get_quaternion(&self, id: &NodeID) -> Result<Quat, RuntimeError> {
+        Ok(self.ref_node(self.try_get_index(id)?).get_quaternion())
+    }
+
+    /// Returns the relative right vector of a node with the given ID.
+    pub fn try_get_local_right_vector(&self, id: &NodeID) -> Result<Vec3, RuntimeError> {
+        Ok(self.ref_node(self.try_get_index(id)?).get_right_vector())
+    }
+
+    /// Returns the relative up vector of a node with the given ID.
+    pub fn try_get_local_up_vector(&self, id: &NodeID) -> Result<Vec3, RuntimeError> {
+        Ok(self.ref_node(self.try_get_index(id)?).get_up_vector())
+    }
+
+    /// Returns the relative look vector of a node with the given ID.
+    pub fn try_get_local_look_vector(&self, id: &NodeID) -> Result<Vec3, RuntimeError> {
+        Ok(self.ref_node(self.try_get_index(id)?).get_look_vector())
+    }
+
+    /// Sets the relative position of a node with the given ID.
+    pub fn try_set_position(&mut self, id: &NodeID, position: Vec3) -> Result<(), RuntimeError> {
+        let index = self.try_get_index(id)?;
+        self.mut_node(index).set_position(position);
+        self.update_transform(id, None);
+        Ok(())
+    }
+
+    /// Sets the relative rotation of a node with the given ID.
+    pub fn try_set_quaternion(&mut self, id: &NodeID, quaternion: Quat) -> Result<(), RuntimeError> {
+        let index = self.try_get_index(id)?;
+        self.mut_node(index).set_quaternion(quaternion);
+        self.update_transform(id, None);
+        Ok(())
+    }
+
+    /// Sets the relative rotation of a node with the given ID.
+    pub fn try_set_look_at_point(&mut self, id: &NodeID, point: Vec3) -> Result<(), RuntimeError> {
+        let index = self.try_get_index(id)?;
+        self.mut_node(index).set_look_at_point(point);
+        self.update_transform(id, None);
+        Ok(())
+    }
+
+    /// Moves the position of a node relative to the node's coordinate system.
+    pub fn try_translate_local(&mut self, id: &NodeID, distance: Vec3) -> Result<(), RuntimeError> {
+        let index = self.try_get_index(id)?;
+        self.mut_node(index).translate_local(distance);
+        self.update_transform(id, None);
+        Ok(())
+    }
+
+    /// Moves the position of a node relative to the world's coordinate system.
+    pub fn try_translate_world(&mut self, id: &NodeID, distance: Vec3) -> Result<(), RuntimeError> {
+        let index = self.try_get_index(id)?;
+        self.mut_node(index).translate_world(distance);
+        self.update_transform(id, None);
+        Ok(())
+    }
+
+    /// Rotates the orientation of a node by an angle with a given axis.
+    pub fn try_rotate_from_angle_axis(&mut self, id: &NodeID, angle_radian: f32, axis: Vec3) -> Result<(), RuntimeError> {
+        let index = self.try_get_index(id)?;
+        self.mut_node(index).rotate_from_angle_axis(angle_radian, axis);
+        self.update_transform(id, None);
+        Ok(())
+    }
+
+    /// Rotates the orientation of a node by a given quaternion.
+    pub fn try_rotate_from_quaternion(&mut self, id: &NodeID, quaternion: Quat) -> Result<(), RuntimeError> {
+        let index = self.try_get_index(id)?;
+        self.mut_node(index).rotate_from_quaternion(quaternion);
+        self.update_transform(id, None);
+        Ok(())
+    }
+}
+
+impl<'a, NodeID> IntoIterator for &'a Model<NodeID>
+where NodeID: fmt::Debug + Clone + Eq + Hash {
+    type Item = &'a ModelNode<NodeID>;
+    type IntoIter = ModelNodeIter<'a, NodeID>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+
+
+/// An iterator over the nodes of a model. See `Model::iter`.
+/// The sibling and child links are followed with an explicit stack of node indices.
+pub struct ModelNodeIter<'a, NodeID = String>
+where NodeID: fmt::Debug + Clone + Eq + Hash {
+    model: &'a Model<NodeID>,
+    stack: Vec<usize>,
+}
+
+impl<'a, NodeID> Iterator for ModelNodeIter<'a, NodeID>
+where NodeID: fmt::Debug + Clone + Eq + Hash {
+    type Item = &'a ModelNode<NodeID>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.model.ref_node(self.stack.pop()?);
+
+        // the sibling is pushed last, so its subtree is visited before the child.
+        if let Some(child) = &node.child {
+            self.stack.push(self.model.get_index(child));
+        }
+        if let Some(sibling) = &node.sibling {
+            self.stack.push(self.model.get_index(sibling));
+        }
+
+        Some(node)
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn single_node_model() -> Model {
+        let root = ModelNode {
+            id: "Root".to_string(),
+            transform: Mat4x4::from_translation(Vec3::new_vector(1.0, 2.0, 3.0)),
+            world_matrix: Mat4x4::IDENTITY,
+            mesh: None,
+            shader: None,
+            parent: None,
+            sibling: None,
+            child: None,
+        };
+        Model::from_nodes("Test", "Root".to_string(), [root]).unwrap()
+    }
+
+    fn node(id: &str, translation: Vec3, sibling: Option<&str>, child: Option<&str>) -> ModelNode {
+        ModelNode {
+            id: id.to_string(),
+            transform: Mat4x4::from_translation(translation),
+            world_matrix: Mat4x4::IDENTITY,
+            mesh: None,
+            shader: None,
+            parent: None,
+            sibling: sibling.map(str::to_string),
+            child: child.map(str::to_string),
+        }
+    }
+
+    /// `Root` has the children `Arm` and `Leg`, and `Arm` has the child `Hand`.
+    fn hierarchy_model() -> Model {
+        let nodes = [
+            node("Root", Vec3::new_vector(0.0, 1.0, 0.0), None, Some("Arm")),
+            node("Arm", Vec3::new_vector(1.0, 0.0, 0.0), Some("Leg"), Some("Hand")),
+            node("Leg", Vec3::new_vector(0.0, -1.0, 0.0), None, None),
+            node("Hand", Vec3::new_vector(0.5, 0.0, 0.0), None, None),
+        ];
+        let mut model = Model::from_nodes("Test", "Root".to_string(), nodes).unwrap();
+        model.update_transform(&"Root".to_string(), Some(Mat4x4::IDENTITY));
+        model
+    }
+
+    #[test]
+    fn unregistered_node_id_is_an_error() {
+        let mut model = single_node_model();
+        let missing = "Missing".to_string();
+
+        let msg = model.try_get_position(&missing).unwrap_err();
+        assert!(msg.what().contains("not found"), "{}", msg.what());
+        assert!(model.try_set_position(&missing, Vec3::ZERO).is_err());
+        assert!(model.try_rotate_from_quaternion(&missing, Quat::IDENTITY).is_err());
+    }
+
+    #[test]
+    fn registered_node_id_is_found() {
+        let mut model = single_node_model();
+        let root = "Root".to_string();
+
+        assert_eq!(model.try_get_position(&root).unwrap(), Vec3::new_vector(1.0, 2.0, 3.0));
+        model.try_set_position(&root, Vec3::ONE).unwrap();
+        assert_eq!(model.try_get_position(&root).unwrap(), Vec3::ONE);
+    }
+
+
+    #[test]
+    fn updated_hierarchy_has_valid_transforms() {
+        let model = hierarchy_model();
+        assert!(model.validate_transforms(1e-6).is_ok());
+
+        let hand = model.ref_node(model.get_index(&"Hand".to_string()));
+        assert_eq!(hand.world_matrix.r4c1, 1.5);
+        assert_eq!(hand.world_matrix.r4c2, 1.0);
+    }
+
+    #[test]
+    fn corrupted_node_fails_validation() {
+        let mut model = hierarchy_model();
+        let index = model.get_index(&"Hand".to_string());
+        model.mut_node(index).world_matrix = Mat4x4::IDENTITY;
+
+        let msg = model.validate_transforms(1e-6).unwrap_err();
+        assert!(msg.what().contains("Hand"), "{}", msg.what());
+    }
+
+    #[test]
+    fn iter_visits_nodes_in_the_ref_nodes_order() {
+        let model = hierarchy_model();
+        let iterated: Vec<&str> = model.iter().map(|node| node.id.as_str()).collect();
+        let referenced: Vec<&str> = model.ref_nodes().iter().map(|node| node.id.as_str()).collect();
+
+        assert_eq!(iterated, referenced);
+        assert_eq!(iterated, ["Root", "Arm", "Leg", "Hand"]);
+    }
+}
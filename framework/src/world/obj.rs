@@ -0,0 +1,195 @@
+use std::collections::HashMap;
+
+use crate::math::*;
+use crate::{err, error::RuntimeError};
+
+
+
+/// The vertex data of a Wavefront `.obj` file, ready to be uploaded as an indexed triangle list.
+/// 
+/// The vertices referencing the same position, texture coordinate and normal are shared.
+/// `normals` and `uvs` are `None` if the faces do not reference them.
+/// 
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ObjMeshData {
+    pub positions: Vec<Vec3>,
+    pub normals: Option<Vec<Vec3>>,
+    pub uvs: Option<Vec<Vec2>>,
+    pub indices: Vec<u32>,
+}
+
+
+/// The indices of the position, texture coordinate and normal of a face vertex. (zero-based)
+type FaceVertex = (usize, Option<usize>, Option<usize>);
+
+
+/// Parse the source of a Wavefront `.obj` file.
+/// 
+/// Only the `v`, `vt`, `vn` and `f` statements are used, and the other statements are ignored.
+/// Polygons are triangulated as a fan around their first vertex.
+/// The `v` texture coordinate is flipped, so that the top-left corner of the texture is `(0, 0)`.
+/// 
+/// # Runtime Error
+/// - Return the `RuntimeError` if a statement has missing or invalid numbers.
+/// - Return the `RuntimeError` if a face has less than three vertices or an index is out of range.
+/// - Return the `RuntimeError` if some face vertices have a texture coordinate or normal and others do not.
+/// - Return the `RuntimeError` if the file has no faces.
+/// 
+pub fn parse_obj(source: &str) -> Result<ObjMeshData, RuntimeError> {
+    let mut positions = Vec::new();
+    let mut uvs = Vec::new();
+    let mut normals = Vec::new();
+    let mut faces: Vec<FaceVertex> = Vec::new();
+    let mut layout: Option<(bool, bool)> = None;
+
+    for (line_index, line) in source.lines().enumerate() {
+        let line_number = line_index + 1;
+        let line = line.split('#').next().unwrap_or_default();
+        let mut tokens = line.split_whitespace();
+        let keyword = match tokens.next() {
+            Some(keyword) => keyword,
+            None => continue,
+        };
+
+        match keyword {
+            "v" => {
+                let [x, y, z] = parse_floats::<3>(&mut tokens, 3, line_number)?;
+                positions.push(Vec3::new_vector(x, y, z));
+            },
+            "vt" => {
+                let [u, v] = parse_floats::<2>(&mut tokens, 1, line_number)?;
+                uvs.push(Vec2::new_vector(u, 1.0 - v));
+            },
+            "vn" => {
+                let [x, y, z] = parse_floats::<3>(&mut tokens, 3, line_number)?;
+                normals.push(Vec3::new_vector(x, y, z));
+            },
+            "f" => {
+                let polygon = tokens
+                    .map(|token| parse_face_vertex(token, positions.len(), uvs.len(), normals.len(), line_number))
+                    .collect::<Result<Vec<_>, _>>()?;
+                if polygon.len() < 3 {
+                    return Err(err!("Invalid OBJ file: A face needs at least three vertices. (line: {})", line_number));
+                }
+
+                for &(_, uv, normal) in polygon.iter() {
+                    let vertex_layout = (uv.is_some(), normal.is_some());
+                    if *layout.get_or_insert(vertex_layout) != vertex_layout {
+                        return Err(err!(
+                            "Invalid OBJ file: All face vertices must reference the same attributes. (line: {})",
+                            line_number
+                        ));
+                    }
+                }
+
+                for i in 1..polygon.len() - 1 {
+                    faces.extend([polygon[0], polygon[i], polygon[i + 1]]);
+                }
+            },
+            _ => { /* materials, groups and other statements are not used. */ },
+        }
+    }
+
+    let (has_uvs, has_normals) = layout
+        .ok_or_else(|| err!("Invalid OBJ file: The file has no faces."))?;
+
+    // share the vertices with the same attributes.
+    let mut data = ObjMeshData {
+        normals: has_normals.then(Vec::new),
+        uvs: has_uvs.then(Vec::new),
+        ..Default::default()
+    };
+    let mut vertex_map: HashMap<FaceVertex, u32> = HashMap::new();
+    for vertex in faces {
+        let index = *vertex_map.entry(vertex).or_insert_with(|| {
+            let (position, uv, normal) = vertex;
+            data.positions.push(positions[position]);
+            if let (Some(uvs_out), Some(uv)) = (data.uvs.as_mut(), uv) {
+                uvs_out.push(uvs[uv]);
+            }
+            if let (Some(normals_out), Some(normal)) = (data.normals.as_mut(), normal) {
+                normals_out.push(normals[normal]);
+            }
+            (data.positions.len() - 1) as u32
+        });
+        data.indices.push(index);
+    }
+
+    Ok(data)
+}
+
+
+/// Parse `N` floats, of which the first `required` floats must be present. (the others default to `0.0`)
+/// Any further values, such as the `w` of a position, are ignored.
+#[inline]
+fn parse_floats<'a, const N: usize>(
+    tokens: &mut impl Iterator<Item = &'a str>,
+    required: usize,
+    line_number: usize
+) -> Result<[f32; N], RuntimeError> {
+    let mut values = [0.0; N];
+    for (i, value) in values.iter_mut().enumerate() {
+        match tokens.next() {
+            Some(token) => *value = token.parse::<f32>()
+                .map_err(|_| err!("Invalid OBJ file: '{}' is not a number. (line: {})", token, line_number))?,
+            None if i >= required => break,
+            None => return Err(err!("Invalid OBJ file: Expected {} numbers. (line: {})", required, line_number)),
+        }
+    }
+    Ok(values)
+}
+
+
+/// Parse a face vertex in the form of `v`, `v/vt`, `v//vn` or `v/vt/vn`.
+#[inline]
+fn parse_face_vertex(
+    token: &str,
+    num_positions: usize,
+    num_uvs: usize,
+    num_normals: usize,
+    line_number: usize
+) -> Result<FaceVertex, RuntimeError> {
+    let mut parts = token.split('/');
+    let position = parse_index(parts.next(), num_positions, token, line_number)?
+        .ok_or_else(|| err!("Invalid OBJ file: The face vertex '{}' has no position. (line: {})", token, line_number))?;
+    let uv = parse_index(parts.next(), num_uvs, token, line_number)?;
+    let normal = parse_index(parts.next(), num_normals, token, line_number)?;
+    if parts.next().is_some() {
+        return Err(err!("Invalid OBJ file: The face vertex '{}' has too many elements. (line: {})", token, line_number));
+    }
+
+    Ok((position, uv, normal))
+}
+
+
+/// Convert a one-based or negative (relative to the end) index to a zero-based index.
+/// An empty or missing index is `None`.
+#[inline]
+fn parse_index(
+    part: Option<&str>,
+    count: usize,
+    token: &str,
+    line_number: usize
+) -> Result<Option<usize>, RuntimeError> {
+    let part = match part {
+        Some(part) if !part.is_empty() => part,
+        _ => return Ok(None),
+    };
+
+    let index = part.parse::<i64>()
+        .map_err(|_| err!("Invalid OBJ file: The face vertex '{}' has an invalid index. (line: {})", token, line_number))?;
+    let resolved = match index {
+        1.. => index - 1,
+        ..=-1 => count as i64 + index,
+        0 => -1,
+    };
+
+    if resolved < 0 || resolved >= count as i64 {
+        return Err(err!(
+            "Invalid OBJ file: The index {} of the face vertex '{}' is out of range. (count: {}, line: {})",
+            index, token, count, line_number
+        ));
+    }
+
+    Ok(Some(resolved as usize))
+}
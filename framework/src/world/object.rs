@@ -4,6 +4,9 @@ use vulkano::command_buffer::{AutoCommandBufferBuilder, PrimaryAutoCommandBuffer
 
 use crate::math::*;
 use crate::renderer::RenderContext;
+use crate::world::bvh::BoundingSphere;
+use crate::world::mesh::Mesh;
+use crate::world::shader::GraphicsShader;
 use crate::{err, error::RuntimeError};
 
 
@@ -11,6 +14,12 @@ pub trait GameObject : Sync + Send { }
 
 pub trait DrawAttributePrimary {
     fn draw(&self, _render_ctx: &Arc<RenderContext>, _command_buffer_builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>) -> Result<(), RuntimeError> { Ok(()) }
+
+    /// Record the draws of the object into a pass that only reads its positions with the given shader.
+    /// (e.g. a cascade of a shadow map or the geometry pass of the ambient occlusion)
+    /// The shader reads the world matrix from the pushed object data, not from the instance transform buffer.
+    /// Default does nothing. (e.g. objects that cast no shadows)
+    fn draw_depth(&self, _shader: &Arc<GraphicsShader>, _command_buffer_builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>) -> Result<(), RuntimeError> { Ok(()) }
 }
 
 pub trait DrawAttributeSecondary {
@@ -19,6 +28,11 @@ pub trait DrawAttributeSecondary {
 
 pub trait DrawableObject : DrawAttributePrimary + DrawAttributeSecondary + GameObject {
     fn is_visible(&self) -> bool { false }
+
+    /// Write the per-object uniform data of the object. Called before the draw commands are recorded.
+    /// `frame_index` is the index of the swapchain image being drawn.
+    /// Default does nothing. (e.g. objects that use push constants)
+    fn upload_uniforms(&self, _render_ctx: &Arc<RenderContext>, _frame_index: u32) -> Result<(), RuntimeError> { Ok(()) }
 }
 
 
@@ -152,6 +166,60 @@ pub trait WorldObject : DrawableObject + DynamicObject {
         *mat = rot * mat.clone();
     }
 
+    /// Returns the shader used instead of the model's shaders when drawing. Default is `None`.
+    /// (e.g. selection highlighting, debug visualization)
+    #[inline]
+    fn ref_shader_override(&self) -> Option<&Arc<GraphicsShader>> { None }
+
+    /// Returns the bounding sphere of the object in world space, which is used for picking.
+    /// Default is `None`. (the object cannot be picked)
+    #[inline]
+    fn get_bounding_sphere(&self) -> Option<BoundingSphere> { None }
+
+    /// Returns the level of detail to draw for the radius of the object on the screen. (pixel)
+    /// `0` is the most detailed level. Default is `0`.
+    #[inline]
+    fn lod_level(&self, _pixel_radius: f32) -> usize { 0 }
+
+    /// Set the color of the object. Returns `false` if the object has no color. Default is `false`.
+    #[inline]
+    fn set_color(&mut self, _color: Vec4) -> bool { false }
+
+    /// Set the animation speed of the object. Returns `false` if the object is not animated. Default is `false`.
+    #[inline]
+    fn set_speed(&mut self, _speed: f32) -> bool { false }
+
+    /// Store the current transform as the previous update state. Called before each fixed-rate update. Default does nothing.
+    #[inline]
+    fn store_previous_transform(&mut self) { }
+
+    /// Draw the object at the blend of the previous and the current update state.
+    /// `alpha` is the fraction of the update interval elapsed since the last update. (`0` to `1`)
+    /// Default does nothing.
+    #[inline]
+    fn interpolate_transform(&mut self, _alpha: f32) { }
+
+    /// Returns the transform the object is drawn with. (e.g. the interpolated transform)
+    /// Default is the transform of the object.
+    #[inline]
+    fn get_draw_transform(&self) -> Mat4x4 { *self.ref_transform() }
+
+    /// Set the index of the object's transform in the instance transform buffer of the scene.
+    /// Objects that read the transform from the buffer draw with the index as the first instance.
+    /// Default does nothing.
+    #[inline]
+    fn set_instance_index(&mut self, _index: u32) { }
+
+    /// Replace the meshes and shaders of the object after the renderer is recreated.
+    /// `meshes` and `shaders` return the recreated resource of a previous one, or `None` to keep it.
+    /// Default does nothing.
+    #[inline]
+    fn rebind_resources(
+        &mut self,
+        _meshes: &dyn Fn(&Arc<Mesh>) -> Option<Arc<Mesh>>,
+        _shaders: &dyn Fn(&Arc<GraphicsShader>) -> Option<Arc<GraphicsShader>>
+    ) { }
+
     fn ref_transform(&self) -> &Mat4x4;
     
     fn mut_transform(&mut self) -> &mut Mat4x4;
@@ -0,0 +1,152 @@
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::collections::HashMap;
+
+
+
+/// Statistics of an `ObjectPool`.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ObjectPoolStats {
+    /// The number of objects allocated by the pool.
+    pub num_allocated: u32,
+    /// The number of acquisitions served by a recycled object.
+    pub num_reused: u32,
+    /// The number of objects currently in use.
+    pub num_active: u32,
+    /// The number of released objects waiting to be reused.
+    pub num_free: u32,
+}
+
+
+
+/// Recycles the allocations of released objects.
+/// 
+/// An acquired object is shared as an `Arc<Mutex<T>>`, which may be coerced to a trait object.
+/// When it is released, its allocation is kept and reused by the next acquisition
+/// instead of being freed and allocated again.
+pub struct ObjectPool<T> {
+    free: Vec<Arc<Mutex<T>>>,
+    active: HashMap<usize, Arc<Mutex<T>>>,
+    num_allocated: u32,
+    num_reused: u32,
+}
+
+impl<T> ObjectPool<T> {
+    #[inline]
+    pub fn new() -> Self {
+        Self { free: Vec::new(), active: HashMap::new(), num_allocated: 0, num_reused: 0 }
+    }
+
+    /// Take an object out of the pool.
+    /// If a released object is available, its fields are overwritten with `object` and its allocation is reused.
+    pub fn acquire(&mut self, object: T) -> Arc<Mutex<T>> {
+        let slot = match self.free.pop() {
+            Some(slot) => {
+                *slot.lock().unwrap() = object;
+                self.num_reused += 1;
+                slot
+            },
+            None => {
+                self.num_allocated += 1;
+                Arc::new(Mutex::new(object))
+            }
+        };
+
+        self.active.insert(get_address(&slot), slot.clone());
+        slot
+    }
+
+    /// Return an object to the pool. The object may be a trait object coerced from the acquired object.
+    /// Returns `false` if the object was not acquired from this pool.
+    /// 
+    /// The allocation is only reused once no other reference to the object is left.
+    /// If the object is still shared elsewhere, it is dropped from the pool instead.
+    pub fn release<U: ?Sized>(&mut self, object: Arc<Mutex<U>>) -> bool {
+        let address = get_address(&object);
+        drop(object);
+
+        match self.active.remove(&address) {
+            Some(slot) => {
+                if Arc::strong_count(&slot) == 1 {
+                    self.free.push(slot);
+                }
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Release all objects and free the recycled allocations.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.free.clear();
+        self.active.clear();
+    }
+
+    #[inline]
+    pub fn get_stats(&self) -> ObjectPoolStats {
+        ObjectPoolStats {
+            num_allocated: self.num_allocated,
+            num_reused: self.num_reused,
+            num_active: self.active.len() as u32,
+            num_free: self.free.len() as u32,
+        }
+    }
+}
+
+impl<T> Default for ObjectPool<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> fmt::Debug for ObjectPool<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ObjectPool")
+            .field("stats", &self.get_stats())
+            .finish()
+    }
+}
+
+
+/// Get the address of the shared object. It is the same for the object and the trait objects coerced from it.
+#[inline]
+fn get_address<U: ?Sized>(object: &Arc<Mutex<U>>) -> usize {
+    Arc::as_ptr(object) as *const () as usize
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn released_object_is_reused_by_the_next_acquisition() {
+        let mut pool = ObjectPool::new();
+        let object = pool.acquire(1u32);
+        let address = get_address(&object);
+        assert!(pool.release(object));
+        assert_eq!(pool.get_stats(), ObjectPoolStats { num_allocated: 1, num_reused: 0, num_active: 0, num_free: 1 });
+
+        let object = pool.acquire(2u32);
+        assert_eq!(get_address(&object), address);
+        assert_eq!(*object.lock().unwrap(), 2);
+        assert_eq!(pool.get_stats(), ObjectPoolStats { num_allocated: 1, num_reused: 1, num_active: 1, num_free: 0 });
+    }
+
+    #[test]
+    fn shared_object_is_not_reused() {
+        let mut pool = ObjectPool::new();
+        let object = pool.acquire(1u32);
+        let shared = object.clone();
+        assert!(pool.release(object));
+        assert!(!pool.release(shared));
+
+        pool.acquire(2u32);
+        assert_eq!(pool.get_stats().num_allocated, 2);
+        assert_eq!(pool.get_stats().num_reused, 0);
+    }
+}
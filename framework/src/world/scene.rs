@@ -1,9 +1,13 @@
 use std::fmt;
 use std::hash::Hash;
+use std::path::PathBuf;
 use std::collections::{VecDeque, HashMap};
 
+use crate::math::*;
 use crate::timer::*;
 use crate::renderer::*;
+use crate::input::TouchEvent;
+use crate::world::pool::ObjectPoolStats;
 use crate::{err, error::RuntimeError};
 
 
@@ -65,6 +69,17 @@ where SceneID: fmt::Debug + Clone + Eq + Hash {
             .clone()
     }
 
+    /// Borrow the scene node.
+    /// 
+    /// # Panics
+    /// Stop program execution if scene node is not registered in scene manager.
+    /// 
+    #[inline]
+    fn ref_scene_node(&self, id: &SceneID) -> &Box<dyn SceneNode<SceneID>> {
+        self.nodes.get(id)
+            .expect("Logic Error: Node not registered in scene manager.")
+    }
+
     /// Borrow the scene node.
     /// 
     /// # Panics
@@ -138,11 +153,152 @@ where SceneID: fmt::Debug + Clone + Eq + Hash {
             }
         }
 
-        curr_node.update(timer, renderer)?;
-        curr_node.draw(renderer)?;
+        // a scene node that is not ready yet is skipped instead of failing the frame.
+        if curr_node.is_ready() {
+            curr_node.update(timer, renderer)?;
+            curr_node.draw(renderer)?;
+        }
         
         Ok(())
     }
+
+    /// Set the color of an object in the current scene.
+    /// 
+    /// # Runtime Errors
+    /// - Returns a runtime error message if the object does not exist or has no color.
+    /// 
+    /// # Panics
+    /// - Stop program execution if there is no current node.
+    /// - Stop program execution if scene node is not registered in scene manager.
+    /// 
+    #[inline]
+    pub fn set_object_color(&mut self, object_id: usize, color: Vec4) -> Result<(), RuntimeError> {
+        self.mut_scene_node(&self.get_current_id()).set_object_color(object_id, color)
+    }
+
+    /// Set the animation speed of an object in the current scene.
+    /// 
+    /// # Runtime Errors
+    /// - Returns a runtime error message if the object does not exist or is not animated.
+    /// 
+    /// # Panics
+    /// - Stop program execution if there is no current node.
+    /// - Stop program execution if scene node is not registered in scene manager.
+    /// 
+    #[inline]
+    pub fn set_object_speed(&mut self, object_id: usize, speed: f32) -> Result<(), RuntimeError> {
+        self.mut_scene_node(&self.get_current_id()).set_object_speed(object_id, speed)
+    }
+
+    /// Spawn an object in the current scene. Returns the id of the object.
+    /// Returns `None` if the scene cannot spawn more objects.
+    /// 
+    /// # Panics
+    /// - Stop program execution if there is no current node.
+    /// - Stop program execution if scene node is not registered in scene manager.
+    /// 
+    #[inline]
+    pub fn spawn_object(&mut self) -> Option<usize> {
+        self.mut_scene_node(&self.get_current_id()).spawn_object()
+    }
+
+    /// Despawn an object in the current scene.
+    /// Returns `false` if the object does not exist.
+    /// 
+    /// # Panics
+    /// - Stop program execution if there is no current node.
+    /// - Stop program execution if scene node is not registered in scene manager.
+    /// 
+    #[inline]
+    pub fn despawn_object(&mut self, object_id: usize) -> bool {
+        self.mut_scene_node(&self.get_current_id()).despawn_object(object_id)
+    }
+
+    /// Get the object pool statistics of the current scene.
+    /// Returns `None` if the scene has no object pool.
+    /// 
+    /// # Panics
+    /// - Stop program execution if there is no current node.
+    /// - Stop program execution if scene node is not registered in scene manager.
+    /// 
+    #[inline]
+    pub fn get_object_pool_stats(&mut self) -> Option<ObjectPoolStats> {
+        self.mut_scene_node(&self.get_current_id()).get_object_pool_stats()
+    }
+
+    /// Shake the camera of the current scene.
+    /// Returns `false` if the scene has no camera.
+    /// 
+    /// # Panics
+    /// - Stop program execution if there is no current node.
+    /// - Stop program execution if scene node is not registered in scene manager.
+    /// 
+    #[inline]
+    pub fn trigger_camera_shake(&mut self, intensity: f32, duration_in_sec: f32) -> bool {
+        self.mut_scene_node(&self.get_current_id()).trigger_camera_shake(intensity, duration_in_sec)
+    }
+
+    /// Describe the state of the current scene for bug reports.
+    /// Returns an empty string if there is no current scene.
+    /// 
+    /// # Panics
+    /// - Stop program execution if scene node is not registered in scene manager.
+    /// 
+    pub fn debug_dump(&self) -> String {
+        match self.stack.back() {
+            Some(id) => format!("scene: {:?}\n{}", id, self.ref_scene_node(id).debug_dump()),
+            None => String::new(),
+        }
+    }
+
+    /// Pass the touch events to the current scene.
+    /// 
+    /// # Runtime Error
+    /// Return the `RuntimeError` if an error occurs while handling the events.
+    /// 
+    /// # Panics
+    /// - Stop program execution if there is no current node.
+    /// - Stop program execution if scene node is not registered in scene manager.
+    /// 
+    pub fn touch(&mut self, events: &[TouchEvent], renderer: &Renderer) -> Result<(), RuntimeError> {
+        let node = self.mut_scene_node(&self.get_current_id());
+        for event in events.iter() {
+            node.touch(event, renderer)?;
+        }
+        Ok(())
+    }
+
+    /// Recreate the GPU resources of the current scene after the renderer is recreated.
+    /// 
+    /// # Runtime Error
+    /// Return the `RuntimeError` if an error occurs while recreating the resources.
+    /// 
+    /// # Panics
+    /// - Stop program execution if there is no current node.
+    /// - Stop program execution if scene node is not registered in scene manager.
+    /// 
+    #[inline]
+    pub fn recreate_resources(&mut self, renderer: &Renderer) -> Result<(), RuntimeError> {
+        self.mut_scene_node(&self.get_current_id()).recreate_resources(renderer)
+    }
+
+    /// Pass the shaders reloaded since the last call to the current scene to rebuild its pipelines.
+    /// See `Renderer::take_reloaded_shaders`.
+    /// 
+    /// # Runtime Error
+    /// Return the `RuntimeError` if an error occurs while rebuilding the pipelines.
+    /// 
+    /// # Panics
+    /// - Stop program execution if there is no current node.
+    /// - Stop program execution if scene node is not registered in scene manager.
+    /// 
+    pub fn reload_shaders(&mut self, renderer: &mut Renderer) -> Result<(), RuntimeError> {
+        let reloaded = renderer.take_reloaded_shaders();
+        if reloaded.is_empty() {
+            return Ok(());
+        }
+        self.mut_scene_node(&self.get_current_id()).reload_shaders(&reloaded, renderer)
+    }
 }
 
 
@@ -181,6 +337,10 @@ where SceneID: fmt::Debug + Clone + Eq + Hash {
     /// 
     fn resume(&mut self, timer: &Timer, renderer: &Renderer) -> Result<(), RuntimeError> { Ok(()) }
 
+    /// Returns `true` if the scene node can be updated and drawn.
+    /// The scene manager skips the update and draw of a scene node that is not ready. Default is `true`.
+    fn is_ready(&self) -> bool { true }
+
     /// This function is called when updating a scene node.
     /// 
     /// # Runtime Error
@@ -194,4 +354,52 @@ where SceneID: fmt::Debug + Clone + Eq + Hash {
     /// Return the `RuntimeError` if an error occurs while drawing the scene node.
     /// 
     fn draw(&mut self, renderer: &mut Renderer) -> Result<(), RuntimeError> { Ok(()) }
+
+    /// Set the color of an object in the scene node. Default returns an error because the scene node has no objects.
+    fn set_object_color(&mut self, object_id: usize, color: Vec4) -> Result<(), RuntimeError> {
+        Err(err!("Object ID not found in scene. (object id: {})", object_id))
+    }
+
+    /// Set the animation speed of an object in the scene node. Default returns an error because the scene node has no objects.
+    fn set_object_speed(&mut self, object_id: usize, speed: f32) -> Result<(), RuntimeError> {
+        Err(err!("Object ID not found in scene. (object id: {})", object_id))
+    }
+
+    /// Spawn an object in the scene node. Returns the id of the object, or `None` if no object can be spawned. Default is `None`.
+    fn spawn_object(&mut self) -> Option<usize> { None }
+
+    /// Despawn an object in the scene node. Returns `false` if the object is not found. Default is `false`.
+    fn despawn_object(&mut self, object_id: usize) -> bool { false }
+
+    /// Get the statistics of the object pool of the scene node. Default is `None`.
+    fn get_object_pool_stats(&self) -> Option<ObjectPoolStats> { None }
+
+    /// Shake the camera of the scene node. Returns `false` if the scene node has no camera. Default is `false`.
+    fn trigger_camera_shake(&mut self, intensity: f32, duration_in_sec: f32) -> bool { false }
+
+    /// Describe the state of the scene node (e.g. the objects and the camera) as human-readable lines. Default is an empty string.
+    fn debug_dump(&self) -> String { String::new() }
+
+    /// Handle a touch event received since the last frame. Called before `update`.
+    /// 
+    /// # Runtime Error
+    /// Return the `RuntimeError` if an error occurs while handling the event.
+    /// 
+    fn touch(&mut self, event: &TouchEvent, renderer: &Renderer) -> Result<(), RuntimeError> { Ok(()) }
+
+    /// Recreate the GPU resources of the scene node after the renderer is recreated.
+    /// The logical state of the scene node (e.g. object transforms and the camera) should be preserved.
+    /// 
+    /// # Runtime Error
+    /// Return the `RuntimeError` if an error occurs while recreating the resources.
+    /// 
+    fn recreate_resources(&mut self, renderer: &Renderer) -> Result<(), RuntimeError> { Ok(()) }
+
+    /// Rebuild the pipelines that use the reloaded shaders. (the paths are resolved against the assets directory)
+    /// The reloaded modules can be taken with `Renderer::get_reloaded_shader`.
+    /// 
+    /// # Runtime Error
+    /// Return the `RuntimeError` if an error occurs while rebuilding the pipelines.
+    /// 
+    fn reload_shaders(&mut self, reloaded: &[PathBuf], renderer: &Renderer) -> Result<(), RuntimeError> { Ok(()) }
 }
@@ -1,14 +1,22 @@
 use std::sync::Arc;
 use std::collections::HashMap;
+use std::hash::BuildHasher;
 
 use vulkano::buffer::BufferContents;
 use vulkano::descriptor_set::PersistentDescriptorSet;
 use vulkano::descriptor_set::allocator::StandardDescriptorSetAllocator;
 use vulkano::pipeline::{GraphicsPipeline, PipelineBindPoint, Pipeline};
+use vulkano::pipeline::layout::PushConstantRange;
+use vulkano::pipeline::StateMode;
+use vulkano::pipeline::graphics::rasterization::{RasterizationState, PolygonMode, CullMode, FrontFace, DepthBiasState, DepthBias};
 use vulkano::command_buffer::AutoCommandBufferBuilder;
 use vulkano::command_buffer::allocator::CommandBufferAllocator;
+use vulkano::shader::DescriptorBindingRequirements;
 
 use crate::world::variable::ShaderVariableAbstract;
+use crate::world::bindless::BINDLESS_SET_INDEX;
+use crate::world::mesh::Mesh;
+use crate::renderer::RenderContext;
 use crate::{err, error::RuntimeError};
 
 
@@ -37,6 +45,8 @@ impl GraphicsShader {
             })
         );
 
+        validate_variables(&pipeline, &variables)?;
+
         let descriptor_set = if !variables.is_empty() {
             let descriptor_writes: Vec<_> = variables
                 .iter()
@@ -45,7 +55,10 @@ impl GraphicsShader {
                 })
                 .collect();
 
-            let layout = pipeline.layout().set_layouts().get(0).unwrap().clone();
+            let layout = match pipeline.layout().set_layouts().get(0) {
+                Some(layout) => layout.clone(),
+                None => return Err(err!("The pipeline has no descriptor set layout.")),
+            };
             let descriptor_set = match PersistentDescriptorSet::new(
                 allocator, 
                 layout, 
@@ -68,6 +81,18 @@ impl GraphicsShader {
         }))
     }
 
+    /// Check that the mesh can be drawn with the pipeline of the shader. See `Mesh::validate_pipeline`.
+    #[inline]
+    pub fn validate_mesh(&self, mesh: &Mesh) -> Result<(), RuntimeError> {
+        mesh.validate_pipeline(&self.pipeline)
+    }
+
+    /// Returns the push constant ranges of the pipeline layout.
+    #[inline]
+    pub fn ref_push_constant_ranges(&self) -> &[PushConstantRange] {
+        self.pipeline.layout().push_constant_ranges()
+    }
+
     #[inline]
     pub unsafe fn bind_pipeline<L, A: CommandBufferAllocator>(
         &self, 
@@ -102,10 +127,173 @@ impl GraphicsShader {
         Pc: BufferContents,
         A: CommandBufferAllocator,
     {
+        debug_assert!(
+            self.ref_push_constant_ranges().iter().any(|range| {
+                range.offset <= offset && offset + std::mem::size_of_val(&push_constants) as u32 <= range.offset + range.size
+            }),
+            "Push constants out of range. (offset: {})", offset
+        );
         command_buffer_builder.push_constants(
             self.pipeline.layout().clone(), 
             offset, 
             push_constants
         );
     }
-}
\ No newline at end of file
+}
+
+
+/// The rasterization settings of a graphics pipeline.
+/// 
+/// The front face is decided by the winding of the triangle on the screen.
+/// With the left-handed camera of the framework, a triangle `(p0, p1, p2)` is counter-clockwise
+/// when `(p1 - p0).cross(&(p2 - p0))` points to the outside of the mesh.
+#[derive(Debug, Clone, Copy)]
+pub struct RasterizationSettings {
+    /// `PolygonMode::Line` draws the mesh as wireframe.
+    pub polygon_mode: PolygonMode,
+    pub cull_mode: CullMode,
+    pub front_face: FrontFace,
+    /// The constant and slope factors added to the depth of every fragment. `None` disables the depth bias.
+    pub depth_bias: Option<DepthBias>,
+}
+
+impl Default for RasterizationSettings {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            polygon_mode: PolygonMode::Fill,
+            cull_mode: CullMode::Back,
+            front_face: FrontFace::CounterClockwise,
+            depth_bias: None,
+        }
+    }
+}
+
+
+/// Create the rasterization state of a graphics pipeline with the given settings.
+/// 
+/// # Runtime Error
+/// - Return the `RuntimeError` if the polygon mode is not `PolygonMode::Fill` and the device does not enable the `fill_mode_non_solid` feature.
+/// - Return the `RuntimeError` if the depth bias factors are not finite numbers.
+/// 
+pub fn create_rasterization_state(
+    settings: &RasterizationSettings,
+    render_ctx: &RenderContext,
+) -> Result<RasterizationState, RuntimeError> {
+    if settings.polygon_mode != PolygonMode::Fill && !render_ctx.supports_non_solid_fill_mode() {
+        return Err(err!("Polygon mode {:?} requires the fill_mode_non_solid device feature, which is not supported.", settings.polygon_mode));
+    }
+
+    let mut state = RasterizationState::new()
+        .polygon_mode(settings.polygon_mode)
+        .cull_mode(settings.cull_mode)
+        .front_face(settings.front_face);
+
+    if let Some(bias) = settings.depth_bias {
+        if !bias.constant_factor.is_finite() || !bias.slope_factor.is_finite() || !bias.clamp.is_finite() {
+            return Err(err!("The depth bias factors must be finite numbers. ({:?})", bias));
+        }
+
+        state.depth_bias = Some(DepthBiasState {
+            enable_dynamic: false,
+            bias: StateMode::Fixed(bias),
+        });
+    }
+
+    Ok(state)
+}
+
+
+/// Check the given shader variables against the descriptor requirements reflected from the pipeline's shaders.
+/// 
+/// # Runtime Error
+/// - Return the `RuntimeError` if the shaders use a descriptor set other than set 0 and the bindless texture set.
+/// - Return the `RuntimeError` if a binding used by the shaders has no shader variable.
+/// - Return the `RuntimeError` if the type of a shader variable does not match the type expected by the shaders.
+/// - Return the `RuntimeError` if a shader variable is given at a binding the shaders do not use.
+/// 
+pub fn validate_variables(
+    pipeline: &GraphicsPipeline,
+    variables: &HashMap<u32, Arc<dyn ShaderVariableAbstract>>,
+) -> Result<(), RuntimeError> {
+    check_descriptor_requirements(pipeline.descriptor_binding_requirements(), variables)
+}
+
+
+/// Check the given shader variables against the descriptor requirements. (see `validate_variables`)
+fn check_descriptor_requirements<H: BuildHasher>(
+    requirements: &HashMap<(u32, u32), DescriptorBindingRequirements, H>,
+    variables: &HashMap<u32, Arc<dyn ShaderVariableAbstract>>,
+) -> Result<(), RuntimeError> {
+    for (&(set, binding), requirement) in requirements.iter() {
+        // the bindless texture set is bound by `BindlessTextures`, not by the shader variables.
+        if set == BINDLESS_SET_INDEX {
+            continue;
+        }
+
+        if set != 0 {
+            return Err(err!("Only descriptor set 0 is supported. (set: {}, binding: {})", set, binding));
+        }
+
+        let variable = match variables.get(&binding) {
+            Some(variable) => variable,
+            None => return Err(err!("Missing shader variable at binding {}. (expected: {:?})", binding, requirement.descriptor_types)),
+        };
+
+        if !requirement.descriptor_types.contains(&variable.descriptor_type()) {
+            return Err(err!(
+                "Shader variable type mismatch at binding {}. (expected: {:?}, provided: {:?})", 
+                binding, requirement.descriptor_types, variable.descriptor_type()
+            ));
+        }
+    }
+
+    for (&binding, variable) in variables.iter() {
+        if !requirements.contains_key(&(0, binding)) {
+            return Err(err!("The shaders do not use binding {}. (provided: {:?})", binding, variable.descriptor_type()));
+        }
+    }
+
+    Ok(())
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use vulkano::descriptor_set::WriteDescriptorSet;
+    use vulkano::descriptor_set::layout::DescriptorType;
+
+    use crate::world::variable::ShaderVariableAccess;
+    use super::*;
+
+    #[derive(Debug)]
+    struct MockVariable(DescriptorType);
+
+    impl ShaderVariableAbstract for MockVariable {
+        fn write_descriptor(&self, _binding: u32) -> WriteDescriptorSet { unimplemented!() }
+        fn access(&self) -> ShaderVariableAccess { unimplemented!() }
+        fn descriptor_type(&self) -> DescriptorType { self.0 }
+    }
+
+    fn requirements(binding: u32, descriptor_type: DescriptorType) -> HashMap<(u32, u32), DescriptorBindingRequirements> {
+        HashMap::from([((0, binding), DescriptorBindingRequirements {
+            descriptor_types: vec![descriptor_type],
+            ..Default::default()
+        })])
+    }
+
+    fn variables(binding: u32, descriptor_type: DescriptorType) -> HashMap<u32, Arc<dyn ShaderVariableAbstract>> {
+        HashMap::from([(binding, Arc::new(MockVariable(descriptor_type)) as Arc<dyn ShaderVariableAbstract>)])
+    }
+
+    #[test]
+    fn uniform_buffer_where_storage_is_expected_is_rejected() {
+        let requirements = requirements(1, DescriptorType::StorageBuffer);
+        let msg = check_descriptor_requirements(&requirements, &variables(1, DescriptorType::UniformBuffer)).unwrap_err();
+        assert!(msg.what().contains("binding 1"), "{}", msg.what());
+        assert!(msg.what().contains("StorageBuffer") && msg.what().contains("UniformBuffer"), "{}", msg.what());
+
+        assert!(check_descriptor_requirements(&requirements, &variables(1, DescriptorType::StorageBuffer)).is_ok());
+    }
+}
@@ -0,0 +1,385 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+use crate::math::*;
+
+
+
+/// The weight of the quadrics that keep open boundary edges in place.
+const BOUNDARY_WEIGHT: f64 = 1000.0;
+
+
+
+/// A symmetric 4x4 matrix measuring the sum of the squared distances to a set of planes.
+/// Only the upper triangle is stored.
+#[derive(Debug, Default, Clone, Copy)]
+struct Quadric([f64; 10]);
+
+impl Quadric {
+    /// Create the quadric of a plane, scaled by the weight.
+    #[inline]
+    fn from_plane(plane: &Plane, weight: f64) -> Self {
+        let (a, b, c, d) = (plane.normal.x as f64, plane.normal.y as f64, plane.normal.z as f64, plane.distance as f64);
+        Self([
+            a * a, a * b, a * c, a * d,
+                   b * b, b * c, b * d,
+                          c * c, c * d,
+                                 d * d,
+        ].map(|value| value * weight))
+    }
+
+    #[inline]
+    fn add(&self, rhs: &Self) -> Self {
+        let mut sum = self.0;
+        sum.iter_mut().zip(rhs.0.iter()).for_each(|(a, b)| *a += b);
+        Self(sum)
+    }
+
+    /// Returns the sum of the squared distances from the point to the planes.
+    #[inline]
+    fn error(&self, point: Vec3) -> f64 {
+        let q = &self.0;
+        let (x, y, z) = (point.x as f64, point.y as f64, point.z as f64);
+        q[0] * x * x + 2.0 * q[1] * x * y + 2.0 * q[2] * x * z + 2.0 * q[3] * x
+            + q[4] * y * y + 2.0 * q[5] * y * z + 2.0 * q[6] * y
+            + q[7] * z * z + 2.0 * q[8] * z
+            + q[9]
+    }
+
+    /// Returns the point with the smallest error.
+    /// Returns `None` if the point is not unique. (e.g. all planes are parallel)
+    fn optimal_point(&self) -> Option<Vec3> {
+        let q = &self.0;
+        let det = q[0] * (q[4] * q[7] - q[5] * q[5])
+            - q[1] * (q[1] * q[7] - q[5] * q[2])
+            + q[2] * (q[1] * q[5] - q[4] * q[2]);
+        if det.abs() <= 1e-12 {
+            return None;
+        }
+
+        // solve with Cramer's rule.
+        let (bx, by, bz) = (-q[3], -q[6], -q[8]);
+        let x = (bx * (q[4] * q[7] - q[5] * q[5]) - q[1] * (by * q[7] - q[5] * bz) + q[2] * (by * q[5] - q[4] * bz)) / det;
+        let y = (q[0] * (by * q[7] - bz * q[5]) - bx * (q[1] * q[7] - q[5] * q[2]) + q[2] * (q[1] * bz - by * q[2])) / det;
+        let z = (q[0] * (q[4] * bz - q[5] * by) - q[1] * (q[1] * bz - by * q[2]) + bx * (q[1] * q[5] - q[4] * q[2])) / det;
+        Some(Vec3::new_vector(x as f32, y as f32, z as f32))
+    }
+}
+
+
+
+/// An edge that can be collapsed into a single vertex.
+#[derive(Debug, Clone, Copy)]
+struct Collapse {
+    cost: f64,
+    vertices: (u32, u32),
+    versions: (u32, u32),
+    position: Vec3,
+}
+
+impl PartialEq for Collapse {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Collapse { }
+
+impl PartialOrd for Collapse {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Collapse {
+    /// The cheapest collapse is the greatest, so that it is popped first from a `BinaryHeap`.
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.cost.total_cmp(&self.cost)
+    }
+}
+
+
+
+/// The working state of the decimation.
+struct Decimator {
+    positions: Vec<Vec3>,
+    triangles: Vec<[u32; 3]>,
+    alive: Vec<bool>,
+    num_alive: usize,
+    vertex_triangles: Vec<Vec<usize>>,
+    quadrics: Vec<Quadric>,
+    versions: Vec<u32>,
+    removed: Vec<bool>,
+}
+
+impl Decimator {
+    fn new(positions: &[Vec3], indices: &[u32]) -> Self {
+        let triangles: Vec<[u32; 3]> = indices.chunks_exact(3)
+            .map(|triangle| [triangle[0], triangle[1], triangle[2]])
+            .collect();
+
+        let mut vertex_triangles = vec![Vec::new(); positions.len()];
+        let mut quadrics = vec![Quadric::default(); positions.len()];
+        let mut alive = vec![false; triangles.len()];
+        let mut edges: HashMap<(u32, u32), Vec<usize>> = HashMap::new();
+        for (index, triangle) in triangles.iter().enumerate() {
+            let [a, b, c] = triangle.map(|i| positions[i as usize]);
+            let plane = match Plane::from_points(a, b, c) {
+                Some(plane) => plane,
+                None => continue,
+            };
+
+            // the face quadrics are weighted by the area of the triangle.
+            let area = (b - a).cross(&(c - a)).length() as f64 * 0.5;
+            let quadric = Quadric::from_plane(&plane, area);
+            for &i in triangle.iter() {
+                quadrics[i as usize] = quadrics[i as usize].add(&quadric);
+                vertex_triangles[i as usize].push(index);
+            }
+
+            for k in 0..3 {
+                let (v0, v1) = (triangle[k], triangle[(k + 1) % 3]);
+                edges.entry((v0.min(v1), v0.max(v1))).or_default().push(index);
+            }
+            alive[index] = true;
+        }
+
+        // an open boundary edge belongs to a single triangle.
+        // add a plane perpendicular to the triangle through the edge, so that the boundary does not shrink.
+        for (&(v0, v1), edge_triangles) in edges.iter() {
+            if edge_triangles.len() != 1 {
+                continue;
+            }
+
+            let [a, b, c] = triangles[edge_triangles[0]].map(|i| positions[i as usize]);
+            let normal = (b - a).cross(&(c - a));
+            let edge = positions[v1 as usize] - positions[v0 as usize];
+            if let Some(normal) = edge.cross(&normal).try_normalized() {
+                let plane = Plane::new(normal, -normal.dot(&positions[v0 as usize]));
+                let quadric = Quadric::from_plane(&plane, BOUNDARY_WEIGHT * edge.length_squared() as f64);
+                quadrics[v0 as usize] = quadrics[v0 as usize].add(&quadric);
+                quadrics[v1 as usize] = quadrics[v1 as usize].add(&quadric);
+            }
+        }
+
+        let num_alive = alive.iter().filter(|alive| **alive).count();
+        Self {
+            positions: positions.to_vec(),
+            triangles,
+            alive,
+            num_alive,
+            vertex_triangles,
+            quadrics,
+            versions: vec![0; positions.len()],
+            removed: vec![false; positions.len()],
+        }
+    }
+
+    /// Returns the cheapest way to collapse the edge.
+    fn collapse_of(&self, v0: u32, v1: u32) -> Collapse {
+        let quadric = self.quadrics[v0 as usize].add(&self.quadrics[v1 as usize]);
+        let p0 = self.positions[v0 as usize];
+        let p1 = self.positions[v1 as usize];
+        let (cost, position) = quadric.optimal_point()
+            .into_iter()
+            .chain([p0, p1, (p0 + p1) * 0.5])
+            .map(|position| (quadric.error(position), position))
+            .min_by(|a, b| a.0.total_cmp(&b.0))
+            .unwrap();
+
+        Collapse {
+            cost,
+            vertices: (v0, v1),
+            versions: (self.versions[v0 as usize], self.versions[v1 as usize]),
+            position,
+        }
+    }
+
+    /// Returns `true` if the collapse is still valid and does not flip any triangle.
+    fn can_collapse(&self, collapse: &Collapse) -> bool {
+        let (v0, v1) = collapse.vertices;
+        if self.removed[v0 as usize] || self.removed[v1 as usize] {
+            return false;
+        }
+        if collapse.versions != (self.versions[v0 as usize], self.versions[v1 as usize]) {
+            return false;
+        }
+
+        for &index in self.vertex_triangles[v0 as usize].iter().chain(self.vertex_triangles[v1 as usize].iter()) {
+            let triangle = self.triangles[index];
+            if !self.alive[index] || (triangle.contains(&v0) && triangle.contains(&v1)) {
+                continue;
+            }
+
+            let before = triangle.map(|i| self.positions[i as usize]);
+            let after = triangle.map(|i| match i == v0 || i == v1 {
+                true => collapse.position,
+                false => self.positions[i as usize],
+            });
+            let normal_before = (before[1] - before[0]).cross(&(before[2] - before[0]));
+            let normal_after = (after[1] - after[0]).cross(&(after[2] - after[0]));
+            if normal_before.dot(&normal_after) <= 0.0 {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// Collapse `v1` into `v0`. Returns the neighbors of `v0` after the collapse.
+    fn collapse(&mut self, collapse: &Collapse) -> Vec<u32> {
+        let (v0, v1) = collapse.vertices;
+        self.positions[v0 as usize] = collapse.position;
+        self.quadrics[v0 as usize] = self.quadrics[v0 as usize].add(&self.quadrics[v1 as usize]);
+        self.versions[v0 as usize] += 1;
+        self.removed[v1 as usize] = true;
+
+        let moved = std::mem::take(&mut self.vertex_triangles[v1 as usize]);
+        for index in moved {
+            if !self.alive[index] {
+                continue;
+            }
+
+            let triangle = &mut self.triangles[index];
+            triangle.iter_mut().filter(|i| **i == v1).for_each(|i| *i = v0);
+            if triangle[0] == triangle[1] || triangle[1] == triangle[2] || triangle[2] == triangle[0] {
+                self.alive[index] = false;
+                self.num_alive -= 1;
+            }
+            else {
+                self.vertex_triangles[v0 as usize].push(index);
+            }
+        }
+
+        let alive = &self.alive;
+        self.vertex_triangles[v0 as usize].retain(|index| alive[*index]);
+        self.vertex_triangles[v0 as usize].sort_unstable();
+        self.vertex_triangles[v0 as usize].dedup();
+
+        let mut neighbors: Vec<u32> = self.vertex_triangles[v0 as usize].iter()
+            .flat_map(|index| self.triangles[*index])
+            .filter(|i| *i != v0)
+            .collect();
+        neighbors.sort_unstable();
+        neighbors.dedup();
+        neighbors
+    }
+
+    /// Returns the remaining vertices and triangles, without unused vertices.
+    fn into_mesh(self) -> (Vec<Vec3>, Vec<u32>) {
+        let mut remap = vec![u32::MAX; self.positions.len()];
+        let mut positions = Vec::new();
+        let mut indices = Vec::with_capacity(self.num_alive * 3);
+        for (triangle, _) in self.triangles.iter().zip(self.alive.iter()).filter(|(_, alive)| **alive) {
+            for &i in triangle.iter() {
+                if remap[i as usize] == u32::MAX {
+                    remap[i as usize] = positions.len() as u32;
+                    positions.push(self.positions[i as usize]);
+                }
+                indices.push(remap[i as usize]);
+            }
+        }
+        (positions, indices)
+    }
+}
+
+
+/// Reduce the number of triangles of an indexed triangle list to about `target_ratio` of the original.
+/// 
+/// Edges are collapsed in the order of the quadric error metric (Garland and Heckbert),
+/// which keeps the shape of the mesh while flat regions are merged first.
+/// Collapses that would flip a triangle are skipped, and open boundaries are kept in place.
+/// 
+/// # Panics
+/// - Stop program execution if an index is out of range.
+/// 
+pub fn simplify(positions: &[Vec3], indices: &[u32], target_ratio: f32) -> (Vec<Vec3>, Vec<u32>) {
+    let mut decimator = Decimator::new(positions, indices);
+    let target = (decimator.num_alive as f32 * target_ratio.clamp(0.0, 1.0)).round() as usize;
+
+    // each interior edge is shared by two triangles, so only add it once.
+    let edges: HashSet<(u32, u32)> = decimator.triangles.iter()
+        .zip(decimator.alive.iter())
+        .filter(|(_, alive)| **alive)
+        .flat_map(|(triangle, _)| (0..3).map(|k| {
+            let (v0, v1) = (triangle[k], triangle[(k + 1) % 3]);
+            (v0.min(v1), v0.max(v1))
+        }))
+        .collect();
+    let mut heap: BinaryHeap<Collapse> = edges.into_iter()
+        .map(|(v0, v1)| decimator.collapse_of(v0, v1))
+        .collect();
+
+    while decimator.num_alive > target {
+        let collapse = match heap.pop() {
+            Some(collapse) => collapse,
+            None => break,
+        };
+
+        if !decimator.can_collapse(&collapse) {
+            continue;
+        }
+
+        let v0 = collapse.vertices.0;
+        for neighbor in decimator.collapse(&collapse) {
+            heap.push(decimator.collapse_of(v0, neighbor));
+        }
+    }
+
+    decimator.into_mesh()
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// a closed uv-sphere of radius `1` with a single vertex at each pole.
+    fn sphere(rings: u32, sectors: u32) -> (Vec<Vec3>, Vec<u32>) {
+        let mut positions = vec![Vec3::Y];
+        for ring in 1..rings {
+            let polar = std::f32::consts::PI * ring as f32 / rings as f32;
+            for sector in 0..sectors {
+                let azimuth = std::f32::consts::TAU * sector as f32 / sectors as f32;
+                positions.push(Vec3::new_vector(polar.sin() * azimuth.cos(), polar.cos(), polar.sin() * azimuth.sin()));
+            }
+        }
+        positions.push(-Vec3::Y);
+
+        let south = positions.len() as u32 - 1;
+        let vertex = |ring: u32, sector: u32| 1 + (ring - 1) * sectors + sector % sectors;
+        let mut indices = Vec::new();
+        for sector in 0..sectors {
+            indices.extend([vertex(1, sector), 0, vertex(1, sector + 1)]);
+            indices.extend([vertex(rings - 1, sector), vertex(rings - 1, sector + 1), south]);
+        }
+        for ring in 1..rings - 1 {
+            for sector in 0..sectors {
+                let (top, top_next) = (vertex(ring, sector), vertex(ring, sector + 1));
+                let (bottom, bottom_next) = (vertex(ring + 1, sector), vertex(ring + 1, sector + 1));
+                indices.extend([top, top_next, bottom, bottom, top_next, bottom_next]);
+            }
+        }
+        (positions, indices)
+    }
+
+    #[test]
+    fn half_ratio_halves_the_sphere_triangles() {
+        let (positions, indices) = sphere(32, 64);
+        let (simplified_positions, simplified_indices) = simplify(&positions, &indices, 0.5);
+
+        let (original, simplified) = (indices.len() / 3, simplified_indices.len() / 3);
+        let ratio = simplified as f32 / original as f32;
+        assert!((0.45..=0.55).contains(&ratio), "{} -> {}", original, simplified);
+        assert!(simplified_indices.iter().all(|&index| (index as usize) < simplified_positions.len()));
+
+        // the simplified sphere keeps the bounding sphere of the original.
+        let radius = simplified_positions.iter().map(|p| p.length()).fold(0.0, f32::max);
+        assert!((radius - 1.0).abs() < 0.05, "{}", radius);
+        assert!(simplified_positions.iter().all(|p| p.length() > 0.9));
+    }
+}
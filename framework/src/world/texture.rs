@@ -0,0 +1,549 @@
+use std::fs;
+use std::sync::Arc;
+use std::path::{Path, PathBuf};
+
+use vulkano::format::Format;
+use vulkano::buffer::{Buffer, BufferUsage, BufferCreateInfo};
+use vulkano::memory::allocator::{AllocationCreateInfo, MemoryUsage};
+use vulkano::command_buffer::{AutoCommandBufferBuilder, BufferImageCopy, CopyBufferToImageInfo};
+use vulkano::command_buffer::allocator::CommandBufferAllocator;
+use vulkano::image::{ImmutableImage, ImageDimensions, MipmapsCount, ImageUsage, ImageCreateFlags, ImageLayout, ImageSubresourceLayers, ImageAspects};
+use vulkano::image::view::{ImageView, ImageViewAbstract};
+use vulkano::descriptor_set::WriteDescriptorSet;
+use vulkano::descriptor_set::layout::DescriptorType;
+use vulkano::pipeline::graphics::depth_stencil::CompareOp;
+use vulkano::sampler::{Sampler, SamplerCreateInfo, SamplerAddressMode, BorderColor, Filter};
+
+use crate::renderer::RenderContext;
+use crate::world::cache::AssetCache;
+use crate::world::variable::{ShaderVariableAbstract, ShaderVariableAccess};
+use crate::{err, error::{ErrorKind, RuntimeError}};
+
+
+
+/// Compressed formats that can be loaded from a `.ktx2` file.
+const COMPRESSED_FORMATS: [Format; 38] = [
+    Format::ETC2_R8G8B8_UNORM_BLOCK,
+    Format::ETC2_R8G8B8_SRGB_BLOCK,
+    Format::ETC2_R8G8B8A1_UNORM_BLOCK,
+    Format::ETC2_R8G8B8A1_SRGB_BLOCK,
+    Format::ETC2_R8G8B8A8_UNORM_BLOCK,
+    Format::ETC2_R8G8B8A8_SRGB_BLOCK,
+    Format::EAC_R11_UNORM_BLOCK,
+    Format::EAC_R11_SNORM_BLOCK,
+    Format::EAC_R11G11_UNORM_BLOCK,
+    Format::EAC_R11G11_SNORM_BLOCK,
+    Format::ASTC_4x4_UNORM_BLOCK,
+    Format::ASTC_4x4_SRGB_BLOCK,
+    Format::ASTC_5x4_UNORM_BLOCK,
+    Format::ASTC_5x4_SRGB_BLOCK,
+    Format::ASTC_5x5_UNORM_BLOCK,
+    Format::ASTC_5x5_SRGB_BLOCK,
+    Format::ASTC_6x5_UNORM_BLOCK,
+    Format::ASTC_6x5_SRGB_BLOCK,
+    Format::ASTC_6x6_UNORM_BLOCK,
+    Format::ASTC_6x6_SRGB_BLOCK,
+    Format::ASTC_8x5_UNORM_BLOCK,
+    Format::ASTC_8x5_SRGB_BLOCK,
+    Format::ASTC_8x6_UNORM_BLOCK,
+    Format::ASTC_8x6_SRGB_BLOCK,
+    Format::ASTC_8x8_UNORM_BLOCK,
+    Format::ASTC_8x8_SRGB_BLOCK,
+    Format::ASTC_10x5_UNORM_BLOCK,
+    Format::ASTC_10x5_SRGB_BLOCK,
+    Format::ASTC_10x6_UNORM_BLOCK,
+    Format::ASTC_10x6_SRGB_BLOCK,
+    Format::ASTC_10x8_UNORM_BLOCK,
+    Format::ASTC_10x8_SRGB_BLOCK,
+    Format::ASTC_10x10_UNORM_BLOCK,
+    Format::ASTC_10x10_SRGB_BLOCK,
+    Format::ASTC_12x10_UNORM_BLOCK,
+    Format::ASTC_12x10_SRGB_BLOCK,
+    Format::ASTC_12x12_UNORM_BLOCK,
+    Format::ASTC_12x12_SRGB_BLOCK,
+];
+
+/// Block dimensions of the `.astc` file and the matching formats. (unorm, srgb)
+const ASTC_FORMATS: [(u8, u8, Format, Format); 14] = [
+    (4, 4, Format::ASTC_4x4_UNORM_BLOCK, Format::ASTC_4x4_SRGB_BLOCK),
+    (5, 4, Format::ASTC_5x4_UNORM_BLOCK, Format::ASTC_5x4_SRGB_BLOCK),
+    (5, 5, Format::ASTC_5x5_UNORM_BLOCK, Format::ASTC_5x5_SRGB_BLOCK),
+    (6, 5, Format::ASTC_6x5_UNORM_BLOCK, Format::ASTC_6x5_SRGB_BLOCK),
+    (6, 6, Format::ASTC_6x6_UNORM_BLOCK, Format::ASTC_6x6_SRGB_BLOCK),
+    (8, 5, Format::ASTC_8x5_UNORM_BLOCK, Format::ASTC_8x5_SRGB_BLOCK),
+    (8, 6, Format::ASTC_8x6_UNORM_BLOCK, Format::ASTC_8x6_SRGB_BLOCK),
+    (8, 8, Format::ASTC_8x8_UNORM_BLOCK, Format::ASTC_8x8_SRGB_BLOCK),
+    (10, 5, Format::ASTC_10x5_UNORM_BLOCK, Format::ASTC_10x5_SRGB_BLOCK),
+    (10, 6, Format::ASTC_10x6_UNORM_BLOCK, Format::ASTC_10x6_SRGB_BLOCK),
+    (10, 8, Format::ASTC_10x8_UNORM_BLOCK, Format::ASTC_10x8_SRGB_BLOCK),
+    (10, 10, Format::ASTC_10x10_UNORM_BLOCK, Format::ASTC_10x10_SRGB_BLOCK),
+    (12, 10, Format::ASTC_12x10_UNORM_BLOCK, Format::ASTC_12x10_SRGB_BLOCK),
+    (12, 12, Format::ASTC_12x12_UNORM_BLOCK, Format::ASTC_12x12_SRGB_BLOCK),
+];
+
+
+
+/// A two-dimensional texture in device local memory.
+#[derive(Debug)]
+pub struct Texture2D {
+    image: Arc<ImmutableImage>,
+    view: Arc<ImageView<ImmutableImage>>,
+    size_in_bytes: u64,
+}
+
+impl Texture2D {
+    /// Create a texture from compressed block data without decompressing it.
+    /// The mip levels of `data` must be tightly packed, starting from the base level.
+    /// 
+    /// # Runtime Error
+    /// - Return the `RuntimeError` if the format is not a compressed format.
+    /// - Return the `RuntimeError` if the device cannot sample images of the format.
+    /// - Return the `RuntimeError` if `data` is smaller than the mip levels require.
+    /// - Return the `RuntimeError` if an error occurs while creating the image.
+    /// 
+    pub fn from_compressed<L, A>(
+        data: &[u8],
+        format: Format,
+        width: u32,
+        height: u32,
+        mip_levels: u32,
+        render_ctx: &RenderContext,
+        command_buffer_builder: &mut AutoCommandBufferBuilder<L, A>
+    ) -> Result<Arc<Self>, RuntimeError>
+    where A: CommandBufferAllocator {
+        check_compressed_texture(format, width, height, mip_levels, |format| render_ctx.supports_sampled(format))?;
+
+        // compute the copy region of each mip level.
+        let mut regions = Vec::with_capacity(mip_levels as usize);
+        let mut buffer_offset = 0;
+        for mip_level in 0..mip_levels {
+            let mip_width = (width >> mip_level).max(1);
+            let mip_height = (height >> mip_level).max(1);
+            regions.push(BufferImageCopy {
+                buffer_offset,
+                image_subresource: ImageSubresourceLayers {
+                    aspects: ImageAspects::COLOR,
+                    mip_level,
+                    array_layers: 0..1,
+                },
+                image_extent: [mip_width, mip_height, 1],
+                ..Default::default()
+            });
+            buffer_offset += get_compressed_size(format, mip_width, mip_height);
+        }
+        if (data.len() as u64) < buffer_offset {
+            return Err(err!("Texture data is too small: {} bytes (required: {} bytes)", data.len(), buffer_offset));
+        }
+
+        let allocator = render_ctx.ref_memory_allocator();
+        let staging_buffer = Buffer::from_iter(
+            allocator,
+            BufferCreateInfo {
+                usage: BufferUsage::TRANSFER_SRC,
+                ..Default::default()
+            },
+            AllocationCreateInfo {
+                usage: MemoryUsage::Upload,
+                ..Default::default()
+            },
+            data[..buffer_offset as usize].iter().copied()
+        ).map_err(|e| err!("Buffer creation failed: {}", e.to_string()))?;
+
+        let (image, initializer) = ImmutableImage::uninitialized(
+            allocator,
+            ImageDimensions::Dim2d { width, height, array_layers: 1 },
+            format,
+            MipmapsCount::Specific(mip_levels),
+            ImageUsage::TRANSFER_DST | ImageUsage::SAMPLED,
+            ImageCreateFlags::empty(),
+            ImageLayout::ShaderReadOnlyOptimal,
+            [render_ctx.get_queue_fmaily_index()]
+        ).map_err(|e| err!("Image creation failed: {}", e.to_string()))?;
+
+        command_buffer_builder.copy_buffer_to_image(CopyBufferToImageInfo {
+            regions: regions.into(),
+            ..CopyBufferToImageInfo::buffer_image(staging_buffer, initializer)
+        }).map_err(|e| err!("Buffer to image copy failed: {}", e.to_string()))?;
+
+        let view = ImageView::new_default(image.clone())
+            .map_err(|e| err!("Image view creation failed: {}", e.to_string()))?;
+
+        Ok(Arc::new(Self { image, view, size_in_bytes: buffer_offset }))
+    }
+
+    /// Load a compressed texture from a `.ktx2` or `.astc` file.
+    /// `.astc` files are loaded as sRGB textures if `srgb` is `true`. 
+    /// (`.ktx2` files contain their own format.)
+    /// 
+    /// # Runtime Error
+    /// - Return the `RuntimeError` if the file cannot be read.
+    /// - Return the `RuntimeError` if the file is not a supported container.
+    /// - Return the `RuntimeError` if an error occurs while creating the texture.
+    /// 
+    pub fn load_from_file<L, A>(
+        path: &Path,
+        srgb: bool,
+        render_ctx: &RenderContext,
+        command_buffer_builder: &mut AutoCommandBufferBuilder<L, A>
+    ) -> Result<Arc<Self>, RuntimeError>
+    where A: CommandBufferAllocator {
+        let bytes = fs::read(path)
+            .map_err(|e| err!("Texture file read failed: {}", e.to_string()))?;
+
+        let (data, format, width, height, mip_levels) = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("ktx2") => parse_ktx2(&bytes)?,
+            Some("astc") => parse_astc(&bytes, srgb)?,
+            _ => return Err(err!("Unsupported texture file: {}", path.display())),
+        };
+
+        Self::from_compressed(&data, format, width, height, mip_levels, render_ctx, command_buffer_builder)
+    }
+
+    #[inline]
+    pub fn ref_image(&self) -> &Arc<ImmutableImage> {
+        &self.image
+    }
+
+    #[inline]
+    pub fn ref_image_view(&self) -> &Arc<ImageView<ImmutableImage>> {
+        &self.view
+    }
+
+    /// Get the size in bytes of the texture data of all mip levels.
+    #[inline]
+    pub fn get_size_in_bytes(&self) -> u64 {
+        self.size_in_bytes
+    }
+}
+
+/// The texture is bound as a sampled image. (e.g. `layout(binding = 2) uniform texture2D`)
+/// It is sampled with a `TextureSampler` bound at another binding.
+impl ShaderVariableAbstract for Texture2D {
+    #[inline]
+    fn write_descriptor(&self, binding: u32) -> WriteDescriptorSet {
+        WriteDescriptorSet::image_view(binding, self.view.clone())
+    }
+
+    #[inline]
+    fn access(&self) -> ShaderVariableAccess {
+        ShaderVariableAccess::Image(self.view.clone())
+    }
+
+    #[inline]
+    fn descriptor_type(&self) -> DescriptorType {
+        DescriptorType::SampledImage
+    }
+}
+
+
+
+/// An image view rendered by the framework, bound as a sampled image. (e.g. the array view of a shadow map)
+/// It is sampled with a `TextureSampler` bound at another binding.
+#[derive(Debug)]
+pub struct SampledImageView {
+    view: Arc<dyn ImageViewAbstract>,
+}
+
+impl SampledImageView {
+    #[inline]
+    pub fn new(view: Arc<dyn ImageViewAbstract>) -> Arc<Self> {
+        Arc::new(Self { view })
+    }
+}
+
+impl ShaderVariableAbstract for SampledImageView {
+    #[inline]
+    fn write_descriptor(&self, binding: u32) -> WriteDescriptorSet {
+        WriteDescriptorSet::image_view(binding, self.view.clone())
+    }
+
+    #[inline]
+    fn access(&self) -> ShaderVariableAccess {
+        ShaderVariableAccess::Image(self.view.clone())
+    }
+
+    #[inline]
+    fn descriptor_type(&self) -> DescriptorType {
+        DescriptorType::SampledImage
+    }
+}
+
+
+
+/// A sampler bound apart from the textures it samples. (e.g. `layout(binding = 3) uniform sampler`)
+#[derive(Debug)]
+pub struct TextureSampler {
+    sampler: Arc<Sampler>,
+}
+
+impl TextureSampler {
+    /// Create a sampler that filters linearly and repeats the texture coordinates.
+    /// 
+    /// # Runtime Error
+    /// - Return the `RuntimeError` if an error occurs while creating the sampler.
+    /// 
+    pub fn repeat_linear(render_ctx: &RenderContext) -> Result<Arc<Self>, RuntimeError> {
+        let sampler = Sampler::new(
+            render_ctx.ref_device().clone(),
+            SamplerCreateInfo::simple_repeat_linear()
+        ).map_err(|e| err!("Sampler creation failed: {}", e.to_string()))?;
+        Ok(Arc::new(Self { sampler }))
+    }
+
+    /// Create a sampler that compares the depth of a shadow map with the reference depth. (e.g. `sampler2DArrayShadow`)
+    /// The comparison passes if the reference depth is not greater than the stored depth,
+    /// and the coordinates outside the shadow map are not shadowed.
+    /// The results of the neighboring texels are filtered linearly if the format supports it.
+    /// 
+    /// # Runtime Error
+    /// - Return the `RuntimeError` if an error occurs while creating the sampler.
+    /// 
+    pub fn shadow_compare(format: Format, render_ctx: &RenderContext) -> Result<Arc<Self>, RuntimeError> {
+        let filter = match render_ctx.supports_linear_filter(format) {
+            true => Filter::Linear,
+            false => Filter::Nearest,
+        };
+        let sampler = Sampler::new(
+            render_ctx.ref_device().clone(),
+            SamplerCreateInfo {
+                mag_filter: filter,
+                min_filter: filter,
+                address_mode: [SamplerAddressMode::ClampToBorder; 3],
+                border_color: BorderColor::FloatOpaqueWhite,
+                compare: Some(CompareOp::LessOrEqual),
+                ..Default::default()
+            }
+        ).map_err(|e| err!("Sampler creation failed: {}", e.to_string()))?;
+        Ok(Arc::new(Self { sampler }))
+    }
+
+    /// Create a sampler that reads the nearest texel and clamps the coordinates to the edge of the image.
+    /// It is used to read the render targets texel by texel. (e.g. the ambient occlusion targets)
+    ///
+    /// # Runtime Error
+    /// - Return the `RuntimeError` if an error occurs while creating the sampler.
+    ///
+    pub fn clamp_nearest(render_ctx: &RenderContext) -> Result<Arc<Self>, RuntimeError> {
+        let sampler = Sampler::new(
+            render_ctx.ref_device().clone(),
+            SamplerCreateInfo {
+                mag_filter: Filter::Nearest,
+                min_filter: Filter::Nearest,
+                address_mode: [SamplerAddressMode::ClampToEdge; 3],
+                ..Default::default()
+            }
+        ).map_err(|e| err!("Sampler creation failed: {}", e.to_string()))?;
+        Ok(Arc::new(Self { sampler }))
+    }
+}
+
+impl ShaderVariableAbstract for TextureSampler {
+    #[inline]
+    fn write_descriptor(&self, binding: u32) -> WriteDescriptorSet {
+        WriteDescriptorSet::sampler(binding, self.sampler.clone())
+    }
+
+    #[inline]
+    fn access(&self) -> ShaderVariableAccess {
+        ShaderVariableAccess::Sampler(self.sampler.clone())
+    }
+
+    #[inline]
+    fn descriptor_type(&self) -> DescriptorType {
+        DescriptorType::Sampler
+    }
+}
+
+
+
+/// A cache of textures loaded from files, with a byte budget. See `AssetCache`.
+pub type TextureCache = AssetCache<PathBuf, Texture2D>;
+
+impl AssetCache<PathBuf, Texture2D> {
+    /// Get the texture loaded from the file, or load it with `Texture2D::load_from_file`.
+    /// The least recently used textures that are not in use are evicted if the texture does not fit in the budget.
+    /// 
+    /// # Runtime Error
+    /// - Return the `RuntimeError` if an error occurs while loading the texture.
+    /// 
+    pub fn load_texture<L, A>(
+        &mut self,
+        path: &Path,
+        srgb: bool,
+        render_ctx: &RenderContext,
+        command_buffer_builder: &mut AutoCommandBufferBuilder<L, A>
+    ) -> Result<Arc<Texture2D>, RuntimeError>
+    where A: CommandBufferAllocator {
+        self.get_or_load(path.to_path_buf(), || {
+            let texture = Texture2D::load_from_file(path, srgb, render_ctx, command_buffer_builder)?;
+            let size_in_bytes = texture.get_size_in_bytes();
+            Ok((texture, size_in_bytes))
+        })
+    }
+}
+
+
+/// Check that a texture of the compressed format can be created.
+/// `supports_sampled` returns `true` if the device can sample images of the format.
+/// 
+/// # Runtime Error
+/// - Return the `RuntimeError` if the format is not a compressed format.
+/// - Return an `ErrorKind::Unsupported` error if the device cannot sample images of the format.
+/// - Return the `RuntimeError` if the size or the mip level count is zero.
+/// 
+fn check_compressed_texture(
+    format: Format, 
+    width: u32, 
+    height: u32, 
+    mip_levels: u32, 
+    supports_sampled: impl FnOnce(Format) -> bool
+) -> Result<(), RuntimeError> {
+    if format.compression().is_none() {
+        return Err(err!("{:?} is not a compressed format.", format));
+    }
+    if !supports_sampled(format) {
+        return Err(err!("Compressed format {:?} is not supported by the device.", format).with_kind(ErrorKind::Unsupported));
+    }
+    if width == 0 || height == 0 || mip_levels == 0 {
+        return Err(err!("Texture size and mip levels cannot be zero."));
+    }
+    Ok(())
+}
+
+
+
+/// Returns the size in bytes of an image of the compressed format.
+#[inline]
+fn get_compressed_size(format: Format, width: u32, height: u32) -> u64 {
+    let [block_width, block_height, _] = format.block_extent();
+    let blocks_x = ((width + block_width - 1) / block_width) as u64;
+    let blocks_y = ((height + block_height - 1) / block_height) as u64;
+    blocks_x * blocks_y * format.block_size().unwrap_or(0)
+}
+
+
+#[inline]
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+
+#[inline]
+fn read_u64(bytes: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap())
+}
+
+
+/// Parse a `.ktx2` file into tightly packed mip levels, format, width, height and mip level count.
+/// 
+/// # Runtime Error
+/// - Return the `RuntimeError` if the file is not a valid `.ktx2` file.
+/// - Return the `RuntimeError` if the file is supercompressed or is not a compressed 2D texture.
+/// 
+fn parse_ktx2(bytes: &[u8]) -> Result<(Vec<u8>, Format, u32, u32, u32), RuntimeError> {
+    const IDENTIFIER: [u8; 12] = [0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A];
+    const HEADER_SIZE: usize = 80;
+    const LEVEL_INDEX_SIZE: usize = 24;
+
+    if bytes.len() < HEADER_SIZE || bytes[..12] != IDENTIFIER {
+        return Err(err!("Invalid ktx2 file."));
+    }
+
+    let vk_format = read_u32(bytes, 12);
+    let width = read_u32(bytes, 20);
+    let height = read_u32(bytes, 24);
+    let depth = read_u32(bytes, 28);
+    let layer_count = read_u32(bytes, 32);
+    let face_count = read_u32(bytes, 36);
+    let level_count = read_u32(bytes, 40).max(1);
+    let supercompression_scheme = read_u32(bytes, 44);
+
+    if supercompression_scheme != 0 {
+        return Err(err!("Supercompressed ktx2 files are not supported."));
+    }
+    if depth > 1 || layer_count > 1 || face_count != 1 {
+        return Err(err!("Only 2D ktx2 textures are supported."));
+    }
+    let format = COMPRESSED_FORMATS.into_iter()
+        .find(|&format| format as u32 == vk_format)
+        .ok_or_else(|| err!("Unsupported ktx2 format: {}", vk_format))?;
+
+    let level_index_end = HEADER_SIZE + LEVEL_INDEX_SIZE * level_count as usize;
+    if bytes.len() < level_index_end {
+        return Err(err!("Invalid ktx2 file."));
+    }
+
+    let mut data = Vec::new();
+    for level in 0..level_count as usize {
+        let offset = read_u64(bytes, HEADER_SIZE + LEVEL_INDEX_SIZE * level) as usize;
+        let length = read_u64(bytes, HEADER_SIZE + LEVEL_INDEX_SIZE * level + 8) as usize;
+        let level_data = offset.checked_add(length)
+            .and_then(|end| bytes.get(offset..end))
+            .ok_or_else(|| err!("Invalid ktx2 file."))?;
+        data.extend_from_slice(level_data);
+    }
+
+    Ok((data, format, width, height, level_count))
+}
+
+
+/// Parse an `.astc` file into block data, format, width, height and mip level count.
+/// 
+/// # Runtime Error
+/// - Return the `RuntimeError` if the file is not a valid `.astc` file.
+/// - Return the `RuntimeError` if the file is a 3D texture or uses an unsupported block size.
+/// 
+fn parse_astc(bytes: &[u8], srgb: bool) -> Result<(Vec<u8>, Format, u32, u32, u32), RuntimeError> {
+    const MAGIC: [u8; 4] = [0x13, 0xAB, 0xA1, 0x5C];
+    const HEADER_SIZE: usize = 16;
+
+    if bytes.len() < HEADER_SIZE || bytes[..4] != MAGIC {
+        return Err(err!("Invalid astc file."));
+    }
+
+    let (block_x, block_y, block_z) = (bytes[4], bytes[5], bytes[6]);
+    let read_u24 = |offset: usize| -> u32 {
+        bytes[offset] as u32 | (bytes[offset + 1] as u32) << 8 | (bytes[offset + 2] as u32) << 16
+    };
+    let width = read_u24(7);
+    let height = read_u24(10);
+    let depth = read_u24(13);
+
+    if block_z != 1 || depth != 1 {
+        return Err(err!("Only 2D astc textures are supported."));
+    }
+    let format = ASTC_FORMATS.into_iter()
+        .find(|&(x, y, _, _)| x == block_x && y == block_y)
+        .map(|(_, _, unorm, srgb_format)| if srgb { srgb_format } else { unorm })
+        .ok_or_else(|| err!("Unsupported astc block size: {}x{}", block_x, block_y))?;
+
+    Ok((bytes[HEADER_SIZE..].to_vec(), format, width, height, 1))
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unsupported_compressed_format_is_rejected() {
+        let error = check_compressed_texture(Format::ASTC_4x4_SRGB_BLOCK, 256, 256, 1, |_| false).unwrap_err();
+        assert!(error.is_unsupported());
+        assert!(error.what().contains("ASTC_4x4_SRGB_BLOCK"), "{}", error.what());
+
+        let error = check_compressed_texture(Format::R8G8B8A8_UNORM, 256, 256, 1, |_| true).unwrap_err();
+        assert!(!error.is_unsupported());
+    }
+
+    #[test]
+    fn supported_compressed_format_is_accepted() {
+        let format = Format::ASTC_4x4_SRGB_BLOCK;
+        assert!(check_compressed_texture(format, 256, 256, 9, |f| f == format).is_ok());
+        assert!(check_compressed_texture(format, 0, 256, 1, |_| true).is_err());
+
+        // 16 bytes per 4x4 block, and a partial block is rounded up.
+        assert_eq!(get_compressed_size(format, 256, 256), 64 * 64 * 16);
+        assert_eq!(get_compressed_size(format, 5, 1), 2 * 16);
+    }
+}
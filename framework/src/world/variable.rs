@@ -1,12 +1,17 @@
 use std::fmt;
 use std::mem;
 use std::ptr;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
+use bytemuck::{Pod, Zeroable};
 use vulkano::descriptor_set::WriteDescriptorSet;
+use vulkano::descriptor_set::layout::DescriptorType;
 use vulkano::buffer::{Subbuffer, BufferContents, Buffer, BufferCreateInfo, BufferUsage};
+use vulkano::image::ImageViewAbstract;
+use vulkano::sampler::Sampler;
 use vulkano::memory::allocator::{MemoryAllocator, AllocationCreateInfo, MemoryUsage};
 
+use crate::math::*;
 use crate::{err, error::RuntimeError};
 
 
@@ -14,6 +19,8 @@ use crate::{err, error::RuntimeError};
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ShaderVariableAccess {
     Buffer(Subbuffer<[u8]>),
+    Image(Arc<dyn ImageViewAbstract>),
+    Sampler(Arc<Sampler>),
 }
 
 
@@ -21,6 +28,7 @@ pub enum ShaderVariableAccess {
 pub trait ShaderVariableAbstract : fmt::Debug + Send + Sync {
     fn write_descriptor(&self, binding: u32) -> WriteDescriptorSet;
     fn access(&self) -> ShaderVariableAccess;
+    fn descriptor_type(&self) -> DescriptorType;
 }
 
 
@@ -28,7 +36,8 @@ pub trait ShaderVariableAbstract : fmt::Debug + Send + Sync {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct UniformBuffer<T> 
 where T: fmt::Debug + BufferContents {
-    buffer: Subbuffer<T>
+    buffer: Subbuffer<T>,
+    last_data: LastWrittenData<T>,
 }
 
 
@@ -48,7 +57,8 @@ where T: fmt::Debug + BufferContents {
                     ..Default::default()
                 },
                 mem::size_of::<T>() as u64,
-            ).map_err(|e| err!("Buffer creation failed: {}", e.to_string()))?
+            ).map_err(|e| err!("Buffer creation failed: {}", e.to_string()))?,
+            last_data: LastWrittenData::default(),
         }))
     }
 
@@ -69,12 +79,28 @@ where T: fmt::Debug + BufferContents {
                     ..Default::default()
                 },
                 data
-            ).map_err(|e| err!("Buffer creation failed: {}", e.to_string()))?
+            ).map_err(|e| err!("Buffer creation failed: {}", e.to_string()))?,
+            last_data: LastWrittenData::default(),
         }))
     }
 
     #[inline]
     pub fn write_data(&self, data: T) {
+        *self.last_data.0.lock().unwrap() = None;
+        self.write_mapped(data);
+    }
+
+    /// Write the data only if it differs from the last data written by this function.
+    /// Returns `true` if the data was written.
+    #[inline]
+    pub fn write_data_if_changed(&self, data: T) -> bool
+    where T: PartialEq + Copy {
+        let mut last_data = self.last_data.0.lock().unwrap();
+        write_if_changed(&mut last_data, data, |data| self.write_mapped(data))
+    }
+
+    #[inline]
+    fn write_mapped(&self, data: T) {
         if let Some(ptr) = self.buffer.mapped_ptr() {
             unsafe { 
                 std::ptr::write(
@@ -87,6 +113,49 @@ where T: fmt::Debug + BufferContents {
 }
 
 
+/// The last data written by `UniformBuffer::write_data_if_changed`.
+/// Clones share it with the buffer memory, and it is ignored when comparing buffers.
+#[derive(Debug)]
+struct LastWrittenData<T>(Arc<Mutex<Option<T>>>);
+
+impl<T> Default for LastWrittenData<T> {
+    #[inline]
+    fn default() -> Self {
+        Self(Arc::new(Mutex::new(None)))
+    }
+}
+
+impl<T> Clone for LastWrittenData<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T> PartialEq for LastWrittenData<T> {
+    #[inline]
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl<T> Eq for LastWrittenData<T> { }
+
+
+/// Call `write` with the data only if it differs from `last_data`, and remember it.
+/// Returns `true` if the data was written.
+#[inline]
+fn write_if_changed<T: PartialEq + Copy>(last_data: &mut Option<T>, data: T, write: impl FnOnce(T)) -> bool {
+    if last_data.as_ref() == Some(&data) {
+        return false;
+    }
+
+    write(data);
+    *last_data = Some(data);
+    true
+}
+
+
 impl<T> ShaderVariableAbstract for UniformBuffer<T>
 where T: fmt::Debug + BufferContents {
     fn write_descriptor(&self, binding: u32) -> WriteDescriptorSet {
@@ -97,4 +166,355 @@ where T: fmt::Debug + BufferContents {
     fn access(&self) -> ShaderVariableAccess {
         ShaderVariableAccess::Buffer(self.buffer.as_bytes().clone())
     }
-}
\ No newline at end of file
+
+    #[inline]
+    fn descriptor_type(&self) -> DescriptorType {
+        DescriptorType::UniformBuffer
+    }
+}
+
+
+
+/// A host-visible storage buffer holding up to `capacity` elements of `T`.
+/// 
+/// # Usage
+/// - Write the whole array of elements once per frame with `write_all`.
+/// - Elements after the written slice keep the values of the previous writes.
+/// 
+#[derive(Debug)]
+pub struct StorageBuffer<T>
+where T: fmt::Debug + Pod + Send + Sync {
+    buffer: Subbuffer<[T]>,
+    capacity: usize,
+}
+
+
+impl<T> StorageBuffer<T>
+where T: fmt::Debug + Pod + Send + Sync {
+    /// # Runtime Error
+    /// - Return the `RuntimeError` if the `capacity` is zero.
+    /// - Return the `RuntimeError` if the buffer creation fails.
+    /// 
+    #[inline]
+    pub fn with_capacity(
+        capacity: usize,
+        allocator: &impl MemoryAllocator,
+    ) -> Result<Arc<Self>, RuntimeError> {
+        if capacity == 0 {
+            return Err(err!("Storage buffer capacity must be greater than zero."));
+        }
+
+        Ok(Arc::new(Self {
+            buffer: Buffer::new_slice(
+                allocator,
+                BufferCreateInfo {
+                    usage: BufferUsage::STORAGE_BUFFER,
+                    ..Default::default()
+                },
+                AllocationCreateInfo {
+                    usage: MemoryUsage::Upload,
+                    ..Default::default()
+                },
+                capacity as u64,
+            ).map_err(|e| err!("Buffer creation failed: {}", e.to_string()))?,
+            capacity,
+        }))
+    }
+
+    #[inline]
+    pub fn get_capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Maps the buffer once and writes the `items` from the first element.
+    /// 
+    /// # Runtime Error
+    /// - Return the `RuntimeError` if the number of `items` exceeds the capacity of the buffer.
+    /// - Return the `RuntimeError` if the buffer memory is not host-visible.
+    /// 
+    #[inline]
+    pub fn write_all(&self, items: &[T]) -> Result<(), RuntimeError> {
+        self.write_at(0, items)
+    }
+
+    /// Maps the buffer once and writes the `items` from the `first` element.
+    /// 
+    /// # Runtime Error
+    /// - Return the `RuntimeError` if the `items` do not fit in the buffer from the `first` element.
+    /// - Return the `RuntimeError` if the buffer memory is not host-visible.
+    /// 
+    pub fn write_at(&self, first: usize, items: &[T]) -> Result<(), RuntimeError> {
+        write_mapped_slice(first, items, self.capacity, || {
+            self.buffer.mapped_ptr().map(|ptr| unsafe {
+                std::slice::from_raw_parts_mut(ptr.cast::<T>().as_ptr(), self.capacity)
+            })
+        })
+    }
+}
+
+
+/// Writes the `items` from the `first` element of the memory returned by `map`.
+/// `map` is called once, after the number of items is validated.
+/// 
+/// # Runtime Error
+/// - Return the `RuntimeError` if the `items` do not fit in the `capacity` from the `first` element.
+/// - Return the `RuntimeError` if `map` returns `None`. (the memory is not host-visible)
+/// 
+#[inline]
+fn write_mapped_slice<'a, T: Pod>(
+    first: usize,
+    items: &[T],
+    capacity: usize,
+    map: impl FnOnce() -> Option<&'a mut [T]>
+) -> Result<(), RuntimeError> {
+    if first.checked_add(items.len()).map_or(true, |end| end > capacity) {
+        return Err(err!(
+            "Too many items for the storage buffer. (first:{}, items:{}, capacity:{})",
+            first, items.len(), capacity
+        ));
+    }
+
+    let mapped = map().ok_or_else(|| err!("Storage buffer memory is not host-visible."))?;
+    mapped[first..first + items.len()].copy_from_slice(items);
+    Ok(())
+}
+
+
+impl<T> ShaderVariableAbstract for StorageBuffer<T>
+where T: fmt::Debug + Pod + Send + Sync {
+    fn write_descriptor(&self, binding: u32) -> WriteDescriptorSet {
+        WriteDescriptorSet::buffer(binding, self.buffer.clone())
+    }
+
+    #[inline]
+    fn access(&self) -> ShaderVariableAccess {
+        ShaderVariableAccess::Buffer(self.buffer.as_bytes().clone())
+    }
+
+    #[inline]
+    fn descriptor_type(&self) -> DescriptorType {
+        DescriptorType::StorageBuffer
+    }
+}
+
+
+
+/// A `Vec3` with the layout of a GLSL `vec3` in `std140` and `std430` blocks.
+/// 
+/// # Alignment Rules
+/// - Scalars are aligned to 4 bytes, and `vec2` is aligned to 8 bytes.
+/// - `vec3` and `vec4` are aligned to 16 bytes. A `vec3` only occupies 12 bytes,
+///   but this type is always padded to 16 bytes so that the next member starts on a valid offset.
+/// - A scalar that follows a `vec3` is packed into its last 4 bytes (offset +12) by GLSL,
+///   while in Rust it follows the padding of this type (offset +16).
+///   The GLSL block must therefore declare an explicit `float` padding member after each `vec3`
+///   that is followed by a scalar, or be reordered so that the `vec3` is followed by a `vec3` or `vec4`.
+/// - A `mat3` is stored as three `vec3` columns, each aligned like a `vec4`. (see `Std140Mat3`)
+/// - In `std140`, the stride of arrays and the size of structs are rounded up to 16 bytes.
+///   `std430` only rounds them up to the alignment of the element.
+/// 
+#[repr(C, align(16))]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Pod, Zeroable)]
+pub struct Std140Vec3 {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    padding: f32,
+}
+
+impl Std140Vec3 {
+    #[inline]
+    pub const fn new(vec: Vec3) -> Self {
+        Self { x: vec.x, y: vec.y, z: vec.z, padding: 0.0 }
+    }
+
+    #[inline]
+    pub const fn into_vector3(self) -> Vec3 {
+        Vec3::new_vector(self.x, self.y, self.z)
+    }
+}
+
+impl From<Vec3> for Std140Vec3 {
+    #[inline]
+    fn from(vec: Vec3) -> Self {
+        Self::new(vec)
+    }
+}
+
+impl From<Std140Vec3> for Vec3 {
+    #[inline]
+    fn from(vec: Std140Vec3) -> Self {
+        vec.into_vector3()
+    }
+}
+
+
+
+/// A `Mat3x3` with the layout of a GLSL `mat3` in `std140` and `std430` blocks. (16-byte column stride)
+/// Each row of the matrix becomes a column of the GLSL `mat3`, the same way a `Mat4x4` is uploaded,
+/// so `v * M` in Rust matches `M * v` in the shader.
+#[repr(C, align(16))]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Pod, Zeroable)]
+pub struct Std140Mat3 {
+    pub cols: [Std140Vec3; 3],
+}
+
+impl Std140Mat3 {
+    #[inline]
+    pub fn new(mat: Mat3x3) -> Self {
+        Self { cols: [mat.row(0).into(), mat.row(1).into(), mat.row(2).into()] }
+    }
+
+    #[inline]
+    pub fn into_matrix3x3(self) -> Mat3x3 {
+        Mat3x3::new_rows(self.cols[0].into(), self.cols[1].into(), self.cols[2].into())
+    }
+}
+
+impl From<Mat3x3> for Std140Mat3 {
+    #[inline]
+    fn from(mat: Mat3x3) -> Self {
+        Self::new(mat)
+    }
+}
+
+impl From<Std140Mat3> for Mat3x3 {
+    #[inline]
+    fn from(mat: Std140Mat3) -> Self {
+        mat.into_matrix3x3()
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+
+    fn transform(i: usize) -> Mat4x4 {
+        Mat4x4::from_translation(Vec3::new_vector(i as f32, 2.0 * i as f32, -(i as f32)))
+    }
+
+    #[test]
+    fn identical_uniform_updates_write_once() {
+        let mut last_data = None;
+        let writes = Cell::new(0);
+        let write = |_: Mat4x4| writes.set(writes.get() + 1);
+
+        assert!(write_if_changed(&mut last_data, transform(1), write));
+        assert!(!write_if_changed(&mut last_data, transform(1), write));
+        assert_eq!(writes.get(), 1);
+
+        assert!(write_if_changed(&mut last_data, transform(2), write));
+        assert_eq!(writes.get(), 2);
+    }
+
+    #[test]
+    fn write_1000_transforms_with_one_mapping() {
+        let transforms: Vec<Mat4x4> = (0..1000).map(transform).collect();
+        let mut memory = vec![Mat4x4::IDENTITY; 1024];
+        let mappings = Cell::new(0);
+
+        write_mapped_slice(0, &transforms, memory.len(), || {
+            mappings.set(mappings.get() + 1);
+            Some(&mut memory[..])
+        }).unwrap();
+
+        assert_eq!(mappings.get(), 1);
+        for i in [0, 1, 499, 998, 999] {
+            assert_eq!(memory[i], transform(i));
+        }
+        // the elements after the written slice are not touched.
+        assert!(memory[1000..].iter().all(|m| *m == Mat4x4::IDENTITY));
+    }
+
+    #[test]
+    fn write_past_capacity_is_rejected_without_mapping() {
+        let transforms: Vec<Mat4x4> = (0..1000).map(transform).collect();
+        let mut memory = vec![Mat4x4::IDENTITY; 999];
+        let mappings = Cell::new(0);
+
+        let result = write_mapped_slice(0, &transforms, memory.len(), || {
+            mappings.set(mappings.get() + 1);
+            Some(&mut memory[..])
+        });
+
+        assert!(result.is_err());
+        assert_eq!(mappings.get(), 0);
+    }
+
+    #[test]
+    fn write_at_offset_only_touches_its_region() {
+        let transforms: Vec<Mat4x4> = (0..4).map(transform).collect();
+        let mut memory = vec![Mat4x4::IDENTITY; 12];
+
+        write_mapped_slice(4, &transforms, memory.len(), || Some(&mut memory[..])).unwrap();
+
+        assert_eq!(&memory[4..8], &transforms[..]);
+        assert!(memory[..4].iter().chain(&memory[8..]).all(|m| *m == Mat4x4::IDENTITY));
+        // the items do not fit after the ninth element.
+        assert!(write_mapped_slice(9, &transforms, memory.len(), || Some(&mut memory[..])).is_err());
+    }
+
+    #[test]
+    fn write_to_unmapped_memory_is_rejected() {
+        assert!(write_mapped_slice::<Mat4x4>(0, &[Mat4x4::IDENTITY], 1, || None).is_err());
+    }
+
+    #[test]
+    fn std140_vec3_is_padded_to_16_bytes() {
+        assert_eq!(mem::size_of::<Std140Vec3>(), 16);
+        assert_eq!(mem::align_of::<Std140Vec3>(), 16);
+        assert_eq!(mem::size_of::<Std140Mat3>(), 48);
+    }
+
+    #[test]
+    fn struct_with_std140_vec3_matches_glsl_block_with_explicit_padding() {
+        // layout(std140) uniform LightData {
+        //     float intensity;  // offset 0
+        //     vec3 direction;   // offset 16
+        //     float _padding0;  // offset 28, without it `range` would be packed at offset 28.
+        //     float range;      // offset 32
+        //     vec3 color;       // offset 48
+        //     float _padding1;  // offset 60
+        // };
+        const GLSL_OFFSETS: [usize; 4] = [0, 16, 32, 48];
+        const GLSL_SIZE: usize = 64;
+
+        #[repr(C)]
+        #[derive(Default)]
+        struct LightData {
+            intensity: f32,
+            direction: Std140Vec3,
+            range: f32,
+            color: Std140Vec3,
+        }
+
+        let offsets = [
+            bytemuck::offset_of!(LightData, intensity),
+            bytemuck::offset_of!(LightData, direction),
+            bytemuck::offset_of!(LightData, range),
+            bytemuck::offset_of!(LightData, color),
+        ];
+        assert_eq!(offsets, GLSL_OFFSETS);
+        assert_eq!(mem::size_of::<LightData>(), GLSL_SIZE);
+    }
+
+    #[test]
+    fn scalar_after_glsl_vec3_is_packed_at_offset_12() {
+        // layout(std140) uniform LightData { vec3 direction; float range; };
+        // `range` is packed at offset 12, so it is mirrored with a plain array instead of `Std140Vec3`.
+        #[repr(C, align(16))]
+        #[derive(Default)]
+        struct LightData {
+            direction: [f32; 3],
+            range: f32,
+        }
+
+        assert_eq!(bytemuck::offset_of!(LightData, direction), 0);
+        assert_eq!(bytemuck::offset_of!(LightData, range), 12);
+        assert_eq!(mem::size_of::<LightData>(), 16);
+    }
+}